@@ -1,4 +1,5 @@
 use axum::{
+    middleware,
     routing::{get},
     Router,
 };
@@ -7,7 +8,9 @@ use std::env;
 
 use crate::backend::{
     db::connection::AppDatabase,
-    routes::{auth, bot, health, users, trade},
+    handlers::metrics::get_metrics,
+    routes::{admin, auth, bot, health, pool, position, users, trade},
+    throttle::throttle_user,
 };
 
 pub fn create_app(database: AppDatabase) -> Router {
@@ -33,10 +36,14 @@ pub fn create_app(database: AppDatabase) -> Router {
     // Build application with routes
     Router::new()
         .route("/", get(health::health_check))
+        .route("/metrics", get(get_metrics))
         .nest("/auth", auth::auth_routes())
         .nest("/users", users::user_routes())
-        .nest("/bots", bot::bot_routes())
-        .nest("/trades", trade::trade_routes())
+        .nest("/bots", bot::bot_routes().route_layer(middleware::from_fn(throttle_user)))
+        .nest("/positions", position::position_routes().route_layer(middleware::from_fn(throttle_user)))
+        .nest("/pools", pool::pool_routes().route_layer(middleware::from_fn(throttle_user)))
+        .nest("/trades", trade::trade_routes().route_layer(middleware::from_fn(throttle_user)))
+        .nest("/admin", admin::admin_routes())
         .with_state(database)
         .layer(cors)
 }
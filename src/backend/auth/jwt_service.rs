@@ -2,42 +2,88 @@ use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation}
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::backend::error::{AppResult, AppError};
+use crate::backend::{
+    config::{resolve_jwt_expiry, resolve_jwt_secret, DEV_JWT_SECRET},
+    error::{AppError, AppResult},
+};
+
+/// Refresh tokens can renew access for 30 days
+pub const REFRESH_TOKEN_TTL_SECS: usize = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    /// Tokens issued before refresh tokens existed carry no type
+    #[default]
+    Access,
+    Refresh,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // User ID
     pub exp: usize,  // Expiration time
     pub iat: usize,  // Issued at
+    #[serde(default)]
+    pub admin: bool, // Operator access to /admin
+    #[serde(default)]
+    pub token_type: TokenType,
 }
 
 pub struct JwtService {
     secret: Vec<u8>,
+    access_ttl_secs: usize,
 }
 
 impl JwtService {
-    pub fn new() -> Self {
-        // In a real app, you'd get this from config
-        let secret = std::env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "your-secret-key-change-in-production".to_string());
-        
-        Self {
-            secret: secret.into_bytes(),
+    /// Reads `JWT_SECRET`, `JWT_EXPIRY_SECONDS` and `RUST_ENV`. Errors in
+    /// production when no secret is set rather than signing with a public one.
+    pub fn new() -> AppResult<Self> {
+        Self::from_env_values(
+            std::env::var("JWT_SECRET").ok(),
+            std::env::var("RUST_ENV").ok().as_deref(),
+            std::env::var("JWT_EXPIRY_SECONDS").ok().as_deref(),
+        )
+    }
+
+    fn from_env_values(secret: Option<String>, rust_env: Option<&str>, expiry: Option<&str>) -> AppResult<Self> {
+        let secret = resolve_jwt_secret(secret, rust_env).map_err(|e| AppError::internal(e.to_string()))?;
+        if secret == DEV_JWT_SECRET {
+            tracing::warn!("⚠️ JWT_SECRET is unset, tokens are signed with the public development secret");
         }
+        let access_ttl_secs = resolve_jwt_expiry(expiry).map_err(|e| AppError::internal(e.to_string()))?;
+
+        Ok(Self {
+            secret: secret.into_bytes(),
+            access_ttl_secs,
+        })
+    }
+
+    /// Lifetime of the access tokens this service issues
+    pub fn access_ttl_secs(&self) -> usize {
+        self.access_ttl_secs
     }
 
     pub fn create_token(&self, user_id: &str) -> AppResult<String> {
+        self.create_typed_token(user_id, TokenType::Access, self.access_ttl_secs)
+    }
+
+    pub fn create_refresh_token(&self, user_id: &str) -> AppResult<String> {
+        self.create_typed_token(user_id, TokenType::Refresh, REFRESH_TOKEN_TTL_SECS)
+    }
+
+    fn create_typed_token(&self, user_id: &str, token_type: TokenType, ttl_secs: usize) -> AppResult<String> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs() as usize;
         
-        let expiration = now + (24 * 60 * 60); // 24 hours
-        
         let claims = Claims {
             sub: user_id.to_string(),
-            exp: expiration,
+            exp: now + ttl_secs,
             iat: now,
+            admin: is_admin_user(user_id),
+            token_type,
         };
         
         let token = encode(
@@ -49,7 +95,27 @@ impl JwtService {
         Ok(token)
     }
 
+    /// Verifies an access token, refresh tokens are refused
     pub fn verify_token(&self, token: &str) -> AppResult<Claims> {
+        let claims = self.decode_claims(token)?;
+        if claims.token_type != TokenType::Access {
+            return Err(AppError::auth("Refresh tokens can't be used for access"));
+        }
+
+        Ok(claims)
+    }
+
+    /// Verifies a refresh token, access tokens are refused
+    pub fn verify_refresh_token(&self, token: &str) -> AppResult<Claims> {
+        let claims = self.decode_claims(token)?;
+        if claims.token_type != TokenType::Refresh {
+            return Err(AppError::auth("Not a refresh token"));
+        }
+
+        Ok(claims)
+    }
+
+    fn decode_claims(&self, token: &str) -> AppResult<Claims> {
         let token_data = decode::<Claims>(
             token,
             &DecodingKey::from_secret(&self.secret),
@@ -60,6 +126,14 @@ impl JwtService {
     }
 }
 
+/// Users listed in the comma separated `ADMIN_USER_IDS` get the admin claim.
+/// The admin endpoints are unreachable while it is unset.
+fn is_admin_user(user_id: &str) -> bool {
+    std::env::var("ADMIN_USER_IDS")
+        .map(|ids| ids.split(',').any(|id| id.trim() == user_id))
+        .unwrap_or(false)
+}
+
 pub fn extract_token_from_header(auth_header: &str) -> Option<&str> {
     if auth_header.starts_with("Bearer ") {
         Some(&auth_header[7..])
@@ -67,3 +141,50 @@ pub fn extract_token_from_header(auth_header: &str) -> Option<&str> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_and_access_tokens_are_not_interchangeable() {
+        let jwt_service = JwtService::from_env_values(Some("secret".to_string()), None, None).unwrap();
+        let access = jwt_service.create_token("user").unwrap();
+        let refresh = jwt_service.create_refresh_token("user").unwrap();
+
+        assert_eq!(jwt_service.verify_token(&access).unwrap().sub, "user");
+        let claims = jwt_service.verify_refresh_token(&refresh).unwrap();
+        assert_eq!(claims.exp - claims.iat, REFRESH_TOKEN_TTL_SECS);
+
+        assert!(jwt_service.verify_token(&refresh).is_err());
+        assert!(jwt_service.verify_refresh_token(&access).is_err());
+
+        // Tokens issued before the claim existed still work for access
+        let legacy = encode(
+            &Header::default(),
+            &serde_json::json!({ "sub": "user", "exp": claims.exp, "iat": claims.iat }),
+            &EncodingKey::from_secret(&jwt_service.secret),
+        )
+        .unwrap();
+        assert_eq!(jwt_service.verify_token(&legacy).unwrap().token_type, TokenType::Access);
+    }
+
+    #[test]
+    fn missing_secret_only_fails_in_production() {
+        assert!(JwtService::from_env_values(None, Some("production"), None).is_err());
+        assert!(JwtService::from_env_values(Some(String::new()), Some("production"), None).is_err());
+
+        let development = JwtService::from_env_values(None, Some("development"), None).unwrap();
+        assert_eq!(development.secret, DEV_JWT_SECRET.as_bytes());
+
+        let production =
+            JwtService::from_env_values(Some("secret".to_string()), Some("production"), Some("3600")).unwrap();
+        let token = production.create_token("user").unwrap();
+        let claims = production.verify_token(&token).unwrap();
+        assert_eq!(claims.exp - claims.iat, 3600);
+        assert_eq!(production.access_ttl_secs(), 3600);
+
+        assert!(JwtService::from_env_values(Some("secret".to_string()), None, Some("0")).is_err());
+        assert!(JwtService::from_env_values(Some("secret".to_string()), None, Some("a day")).is_err());
+    }
+}
@@ -1,10 +1,60 @@
 use std::env;
-use anyhow::Result;
+use anyhow::{bail, Result};
+
+/// Signing key used outside production when `JWT_SECRET` is unset. It is
+/// public, so tokens signed with it can be forged by anyone.
+pub const DEV_JWT_SECRET: &str = "your-secret-key-change-in-production";
+
+/// Access tokens expire after a day unless `JWT_EXPIRY_SECONDS` says otherwise
+pub const DEFAULT_JWT_EXPIRY_SECS: usize = 24 * 60 * 60;
+
+/// `JWT_SECRET`, refusing to fall back to `DEV_JWT_SECRET` when `RUST_ENV`
+/// is production
+pub fn resolve_jwt_secret(secret: Option<String>, rust_env: Option<&str>) -> Result<String> {
+    match secret.filter(|secret| !secret.is_empty()) {
+        Some(secret) => Ok(secret),
+        None if rust_env == Some("production") => {
+            bail!("JWT_SECRET must be set when RUST_ENV is production")
+        }
+        None => Ok(DEV_JWT_SECRET.to_string()),
+    }
+}
+
+/// Key wallets are encrypted with outside production when
+/// `WALLET_ENCRYPTION_KEY` is unset. Being public it only keeps the format.
+pub const DEV_WALLET_ENCRYPTION_KEY: &str = "wallet-encryption-key-change-in-production";
+
+/// `WALLET_ENCRYPTION_KEY`, refusing to fall back to the development key when
+/// `RUST_ENV` is production
+pub fn resolve_wallet_encryption_key(key: Option<String>, rust_env: Option<&str>) -> Result<String> {
+    match key.filter(|key| !key.is_empty()) {
+        Some(key) => Ok(key),
+        None if rust_env == Some("production") => {
+            bail!("WALLET_ENCRYPTION_KEY must be set when RUST_ENV is production")
+        }
+        None => {
+            tracing::warn!("⚠️ WALLET_ENCRYPTION_KEY is unset, wallets are encrypted with the public development key");
+            Ok(DEV_WALLET_ENCRYPTION_KEY.to_string())
+        }
+    }
+}
+
+/// `JWT_EXPIRY_SECONDS`, which has to be a positive number when set
+pub fn resolve_jwt_expiry(expiry: Option<&str>) -> Result<usize> {
+    match expiry {
+        None => Ok(DEFAULT_JWT_EXPIRY_SECS),
+        Some(raw) => match raw.trim().parse::<usize>() {
+            Ok(secs) if secs > 0 => Ok(secs),
+            _ => bail!("JWT_EXPIRY_SECONDS must be a positive number of seconds, got {:?}", raw),
+        },
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub mongodb_uri: String,
     pub jwt_secret: String,
+    pub jwt_expiry_secs: usize,
     pub rust_log: String,
     pub port: u16,
 }
@@ -16,8 +66,11 @@ impl Config {
         Ok(Self {
             mongodb_uri: env::var("MONGODB_URI")
                 .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
-            jwt_secret: env::var("JWT_SECRET")
-                .unwrap_or_else(|_| "your-secret-key-change-in-production".to_string()),
+            jwt_secret: resolve_jwt_secret(
+                env::var("JWT_SECRET").ok(),
+                env::var("RUST_ENV").ok().as_deref(),
+            )?,
+            jwt_expiry_secs: resolve_jwt_expiry(env::var("JWT_EXPIRY_SECONDS").ok().as_deref())?,
             rust_log: env::var("RUST_LOG")
                 .unwrap_or_else(|_| "info".to_string()),
             port: env::var("PORT")
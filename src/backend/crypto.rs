@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    hkdf::{Salt, HKDF_SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
+
+use crate::backend::config::resolve_wallet_encryption_key;
+
+/// Marks a stored private key as encrypted, anything else is a legacy plaintext key
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+const KDF_SALT: &[u8] = b"pump-raydium-trading-bot/wallet";
+const KDF_INFO: &[u8] = b"private_key";
+
+static WALLET_CIPHER: OnceCell<WalletCipher> = OnceCell::new();
+
+/// Encrypts wallet private keys with AES-256-GCM. The stored form is
+/// `ENCRYPTED_PREFIX` followed by base64 of the nonce and ciphertext.
+pub struct WalletCipher {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl WalletCipher {
+    /// Derives the AES key from `secret` with HKDF-SHA256
+    pub fn from_secret(secret: &str) -> Result<Self> {
+        let prk = Salt::new(HKDF_SHA256, KDF_SALT).extract(secret.as_bytes());
+        let okm = prk
+            .expand(&[KDF_INFO], &AES_256_GCM)
+            .map_err(|_| anyhow!("Failed to derive the wallet encryption key"))?;
+
+        Ok(Self {
+            key: LessSafeKey::new(UnboundKey::from(okm)),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    pub fn is_encrypted(stored: &str) -> bool {
+        stored.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    pub fn encrypt(&self, private_key: &str) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("Failed to generate a nonce"))?;
+
+        let mut sealed = private_key.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+            .map_err(|_| anyhow!("Failed to encrypt the private key"))?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend(sealed);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, base64::encode(stored)))
+    }
+
+    /// Decrypts a stored private key, passing a legacy plaintext key through
+    /// until the startup migration has encrypted it
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let mut sealed = base64::decode(encoded)?;
+        if sealed.len() < NONCE_LEN {
+            return Err(anyhow!("Encrypted private key is truncated"));
+        }
+        let ciphertext = sealed.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&sealed)
+            .map_err(|_| anyhow!("Encrypted private key has an invalid nonce"))?;
+
+        let mut in_out = ciphertext;
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| anyhow!("Failed to decrypt the private key, is WALLET_ENCRYPTION_KEY right?"))?;
+        Ok(String::from_utf8(plaintext.to_vec())?)
+    }
+}
+
+/// Cipher keyed from `WALLET_ENCRYPTION_KEY`, which production requires
+pub fn wallet_cipher() -> Result<&'static WalletCipher> {
+    WALLET_CIPHER.get_or_try_init(|| {
+        let secret = resolve_wallet_encryption_key(
+            std::env::var("WALLET_ENCRYPTION_KEY").ok(),
+            std::env::var("RUST_ENV").ok().as_deref(),
+        )?;
+        WalletCipher::from_secret(&secret)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_keys_round_trip_and_need_the_right_key() {
+        let cipher = WalletCipher::from_secret("secret").unwrap();
+        let private_key = "4wBqpZM9xaSheZzJSMawUKKwhdpChKbZ5eu5ky4Vigw";
+
+        let stored = cipher.encrypt(private_key).unwrap();
+        assert!(WalletCipher::is_encrypted(&stored));
+        assert!(!stored.contains(private_key));
+        assert_eq!(cipher.decrypt(&stored).unwrap(), private_key);
+
+        // A fresh nonce each time, so equal keys don't store equal
+        assert_ne!(cipher.encrypt(private_key).unwrap(), stored);
+
+        let other = WalletCipher::from_secret("other secret").unwrap();
+        assert!(other.decrypt(&stored).is_err());
+
+        let mut tampered = stored.clone();
+        tampered.replace_range(tampered.len() - 4.., "AAAA");
+        assert!(cipher.decrypt(&tampered).is_err());
+        assert!(cipher.decrypt(&format!("{}AAAA", ENCRYPTED_PREFIX)).is_err());
+
+        // Keys stored before encryption are read as they are
+        assert!(!WalletCipher::is_encrypted(private_key));
+        assert_eq!(cipher.decrypt(private_key).unwrap(), private_key);
+    }
+}
@@ -3,7 +3,7 @@ use bson::{doc, oid::ObjectId};
 use futures::StreamExt;
 use mongodb::{Collection, Database};
 
-use crate::backend::models::bot::BotSettings;
+use crate::backend::{error::DocumentNotFound, models::bot::{BotSettings, FeeBudget}};
 
 pub struct BotRepository {
     collection: Collection<BotSettings>,
@@ -47,6 +47,94 @@ impl BotRepository {
         Ok(bots)
     }
 
+    pub async fn find_all(&self) -> Result<Vec<BotSettings>> {
+        let mut cursor = self.collection.find(doc! {}).await?;
+
+        let mut bots = Vec::new();
+        while let Some(bot_result) = cursor.next().await {
+            let bot = bot_result?;
+            bots.push(bot);
+        }
+
+        Ok(bots)
+    }
+
+    /// Bots with a pending scheduled start or stop
+    pub async fn find_scheduled(&self) -> Result<Vec<BotSettings>> {
+        let filter = doc! { "$or": [
+            { "scheduled_start": { "$type": "number" } },
+            { "scheduled_stop": { "$type": "number" } },
+        ]};
+        let mut cursor = self.collection.find(filter).await?;
+
+        let mut bots = Vec::new();
+        while let Some(bot_result) = cursor.next().await {
+            let bot = bot_result?;
+            bots.push(bot);
+        }
+
+        Ok(bots)
+    }
+
+    /// Bots that opted into entry_percent auto-tuning
+    pub async fn find_auto_tuned(&self) -> Result<Vec<BotSettings>> {
+        let filter = doc! { "auto_tune_entry": true };
+        let mut cursor = self.collection.find(filter).await?;
+
+        let mut bots = Vec::new();
+        while let Some(bot_result) = cursor.next().await {
+            let bot = bot_result?;
+            bots.push(bot);
+        }
+
+        Ok(bots)
+    }
+
+    /// Overwrite only the schedule fields, leaving concurrent edits alone
+    pub async fn update_schedule(&self, bot: &BotSettings) -> Result<()> {
+        let filter = doc! { "_id": bot.id };
+        let update = doc! { "$set": {
+            "scheduled_start": bot.scheduled_start,
+            "scheduled_stop": bot.scheduled_stop,
+        }};
+        self.collection.update_one(filter, update).await?;
+
+        Ok(())
+    }
+
+    /// Overwrite only the auto-tuned entry_percent and when it was tuned
+    pub async fn update_entry_tuning(&self, bot: &BotSettings) -> Result<()> {
+        let filter = doc! { "_id": bot.id };
+        let update = doc! { "$set": {
+            "entry_percent": bot.entry_percent,
+            "auto_tuned_at": bot.auto_tuned_at,
+        }};
+        self.collection.update_one(filter, update).await?;
+
+        Ok(())
+    }
+
+    /// Overwrite only the loss circuit breaker state
+    pub async fn update_breaker(&self, bot: &BotSettings) -> Result<()> {
+        let filter = doc! { "_id": bot.id };
+        let update = doc! { "$set": {
+            "consecutive_losses": bot.consecutive_losses as i64,
+            "halted": bot.halted,
+        }};
+        self.collection.update_one(filter, update).await?;
+
+        Ok(())
+    }
+
+    /// Overwrite only the fee budget counters of the user's bot
+    pub async fn update_fee_budget(&self, user_id: &str, budget: &FeeBudget) -> Result<()> {
+        let filter = doc! { "user_id": user_id };
+        let update = doc! { "$set": { "fee_budget": bson::to_bson(budget)? } };
+        self.collection.update_one(filter, update).await?;
+
+        Ok(())
+    }
+
     pub async fn update(&self, bot: &BotSettings) -> Result<()> {
         let filter = doc! { "_id": bot.id };
         let update = doc! { "$set": {
@@ -54,19 +142,70 @@ impl BotRepository {
             "pool_address": &bot.pool_address,
             "buy_sol_amount": bot.buy_sol_amount,
             "entry_percent": bot.entry_percent,
+            "entry_reference": bson::to_bson(&bot.entry_reference)?,
+            "entry_window_secs": bot.entry_window_secs as i64,
             "entry_slippage": bot.entry_slippage,
             "exit_slippage": bot.exit_slippage,
             "stop_loss": bot.stop_loss,
             "take_profit": bot.take_profit,
             "auto_exit": bot.auto_exit as i64,
+            "reentry_cooldown_secs": bot.reentry_cooldown_secs as i64,
+            "confirm_timeout_secs": bot.confirm_timeout_secs as i64,
+            "slow_entry_slots": bot.slow_entry_slots as i64,
+            "slow_entry_ms": bot.slow_entry_ms as i64,
             "confirm_service": &bot.confirm_service,
             "cu": bot.cu as i64,
             "priority_fee_micro_lamport": bot.priority_fee_micro_lamport as i64,
             "third_party_fee": bot.third_party_fee,
+            "min_observations_before_trading": bot.min_observations_before_trading as i64,
+            "observer": bot.observer,
+            "slippage_overrides": bson::to_bson(&bot.slippage_overrides)?,
+            "max_tx_fee_sol": bot.max_tx_fee_sol,
+            "auto_cu": bot.auto_cu,
+            "max_slippage_retries": bot.max_slippage_retries as i64,
+            "slippage_retry_step": bot.slippage_retry_step,
+            "min_out_cost_floor": bot.min_out_cost_floor,
+            "scheduled_start": bot.scheduled_start,
+            "scheduled_stop": bot.scheduled_stop,
+            "max_consecutive_losses": bot.max_consecutive_losses as i64,
+            "max_fees_sol_per_hour": bot.max_fees_sol_per_hour,
+            "max_fees_sol_per_day": bot.max_fees_sol_per_day,
+            "max_open_positions": bot.max_open_positions as i64,
+            "tp_decay_min_profit_pct": bot.tp_decay_min_profit_pct,
+            "tp_decay_secs": bot.tp_decay_secs as i64,
+            "trade_tag": &bot.trade_tag,
+            "break_even_tp_floor": bot.break_even_tp_floor,
+            "drain_detection": bot.drain_detection,
+            "drain_window_secs": bot.drain_window_secs as i64,
+            "drain_outflow_pct": bot.drain_outflow_pct,
+            "drain_min_sells": bot.drain_min_sells as i64,
+            "regime_exits": bson::to_bson(&bot.regime_exits)?,
+            "zslot_region": &bot.zslot_region,
+            "tip_addr_idx": bot.tip_addr_idx.map(|idx| idx as i32),
+            "multi_relay": &bot.multi_relay,
+            "mint_blacklist": &bot.mint_blacklist,
+            "mint_whitelist": &bot.mint_whitelist,
+            "min_pool_liquidity_sol": bot.min_pool_liquidity_sol,
+            "max_price_impact_pct": bot.max_price_impact_pct,
+            "trailing_stop_pct": bot.trailing_stop_pct,
+            "move_stop_to_be_at_pct": bot.move_stop_to_be_at_pct,
+            "break_even_stop_offset_pct": bot.break_even_stop_offset_pct,
+            "take_profit_tiers": bson::to_bson(&bot.take_profit_tiers)?,
+            "dca_levels": &bot.dca_levels,
+            "dca_amounts": &bot.dca_amounts,
+            "anti_sandwich": bot.anti_sandwich,
+            "sandwich_min_move_pct": bot.sandwich_min_move_pct,
+            "auto_tune_entry": bot.auto_tune_entry,
+            "auto_tune_min_entry_percent": bot.auto_tune_min_entry_percent,
+            "auto_tune_max_entry_percent": bot.auto_tune_max_entry_percent,
+            "live_trading_acknowledged": bot.live_trading_acknowledged,
             "updated_at": bson::DateTime::now()
         }};
 
-        self.collection.update_one(filter, update).await?;
+        let result = self.collection.update_one(filter, update).await?;
+        if result.matched_count == 0 {
+            return Err(DocumentNotFound("Bot".to_string()).into());
+        }
 
         Ok(())
     }
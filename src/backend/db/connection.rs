@@ -29,6 +29,11 @@ pub async fn init_database(config: &Config) -> Result<AppDatabase> {
 async fn create_indexes(database: &AppDatabase) -> Result<()> {
     let users = database.collection::<crate::backend::models::user::User>("users");
     let bots = database.collection::<crate::backend::models::bot::BotSettings>("bot_settings");
+    let pool_prices =
+        database.collection::<crate::backend::models::pool_price::PoolPrice>("pool_prices");
+    let trades = database.collection::<crate::backend::models::trade::TradeData>("trade_data");
+    let positions =
+        database.collection::<crate::backend::models::position::StoredPosition>("positions");
 
     // User indexes
     let user_indexes = vec![
@@ -53,6 +58,23 @@ async fn create_indexes(database: &AppDatabase) -> Result<()> {
             .build(),
     ];
 
+    // Pool price indexes
+    let pool_price_index = IndexModel::builder()
+        .keys(doc! { "pool_address": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+
+    // One stored position per user and pool
+    let position_index = IndexModel::builder()
+        .keys(doc! { "pool_id": 1, "user_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+
+    // Trade index for the leaderboard's period match and per-user grouping
+    let trade_index = IndexModel::builder()
+        .keys(doc! { "created_at": -1, "user_id": 1 })
+        .build();
+
     // Create user indexes
     for index in user_indexes {
         users
@@ -69,7 +91,22 @@ async fn create_indexes(database: &AppDatabase) -> Result<()> {
             .context("Failed to create index on bot_settings collection")?;
     }
 
-    println!("✅ Indexes ensured on 'users' and 'bot_settings' collections");
+    pool_prices
+        .create_index(pool_price_index)
+        .await
+        .context("Failed to create index on pool_prices collection")?;
+
+    trades
+        .create_index(trade_index)
+        .await
+        .context("Failed to create index on trade_data collection")?;
+
+    positions
+        .create_index(position_index)
+        .await
+        .context("Failed to create index on positions collection")?;
+
+    println!("✅ Indexes ensured on 'users', 'bot_settings', 'pool_prices', 'trade_data' and 'positions' collections");
 
     Ok(())
 }
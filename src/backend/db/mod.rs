@@ -2,3 +2,6 @@ pub mod connection;
 pub mod user_repository;
 pub mod bot_repository;
 pub mod trade_repository;
+pub mod pool_price_repository;
+pub mod position_repository;
+pub mod pool_table_repository;
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use bson::doc;
+use mongodb::{Collection, Database};
+
+use crate::backend::models::pool_price::PoolPrice;
+
+pub struct PoolPriceRepository {
+    collection: Collection<PoolPrice>,
+}
+
+impl PoolPriceRepository {
+    pub fn new(database: Database) -> Self {
+        Self {
+            collection: database.collection("pool_prices"),
+        }
+    }
+
+    pub async fn upsert(&self, pool_address: &str, price: f64) -> Result<()> {
+        let filter = doc! { "pool_address": pool_address };
+        let update = doc! { "$set": {
+            "pool_address": pool_address,
+            "price": price,
+            "updated_at": bson::DateTime::now()
+        }};
+
+        self.collection.update_one(filter, update).upsert(true).await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_pool_address(&self, pool_address: &str) -> Result<Option<PoolPrice>> {
+        let filter = doc! { "pool_address": pool_address };
+        let price = self.collection.find_one(filter).await?;
+
+        Ok(price)
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use bson::doc;
+use futures::StreamExt;
+use mongodb::{Collection, Database};
+
+use crate::backend::models::pool_table::StoredPoolTable;
+
+pub struct PoolTableRepository {
+    collection: Collection<StoredPoolTable>,
+}
+
+impl PoolTableRepository {
+    pub fn new(database: Database) -> Self {
+        Self {
+            collection: database.collection("pool_tables"),
+        }
+    }
+
+    pub async fn insert(&self, table: &StoredPoolTable) -> Result<()> {
+        self.collection.insert_one(table).await?;
+
+        Ok(())
+    }
+
+    /// The pool's table that is still active
+    pub async fn find_active(&self, pool_id: &str) -> Result<Option<StoredPoolTable>> {
+        let filter = doc! { "pool_id": pool_id, "deactivated_slot": null };
+        Ok(self.collection.find_one(filter).await?)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<StoredPoolTable>> {
+        let mut cursor = self.collection.find(doc! {}).await?;
+
+        let mut tables = Vec::new();
+        while let Some(table) = cursor.next().await {
+            tables.push(table?);
+        }
+
+        Ok(tables)
+    }
+
+    pub async fn set_deactivated(&self, table: &str, slot: u64) -> Result<()> {
+        self.collection
+            .update_one(doc! { "table": table }, doc! { "$set": { "deactivated_slot": slot as i64 } })
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, table: &str) -> Result<()> {
+        self.collection.delete_one(doc! { "table": table }).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use bson::doc;
+use futures::StreamExt;
+use mongodb::{Collection, Database};
+
+use crate::backend::models::position::StoredPosition;
+
+pub struct PositionRepository {
+    collection: Collection<StoredPosition>,
+}
+
+impl PositionRepository {
+    pub fn new(database: Database) -> Self {
+        Self {
+            collection: database.collection("positions"),
+        }
+    }
+
+    pub async fn upsert(&self, position: &StoredPosition) -> Result<()> {
+        let filter = doc! { "pool_id": &position.pool_id, "user_id": &position.user_id };
+        let mut document = bson::to_document(position)?;
+        document.remove("_id");
+
+        self.collection
+            .update_one(filter, doc! { "$set": document })
+            .upsert(true)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, pool_id: &str, user_id: &str) -> Result<()> {
+        self.collection
+            .delete_one(doc! { "pool_id": pool_id, "user_id": user_id })
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        self.collection.delete_many(doc! { "user_id": user_id }).await?;
+
+        Ok(())
+    }
+
+    /// Positions still bought or closed recently enough to be cooling down
+    pub async fn find_restorable(&self) -> Result<Vec<StoredPosition>> {
+        let filter = doc! { "$or": [{ "is_bought": true }, { "last_exit_at": { "$ne": null } }] };
+        let mut cursor = self.collection.find(filter).await?;
+
+        let mut positions = Vec::new();
+        while let Some(position_result) = cursor.next().await {
+            let position = position_result?;
+            positions.push(position);
+        }
+
+        Ok(positions)
+    }
+}
@@ -1,8 +1,166 @@
-use bson::{doc, oid::ObjectId};
-use mongodb::{Collection, Database};
+use bson::{doc, oid::ObjectId, Document};
+use mongodb::{options::FindOptions, Collection, Database};
 use anyhow::Result;
+use serde::Serialize;
 
-use crate::backend::models::trade::TradeData;
+use crate::backend::models::trade::{FeeBreakdown, TradeData};
+
+/// Stats bucket for trades saved before tagging
+const UNTAGGED: &str = "untagged";
+
+/// Trades collection filter for an optional user and strategy tag
+pub fn trade_filter(user_id: Option<&str>, tag: Option<&str>) -> Document {
+    let mut filter = doc! {};
+    if let Some(user_id) = user_id {
+        filter.insert("user_id", user_id);
+    }
+    if let Some(tag) = tag {
+        filter.insert("tag", tag);
+    }
+    filter
+}
+
+/// Narrows a trade filter to trades created in `[from_ms, to_ms]`, either end optional
+pub fn period_filter(mut filter: Document, from_ms: Option<i64>, to_ms: Option<i64>) -> Document {
+    let mut created_at = doc! {};
+    if let Some(from_ms) = from_ms {
+        created_at.insert("$gte", bson::DateTime::from_millis(from_ms));
+    }
+    if let Some(to_ms) = to_ms {
+        created_at.insert("$lte", bson::DateTime::from_millis(to_ms));
+    }
+    if !created_at.is_empty() {
+        filter.insert("created_at", created_at);
+    }
+    filter
+}
+
+/// `$group` accumulators shared by the stats pipelines
+fn stats_group(id: impl Into<bson::Bson>) -> Document {
+    doc! {
+        "_id": id.into(),
+        "total_trades": { "$sum": 1 },
+        "total_profit": { "$sum": "$profit_sol" },
+        "avg_roi": { "$avg": "$roi_pct" },
+        "profitable_trades": {
+            "$sum": {
+                "$cond": [{ "$gt": ["$profit_sol", 0] }, 1, 0]
+            }
+        },
+        "lp_fee_lamports": { "$sum": "$fee_breakdown.lp_fee_lamports" },
+        "protocol_fee_lamports": { "$sum": "$fee_breakdown.protocol_fee_lamports" },
+        "coin_creator_fee_lamports": { "$sum": "$fee_breakdown.coin_creator_fee_lamports" },
+        "network_fee_lamports": { "$sum": "$fee_breakdown.network_fee_lamports" },
+        "tip_lamports": { "$sum": "$fee_breakdown.tip_lamports" }
+    }
+}
+
+/// `$sum` yields an int32 or int64 depending on the values summed
+fn group_lamports(doc: &Document, key: &str) -> i64 {
+    doc.get_i64(key)
+        .or_else(|_| doc.get_i32(key).map(i64::from))
+        .unwrap_or(0)
+}
+
+/// `stats_group` plus the extremes and hold time a report needs
+fn report_group() -> Document {
+    let mut group = stats_group(bson::Bson::Null);
+    group.insert("best_trade_sol", doc! { "$max": "$profit_sol" });
+    group.insert("worst_trade_sol", doc! { "$min": "$profit_sol" });
+    group.insert("avg_hold_ms", doc! { "$avg": "$program_runtime_ms" });
+    group
+}
+
+/// Read a `report_group` result
+fn report_from_group(doc: &Document, from_ms: Option<i64>, to_ms: Option<i64>) -> TradeReport {
+    TradeReport {
+        from_ms,
+        to_ms,
+        stats: stats_from_group(doc),
+        best_trade_sol: doc.get_f64("best_trade_sol").ok(),
+        worst_trade_sol: doc.get_f64("worst_trade_sol").ok(),
+        avg_hold_ms: doc.get_f64("avg_hold_ms").unwrap_or(0.0),
+    }
+}
+
+/// Read a `stats_group` result
+fn stats_from_group(doc: &Document) -> TradeStats {
+    let mut stats = TradeStats {
+        total_trades: doc.get_i32("total_trades").unwrap_or(0) as u32,
+        total_profit: doc.get_f64("total_profit").unwrap_or(0.0),
+        avg_roi: doc.get_f64("avg_roi").unwrap_or(0.0),
+        profitable_trades: doc.get_i32("profitable_trades").unwrap_or(0) as u32,
+        win_rate: 0.0,
+        fee_breakdown: FeeBreakdown {
+            lp_fee_lamports: group_lamports(doc, "lp_fee_lamports"),
+            protocol_fee_lamports: group_lamports(doc, "protocol_fee_lamports"),
+            coin_creator_fee_lamports: group_lamports(doc, "coin_creator_fee_lamports"),
+            network_fee_lamports: group_lamports(doc, "network_fee_lamports"),
+            tip_lamports: group_lamports(doc, "tip_lamports"),
+        },
+    };
+    if stats.total_trades > 0 {
+        stats.win_rate = (stats.profitable_trades as f64 / stats.total_trades as f64) * 100.0;
+    }
+    stats
+}
+
+/// Newest trades first, at most `limit` of them when set
+pub fn newest_first(limit: Option<i64>) -> FindOptions {
+    FindOptions::builder()
+        .sort(doc! { "created_at": -1 })
+        .limit(limit)
+        .build()
+}
+
+/// Totals over the trades of an optional user created in `[from_ms, to_ms]`,
+/// read with `stats_from_group`
+pub fn stats_pipeline(user_id: Option<&str>, from_ms: Option<i64>, to_ms: Option<i64>) -> Vec<Document> {
+    vec![
+        doc! { "$match": period_filter(trade_filter(user_id, None), from_ms, to_ms) },
+        doc! { "$group": stats_group(bson::Bson::Null) },
+    ]
+}
+
+/// What the leaderboard ranks traders by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardSort {
+    #[default]
+    Pnl,
+    WinRate,
+}
+
+/// Per-user totals since `since_ms`, excluding `hidden_user_ids`, ranked by
+/// `sort` (ties broken by user id so pages are stable)
+pub fn leaderboard_pipeline(
+    since_ms: i64,
+    hidden_user_ids: &[String],
+    sort: LeaderboardSort,
+    skip: u64,
+    limit: i64,
+) -> Vec<Document> {
+    let sort_key = match sort {
+        LeaderboardSort::Pnl => "total_profit",
+        LeaderboardSort::WinRate => "win_rate",
+    };
+
+    vec![
+        doc! { "$match": {
+            "created_at": { "$gte": bson::DateTime::from_millis(since_ms) },
+            "user_id": { "$nin": hidden_user_ids },
+        }},
+        doc! { "$group": stats_group("$user_id") },
+        doc! { "$addFields": {
+            "win_rate": {
+                "$multiply": [{ "$divide": ["$profitable_trades", "$total_trades"] }, 100.0]
+            }
+        }},
+        doc! { "$sort": { sort_key: -1, "_id": 1 } },
+        doc! { "$skip": skip as i64 },
+        doc! { "$limit": limit },
+    ]
+}
 
 pub struct TradeRepository {
     collection: Collection<TradeData>,
@@ -24,9 +182,14 @@ impl TradeRepository {
         Ok(trade)
     }
 
+    /// The user's trades, newest first
     pub async fn find_by_user_id(&self, user_id: &str) -> Result<Vec<TradeData>> {
         let filter = doc! { "user_id": user_id };
-        let mut cursor = self.collection.find(filter).await?;
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .with_options(newest_first(None))
+            .await?;
         
         let mut trades = Vec::new();
         while cursor.advance().await? {
@@ -36,70 +199,503 @@ impl TradeRepository {
         Ok(trades)
     }
 
+    /// The user's trades with the strategy tag, newest first
+    pub async fn find_by_user_id_and_tag(&self, user_id: &str, tag: &str) -> Result<Vec<TradeData>> {
+        let filter = trade_filter(Some(user_id), Some(tag));
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .with_options(newest_first(None))
+            .await?;
+
+        let mut trades = Vec::new();
+        while cursor.advance().await? {
+            trades.push(cursor.deserialize_current()?);
+        }
+
+        Ok(trades)
+    }
+
+    /// The user's trades created after `since_ms`, oldest first
+    pub async fn find_by_user_id_since(&self, user_id: &str, since_ms: Option<i64>) -> Result<Vec<TradeData>> {
+        let filter = period_filter(trade_filter(Some(user_id), None), since_ms.map(|ms| ms + 1), None);
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .sort(doc! { "created_at": 1 })
+            .await?;
+
+        let mut trades = Vec::new();
+        while cursor.advance().await? {
+            trades.push(cursor.deserialize_current()?);
+        }
+
+        Ok(trades)
+    }
+
+    /// The newest `limit` trades across all users, newest first
     pub async fn find_recent(&self, limit: i64) -> Result<Vec<TradeData>> {
-        let mut cursor = self.collection.find(doc! {}).await?;
-        
+        let mut cursor = self
+            .collection
+            .find(doc! {})
+            .with_options(newest_first(Some(limit)))
+            .await?;
+
         let mut trades = Vec::new();
-        let mut count = 0;
-        while cursor.advance().await? && count < limit {
+        while cursor.advance().await? {
             trades.push(cursor.deserialize_current()?);
-            count += 1;
         }
-        
+
         Ok(trades)
     }
 
-    pub async fn get_stats(&self, user_id: Option<&str>) -> Result<TradeStats> {
-        let filter = if let Some(user_id) = user_id {
-            doc! { "user_id": user_id }
-        } else {
-            doc! {}
-        };
+    pub async fn get_stats(
+        &self,
+        user_id: Option<&str>,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<TradeStats> {
+        let mut cursor = self
+            .collection
+            .aggregate(stats_pipeline(user_id, from_ms, to_ms))
+            .await?;
+        let mut stats = TradeStats::default();
+
+        if cursor.advance().await? {
+            stats = stats_from_group(&cursor.deserialize_current()?);
+        }
+
+        Ok(stats)
+    }
 
+    /// Performance summary of the user's trades created in `[from_ms, to_ms]`
+    pub async fn get_report(
+        &self,
+        user_id: &str,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> Result<TradeReport> {
         let pipeline = vec![
-            doc! {
-                "$match": filter
-            },
-            doc! {
-                "$group": {
-                    "_id": null,
-                    "total_trades": { "$sum": 1 },
-                    "total_profit": { "$sum": "$profit_sol" },
-                    "avg_roi": { "$avg": "$roi_pct" },
-                    "profitable_trades": {
-                        "$sum": {
-                            "$cond": [{ "$gt": ["$profit_sol", 0] }, 1, 0]
-                        }
-                    }
-                }
-            }
+            doc! { "$match": period_filter(trade_filter(Some(user_id), None), from_ms, to_ms) },
+            doc! { "$group": report_group() },
         ];
 
         let mut cursor = self.collection.aggregate(pipeline).await?;
-        let mut stats = TradeStats::default();
+        let mut report = report_from_group(&doc! {}, from_ms, to_ms);
 
         if cursor.advance().await? {
+            report = report_from_group(&cursor.deserialize_current()?, from_ms, to_ms);
+        }
+
+        Ok(report)
+    }
+
+    /// Per-user trade totals, one entry for every user with at least one trade
+    pub async fn get_stats_by_user(&self) -> Result<Vec<(String, TradeStats)>> {
+        let pipeline = vec![doc! { "$group": stats_group("$user_id") }];
+
+        let mut cursor = self.collection.aggregate(pipeline).await?;
+        let mut per_user = Vec::new();
+
+        while cursor.advance().await? {
             let doc = cursor.deserialize_current()?;
-            stats.total_trades = doc.get_i32("total_trades").unwrap_or(0) as u32;
-            stats.total_profit = doc.get_f64("total_profit").unwrap_or(0.0);
-            stats.avg_roi = doc.get_f64("avg_roi").unwrap_or(0.0);
-            stats.profitable_trades = doc.get_i32("profitable_trades").unwrap_or(0) as u32;
-            stats.win_rate = if stats.total_trades > 0 {
-                (stats.profitable_trades as f64 / stats.total_trades as f64) * 100.0
-            } else {
-                0.0
-            };
+            let user_id = doc.get_str("_id").unwrap_or_default().to_string();
+            per_user.push((user_id, stats_from_group(&doc)));
         }
 
-        Ok(stats)
+        Ok(per_user)
+    }
+
+    /// One leaderboard page of per-user totals, best first
+    pub async fn get_leaderboard(
+        &self,
+        since_ms: i64,
+        hidden_user_ids: &[String],
+        sort: LeaderboardSort,
+        skip: u64,
+        limit: i64,
+    ) -> Result<Vec<(String, TradeStats)>> {
+        let pipeline = leaderboard_pipeline(since_ms, hidden_user_ids, sort, skip, limit);
+
+        let mut cursor = self.collection.aggregate(pipeline).await?;
+        let mut ranked = Vec::new();
+
+        while cursor.advance().await? {
+            let doc = cursor.deserialize_current()?;
+            let user_id = doc.get_str("_id").unwrap_or_default().to_string();
+            ranked.push((user_id, stats_from_group(&doc)));
+        }
+
+        Ok(ranked)
+    }
+
+    /// Trade totals and average realized slippage per pool, trades saved
+    /// before their pool was recorded are left out
+    pub async fn get_stats_by_pool(&self, user_id: Option<&str>) -> Result<Vec<PoolStats>> {
+        let mut filter = trade_filter(user_id, None);
+        filter.insert("pool_id", doc! { "$type": "string" });
+        let mut group = stats_group("$pool_id");
+        group.insert("avg_entry_slippage_pct", doc! { "$avg": "$slippage.entry_pct" });
+        group.insert("avg_exit_slippage_pct", doc! { "$avg": "$slippage.exit_pct" });
+        group.insert("avg_entry_latency_slots", doc! { "$avg": "$entry_latency.slots" });
+        group.insert("avg_entry_latency_ms", doc! { "$avg": "$entry_latency.ms" });
+        // Trades without a measured entry are left out of the share
+        group.insert(
+            "slow_entry_rate",
+            doc! { "$avg": { "$cond": [
+                { "$eq": [{ "$type": "$entry_latency" }, "missing"] },
+                null,
+                { "$cond": ["$entry_latency.slow", 1, 0] },
+            ] } },
+        );
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$group": group },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+
+        let mut cursor = self.collection.aggregate(pipeline).await?;
+        let mut per_pool = Vec::new();
+
+        while cursor.advance().await? {
+            let doc = cursor.deserialize_current()?;
+            per_pool.push(PoolStats {
+                pool_id: doc.get_str("_id").unwrap_or_default().to_string(),
+                // `$avg` is null when no trade of the pool recorded slippage
+                avg_entry_slippage_pct: doc.get_f64("avg_entry_slippage_pct").ok(),
+                avg_exit_slippage_pct: doc.get_f64("avg_exit_slippage_pct").ok(),
+                avg_entry_latency_slots: doc.get_f64("avg_entry_latency_slots").ok(),
+                avg_entry_latency_ms: doc.get_f64("avg_entry_latency_ms").ok(),
+                slow_entry_pct: doc.get_f64("slow_entry_rate").ok().map(|rate| rate * 100.0),
+                stats: stats_from_group(&doc),
+            });
+        }
+
+        Ok(per_pool)
+    }
+
+    /// Trade totals per strategy tag, trades saved before tagging count as `untagged`
+    pub async fn get_stats_by_tag(&self, user_id: Option<&str>) -> Result<Vec<TagStats>> {
+        let pipeline = vec![
+            doc! { "$match": trade_filter(user_id, None) },
+            doc! { "$group": stats_group(doc! { "$ifNull": ["$tag", UNTAGGED] }) },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+
+        let mut cursor = self.collection.aggregate(pipeline).await?;
+        let mut per_tag = Vec::new();
+
+        while cursor.advance().await? {
+            let doc = cursor.deserialize_current()?;
+            per_tag.push(TagStats {
+                tag: doc.get_str("_id").unwrap_or(UNTAGGED).to_string(),
+                stats: stats_from_group(&doc),
+            });
+        }
+
+        Ok(per_tag)
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct TradeStats {
     pub total_trades: u32,
     pub total_profit: f64,
     pub avg_roi: f64,
     pub profitable_trades: u32,
     pub win_rate: f64,
+    /// Summed over the trades that recorded one
+    pub fee_breakdown: FeeBreakdown,
+}
+
+/// Shareable performance summary over a period, see `get_report`
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeReport {
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+    #[serde(flatten)]
+    pub stats: TradeStats,
+    /// Largest and smallest single-trade profit, none without trades
+    pub best_trade_sol: Option<f64>,
+    pub worst_trade_sol: Option<f64>,
+    /// Average time from bot start to the closing sell
+    pub avg_hold_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagStats {
+    pub tag: String,
+    #[serde(flatten)]
+    pub stats: TradeStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStats {
+    pub pool_id: String,
+    pub avg_entry_slippage_pct: Option<f64>,
+    pub avg_exit_slippage_pct: Option<f64>,
+    /// How many slots after the signalling swap the pool's entries landed,
+    /// a pool that is consistently slow is a race the bot is losing
+    pub avg_entry_latency_slots: Option<f64>,
+    pub avg_entry_latency_ms: Option<f64>,
+    /// Share of the pool's entries over the bot's slow-entry thresholds
+    pub slow_entry_pct: Option<f64>,
+    #[serde(flatten)]
+    pub stats: TradeStats,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::Bson;
+
+    fn number(value: &Bson) -> f64 {
+        match value {
+            Bson::Double(v) => *v,
+            Bson::Int32(v) => *v as f64,
+            Bson::Int64(v) => *v as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Evaluates the field paths, literals and `$cond`/`$gt` the stats
+    /// accumulators use against one document
+    fn eval(doc: &Document, expr: &Bson) -> Option<Bson> {
+        match expr {
+            Bson::String(path) if path.starts_with('$') => {
+                let mut value = Bson::Document(doc.clone());
+                for key in path[1..].split('.') {
+                    value = value.as_document()?.get(key)?.clone();
+                }
+                Some(value)
+            }
+            Bson::Document(op) => {
+                let [cond, then, otherwise] = op.get_array("$cond").ok()?.as_slice() else {
+                    return None;
+                };
+                let [lhs, rhs] = cond.as_document()?.get_array("$gt").ok()?.as_slice() else {
+                    return None;
+                };
+                let gt = number(&eval(doc, lhs)?) > number(&eval(doc, rhs)?);
+                eval(doc, if gt { then } else { otherwise })
+            }
+            literal => Some(literal.clone()),
+        }
+    }
+
+    /// Runs a `$match` on equal fields followed by a `$group` of `$sum` and
+    /// `$avg` accumulators over the stored form of `trades`, as MongoDB would
+    fn aggregate(pipeline: &[Document], trades: &[TradeData]) -> Document {
+        let matched = pipeline[0].get_document("$match").unwrap();
+        let docs: Vec<Document> = trades
+            .iter()
+            .map(|trade| bson::to_document(trade).unwrap())
+            .filter(|doc| matched.iter().all(|(key, value)| doc.get(key) == Some(value)))
+            .collect();
+
+        let mut group = doc! {};
+        for (key, accumulator) in pipeline[1].get_document("$group").unwrap() {
+            if key == "_id" {
+                group.insert(key, accumulator.clone());
+                continue;
+            }
+            let (op, expr) = accumulator.as_document().unwrap().iter().next().unwrap();
+            let values: Vec<Bson> = docs.iter().filter_map(|doc| eval(doc, expr)).collect();
+            let total: f64 = values.iter().map(number).sum();
+            let value = match op.as_str() {
+                "$sum" if values.iter().any(|v| matches!(v, Bson::Double(_))) => Bson::Double(total),
+                "$sum" if values.iter().any(|v| matches!(v, Bson::Int64(_))) => Bson::Int64(total as i64),
+                "$sum" => Bson::Int32(total as i32),
+                "$avg" if values.is_empty() => Bson::Null,
+                "$avg" => Bson::Double(total / values.len() as f64),
+                other => panic!("unsupported accumulator {}", other),
+            };
+            group.insert(key, value);
+        }
+        group
+    }
+
+    /// Applies the `created_at` sort and the limit of `options` to `trades`,
+    /// as a find over them would
+    fn find(options: &FindOptions, trades: &[TradeData]) -> Vec<TradeData> {
+        let sort = options.sort.as_ref().unwrap();
+        assert_eq!(sort.len(), 1);
+        let direction = sort.get_i32("created_at").unwrap();
+
+        let mut found = trades.to_vec();
+        found.sort_by_key(|trade| trade.created_at.timestamp_millis() * direction as i64);
+        if let Some(limit) = options.limit {
+            found.truncate(limit as usize);
+        }
+        found
+    }
+
+    fn trade(user_id: &str, profit_sol: f64) -> TradeData {
+        TradeData::new(
+            user_id.to_string(),
+            profit_sol,
+            5_000,
+            0.000005,
+            profit_sol * 100.0,
+            10_000,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn recent_trades_are_the_newest_limit_newest_first() {
+        let options = newest_first(Some(20));
+        assert_eq!(options.sort, Some(doc! { "created_at": -1 }));
+        assert_eq!(options.limit, Some(20));
+        assert_eq!(newest_first(None).limit, None);
+
+        // 100 trades a second apart, seeded out of order
+        let trades: Vec<TradeData> = (0..100_i64)
+            .map(|i| (i * 37) % 100)
+            .map(|i| TradeData {
+                created_at: bson::DateTime::from_millis(1_000 * i),
+                ..trade("user", 0.0)
+            })
+            .collect();
+
+        let recent = find(&options, &trades);
+        assert_eq!(recent.len(), 20);
+        let created: Vec<i64> = recent.iter().map(|t| t.created_at.timestamp_millis()).collect();
+        let expected: Vec<i64> = (80..100).rev().map(|i| 1_000 * i).collect();
+        assert_eq!(created, expected);
+
+        assert_eq!(find(&newest_first(None), &trades).len(), 100);
+    }
+
+    #[test]
+    fn seeded_trades_give_the_win_rate() {
+        // A break-even trade isn't a win
+        let trades = vec![
+            trade("user", 0.2),
+            trade("user", -0.05),
+            trade("user", 0.1),
+            trade("user", 0.0),
+            trade("other", 0.5),
+        ];
+
+        let stats = stats_from_group(&aggregate(&stats_pipeline(Some("user"), None, None), &trades));
+        assert_eq!(stats.total_trades, 4);
+        assert_eq!(stats.profitable_trades, 2);
+        assert_eq!(stats.win_rate, 50.0);
+        assert!((stats.total_profit - 0.25).abs() < 1e-12);
+
+        let global = stats_from_group(&aggregate(&stats_pipeline(None, None, None), &trades));
+        assert_eq!(global.total_trades, 5);
+        assert_eq!(global.win_rate, 60.0);
+
+        let none = stats_from_group(&aggregate(&stats_pipeline(Some("nobody"), None, None), &trades));
+        assert_eq!(none.total_trades, 0);
+        assert_eq!(none.win_rate, 0.0);
+    }
+
+    #[test]
+    fn tag_filter_narrows_user_query() {
+        assert_eq!(trade_filter(None, None), doc! {});
+        assert_eq!(trade_filter(Some("user"), None), doc! { "user_id": "user" });
+        assert_eq!(
+            trade_filter(Some("user"), Some("momentum")),
+            doc! { "user_id": "user", "tag": "momentum" }
+        );
+    }
+
+    #[test]
+    fn leaderboard_ranks_by_requested_metric_and_skips_hidden_users() {
+        let hidden = vec!["private-user".to_string()];
+        let pipeline = leaderboard_pipeline(1_000, &hidden, LeaderboardSort::WinRate, 20, 10);
+
+        let matched = pipeline[0].get_document("$match").unwrap();
+        assert_eq!(
+            matched.get_document("user_id").unwrap(),
+            &doc! { "$nin": ["private-user"] }
+        );
+        assert_eq!(
+            matched.get_document("created_at").unwrap(),
+            &doc! { "$gte": bson::DateTime::from_millis(1_000) }
+        );
+        assert_eq!(pipeline[3], doc! { "$sort": { "win_rate": -1, "_id": 1 } });
+        assert_eq!(pipeline[4], doc! { "$skip": 20_i64 });
+        assert_eq!(pipeline[5], doc! { "$limit": 10_i64 });
+
+        let by_pnl = leaderboard_pipeline(1_000, &[], LeaderboardSort::Pnl, 0, 10);
+        assert_eq!(by_pnl[3], doc! { "$sort": { "total_profit": -1, "_id": 1 } });
+    }
+
+    #[test]
+    fn report_covers_the_period_and_its_extremes() {
+        let filter = period_filter(trade_filter(Some("user"), None), Some(1_000), Some(5_000));
+        assert_eq!(
+            filter,
+            doc! {
+                "user_id": "user",
+                "created_at": {
+                    "$gte": bson::DateTime::from_millis(1_000),
+                    "$lte": bson::DateTime::from_millis(5_000),
+                },
+            }
+        );
+        assert_eq!(period_filter(doc! {}, None, None), doc! {});
+
+        let group = report_group();
+        assert_eq!(group.get_document("best_trade_sol").unwrap(), &doc! { "$max": "$profit_sol" });
+        assert!(group.contains_key("tip_lamports"));
+
+        // Trades of +0.2, -0.05 and +0.1 SOL held 10s, 20s and 30s
+        let fixture = doc! {
+            "_id": bson::Bson::Null,
+            "total_trades": 3,
+            "total_profit": 0.25,
+            "avg_roi": 5.0,
+            "profitable_trades": 2,
+            "tip_lamports": 30_000,
+            "best_trade_sol": 0.2,
+            "worst_trade_sol": -0.05,
+            "avg_hold_ms": 20_000.0,
+        };
+        let report = report_from_group(&fixture, Some(1_000), None);
+        assert_eq!(report.stats.total_trades, 3);
+        assert!((report.stats.win_rate - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.stats.fee_breakdown.tip_lamports, 30_000);
+        assert_eq!(report.best_trade_sol, Some(0.2));
+        assert_eq!(report.worst_trade_sol, Some(-0.05));
+        assert_eq!(report.avg_hold_ms, 20_000.0);
+
+        let empty = report_from_group(&doc! {}, None, None);
+        assert_eq!(empty.stats.total_trades, 0);
+        assert_eq!(empty.best_trade_sol, None);
+    }
+
+    #[test]
+    fn per_tag_group_is_read_into_stats() {
+        let group = doc! {
+            "_id": "dip",
+            "total_trades": 4,
+            "total_profit": 0.3,
+            "avg_roi": 7.5,
+            "profitable_trades": 3,
+            "lp_fee_lamports": 12_000_i64,
+            "network_fee_lamports": 20_000,
+        };
+
+        let stats = stats_from_group(&group);
+        assert_eq!(stats.total_trades, 4);
+        assert_eq!(stats.fee_breakdown.lp_fee_lamports, 12_000);
+        assert_eq!(stats.fee_breakdown.network_fee_lamports, 20_000);
+        assert_eq!(stats.fee_breakdown.tip_lamports, 0);
+        assert_eq!(stats.profitable_trades, 3);
+        assert_eq!(stats.win_rate, 75.0);
+
+        let empty = stats_from_group(&doc! { "_id": "momentum" });
+        assert_eq!(empty.total_trades, 0);
+        assert_eq!(empty.win_rate, 0.0);
+    }
 }
@@ -2,7 +2,11 @@ use bson::{doc, oid::ObjectId};
 use mongodb::{Collection, Database};
 use anyhow::Result;
 
-use crate::backend::models::user::User;
+use crate::backend::{
+    crypto::{wallet_cipher, WalletCipher, ENCRYPTED_PREFIX},
+    error::DocumentNotFound,
+    models::user::User,
+};
 
 pub struct UserRepository {
     collection: Collection<User>,
@@ -15,7 +19,11 @@ impl UserRepository {
         }
     }
 
+    /// Stores the user with its private key encrypted, see `WalletCipher`
     pub async fn create(&self, mut user: User) -> Result<User> {
+        if !WalletCipher::is_encrypted(&user.private_key) {
+            user.private_key = wallet_cipher()?.encrypt(&user.private_key)?;
+        }
         user.id = Some(ObjectId::new());
         user.created_at = bson::DateTime::now();
         user.updated_at = bson::DateTime::now();
@@ -47,16 +55,56 @@ impl UserRepository {
         Ok(user)
     }
 
+    /// Ids of users who opted out of the leaderboard
+    pub async fn find_hidden_from_leaderboard(&self) -> Result<Vec<String>> {
+        let filter = doc! { "hide_from_leaderboard": true };
+        let mut cursor = self.collection.find(filter).await?;
+
+        let mut ids = Vec::new();
+        while cursor.advance().await? {
+            let user = cursor.deserialize_current()?;
+            if let Some(id) = user.id {
+                ids.push(id.to_hex());
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Encrypts private keys still stored in plaintext, returning how many were
+    pub async fn encrypt_plaintext_keys(&self, cipher: &WalletCipher) -> Result<usize> {
+        let filter = doc! { "private_key": { "$not": { "$regex": format!("^{}", ENCRYPTED_PREFIX) } } };
+        let mut cursor = self.collection.find(filter).await?;
+
+        let mut encrypted = 0;
+        while cursor.advance().await? {
+            let user = cursor.deserialize_current()?;
+            let update = doc! { "$set": { "private_key": cipher.encrypt(&user.private_key)? } };
+            // Only replace the key that was read, a concurrent write wins
+            let filter = doc! { "_id": user.id, "private_key": &user.private_key };
+            if self.collection.update_one(filter, update).await?.modified_count > 0 {
+                encrypted += 1;
+            }
+        }
+
+        Ok(encrypted)
+    }
+
     pub async fn update(&self, user: &User) -> Result<()> {
         let filter = doc! { "_id": user.id };
         let update = doc! { "$set": {
             "email": &user.email,
             "username": &user.username,
             "password_hash": &user.password_hash,
+            "hide_from_leaderboard": user.hide_from_leaderboard,
+            "co_signer_id": &user.co_signer_id,
             "updated_at": bson::DateTime::now()
         }};
         
-        self.collection.update_one(filter, update).await?;
+        let result = self.collection.update_one(filter, update).await?;
+        if result.matched_count == 0 {
+            return Err(DocumentNotFound("User".to_string()).into());
+        }
         
         Ok(())
     }
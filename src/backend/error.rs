@@ -9,7 +9,7 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
-    Database(#[from] anyhow::Error),
+    Database(anyhow::Error),
     
     #[error("Authentication error: {0}")]
     Auth(String),
@@ -52,6 +52,21 @@ impl IntoResponse for AppError {
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// A repository's lookup or write matched no document. Repositories return
+/// it inside their `anyhow` errors so it still reaches the client as a 404.
+#[derive(Error, Debug)]
+#[error("{0} not found")]
+pub struct DocumentNotFound(pub String);
+
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<DocumentNotFound>() {
+            Ok(missing) => AppError::NotFound(missing.to_string()),
+            Err(error) => AppError::Database(error),
+        }
+    }
+}
+
 // Helper functions for common error cases
 impl AppError {
     pub fn auth(message: impl Into<String>) -> Self {
@@ -74,3 +89,22 @@ impl AppError {
         AppError::Internal(message.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_missing_document_is_a_404_not_a_500() {
+        let missing = AppError::from(anyhow::Error::new(DocumentNotFound("Bot".to_string())));
+        assert!(matches!(&missing, AppError::NotFound(msg) if msg == "Bot not found"));
+        assert_eq!(missing.into_response().status(), StatusCode::NOT_FOUND);
+
+        // Context added on the way up doesn't hide it
+        let wrapped = anyhow::Error::new(DocumentNotFound("User".to_string())).context("Failed to update user");
+        assert_eq!(AppError::from(wrapped).into_response().status(), StatusCode::NOT_FOUND);
+
+        let failed = AppError::from(anyhow::anyhow!("connection reset"));
+        assert_eq!(failed.into_response().status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,61 @@
+use axum::{extract::State, http::HeaderMap, response::Json};
+
+use crate::backend::{
+    auth::jwt_service::extract_token_from_header,
+    auth::jwt_service::JwtService,
+    db::connection::AppDatabase,
+    error::{AppError, AppResult},
+    services::admin_service::{AdminService, ReconcileReport, RepairResult},
+};
+use crate::utils::self_test::{self, ServiceCheck};
+
+/// Reject tokens without the admin claim
+pub(crate) async fn require_admin(headers: &HeaderMap) -> AppResult<String> {
+    let auth_header = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::auth("Authorization header is required"))?;
+
+    let token = extract_token_from_header(auth_header)
+        .ok_or_else(|| AppError::auth("Token must be in format: Bearer <token>"))?;
+
+    let jwt_service = JwtService::new()?;
+    let claims = jwt_service.verify_token(token)?;
+    if !claims.admin {
+        return Err(AppError::auth("Admin access required"));
+    }
+
+    Ok(claims.sub)
+}
+
+pub async fn get_reconcile(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<ReconcileReport>> {
+    require_admin(&headers).await?;
+
+    let admin_service = AdminService::new(database);
+    let report = admin_service.reconcile_report().await?;
+
+    Ok(Json(report))
+}
+
+pub async fn repair_reconcile(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<RepairResult>> {
+    let admin_id = require_admin(&headers).await?;
+    println!("🩺 Reconcile repair requested by {}", admin_id);
+
+    let admin_service = AdminService::new(database);
+    let result = admin_service.repair().await?;
+
+    Ok(Json(result))
+}
+
+/// Re-runs the submission service self-test shown on the health endpoint
+pub async fn run_self_test(headers: HeaderMap) -> AppResult<Json<Vec<ServiceCheck>>> {
+    require_admin(&headers).await?;
+
+    Ok(Json(self_test::run_self_test().await))
+}
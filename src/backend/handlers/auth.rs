@@ -4,7 +4,7 @@ use validator::Validate;
 use crate::backend::{
     db::connection::AppDatabase,
     error::{AppError, AppResult},
-    models::auth::{AuthResponse, SigninRequest, SignupRequest},
+    models::auth::{AuthResponse, RefreshRequest, SigninRequest, SignupRequest, TokenResponse},
     models::user::UserResponse,
     services::user_service::UserService,
     services::auth_service::AuthService,
@@ -19,7 +19,7 @@ pub async fn signup(
         .validate()
         .map_err(|e| AppError::validation(format!("Validation failed: {:?}", e)))?;
 
-    let auth_service = AuthService::new(database);
+    let auth_service = AuthService::new(database)?;
     let response = auth_service.signup(payload).await?;
 
     Ok(Json(response))
@@ -35,7 +35,7 @@ pub async fn signin(
         .map_err(|e| AppError::validation(format!("Validation failed: {:?}", e)))?;
 
     // Use real authentication service
-    let auth_service = AuthService::new(database);
+    let auth_service = AuthService::new(database)?;
     
     let response = match auth_service.signin(payload).await {
         Ok(resp) => {
@@ -49,11 +49,21 @@ pub async fn signin(
     Ok(Json(response))
 }
 
+pub async fn refresh(
+    State(database): State<AppDatabase>,
+    Json(payload): Json<RefreshRequest>,
+) -> AppResult<Json<TokenResponse>> {
+    let auth_service = AuthService::new(database)?;
+    let response = auth_service.refresh(payload).await?;
+
+    Ok(Json(response))
+}
+
 pub async fn get_current_user(
     State(database): State<AppDatabase>,
     headers: HeaderMap,
 ) -> AppResult<Json<UserResponse>> {
-    let user_service = UserService::new(database);
+    let user_service = UserService::new(database)?;
     let user = user_service.get_current_user(headers).await?;
     Ok(Json(user))
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::HeaderMap,
     response::Json,
 };
@@ -11,9 +11,15 @@ use crate::backend::{
     auth::jwt_service::JwtService,
     db::connection::AppDatabase,
     error::{AppError, AppResult},
-    models::bot::BotSettingsResponse,
+    models::bot::{
+        BotPositionResponse, BotSettingsResponse, BotStatusResponse, BuyPreviewResponse,
+        DryRunReport, OpenPositionsResponse, PanicSellResponse, PreviewOverrides,
+        UpdateTradingParamsRequest,
+    },
     services::bot_service::BotService,
+    user_logs::{recent_user_logs, UserLogLine},
 };
+use crate::utils::tx_failures::{recent_failures, FailedTransaction};
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateBotRequest {
@@ -23,28 +29,6 @@ pub struct CreateBotRequest {
     pub pool_address: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
-pub struct UpdateTradingParamsRequest {
-    #[validate(length(min = 32, max = 44))]
-    pub pool_address: Option<String>,
-    #[validate(range(min = 0.0001, max = 1000.0))]
-    pub buy_sol_amount: Option<f64>,
-    #[validate(range(min = 0.1, max = 100000.0))]
-    pub buy_usd1_amount: Option<f64>,
-    #[validate(range(min = 0.0001, max = 100.0))]
-    pub entry_percent: Option<f64>,
-    #[validate(range(min = 0.1, max = 50.0))]
-    pub entry_slippage: Option<f64>,
-    #[validate(range(min = 0.1, max = 100.0))]
-    pub exit_slippage: Option<f64>,
-    #[validate(range(min = 0.0001, max = 100.0))]
-    pub stop_loss: Option<f64>,
-    #[validate(range(min = 0.0001, max = 1000.0))]
-    pub take_profit: Option<f64>,
-    #[validate(range(min = 0, max = 86400))]
-    pub auto_exit: Option<u64>,
-}
-
 #[derive(Debug, Deserialize, Validate)]
 pub struct UpdateMevConfigRequest {
     #[validate(length(min = 1, max = 20))]
@@ -57,8 +41,13 @@ pub struct UpdateMevConfigRequest {
     pub third_party_fee: Option<f64>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BotLogsQuery {
+    pub limit: Option<usize>,
+}
+
 /// Extract user ID from JWT token
-async fn get_user_id_from_token(headers: &HeaderMap) -> AppResult<String> {
+pub(crate) async fn get_user_id_from_token(headers: &HeaderMap) -> AppResult<String> {
     let auth_header = headers
         .get("authorization")
         .and_then(|h| h.to_str().ok())
@@ -67,7 +56,7 @@ async fn get_user_id_from_token(headers: &HeaderMap) -> AppResult<String> {
     let token = extract_token_from_header(auth_header)
         .ok_or_else(|| AppError::auth("Token must be in format: Bearer <token>"))?;
 
-    let jwt_service = JwtService::new();
+    let jwt_service = JwtService::new()?;
     let claims = jwt_service.verify_token(token)?;
 
     Ok(claims.sub)
@@ -116,18 +105,7 @@ pub async fn update_trading_params(
     let user_id = get_user_id_from_token(&headers).await?;
 
     let bot_service = BotService::new(database);
-    let bot = bot_service.update_trading_params(
-        &user_id,
-        payload.pool_address,
-        payload.buy_sol_amount,
-        payload.buy_usd1_amount,
-        payload.entry_percent,
-        payload.entry_slippage,
-        payload.exit_slippage,
-        payload.stop_loss,
-        payload.take_profit,
-        payload.auto_exit,
-    ).await?;
+    let bot = bot_service.update_trading_params(&user_id, payload).await?;
 
     Ok(Json(bot))
 }
@@ -174,4 +152,98 @@ pub async fn stop_bot(
     let bot_service = BotService::new(database);
     let bot = bot_service.stop_bot(&user_id).await?;
     Ok(Json(bot))
-}
\ No newline at end of file
+}
+
+pub async fn panic_sell(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<PanicSellResponse>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let bot_service = BotService::new(database);
+    let response = bot_service.panic_sell(&user_id).await?;
+    Ok(Json(response))
+}
+
+pub async fn get_bot_status(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<BotStatusResponse>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let bot_service = BotService::new(database);
+    let status = bot_service.get_status(&user_id).await?;
+    Ok(Json(status))
+}
+
+pub async fn get_bot_position(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<BotPositionResponse>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let bot_service = BotService::new(database);
+    let position = bot_service.get_position(&user_id).await?;
+    Ok(Json(position))
+}
+
+pub async fn get_open_positions(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<OpenPositionsResponse>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let bot_service = BotService::new(database);
+    let positions = bot_service.get_open_positions(&user_id).await?;
+    Ok(Json(positions))
+}
+
+pub async fn preview_buy(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+    Query(overrides): Query<PreviewOverrides>,
+) -> AppResult<Json<BuyPreviewResponse>> {
+    overrides.validate()
+        .map_err(|e: validator::ValidationErrors| AppError::validation(format!("Validation failed: {:?}", e)))?;
+
+    let user_id = get_user_id_from_token(&headers).await?;
+    let bot_service = BotService::new(database);
+    let preview = bot_service.preview_buy(&user_id, overrides).await?;
+    Ok(Json(preview))
+}
+
+pub async fn dry_run_bot(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<DryRunReport>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let bot_service = BotService::new(database);
+    let report = bot_service.dry_run(&user_id).await?;
+    Ok(Json(report))
+}
+
+pub async fn get_bot_logs(
+    headers: HeaderMap,
+    Query(query): Query<BotLogsQuery>,
+) -> AppResult<Json<Vec<UserLogLine>>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let limit = query.limit.unwrap_or(100);
+
+    Ok(Json(recent_user_logs(&user_id, limit)))
+}
+
+pub async fn resume_bot(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<BotSettingsResponse>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let bot_service = BotService::new(database);
+    let bot = bot_service.resume_bot(&user_id).await?;
+    Ok(Json(bot))
+}
+
+pub async fn get_bot_failures(
+    headers: HeaderMap,
+    Query(query): Query<BotLogsQuery>,
+) -> AppResult<Json<Vec<FailedTransaction>>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    let limit = query.limit.unwrap_or(20);
+
+    Ok(Json(recent_failures(&user_id, limit)))
+}
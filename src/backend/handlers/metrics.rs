@@ -0,0 +1,60 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::backend::{
+    auth::jwt_service::extract_token_from_header, db::connection::AppDatabase,
+    services::metrics_service::MetricsService,
+};
+
+/// Prometheus scrape endpoint, served only when `METRICS_TOKEN` is set and
+/// the scraper sends it as a bearer token, since the labels expose per-user PnL.
+pub async fn get_metrics(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    authorize_scrape(std::env::var("METRICS_TOKEN").ok().as_deref(), &headers)?;
+
+    let metrics_service = MetricsService::new(database);
+    match metrics_service.render().await {
+        Ok(body) => Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)),
+        Err(e) => {
+            eprintln!("Failed to render metrics: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Without a configured token the endpoint doesn't exist, with one the
+/// request must carry it
+fn authorize_scrape(expected: Option<&str>, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let expected = expected.filter(|token| !token.is_empty()).ok_or(StatusCode::NOT_FOUND)?;
+    let provided = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(extract_token_from_header);
+    if provided != Some(expected) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_are_hidden_unless_a_token_is_configured_and_sent() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(authorize_scrape(None, &headers), Err(StatusCode::NOT_FOUND));
+        assert_eq!(authorize_scrape(Some(""), &headers), Err(StatusCode::NOT_FOUND));
+        assert_eq!(authorize_scrape(Some("scrape"), &headers), Err(StatusCode::UNAUTHORIZED));
+
+        headers.insert("authorization", "Bearer wrong".parse().unwrap());
+        assert_eq!(authorize_scrape(Some("scrape"), &headers), Err(StatusCode::UNAUTHORIZED));
+        headers.insert("authorization", "Bearer scrape".parse().unwrap());
+        assert_eq!(authorize_scrape(Some("scrape"), &headers), Ok(()));
+    }
+}
@@ -2,3 +2,6 @@ pub mod auth;
 pub mod users;
 pub mod bot;
 pub mod trade;
+pub mod metrics;
+pub mod admin;
+pub mod pool;
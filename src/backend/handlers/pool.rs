@@ -0,0 +1,17 @@
+use axum::{extract::Path, http::HeaderMap, response::Json};
+
+use crate::backend::{
+    error::AppResult,
+    handlers::bot::get_user_id_from_token,
+    models::pool_price::PoolPriceResponse,
+    services::pool_service,
+};
+
+pub async fn get_pool_price(
+    headers: HeaderMap,
+    Path(pool_address): Path<String>,
+) -> AppResult<Json<PoolPriceResponse>> {
+    get_user_id_from_token(&headers).await?;
+    let price = pool_service::pool_price(&pool_address).await?;
+    Ok(Json(price))
+}
@@ -1,13 +1,16 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Json,
 };
 use serde::Deserialize;
 
 use crate::backend::{
     db::connection::AppDatabase,
-    services::trade_service::TradeService,
+    db::trade_repository::{LeaderboardSort, PoolStats, TagStats, TradeReport, TradeStats},
+    error::{AppError, AppResult},
+    handlers::{admin::require_admin, bot::get_user_id_from_token},
+    services::trade_service::{LeaderboardEntry, TradeService},
 };
 
 #[derive(Debug, Deserialize)]
@@ -15,6 +18,28 @@ pub struct TradeQuery {
     pub limit: Option<i64>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    pub sort: LeaderboardSort,
+    /// Look-back period in days, zero for all time
+    pub days: Option<u64>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserTradesQuery {
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    /// Period start and end as unix milliseconds, all time when omitted
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
 pub async fn get_trade_data(
     State(database): State<AppDatabase>,
     Query(query): Query<TradeQuery>,
@@ -35,6 +60,7 @@ pub async fn get_trade_data(
 pub async fn get_user_trades(
     State(database): State<AppDatabase>,
     Path(user_id): Path<String>,
+    Query(query): Query<UserTradesQuery>,
 ) -> Result<Json<Vec<crate::backend::models::trade::TradeDataResponse>>, StatusCode> {
     let trade_service = TradeService::new(database);
     
@@ -43,15 +69,98 @@ pub async fn get_user_trades(
         return Err(StatusCode::BAD_REQUEST);
     }
     
-    match trade_service.get_user_trades(&user_id).await {
-        Ok(mut trades) => {
-            // Sort by created_at descending (most recent first) and limit results
-            trades.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-            Ok(Json(trades))
-        },
+    match trade_service.get_user_trades(&user_id, query.tag.as_deref()).await {
+        Ok(trades) => Ok(Json(trades)),
         Err(e) => {
             eprintln!("Failed to get user trades: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
+
+pub async fn get_trade_stats(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<TradeStats>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+
+    let trade_service = TradeService::new(database);
+    let stats = trade_service.get_stats(Some(&user_id)).await?;
+
+    Ok(Json(stats))
+}
+
+pub async fn get_global_trade_stats(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<TradeStats>> {
+    require_admin(&headers).await?;
+
+    let trade_service = TradeService::new(database);
+    let stats = trade_service.get_stats(None).await?;
+
+    Ok(Json(stats))
+}
+
+pub async fn get_tag_stats(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<TagStats>>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+
+    let trade_service = TradeService::new(database);
+    let stats = trade_service.get_tag_stats(&user_id).await?;
+
+    Ok(Json(stats))
+}
+
+pub async fn get_pool_stats(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<PoolStats>>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+
+    let trade_service = TradeService::new(database);
+    let stats = trade_service.get_pool_stats(&user_id).await?;
+
+    Ok(Json(stats))
+}
+
+pub async fn get_leaderboard(
+    State(database): State<AppDatabase>,
+    Query(query): Query<LeaderboardQuery>,
+) -> AppResult<Json<Vec<LeaderboardEntry>>> {
+    if !*crate::config::LEADERBOARD_ENABLED {
+        return Err(AppError::not_found("Leaderboard is disabled"));
+    }
+
+    let trade_service = TradeService::new(database);
+    let entries = trade_service
+        .get_leaderboard(
+            query.sort,
+            query.days.unwrap_or(30),
+            query.page.unwrap_or(1),
+            query.per_page.unwrap_or(20).clamp(1, 100),
+        )
+        .await?;
+
+    Ok(Json(entries))
+}
+
+pub async fn get_trade_report(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+    Query(query): Query<ReportQuery>,
+) -> AppResult<Json<TradeReport>> {
+    let user_id = get_user_id_from_token(&headers).await?;
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(AppError::validation("`from` must not be after `to`"));
+        }
+    }
+
+    let trade_service = TradeService::new(database);
+    let report = trade_service.get_report(&user_id, query.from, query.to).await?;
+
+    Ok(Json(report))
+}
@@ -1,22 +1,82 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::HeaderMap,
     response::Json,
 };
 
+use serde::Deserialize;
+
 use crate::backend::{
     db::connection::AppDatabase,
     error::AppResult,
-    models::user::UserResponse,
-    services::user_service::UserService,
+    models::user::{UpdateCoSignerRequest, UpdatePrivacyRequest, UserResponse},
+    services::{
+        approval_service::{PendingApproval, SensitiveOperation},
+        user_service::UserService,
+    },
 };
 
 pub async fn get_current_user(
     State(database): State<AppDatabase>,
     headers: HeaderMap,
 ) -> AppResult<Json<UserResponse>> {
-    let user_service = UserService::new(database);
+    let user_service = UserService::new(database)?;
     let user = user_service.get_current_user(headers).await?;
     
     Ok(Json(user))
 }
+
+pub async fn update_privacy(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdatePrivacyRequest>,
+) -> AppResult<Json<UserResponse>> {
+    let user_service = UserService::new(database)?;
+    let user = user_service
+        .update_privacy(headers, payload.hide_from_leaderboard)
+        .await?;
+
+    Ok(Json(user))
+}
+
+pub async fn update_co_signer(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateCoSignerRequest>,
+) -> AppResult<Json<UserResponse>> {
+    let user_service = UserService::new(database)?;
+    let user = user_service
+        .update_co_signer(headers, payload.co_signer_id, payload.approval_id)
+        .await?;
+
+    Ok(Json(user))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalRequest {
+    pub operation: SensitiveOperation,
+}
+
+pub async fn request_approval(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+    Json(payload): Json<ApprovalRequest>,
+) -> AppResult<Json<PendingApproval>> {
+    let user_service = UserService::new(database)?;
+    let approval = user_service
+        .request_approval(headers, payload.operation)
+        .await?;
+
+    Ok(Json(approval))
+}
+
+pub async fn approve(
+    State(database): State<AppDatabase>,
+    headers: HeaderMap,
+    Path(approval_id): Path<String>,
+) -> AppResult<Json<PendingApproval>> {
+    let user_service = UserService::new(database)?;
+    let approval = user_service.approve(headers, &approval_id).await?;
+
+    Ok(Json(approval))
+}
@@ -5,7 +5,10 @@ pub mod auth;
 pub mod utils;
 pub mod server;
 pub mod config;
+pub mod crypto;
 pub mod error;
 pub mod app;
 pub mod routes;
-pub mod handlers;
\ No newline at end of file
+pub mod handlers;
+pub mod user_logs;
+pub mod throttle;
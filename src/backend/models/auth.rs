@@ -18,9 +18,16 @@ pub struct SigninRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     pub token: String,
+    /// Exchanged on `/auth/refresh` for a new `token`
+    pub refresh_token: String,
     pub user: crate::backend::models::user::UserResponse,
     pub bot: crate::backend::models::bot::BotSettingsResponse,
 }
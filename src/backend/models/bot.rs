@@ -1,6 +1,250 @@
 use bson::{DateTime, oid::ObjectId};
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, str::FromStr};
+use validator::{Validate, ValidationError};
+
+use crate::{
+    service::{tip_accounts, ZSlotRegionsType},
+    utils::price_history::PRICE_HISTORY_MAX_AGE_SECS,
+};
+
+/// Price the entry drop is measured against
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryReference {
+    /// Previous observed price
+    #[default]
+    LastPrice,
+    /// Average price over the last `entry_window_secs`
+    WindowAverage,
+    /// Highest price over the last `entry_window_secs`
+    SessionHigh,
+}
+
+/// Closed trades an auto-tuning pass needs before it adjusts `entry_percent`
+pub const AUTO_TUNE_MIN_TRADES: usize = 5;
+
+/// Relative change to `entry_percent` per auto-tuning pass
+const AUTO_TUNE_STEP: f64 = 0.1;
+
+fn default_entry_window_secs() -> u64 {
+    30
+}
+
+fn default_confirm_timeout_secs() -> u64 {
+    60
+}
+
+fn default_slippage_retry_step() -> f64 {
+    5.0
+}
+
+fn default_drain_window_secs() -> u64 {
+    30
+}
+
+fn default_drain_outflow_pct() -> f64 {
+    20.0
+}
+
+fn default_drain_min_sells() -> u32 {
+    3
+}
+
+fn default_sandwich_min_move_pct() -> f64 {
+    1.0
+}
+
+fn default_auto_tune_min_entry_percent() -> f64 {
+    0.01
+}
+
+fn default_auto_tune_max_entry_percent() -> f64 {
+    10.0
+}
+
+/// Market regime read from the pool's recent price path
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MarketRegime {
+    Trending,
+    Ranging,
+}
+
+/// Take-profit / stop-loss pair, both in percent
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ExitParams {
+    pub take_profit: f64,
+    pub stop_loss: f64,
+}
+
+/// Exit parameters chosen by regime. The price path over `window_secs` is
+/// trending when its efficiency ratio (net move / total movement) is at
+/// least `trend_threshold`, otherwise ranging.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RegimeExits {
+    pub trending: ExitParams,
+    pub ranging: ExitParams,
+    pub window_secs: u64,
+    pub trend_threshold: f64,
+}
+
+fn validate_zslot_region(region: &str) -> Result<(), ValidationError> {
+    if region.is_empty() || ZSlotRegionsType::from_name(region).is_some() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_zslot_region"))
+    }
+}
+
+/// Confirm services a swap can be raced across
+pub const RELAY_SERVICES: [&str; 3] = ["JITO", "NOZOMI", "ZERO_SLOT"];
+
+fn validate_multi_relay(relays: &[String]) -> Result<(), ValidationError> {
+    for (i, relay) in relays.iter().enumerate() {
+        if !RELAY_SERVICES.contains(&relay.as_str()) {
+            return Err(ValidationError::new("unknown_relay"));
+        }
+        if relays[..i].contains(relay) {
+            return Err(ValidationError::new("duplicate_relay"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_mint_list(mints: &[String]) -> Result<(), ValidationError> {
+    if mints.iter().all(|mint| Pubkey::from_str(mint).is_ok()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_mint"))
+    }
+}
+
+fn validate_regime_exits(regime_exits: &RegimeExits) -> Result<(), ValidationError> {
+    for params in [regime_exits.trending, regime_exits.ranging] {
+        // Same bounds as the bot-level take_profit / stop_loss
+        if !(0.0001..=1000.0).contains(&params.take_profit)
+            || !(0.0001..=100.0).contains(&params.stop_loss)
+        {
+            return Err(ValidationError::new("regime_exit_params_out_of_range"));
+        }
+    }
+    if regime_exits.window_secs == 0 || regime_exits.window_secs > PRICE_HISTORY_MAX_AGE_SECS {
+        return Err(ValidationError::new("regime_window_out_of_range"));
+    }
+    if !(0.0..=1.0).contains(&regime_exits.trend_threshold) {
+        return Err(ValidationError::new("trend_threshold_out_of_range"));
+    }
+    Ok(())
+}
+
+/// Tiers need a positive gain, a fraction in (0, 1] and fractions summing to at most 1
+fn validate_take_profit_tiers(tiers: &[(f64, f64)]) -> Result<(), ValidationError> {
+    if tiers.len() > 10 {
+        return Err(ValidationError::new("too_many_take_profit_tiers"));
+    }
+    for (gain_pct, fraction) in tiers {
+        if !(0.0001..=1000.0).contains(gain_pct) || *fraction <= 0.0 || *fraction > 1.0 {
+            return Err(ValidationError::new("take_profit_tier_out_of_range"));
+        }
+    }
+    if tiers.iter().map(|(_, fraction)| fraction).sum::<f64>() > 1.0 + 1e-9 {
+        return Err(ValidationError::new("take_profit_tiers_exceed_position"));
+    }
+    Ok(())
+}
+
+/// At most 10 levels, each deeper than the one before
+fn validate_dca_levels(levels: &[f64]) -> Result<(), ValidationError> {
+    if levels.len() > 10 {
+        return Err(ValidationError::new("too_many_dca_levels"));
+    }
+    if levels.iter().any(|level| !(0.0001..=100.0).contains(level)) {
+        return Err(ValidationError::new("dca_level_out_of_range"));
+    }
+    if levels.windows(2).any(|pair| pair[1] <= pair[0]) {
+        return Err(ValidationError::new("dca_levels_not_increasing"));
+    }
+    Ok(())
+}
+
+/// Same bounds as buy_sol_amount
+fn validate_dca_amounts(amounts: &[f64]) -> Result<(), ValidationError> {
+    if amounts.len() > 10 || amounts.iter().any(|amount| !(0.0001..=1000.0).contains(amount)) {
+        return Err(ValidationError::new("dca_amount_out_of_range"));
+    }
+    Ok(())
+}
+
+/// Lets a request tell an explicit `null` (clear) apart from a missing field
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Slippage override for a single pool, unset sides fall back to the bot setting
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct PoolSlippage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_slippage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_slippage: Option<f64>,
+}
+
+const HOUR_MS: i64 = 3_600_000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// Fees and tips a bot paid in the current clock hour and UTC day
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct FeeBudget {
+    /// Unix time (ms) the hour being counted started
+    pub hour_start: i64,
+    pub hour_spent_lamports: u64,
+    /// Unix time (ms) the day being counted started
+    pub day_start: i64,
+    pub day_spent_lamports: u64,
+}
+
+impl FeeBudget {
+    /// The budget as of `now_ms`, with a window that has passed started over
+    pub fn rolled(self, now_ms: i64) -> FeeBudget {
+        let hour_start = now_ms - now_ms.rem_euclid(HOUR_MS);
+        let day_start = now_ms - now_ms.rem_euclid(DAY_MS);
+        FeeBudget {
+            hour_start,
+            hour_spent_lamports: if self.hour_start == hour_start { self.hour_spent_lamports } else { 0 },
+            day_start,
+            day_spent_lamports: if self.day_start == day_start { self.day_spent_lamports } else { 0 },
+        }
+    }
+
+    /// Adds one confirmed swap's fees and tip
+    pub fn record(&mut self, lamports: u64, now_ms: i64) {
+        *self = self.rolled(now_ms);
+        self.hour_spent_lamports = self.hour_spent_lamports.saturating_add(lamports);
+        self.day_spent_lamports = self.day_spent_lamports.saturating_add(lamports);
+    }
+}
+
+fn validate_slippage_overrides(
+    overrides: &HashMap<String, PoolSlippage>,
+) -> Result<(), ValidationError> {
+    for (pool, slippage) in overrides {
+        if Pubkey::from_str(pool).is_err() {
+            return Err(ValidationError::new("invalid_pool_address"));
+        }
+        // Same bounds as the bot-level entry_slippage / exit_slippage
+        if matches!(slippage.entry_slippage, Some(v) if !(0.1..=500.0).contains(&v)) {
+            return Err(ValidationError::new("entry_slippage_out_of_range"));
+        }
+        if matches!(slippage.exit_slippage, Some(v) if !(0.1..=1000.0).contains(&v)) {
+            return Err(ValidationError::new("exit_slippage_out_of_range"));
+        }
+    }
+    Ok(())
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct BotSettings {
@@ -21,25 +265,209 @@ pub struct BotSettings {
     pub buy_usd1_amount: f64,
     #[validate(range(min = 0.0001, max = 100.0))]
     pub entry_percent: f64,
+    #[serde(default)]
+    pub entry_reference: EntryReference,
+    #[serde(default = "default_entry_window_secs")]
+    #[validate(range(min = 1, max = "PRICE_HISTORY_MAX_AGE_SECS"))]
+    pub entry_window_secs: u64,
+    /// Strategy tag stored on trades, empty uses the entry mode
+    #[serde(default)]
+    #[validate(length(max = 32))]
+    pub trade_tag: String,
+    #[serde(default)]
+    #[validate(range(max = 1000))]
+    pub min_observations_before_trading: u32,
+    /// Log entry/exit signals without building or submitting any transactions
+    #[serde(default)]
+    pub observer: bool,
+    /// The owner confirmed this bot may submit real transactions, see `LIVE_TRADING_ENABLED`
+    #[serde(default)]
+    pub live_trading_acknowledged: bool,
 
     #[validate(range(min = 0.1, max = 500.0))]
     pub entry_slippage: f64,
 
     #[validate(range(min = 0.1, max = 1000.0))]
     pub exit_slippage: f64,
+    /// Keep take-profit sells from filling below the buy cost plus fees
+    #[serde(default)]
+    pub min_out_cost_floor: bool,
+    /// Per-pool entry/exit slippage, keyed by pool address
+    #[serde(default)]
+    #[validate(custom = "validate_slippage_overrides")]
+    pub slippage_overrides: HashMap<String, PoolSlippage>,
 
     #[validate(range(min = 0.0001, max = 100.0))]
     pub stop_loss: f64,
+    /// Sell once price falls this % below the peak since entry, unset disables it
+    #[serde(default)]
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub trailing_stop_pct: Option<f64>,
+    /// Move the stop loss up to the slippage-adjusted break-even once the
+    /// position is this % in profit, unset disables it
+    #[serde(default)]
+    #[validate(range(min = 0.0001, max = 1000.0))]
+    pub move_stop_to_be_at_pct: Option<f64>,
+    /// How far above break-even (%) the moved stop sits, capped at
+    /// `move_stop_to_be_at_pct`
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub break_even_stop_offset_pct: f64,
+    /// Partial take-profit levels as (gain %, fraction of the position), empty sells it all at take_profit
+    #[serde(default)]
+    #[validate(custom = "validate_take_profit_tiers")]
+    pub take_profit_tiers: Vec<(f64, f64)>,
+    /// Further drops (%) below the first fill at which to scale in, in increasing order
+    #[serde(default)]
+    #[validate(custom = "validate_dca_levels")]
+    pub dca_levels: Vec<f64>,
+    /// SOL bought at each of dca_levels
+    #[serde(default)]
+    #[validate(custom = "validate_dca_amounts")]
+    pub dca_amounts: Vec<f64>,
     
     #[validate(range(min = 0.0001, max = 1000.0))]
     pub take_profit: f64,
+    /// Profit (%) a position must reach before its take-profit starts decaying, zero disables decay
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 1000.0))]
+    pub tp_decay_min_profit_pct: f64,
+    /// Seconds after buying for the take-profit to decay to break-even
+    #[serde(default)]
+    #[validate(range(max = 86400))]
+    pub tp_decay_secs: u64,
+    /// Decay take-profit to the slippage-adjusted break-even instead of 0%
+    #[serde(default)]
+    pub break_even_tp_floor: bool,
+    /// Suppress entries while the pool's quote reserve is being drained
+    #[serde(default)]
+    pub drain_detection: bool,
+    /// Window the quote reserve outflow is measured over
+    #[serde(default = "default_drain_window_secs")]
+    #[validate(range(min = 1, max = "PRICE_HISTORY_MAX_AGE_SECS"))]
+    pub drain_window_secs: u64,
+    /// Quote reserve drop (%) from the window high that counts as a drain
+    #[serde(default = "default_drain_outflow_pct")]
+    #[validate(range(min = 0.1, max = 100.0))]
+    pub drain_outflow_pct: f64,
+    /// Reserve-reducing swaps within the window needed to call it a drain
+    #[serde(default = "default_drain_min_sells")]
+    #[validate(range(max = 1000))]
+    pub drain_min_sells: u32,
+    /// Suppress entries while the pool shows recent sandwich round trips
+    #[serde(default)]
+    pub anti_sandwich: bool,
+    /// Quote reserve jump (%) from a single swap that counts as a sandwich leg
+    #[serde(default = "default_sandwich_min_move_pct")]
+    #[validate(range(min = 0.01, max = 100.0))]
+    pub sandwich_min_move_pct: f64,
+    /// Nudge entry_percent between the bounds below from recent trade outcomes
+    #[serde(default)]
+    pub auto_tune_entry: bool,
+    /// Lowest entry_percent auto-tuning may set
+    #[serde(default = "default_auto_tune_min_entry_percent")]
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub auto_tune_min_entry_percent: f64,
+    /// Highest entry_percent auto-tuning may set
+    #[serde(default = "default_auto_tune_max_entry_percent")]
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub auto_tune_max_entry_percent: f64,
+    /// Exit parameters per market regime, unset uses take_profit/stop_loss
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(custom = "validate_regime_exits")]
+    pub regime_exits: Option<RegimeExits>,
+    /// ZERO_SLOT region (NewYork, Frankfurt, AMS, LA, Tokyo), empty uses the fastest
+    #[serde(default)]
+    #[validate(custom = "validate_zslot_region")]
+    pub zslot_region: String,
+    /// Relay tip account index, unset uses the service default. Capped at Jito's 8 accounts
+    #[serde(default)]
+    #[validate(range(max = 7))]
+    pub tip_addr_idx: Option<u8>,
+    /// Relays (JITO, NOZOMI, ZERO_SLOT) all sent the same swap, each tipped
+    /// third_party_fee; empty uses confirm_service
+    #[serde(default)]
+    #[validate(custom = "validate_multi_relay")]
+    pub multi_relay: Vec<String>,
+    /// Mints the bot never enters
+    #[serde(default)]
+    #[validate(custom = "validate_mint_list")]
+    pub mint_blacklist: Vec<String>,
+    /// Mints the bot only enters when set, empty trades any mint
+    #[serde(default)]
+    #[validate(custom = "validate_mint_list")]
+    pub mint_whitelist: Vec<String>,
+    /// Refuse entries while the pool's SOL side holds less than this, zero disables it
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 1000000.0))]
+    pub min_pool_liquidity_sol: f64,
+    /// Refuse entries whose buy would fill this many percent worse than the
+    /// spot price along the pool curve, fee left out. Zero disables it
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub max_price_impact_pct: f64,
     
+    /// Seconds to hold a position before selling, zero disables the timeout
     #[validate(range(min = 0, max = 86400))]
     pub auto_exit: u64,
+    /// Seconds after a position closes before the bot may enter again, so a
+    /// stop loss isn't followed straight back into the same drop. Zero
+    /// disables it.
+    #[serde(default)]
+    #[validate(range(max = 86400))]
+    pub reentry_cooldown_secs: u64,
+    /// How long a submitted swap is polled for on-chain confirmation before
+    /// an unconfirmed entry is rolled back
+    #[serde(default = "default_confirm_timeout_secs")]
+    #[validate(range(min = 5, max = 300))]
+    pub confirm_timeout_secs: u64,
+    /// Entries landing more than this many slots after the swap that
+    /// signalled them count as lost races in the pool stats, zero disables it
+    #[serde(default)]
+    #[validate(range(max = 1000))]
+    pub slow_entry_slots: u64,
+    /// The same in milliseconds of block time, zero disables it
+    #[serde(default)]
+    #[validate(range(max = 600000))]
+    pub slow_entry_ms: u64,
+    /// Halt the bot after this many losing trades in a row, zero disables it
+    #[serde(default)]
+    #[validate(range(max = 1000))]
+    pub max_consecutive_losses: u32,
+    /// Refuse to start while the user holds this many bought positions, zero disables it
+    #[serde(default)]
+    #[validate(range(max = 1000))]
+    pub max_open_positions: u32,
+    /// Losing trades in a row, reset by a winning trade
+    #[serde(default)]
+    pub consecutive_losses: u32,
+    /// Set when the loss circuit breaker trips, cleared by a manual resume
+    #[serde(default)]
+    pub halted: bool,
+    /// Pause entries once fees and tips paid this clock hour reach this, zero disables it
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub max_fees_sol_per_hour: f64,
+    /// Pause entries once fees and tips paid this UTC day reach this, zero disables it
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 1000.0))]
+    pub max_fees_sol_per_day: f64,
+    /// What the hourly and daily fee caps count against
+    #[serde(default)]
+    pub fee_budget: FeeBudget,
+    /// Unix time (ms) of the last auto-tuning pass, only later trades count towards the next
+    #[serde(default)]
+    pub auto_tuned_at: Option<i64>,
+    /// Unix time (seconds) to start the bot automatically
+    #[serde(default)]
+    pub scheduled_start: Option<i64>,
+    /// Unix time (seconds) to stop the bot automatically
+    #[serde(default)]
+    pub scheduled_stop: Option<i64>,
 
     // MEV Service Configuration
     #[validate(length(min = 1, max = 20))]
-    pub confirm_service: String, // NOZOMI, JITO, ZSLOT
+    pub confirm_service: String, // NOZOMI, JITO, ZERO_SLOT
 
     // Priority Fee Configuration
     #[validate(range(min = 1, max = 1000000))]
@@ -48,6 +476,23 @@ pub struct BotSettings {
     pub priority_fee_micro_lamport: u64,
     #[validate(range(min = 0.0, max = 100.0))]
     pub third_party_fee: f64,
+    /// Refuse to submit when base fee + priority fee + tip would exceed this, zero disables it
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub max_tx_fee_sol: f64,
+    /// Simulate each swap first and submit it with a compute unit limit of
+    /// what it consumed plus headroom, instead of the static `cu`
+    #[serde(default)]
+    pub auto_cu: bool,
+    /// Times a swap whose simulation fails on slippage is widened by
+    /// `slippage_retry_step` and simulated again, zero disables it
+    #[serde(default)]
+    #[validate(range(max = 10))]
+    pub max_slippage_retries: u32,
+    /// Percentage points of slippage each retry adds
+    #[serde(default = "default_slippage_retry_step")]
+    #[validate(range(min = 0.1, max = 100.0))]
+    pub slippage_retry_step: f64,
 
     pub created_at: DateTime,
     pub updated_at: DateTime,
@@ -67,15 +512,67 @@ impl BotSettings {
             buy_sol_amount: 0.001,
             buy_usd1_amount: 0.1,
             entry_percent: 0.01,
+            entry_reference: EntryReference::default(),
+            entry_window_secs: default_entry_window_secs(),
+            trade_tag: String::new(),
+            min_observations_before_trading: 0,
+            observer: false,
+            live_trading_acknowledged: false,
             entry_slippage: 5.0,
             exit_slippage: 100.0,
+            min_out_cost_floor: false,
+            slippage_overrides: HashMap::new(),
             stop_loss: 0.01,
+            trailing_stop_pct: None,
+            move_stop_to_be_at_pct: None,
+            break_even_stop_offset_pct: 0.0,
+            take_profit_tiers: Vec::new(),
+            dca_levels: Vec::new(),
+            dca_amounts: Vec::new(),
             take_profit: 0.01,
+            tp_decay_min_profit_pct: 0.0,
+            tp_decay_secs: 0,
+            break_even_tp_floor: false,
+            drain_detection: false,
+            drain_window_secs: default_drain_window_secs(),
+            drain_outflow_pct: default_drain_outflow_pct(),
+            drain_min_sells: default_drain_min_sells(),
+            anti_sandwich: false,
+            sandwich_min_move_pct: default_sandwich_min_move_pct(),
+            auto_tune_entry: false,
+            auto_tune_min_entry_percent: default_auto_tune_min_entry_percent(),
+            auto_tune_max_entry_percent: default_auto_tune_max_entry_percent(),
+            regime_exits: None,
+            zslot_region: String::new(),
+            tip_addr_idx: None,
+            multi_relay: Vec::new(),
+            mint_blacklist: Vec::new(),
+            mint_whitelist: Vec::new(),
+            min_pool_liquidity_sol: 0.0,
+            max_price_impact_pct: 0.0,
             auto_exit: 3600,
+            reentry_cooldown_secs: 0,
+            confirm_timeout_secs: default_confirm_timeout_secs(),
+            slow_entry_slots: 0,
+            slow_entry_ms: 0,
+            max_consecutive_losses: 0,
+            max_open_positions: 0,
+            consecutive_losses: 0,
+            halted: false,
+            max_fees_sol_per_hour: 0.0,
+            max_fees_sol_per_day: 0.0,
+            fee_budget: FeeBudget::default(),
+            auto_tuned_at: None,
+            scheduled_start: None,
+            scheduled_stop: None,
             confirm_service: "JITO".to_string(),
             cu: 300000,
             priority_fee_micro_lamport: 20000,
             third_party_fee: 0.0001,
+            max_tx_fee_sol: 0.0,
+            auto_cu: false,
+            max_slippage_retries: 0,
+            slippage_retry_step: default_slippage_retry_step(),
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
         }
@@ -90,48 +587,367 @@ impl BotSettings {
         )
     }
 
-    pub fn update_trading_params(
-        &mut self,
-        pool_address: Option<String>,
-        buy_sol_amount: Option<f64>,
-        buy_usd1_amount: Option<f64>,
-        entry_percent: Option<f64>,
-        entry_slippage: Option<f64>,
-        exit_slippage: Option<f64>,
-        stop_loss: Option<f64>,
-        take_profit: Option<f64>,
-        auto_exit: Option<u64>,
-    ) {
-        if let Some(pool_address) = pool_address {
+    pub fn update_trading_params(&mut self, params: UpdateTradingParamsRequest) {
+        if let Some(pool_address) = params.pool_address {
             self.pool_address = pool_address;
         }
-        if let Some(amount) = buy_sol_amount {
+        if let Some(amount) = params.buy_sol_amount {
             self.buy_sol_amount = amount;
         }
-        if let Some(amount) = buy_usd1_amount {
+        if let Some(amount) = params.buy_usd1_amount {
             self.buy_usd1_amount = amount;
         }
-        if let Some(percent) = entry_percent {
+        if let Some(percent) = params.entry_percent {
             self.entry_percent = percent;
         }
-        if let Some(slippage) = entry_slippage {
+        if let Some(reference) = params.entry_reference {
+            self.entry_reference = reference;
+        }
+        if let Some(window) = params.entry_window_secs {
+            self.entry_window_secs = window;
+        }
+        if let Some(slippage) = params.entry_slippage {
             self.entry_slippage = slippage;
         }
-        if let Some(slippage) = exit_slippage {
+        if let Some(slippage) = params.exit_slippage {
             self.exit_slippage = slippage;
         }
-        if let Some(sl) = stop_loss {
+        if let Some(sl) = params.stop_loss {
             self.stop_loss = sl;
         }
-        if let Some(tp) = take_profit {
+        if let Some(tp) = params.take_profit {
             self.take_profit = tp;
         }
-        if let Some(ae) = auto_exit {
+        if let Some(ae) = params.auto_exit {
             self.auto_exit = ae;
         }
+        if let Some(value) = params.reentry_cooldown_secs {
+            self.reentry_cooldown_secs = value;
+        }
+        if let Some(timeout) = params.confirm_timeout_secs {
+            self.confirm_timeout_secs = timeout;
+        }
+        if let Some(value) = params.slow_entry_slots {
+            self.slow_entry_slots = value;
+        }
+        if let Some(value) = params.slow_entry_ms {
+            self.slow_entry_ms = value;
+        }
+        if let Some(value) = params.min_observations_before_trading {
+            self.min_observations_before_trading = value;
+        }
+        if let Some(value) = params.observer {
+            self.observer = value;
+        }
+        if let Some(value) = params.slippage_overrides {
+            self.slippage_overrides = value;
+        }
+        if let Some(value) = params.max_tx_fee_sol {
+            self.max_tx_fee_sol = value;
+        }
+        if let Some(value) = params.auto_cu {
+            self.auto_cu = value;
+        }
+        if let Some(value) = params.max_slippage_retries {
+            self.max_slippage_retries = value;
+        }
+        if let Some(value) = params.slippage_retry_step {
+            self.slippage_retry_step = value;
+        }
+        if let Some(value) = params.min_out_cost_floor {
+            self.min_out_cost_floor = value;
+        }
+        if let Some(value) = params.scheduled_start {
+            self.scheduled_start = (value > 0).then_some(value);
+        }
+        if let Some(value) = params.scheduled_stop {
+            self.scheduled_stop = (value > 0).then_some(value);
+        }
+        if let Some(value) = params.max_consecutive_losses {
+            self.max_consecutive_losses = value;
+        }
+        if let Some(value) = params.max_fees_sol_per_hour {
+            self.max_fees_sol_per_hour = value;
+        }
+        if let Some(value) = params.max_fees_sol_per_day {
+            self.max_fees_sol_per_day = value;
+        }
+        if let Some(value) = params.max_open_positions {
+            self.max_open_positions = value;
+        }
+        if let Some(value) = params.tp_decay_min_profit_pct {
+            self.tp_decay_min_profit_pct = value;
+        }
+        if let Some(value) = params.tp_decay_secs {
+            self.tp_decay_secs = value;
+        }
+        if let Some(value) = params.trade_tag {
+            self.trade_tag = value;
+        }
+        if let Some(value) = params.break_even_tp_floor {
+            self.break_even_tp_floor = value;
+        }
+        if let Some(value) = params.drain_detection {
+            self.drain_detection = value;
+        }
+        if let Some(value) = params.drain_window_secs {
+            self.drain_window_secs = value;
+        }
+        if let Some(value) = params.drain_outflow_pct {
+            self.drain_outflow_pct = value;
+        }
+        if let Some(value) = params.drain_min_sells {
+            self.drain_min_sells = value;
+        }
+        if let Some(value) = params.regime_exits {
+            self.regime_exits = value;
+        }
+        if let Some(value) = params.zslot_region {
+            self.zslot_region = value;
+        }
+        if let Some(value) = params.tip_addr_idx {
+            self.tip_addr_idx = value;
+        }
+        if let Some(value) = params.multi_relay {
+            self.multi_relay = value;
+        }
+        if let Some(value) = params.mint_blacklist {
+            self.mint_blacklist = value;
+        }
+        if let Some(value) = params.mint_whitelist {
+            self.mint_whitelist = value;
+        }
+        if let Some(value) = params.min_pool_liquidity_sol {
+            self.min_pool_liquidity_sol = value;
+        }
+        if let Some(value) = params.max_price_impact_pct {
+            self.max_price_impact_pct = value;
+        }
+        if let Some(value) = params.trailing_stop_pct {
+            self.trailing_stop_pct = value;
+        }
+        if let Some(value) = params.move_stop_to_be_at_pct {
+            self.move_stop_to_be_at_pct = value;
+        }
+        if let Some(value) = params.break_even_stop_offset_pct {
+            self.break_even_stop_offset_pct = value;
+        }
+        if let Some(value) = params.take_profit_tiers {
+            self.take_profit_tiers = value;
+        }
+        if let Some(value) = params.dca_levels {
+            self.dca_levels = value;
+        }
+        if let Some(value) = params.dca_amounts {
+            self.dca_amounts = value;
+        }
+        if let Some(value) = params.anti_sandwich {
+            self.anti_sandwich = value;
+        }
+        if let Some(value) = params.sandwich_min_move_pct {
+            self.sandwich_min_move_pct = value;
+        }
+        if let Some(value) = params.auto_tune_entry {
+            self.auto_tune_entry = value;
+        }
+        if let Some(value) = params.auto_tune_min_entry_percent {
+            self.auto_tune_min_entry_percent = value;
+        }
+        if let Some(value) = params.auto_tune_max_entry_percent {
+            self.auto_tune_max_entry_percent = value;
+        }
+        if let Some(value) = params.live_trading_acknowledged {
+            self.live_trading_acknowledged = value;
+        }
         self.updated_at = DateTime::now();
     }
 
+    /// Why entries are paused by the fee caps at `now_ms`, `None` while
+    /// `budget` leaves room. Exits are never held back by it.
+    pub fn fee_budget_exhausted(&self, budget: &FeeBudget, now_ms: i64) -> Option<String> {
+        let budget = budget.rolled(now_ms);
+        let lamports_per_sol = solana_sdk::native_token::LAMPORTS_PER_SOL as f64;
+        let hour_spent = budget.hour_spent_lamports as f64 / lamports_per_sol;
+        let day_spent = budget.day_spent_lamports as f64 / lamports_per_sol;
+        if self.max_fees_sol_per_hour > 0.0 && hour_spent >= self.max_fees_sol_per_hour {
+            return Some(format!(
+                "{:.6} SOL of fees this hour reached max_fees_sol_per_hour {:.6}",
+                hour_spent, self.max_fees_sol_per_hour
+            ));
+        }
+        if self.max_fees_sol_per_day > 0.0 && day_spent >= self.max_fees_sol_per_day {
+            return Some(format!(
+                "{:.6} SOL of fees today reached max_fees_sol_per_day {:.6}",
+                day_spent, self.max_fees_sol_per_day
+            ));
+        }
+        None
+    }
+
+    /// Count a closed trade towards the loss circuit breaker. Returns `true`
+    /// when this trade trips it and the bot should be halted.
+    pub fn record_trade_result(&mut self, profit_sol: f64) -> bool {
+        if profit_sol > 0.0 {
+            self.consecutive_losses = 0;
+        } else if profit_sol < 0.0 {
+            self.consecutive_losses += 1;
+        }
+
+        let tripped = self.max_consecutive_losses > 0
+            && !self.halted
+            && self.consecutive_losses >= self.max_consecutive_losses;
+        if tripped {
+            self.halted = true;
+        }
+        tripped
+    }
+
+    /// Nudge `entry_percent` from the profits of trades closed since the last
+    /// pass. Mostly losers means entries were too eager, so the bot waits for
+    /// a deeper dip; mostly winners lets it enter on shallower ones. Returns
+    /// the new value when it changed.
+    pub fn tune_entry_percent(&mut self, recent_profits: &[f64]) -> Option<f64> {
+        let losses = recent_profits.iter().filter(|p| **p < 0.0).count();
+        let wins = recent_profits.iter().filter(|p| **p > 0.0).count();
+        let decided = losses + wins;
+        if decided < AUTO_TUNE_MIN_TRADES {
+            return None;
+        }
+
+        let factor = if losses * 3 >= decided * 2 {
+            1.0 + AUTO_TUNE_STEP
+        } else if wins * 3 >= decided * 2 {
+            1.0 - AUTO_TUNE_STEP
+        } else {
+            return None;
+        };
+        let tuned = (self.entry_percent * factor)
+            .max(self.auto_tune_min_entry_percent)
+            .min(self.auto_tune_max_entry_percent);
+        if (tuned - self.entry_percent).abs() < f64::EPSILON {
+            return None;
+        }
+
+        self.entry_percent = tuned;
+        Some(tuned)
+    }
+
+    /// Entry slippage for `pool`, preferring its override over the bot setting
+    pub fn entry_slippage_for(&self, pool: &str) -> f64 {
+        self.slippage_overrides
+            .get(pool)
+            .and_then(|o| o.entry_slippage)
+            .unwrap_or(self.entry_slippage)
+    }
+
+    /// Buy amount and entry slippage a preview quotes with, the overrides
+    /// winning over the stored settings without changing them
+    pub fn preview_params(&self, overrides: &PreviewOverrides) -> (f64, f64) {
+        (
+            overrides.buy_sol_amount.unwrap_or(self.buy_sol_amount),
+            overrides
+                .slippage
+                .unwrap_or_else(|| self.entry_slippage_for(&self.pool_address)),
+        )
+    }
+
+    /// Exit slippage for `pool`, preferring its override over the bot setting
+    pub fn exit_slippage_for(&self, pool: &str) -> f64 {
+        self.slippage_overrides
+            .get(pool)
+            .and_then(|o| o.exit_slippage)
+            .unwrap_or(self.exit_slippage)
+    }
+
+    /// Exit parameters for `regime`, the plain take_profit/stop_loss when no
+    /// regime sets are configured
+    pub fn exit_params_for(&self, regime: Option<MarketRegime>) -> ExitParams {
+        match (self.regime_exits, regime) {
+            (Some(sets), Some(MarketRegime::Trending)) => sets.trending,
+            (Some(sets), Some(MarketRegime::Ranging)) => sets.ranging,
+            _ => ExitParams {
+                take_profit: self.take_profit,
+                stop_loss: self.stop_loss,
+            },
+        }
+    }
+
+    /// Real transactions go out only when the deployment enables live trading
+    /// and the owner acknowledged it for this bot, otherwise it paper trades
+    pub fn submits_live(&self, live_trading_enabled: bool) -> bool {
+        live_trading_enabled && self.live_trading_acknowledged && !self.observer
+    }
+
+    /// Tip account index for the confirm service, `service_default` unless overridden
+    pub fn tip_account_index(&self, service_default: u8) -> u8 {
+        self.tip_addr_idx.unwrap_or(service_default)
+    }
+
+    /// Checks `tip_addr_idx` against the tip accounts of every relay the bot
+    /// submits through: `multi_relay` when set, else `confirm_service`
+    pub fn validate_tip_addr_idx(&self) -> Result<(), String> {
+        let Some(idx) = self.tip_addr_idx else {
+            return Ok(());
+        };
+        let relays = if self.multi_relay.is_empty() {
+            std::slice::from_ref(&self.confirm_service)
+        } else {
+            self.multi_relay.as_slice()
+        };
+        for relay in relays {
+            if let Some(accounts) = tip_accounts(relay) {
+                if idx as usize >= accounts.len() {
+                    return Err(format!(
+                        "tip_addr_idx {} is out of range for {}, which has tip accounts 0..={}",
+                        idx,
+                        relay,
+                        accounts.len() - 1
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether entries may buy `mint`: never a blacklisted one, and only a
+    /// whitelisted one when a whitelist is set
+    pub fn mint_allowed(&self, mint: &str) -> bool {
+        !self.mint_blacklist.iter().any(|listed| listed == mint)
+            && (self.mint_whitelist.is_empty() || self.mint_whitelist.iter().any(|listed| listed == mint))
+    }
+
+    /// Whether a pool holding `sol_reserve` SOL on its WSOL side is deep
+    /// enough to enter
+    pub fn liquidity_sufficient(&self, sol_reserve: f64) -> bool {
+        self.min_pool_liquidity_sol <= 0.0 || sol_reserve >= self.min_pool_liquidity_sol
+    }
+
+    /// Whether a buy filling `impact_pct` worse than the spot price may be submitted
+    pub fn price_impact_acceptable(&self, impact_pct: f64) -> bool {
+        self.max_price_impact_pct <= 0.0 || impact_pct <= self.max_price_impact_pct
+    }
+
+    /// SOL to spend on a buy: the scale-in amount for a DCA level, else buy_sol_amount
+    pub fn dca_buy_sol_amount(&self, dca_level: Option<usize>) -> f64 {
+        dca_level
+            .and_then(|level| self.dca_amounts.get(level).copied())
+            .unwrap_or(self.buy_sol_amount)
+    }
+
+    /// Tag recorded on this bot's trades: the user's tag, else the entry mode
+    pub fn strategy_tag(&self) -> String {
+        let tag = self.trade_tag.trim();
+        if !tag.is_empty() {
+            return tag.to_string();
+        }
+        match self.entry_reference {
+            EntryReference::LastPrice => "last_price",
+            EntryReference::WindowAverage => "window_average",
+            EntryReference::SessionHigh => "session_high",
+        }
+        .to_string()
+    }
+
     pub fn update_mev_config(
         &mut self,
         confirm_service: Option<String>,
@@ -155,6 +971,226 @@ impl BotSettings {
     }
 }
 
+/// Settings a preview tries in place of the bot's stored ones
+#[derive(Debug, Deserialize, Validate, Default)]
+pub struct PreviewOverrides {
+    #[validate(range(min = 0.0001, max = 1000.0))]
+    pub buy_sol_amount: Option<f64>,
+    /// Entry slippage in percent
+    #[validate(range(min = 0.1, max = 500.0))]
+    pub slippage: Option<f64>,
+}
+
+/// What a buy would get against the pool's current reserves
+#[derive(Debug, Serialize, Clone)]
+pub struct BuyPreviewResponse {
+    pub pool_id: String,
+    pub buy_sol_amount: f64,
+    pub slippage: f64,
+    pub sol_reserve: u64,
+    pub token_reserve: u64,
+    pub expected_tokens_out: u64,
+    pub min_tokens_out: u64,
+}
+
+/// Outcome of one dry-run check
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DryRunCheck {
+    pub check: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl DryRunCheck {
+    pub fn pass(check: &str, detail: impl Into<String>) -> Self {
+        Self { check: check.to_string(), passed: true, detail: detail.into() }
+    }
+
+    pub fn fail(check: &str, detail: impl Into<String>) -> Self {
+        Self { check: check.to_string(), passed: false, detail: detail.into() }
+    }
+}
+
+/// Pre-flight of a bot's settings against the chain and its relays, nothing is submitted
+#[derive(Debug, Serialize, Clone)]
+pub struct DryRunReport {
+    pub pool_id: String,
+    /// Every check passed
+    pub ready: bool,
+    pub checks: Vec<DryRunCheck>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateTradingParamsRequest {
+    #[validate(length(min = 32, max = 44))]
+    pub pool_address: Option<String>,
+    #[validate(range(min = 0.0001, max = 1000.0))]
+    pub buy_sol_amount: Option<f64>,
+    #[validate(range(min = 0.1, max = 100000.0))]
+    pub buy_usd1_amount: Option<f64>,
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub entry_percent: Option<f64>,
+    pub entry_reference: Option<EntryReference>,
+    #[validate(range(min = 1, max = "PRICE_HISTORY_MAX_AGE_SECS"))]
+    pub entry_window_secs: Option<u64>,
+    #[validate(range(min = 0.1, max = 50.0))]
+    pub entry_slippage: Option<f64>,
+    #[validate(range(min = 0.1, max = 100.0))]
+    pub exit_slippage: Option<f64>,
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub stop_loss: Option<f64>,
+    #[validate(range(min = 0.0001, max = 1000.0))]
+    pub take_profit: Option<f64>,
+    #[validate(range(min = 0, max = 86400))]
+    pub auto_exit: Option<u64>,
+    #[validate(range(max = 86400))]
+    pub reentry_cooldown_secs: Option<u64>,
+    #[validate(range(min = 5, max = 300))]
+    pub confirm_timeout_secs: Option<u64>,
+    #[validate(range(max = 1000))]
+    pub slow_entry_slots: Option<u64>,
+    #[validate(range(max = 600000))]
+    pub slow_entry_ms: Option<u64>,
+    #[validate(range(max = 1000))]
+    pub min_observations_before_trading: Option<u32>,
+    pub observer: Option<bool>,
+    #[validate(custom = "validate_slippage_overrides")]
+    pub slippage_overrides: Option<HashMap<String, PoolSlippage>>,
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub max_tx_fee_sol: Option<f64>,
+    pub auto_cu: Option<bool>,
+    #[validate(range(max = 10))]
+    pub max_slippage_retries: Option<u32>,
+    #[validate(range(min = 0.1, max = 100.0))]
+    pub slippage_retry_step: Option<f64>,
+    pub min_out_cost_floor: Option<bool>,
+    /// Zero or negative clears the schedule
+    pub scheduled_start: Option<i64>,
+    /// Zero or negative clears the schedule
+    pub scheduled_stop: Option<i64>,
+    #[validate(range(max = 1000))]
+    pub max_consecutive_losses: Option<u32>,
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub max_fees_sol_per_hour: Option<f64>,
+    #[validate(range(min = 0.0, max = 1000.0))]
+    pub max_fees_sol_per_day: Option<f64>,
+    #[validate(range(max = 1000))]
+    pub max_open_positions: Option<u32>,
+    #[validate(range(min = 0.0, max = 1000.0))]
+    pub tp_decay_min_profit_pct: Option<f64>,
+    #[validate(range(max = 86400))]
+    pub tp_decay_secs: Option<u64>,
+    #[validate(length(max = 32))]
+    pub trade_tag: Option<String>,
+    pub break_even_tp_floor: Option<bool>,
+    pub drain_detection: Option<bool>,
+    #[validate(range(min = 1, max = "PRICE_HISTORY_MAX_AGE_SECS"))]
+    pub drain_window_secs: Option<u64>,
+    #[validate(range(min = 0.1, max = 100.0))]
+    pub drain_outflow_pct: Option<f64>,
+    #[validate(range(max = 1000))]
+    pub drain_min_sells: Option<u32>,
+    /// `null` clears the regime sets
+    #[serde(default, deserialize_with = "double_option")]
+    #[validate(custom = "validate_regime_exits")]
+    pub regime_exits: Option<Option<RegimeExits>>,
+    #[validate(custom = "validate_zslot_region")]
+    pub zslot_region: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    #[validate(range(max = 7))]
+    pub tip_addr_idx: Option<Option<u8>>,
+    #[validate(custom = "validate_multi_relay")]
+    pub multi_relay: Option<Vec<String>>,
+    #[validate(custom = "validate_mint_list")]
+    pub mint_blacklist: Option<Vec<String>>,
+    #[validate(custom = "validate_mint_list")]
+    pub mint_whitelist: Option<Vec<String>>,
+    #[validate(range(min = 0.0, max = 1000000.0))]
+    pub min_pool_liquidity_sol: Option<f64>,
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub max_price_impact_pct: Option<f64>,
+    #[serde(default, deserialize_with = "double_option")]
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub trailing_stop_pct: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "double_option")]
+    #[validate(range(min = 0.0001, max = 1000.0))]
+    pub move_stop_to_be_at_pct: Option<Option<f64>>,
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub break_even_stop_offset_pct: Option<f64>,
+    #[validate(custom = "validate_take_profit_tiers")]
+    pub take_profit_tiers: Option<Vec<(f64, f64)>>,
+    #[validate(custom = "validate_dca_levels")]
+    pub dca_levels: Option<Vec<f64>>,
+    #[validate(custom = "validate_dca_amounts")]
+    pub dca_amounts: Option<Vec<f64>>,
+    pub anti_sandwich: Option<bool>,
+    #[validate(range(min = 0.01, max = 100.0))]
+    pub sandwich_min_move_pct: Option<f64>,
+    pub auto_tune_entry: Option<bool>,
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub auto_tune_min_entry_percent: Option<f64>,
+    #[validate(range(min = 0.0001, max = 100.0))]
+    pub auto_tune_max_entry_percent: Option<f64>,
+    pub live_trading_acknowledged: Option<bool>,
+}
+
+/// Live state of a user's bot
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct BotStatusResponse {
+    pub running: bool,
+    pub pool_id: String,
+    pub is_bought: bool,
+    pub bought_price: Option<f64>,
+    pub latest_pool_price: f64,
+    /// Price the position must reach to exit without a loss after fees,
+    /// tips and exit slippage
+    pub break_even_price: Option<f64>,
+    /// Exits kept failing, the position needs manual attention
+    pub stuck: bool,
+    pub stuck_since: Option<i64>,
+    /// Failed exit attempts within `STUCK_EXIT_WINDOW`
+    pub exit_failures: u32,
+}
+
+/// Positions a panic sell forced to exit
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PanicSellResponse {
+    pub triggered: usize,
+    /// Positions whose forced sell was already requested, they are not sold twice
+    pub already_selling: usize,
+}
+
+/// Open position of a user's bot, without any of its keys
+#[derive(Debug, Serialize, Clone)]
+pub struct BotPositionResponse {
+    pub pool_id: String,
+    pub is_bought: bool,
+    pub bought_price: Option<f64>,
+    pub latest_pool_price: f64,
+    /// Price change since the buy, in percent
+    pub unrealized_pnl_pct: Option<f64>,
+    /// `unrealized_pnl_pct` of the SOL put into the position
+    pub unrealized_pnl_sol: Option<f64>,
+    pub bought_at: Option<i64>,
+    pub hold_secs: Option<i64>,
+    pub fee_lamports: i64,
+    /// Exit thresholds in force right now, in percent from the bought price
+    pub take_profit_pct: f64,
+    pub stop_loss_pct: f64,
+    pub trailing_stop_pct: Option<f64>,
+    pub break_even_price: Option<f64>,
+    /// When `auto_exit` sells the position, if it is enabled
+    pub auto_exit_at: Option<i64>,
+}
+
+/// Every open position of a user across pools
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct OpenPositionsResponse {
+    pub positions: Vec<BotPositionResponse>,
+    /// Unrealized PnL summed over the positions, in SOL
+    pub unrealized_pnl_sol: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BotSettingsResponse {
     pub id: String,
@@ -164,15 +1200,66 @@ pub struct BotSettingsResponse {
     pub buy_sol_amount: f64,
     pub buy_usd1_amount: f64,
     pub entry_percent: f64,
+    pub entry_reference: EntryReference,
+    pub entry_window_secs: u64,
+    pub trade_tag: String,
+    pub min_observations_before_trading: u32,
+    pub observer: bool,
+    pub live_trading_acknowledged: bool,
     pub entry_slippage: f64,
     pub exit_slippage: f64,
+    pub min_out_cost_floor: bool,
+    pub slippage_overrides: HashMap<String, PoolSlippage>,
     pub stop_loss: f64,
+    pub trailing_stop_pct: Option<f64>,
+    pub move_stop_to_be_at_pct: Option<f64>,
+    pub break_even_stop_offset_pct: f64,
+    pub take_profit_tiers: Vec<(f64, f64)>,
+    pub dca_levels: Vec<f64>,
+    pub dca_amounts: Vec<f64>,
     pub take_profit: f64,
+    pub tp_decay_min_profit_pct: f64,
+    pub tp_decay_secs: u64,
+    pub break_even_tp_floor: bool,
+    pub drain_detection: bool,
+    pub drain_window_secs: u64,
+    pub drain_outflow_pct: f64,
+    pub drain_min_sells: u32,
+    pub anti_sandwich: bool,
+    pub sandwich_min_move_pct: f64,
+    pub auto_tune_entry: bool,
+    pub auto_tune_min_entry_percent: f64,
+    pub auto_tune_max_entry_percent: f64,
+    pub regime_exits: Option<RegimeExits>,
+    pub zslot_region: String,
+    pub tip_addr_idx: Option<u8>,
+    pub multi_relay: Vec<String>,
+    pub mint_blacklist: Vec<String>,
+    pub mint_whitelist: Vec<String>,
+    pub min_pool_liquidity_sol: f64,
+    pub max_price_impact_pct: f64,
     pub auto_exit: u64,
+    pub reentry_cooldown_secs: u64,
+    pub confirm_timeout_secs: u64,
+    pub slow_entry_slots: u64,
+    pub slow_entry_ms: u64,
+    pub max_consecutive_losses: u32,
+    pub max_open_positions: u32,
+    pub consecutive_losses: u32,
+    pub halted: bool,
+    pub max_fees_sol_per_hour: f64,
+    pub max_fees_sol_per_day: f64,
+    pub fee_budget: FeeBudget,
+    pub scheduled_start: Option<i64>,
+    pub scheduled_stop: Option<i64>,
     pub confirm_service: String,
     pub cu: u64,
     pub priority_fee_micro_lamport: u64,
     pub third_party_fee: f64,
+    pub max_tx_fee_sol: f64,
+    pub auto_cu: bool,
+    pub max_slippage_retries: u32,
+    pub slippage_retry_step: f64,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
@@ -187,15 +1274,66 @@ impl From<BotSettings> for BotSettingsResponse {
             buy_sol_amount: bot.buy_sol_amount,
             buy_usd1_amount: bot.buy_usd1_amount,
             entry_percent: bot.entry_percent,
+            entry_reference: bot.entry_reference,
+            entry_window_secs: bot.entry_window_secs,
+            trade_tag: bot.trade_tag,
+            min_observations_before_trading: bot.min_observations_before_trading,
+            observer: bot.observer,
+            live_trading_acknowledged: bot.live_trading_acknowledged,
             entry_slippage: bot.entry_slippage,
             exit_slippage: bot.exit_slippage,
+            min_out_cost_floor: bot.min_out_cost_floor,
+            slippage_overrides: bot.slippage_overrides,
             stop_loss: bot.stop_loss,
+            trailing_stop_pct: bot.trailing_stop_pct,
+            move_stop_to_be_at_pct: bot.move_stop_to_be_at_pct,
+            break_even_stop_offset_pct: bot.break_even_stop_offset_pct,
+            take_profit_tiers: bot.take_profit_tiers,
+            dca_levels: bot.dca_levels,
+            dca_amounts: bot.dca_amounts,
             take_profit: bot.take_profit,
+            tp_decay_min_profit_pct: bot.tp_decay_min_profit_pct,
+            tp_decay_secs: bot.tp_decay_secs,
+            break_even_tp_floor: bot.break_even_tp_floor,
+            drain_detection: bot.drain_detection,
+            drain_window_secs: bot.drain_window_secs,
+            drain_outflow_pct: bot.drain_outflow_pct,
+            drain_min_sells: bot.drain_min_sells,
+            anti_sandwich: bot.anti_sandwich,
+            sandwich_min_move_pct: bot.sandwich_min_move_pct,
+            auto_tune_entry: bot.auto_tune_entry,
+            auto_tune_min_entry_percent: bot.auto_tune_min_entry_percent,
+            auto_tune_max_entry_percent: bot.auto_tune_max_entry_percent,
+            regime_exits: bot.regime_exits,
+            zslot_region: bot.zslot_region,
+            tip_addr_idx: bot.tip_addr_idx,
+            multi_relay: bot.multi_relay,
+            mint_blacklist: bot.mint_blacklist,
+            mint_whitelist: bot.mint_whitelist,
+            min_pool_liquidity_sol: bot.min_pool_liquidity_sol,
+            max_price_impact_pct: bot.max_price_impact_pct,
             auto_exit: bot.auto_exit,
+            reentry_cooldown_secs: bot.reentry_cooldown_secs,
+            confirm_timeout_secs: bot.confirm_timeout_secs,
+            slow_entry_slots: bot.slow_entry_slots,
+            slow_entry_ms: bot.slow_entry_ms,
+            max_consecutive_losses: bot.max_consecutive_losses,
+            max_open_positions: bot.max_open_positions,
+            consecutive_losses: bot.consecutive_losses,
+            halted: bot.halted,
+            max_fees_sol_per_hour: bot.max_fees_sol_per_hour,
+            max_fees_sol_per_day: bot.max_fees_sol_per_day,
+            fee_budget: bot.fee_budget,
+            scheduled_start: bot.scheduled_start,
+            scheduled_stop: bot.scheduled_stop,
             confirm_service: bot.confirm_service,
             cu: bot.cu,
             priority_fee_micro_lamport: bot.priority_fee_micro_lamport,
             third_party_fee: bot.third_party_fee,
+            max_tx_fee_sol: bot.max_tx_fee_sol,
+            auto_cu: bot.auto_cu,
+            max_slippage_retries: bot.max_slippage_retries,
+            slippage_retry_step: bot.slippage_retry_step,
             created_at: bot.created_at,
             updated_at: bot.updated_at,
         }
@@ -212,17 +1350,289 @@ impl Default for BotSettingsResponse {
             buy_sol_amount: 0.0,
             buy_usd1_amount: 0.0,
             entry_percent: 0.0,
+            entry_reference: EntryReference::default(),
+            entry_window_secs: 0,
+            trade_tag: String::new(),
+            min_observations_before_trading: 0,
+            observer: false,
+            live_trading_acknowledged: false,
             entry_slippage: 0.0,
             exit_slippage: 0.0,
+            min_out_cost_floor: false,
+            slippage_overrides: HashMap::new(),
             stop_loss: 0.0,
+            trailing_stop_pct: None,
+            move_stop_to_be_at_pct: None,
+            break_even_stop_offset_pct: 0.0,
+            take_profit_tiers: Vec::new(),
+            dca_levels: Vec::new(),
+            dca_amounts: Vec::new(),
             take_profit: 0.0,
+            tp_decay_min_profit_pct: 0.0,
+            tp_decay_secs: 0,
+            break_even_tp_floor: false,
+            drain_detection: false,
+            drain_window_secs: default_drain_window_secs(),
+            drain_outflow_pct: default_drain_outflow_pct(),
+            drain_min_sells: default_drain_min_sells(),
+            anti_sandwich: false,
+            sandwich_min_move_pct: default_sandwich_min_move_pct(),
+            auto_tune_entry: false,
+            auto_tune_min_entry_percent: default_auto_tune_min_entry_percent(),
+            auto_tune_max_entry_percent: default_auto_tune_max_entry_percent(),
+            regime_exits: None,
+            zslot_region: String::new(),
+            tip_addr_idx: None,
+            multi_relay: Vec::new(),
+            mint_blacklist: Vec::new(),
+            mint_whitelist: Vec::new(),
+            min_pool_liquidity_sol: 0.0,
+            max_price_impact_pct: 0.0,
             auto_exit: 0,
+            reentry_cooldown_secs: 0,
+            confirm_timeout_secs: 0,
+            slow_entry_slots: 0,
+            slow_entry_ms: 0,
+            max_consecutive_losses: 0,
+            max_open_positions: 0,
+            consecutive_losses: 0,
+            halted: false,
+            max_fees_sol_per_hour: 0.0,
+            max_fees_sol_per_day: 0.0,
+            fee_budget: FeeBudget::default(),
+            scheduled_start: None,
+            scheduled_stop: None,
             confirm_service: String::new(),
             cu: 0,
             priority_fee_micro_lamport: 0,
             third_party_fee: 0.0,
+            max_tx_fee_sol: 0.0,
+            auto_cu: false,
+            max_slippage_retries: 0,
+            slippage_retry_step: default_slippage_retry_step(),
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL: &str = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2";
+
+    #[test]
+    fn tip_addr_idx_must_fit_every_relay() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        bot.confirm_service = "NOZOMI".to_string();
+        assert_eq!(bot.validate_tip_addr_idx(), Ok(()));
+
+        bot.tip_addr_idx = Some(16);
+        assert_eq!(bot.validate_tip_addr_idx(), Ok(()));
+        bot.tip_addr_idx = Some(17);
+        assert!(bot.validate_tip_addr_idx().unwrap_err().contains("NOZOMI"));
+
+        // Racing Jito too narrows the range to its 8 accounts
+        bot.tip_addr_idx = Some(8);
+        bot.multi_relay = vec!["NOZOMI".to_string(), "JITO".to_string()];
+        let err = bot.validate_tip_addr_idx().unwrap_err();
+        assert!(err.contains("JITO") && err.contains("0..=7"), "{}", err);
+        bot.tip_addr_idx = Some(7);
+        assert_eq!(bot.validate_tip_addr_idx(), Ok(()));
+    }
+
+    #[test]
+    fn pool_override_takes_precedence() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        bot.slippage_overrides.insert(
+            POOL.to_string(),
+            PoolSlippage {
+                entry_slippage: Some(12.0),
+                exit_slippage: None,
+            },
+        );
+
+        assert_eq!(bot.entry_slippage_for(POOL), 12.0);
+        assert_eq!(bot.exit_slippage_for(POOL), bot.exit_slippage);
+        assert_eq!(bot.entry_slippage_for("other"), bot.entry_slippage);
+        assert!(bot.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_overrides_are_rejected() {
+        let out_of_range = HashMap::from([(
+            POOL.to_string(),
+            PoolSlippage {
+                entry_slippage: Some(0.0),
+                exit_slippage: None,
+            },
+        )]);
+        assert!(validate_slippage_overrides(&out_of_range).is_err());
+
+        let bad_key = HashMap::from([("not-a-pool".to_string(), PoolSlippage::default())]);
+        assert!(validate_slippage_overrides(&bad_key).is_err());
+    }
+
+    #[test]
+    fn mint_lists_gate_entries() {
+        const MINT: &str = "So11111111111111111111111111111111111111112";
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        // Empty lists trade anything
+        assert!(bot.mint_allowed(MINT));
+
+        bot.mint_whitelist = vec![POOL.to_string()];
+        assert!(!bot.mint_allowed(MINT));
+        assert!(bot.mint_allowed(POOL));
+        // The blacklist wins over the whitelist
+        bot.mint_blacklist = vec![POOL.to_string()];
+        assert!(!bot.mint_allowed(POOL));
+
+        assert!(validate_mint_list(&[MINT.to_string(), POOL.to_string()]).is_ok());
+        assert!(validate_mint_list(&["rug".to_string()]).is_err());
+    }
+
+    #[test]
+    fn thin_pools_are_not_entered() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        assert!(bot.liquidity_sufficient(0.5));
+
+        bot.min_pool_liquidity_sol = 20.0;
+        assert!(!bot.liquidity_sufficient(19.99));
+        assert!(bot.liquidity_sufficient(20.0));
+        assert!(bot.liquidity_sufficient(350.0));
+
+        assert!(bot.price_impact_acceptable(80.0));
+        bot.max_price_impact_pct = 5.0;
+        assert!(bot.price_impact_acceptable(1.3));
+        assert!(!bot.price_impact_acceptable(10.5));
+    }
+
+    #[test]
+    fn loss_breaker_trips_and_resets() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        bot.max_consecutive_losses = 2;
+
+        assert!(!bot.record_trade_result(-0.1));
+        assert!(!bot.record_trade_result(0.2));
+        assert_eq!(bot.consecutive_losses, 0);
+
+        assert!(!bot.record_trade_result(-0.1));
+        assert!(bot.record_trade_result(-0.1));
+        assert!(bot.halted);
+        // Already halted, doesn't trip again
+        assert!(!bot.record_trade_result(-0.1));
+
+        bot.max_consecutive_losses = 0;
+        bot.halted = false;
+        assert!(!bot.record_trade_result(-0.1));
+    }
+
+    #[test]
+    fn losing_streak_widens_and_winning_streak_tightens_entry() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        bot.entry_percent = 2.0;
+        bot.auto_tune_min_entry_percent = 1.0;
+        bot.auto_tune_max_entry_percent = 2.3;
+
+        // Too few closed trades to judge
+        assert_eq!(bot.tune_entry_percent(&[-0.1, -0.1, -0.1, -0.1]), None);
+
+        let losing = [-0.1, -0.2, 0.05, -0.1, -0.3];
+        let widened = bot.tune_entry_percent(&losing).unwrap();
+        assert!((widened - 2.2).abs() < 1e-9);
+        // Bounded by the configured maximum
+        assert_eq!(bot.tune_entry_percent(&losing), Some(2.3));
+        assert_eq!(bot.tune_entry_percent(&losing), None);
+
+        let winning = [0.1, 0.2, 0.3, -0.05, 0.1];
+        let tightened = bot.tune_entry_percent(&winning).unwrap();
+        assert!((tightened - 2.07).abs() < 1e-9);
+
+        // A mixed record leaves it alone
+        assert_eq!(bot.tune_entry_percent(&[0.1, -0.1, 0.1, -0.1, 0.1, -0.1]), None);
+        assert_eq!(bot.entry_percent, tightened);
+    }
+
+    #[test]
+    fn nothing_is_submitted_live_unless_both_gates_are_open() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        assert!(!bot.submits_live(false));
+        assert!(!bot.submits_live(true));
+
+        bot.live_trading_acknowledged = true;
+        assert!(!bot.submits_live(false));
+        assert!(bot.submits_live(true));
+
+        bot.observer = true;
+        assert!(!bot.submits_live(true));
+    }
+
+    #[test]
+    fn preview_overrides_apply_without_touching_the_settings() {
+        use crate::utils::swap_quote::buy_min_out;
+
+        let bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        let stored = bot.clone();
+
+        let (amount, slippage) = bot.preview_params(&PreviewOverrides::default());
+        assert_eq!((amount, slippage), (bot.buy_sol_amount, bot.entry_slippage));
+
+        let overrides = PreviewOverrides {
+            buy_sol_amount: Some(1.0),
+            slippage: Some(10.0),
+        };
+        assert!(overrides.validate().is_ok());
+        let (amount, slippage) = bot.preview_params(&overrides);
+        assert_eq!((amount, slippage), (1.0, 10.0));
+
+        // 1 SOL into 100 SOL / 1,000,000 tokens: 9,871 expected, 10% less at minimum
+        let (expected, min_out) = buy_min_out(1_000_000_000, 100_000_000_000, 1_000_000, slippage);
+        assert_eq!(expected, 9_871);
+        assert_eq!(min_out, 8_884);
+
+        assert_eq!(bot.buy_sol_amount, stored.buy_sol_amount);
+        assert_eq!(bot.entry_slippage, stored.entry_slippage);
+
+        let too_loose = PreviewOverrides {
+            buy_sol_amount: None,
+            slippage: Some(600.0),
+        };
+        assert!(too_loose.validate().is_err());
+    }
+
+    #[test]
+    fn fee_budget_pauses_entries_until_the_hour_turns() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), POOL.to_string());
+        bot.max_fees_sol_per_hour = 0.01;
+        bot.max_fees_sol_per_day = 0.05;
+        // 2024-01-01 10:00 UTC
+        let ten_am = 1_704_103_200_000;
+        let mut budget = FeeBudget::default();
+
+        budget.record(6_000_000, ten_am + 60_000);
+        assert_eq!(bot.fee_budget_exhausted(&budget, ten_am + 120_000), None);
+        budget.record(4_000_000, ten_am + 30 * 60_000);
+        assert!(bot.fee_budget_exhausted(&budget, ten_am + 31 * 60_000).unwrap().contains("this hour"));
+        assert!(bot.fee_budget_exhausted(&budget, ten_am + HOUR_MS - 1).is_some());
+
+        // The next hour starts its count over, the day keeps counting
+        let eleven_am = ten_am + HOUR_MS;
+        assert_eq!(bot.fee_budget_exhausted(&budget, eleven_am), None);
+        for hour in 0..4 {
+            budget.record(9_000_000, eleven_am + HOUR_MS * hour);
+        }
+        assert_eq!(budget.rolled(eleven_am + 3 * HOUR_MS).day_spent_lamports, 46_000_000);
+        budget.record(4_000_000, eleven_am + 4 * HOUR_MS);
+        assert!(bot.fee_budget_exhausted(&budget, eleven_am + 5 * HOUR_MS).unwrap().contains("today"));
+
+        // Midnight UTC frees the day
+        let midnight = ten_am + 14 * HOUR_MS;
+        assert_eq!(bot.fee_budget_exhausted(&budget, midnight), None);
+        assert_eq!(budget.rolled(midnight), FeeBudget { hour_start: midnight, day_start: midnight, ..FeeBudget::default() });
+
+        bot.max_fees_sol_per_hour = 0.0;
+        bot.max_fees_sol_per_day = 0.0;
+        assert_eq!(bot.fee_budget_exhausted(&budget, eleven_am + 5 * HOUR_MS), None);
+    }
+}
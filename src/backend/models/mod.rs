@@ -2,3 +2,6 @@ pub mod user;
 pub mod auth;
 pub mod bot;
 pub mod trade;
+pub mod pool_price;
+pub mod position;
+pub mod pool_table;
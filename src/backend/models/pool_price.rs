@@ -0,0 +1,32 @@
+use bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// Last price observed for a pool, used to warm start bots
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PoolPrice {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub pool_address: String,
+    pub price: f64,
+    pub updated_at: DateTime,
+}
+
+/// A pool's current price read straight from its reserves
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PoolPriceResponse {
+    pub pool_address: String,
+    pub token_mint: String,
+    pub quote_mint: String,
+    /// `SOL` or `USD1`
+    pub quote_currency: String,
+    /// Quote per whole token
+    pub price: f64,
+    /// SOL per whole token, only for SOL pools
+    pub price_sol: Option<f64>,
+    pub token_decimals: u8,
+    pub quote_decimals: u8,
+    pub token_reserve: u64,
+    pub quote_reserve: u64,
+    /// Quote side of the pool in whole units of the quote currency
+    pub liquidity: f64,
+}
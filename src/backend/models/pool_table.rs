@@ -0,0 +1,29 @@
+use bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// An address lookup table created for a pool's swaps, kept so restarts
+/// reuse it and it is closed once no bot trades the pool
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StoredPoolTable {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub pool_id: String,
+    /// The table's address
+    pub table: String,
+    /// Slot the table was deactivated in, it can be closed once that slot
+    /// is no longer recent
+    pub deactivated_slot: Option<u64>,
+    pub created_at: DateTime,
+}
+
+impl StoredPoolTable {
+    pub fn new(pool_id: String, table: String) -> Self {
+        Self {
+            id: None,
+            pool_id,
+            table,
+            deactivated_slot: None,
+            created_at: DateTime::now(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::models::trade::{EntryLatency, FundRoute};
+use crate::backend::services::bot_service::RealPoolInfo;
+
+/// The part of an open position needed to keep selling it after a restart
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StoredPosition {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub pool_id: String,
+    pub user_id: String,
+    pub is_bought: bool,
+    pub bought_price: Option<f64>,
+    pub bought_at: Option<i64>,
+    pub signature: Option<String>,
+    pub last_input_lamports_delta: Option<i64>,
+    /// Positions stored before fees were kept in lamports restore with none
+    #[serde(default)]
+    pub fee_lamports: i64,
+    /// When the last round trip closed, in milliseconds, for the re-entry cooldown
+    #[serde(default)]
+    pub last_exit_at: Option<i64>,
+    /// Wall-clock stand-in for `start_time`, in milliseconds
+    pub started_at: i64,
+    /// Tokens the position still holds and bought, so sells stay scoped to
+    /// it after a restart
+    #[serde(default)]
+    pub held_token_amount: Option<i64>,
+    #[serde(default)]
+    pub bought_token_amount: Option<i64>,
+    /// The open round trip's route so far, so its trade record survives a restart
+    #[serde(default)]
+    pub route: FundRoute,
+    /// How long the open round trip's entry took to land, for its trade record
+    #[serde(default)]
+    pub entry_latency: Option<EntryLatency>,
+    pub updated_at: DateTime,
+}
+
+impl StoredPosition {
+    pub fn from_position(info: &RealPoolInfo) -> Self {
+        Self {
+            id: None,
+            pool_id: info.user_bot_data.pool_id.clone(),
+            user_id: info.user_bot_data.user_id.clone(),
+            is_bought: info.is_bought,
+            bought_price: info.bought_price,
+            bought_at: info.bought_at,
+            signature: info.signature.clone(),
+            // BSON has no 128-bit integers, a lamport delta fits in 64 bits
+            last_input_lamports_delta: info
+                .last_input_lamports_delta
+                .and_then(|delta| i64::try_from(delta).ok()),
+            fee_lamports: info.fee_lamports,
+            last_exit_at: info.last_exit_at,
+            started_at: info.started_at,
+            held_token_amount: info.held_token_amount.and_then(|amount| i64::try_from(amount).ok()),
+            bought_token_amount: info.bought_token_amount.and_then(|amount| i64::try_from(amount).ok()),
+            route: info.route.clone(),
+            entry_latency: info.entry_latency,
+            updated_at: DateTime::now(),
+        }
+    }
+}
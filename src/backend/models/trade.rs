@@ -2,6 +2,154 @@ use bson::{DateTime, oid::ObjectId};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+/// Token flow of one round trip, reconstructed from the confirmed buy and
+/// sell transactions: SOL in → tokens received → tokens out → SOL received
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FundRoute {
+    pub mint: String,
+    pub sol_in_lamports: i64,
+    pub tokens_received: i64,
+    pub tokens_sold: i64,
+    pub sol_out_lamports: i64,
+}
+
+impl FundRoute {
+    /// Start a new route from a confirmed buy
+    pub fn record_buy(&mut self, mint: String, sol_in_lamports: i128, tokens_received: i128) {
+        *self = Self {
+            mint,
+            sol_in_lamports: sol_in_lamports as i64,
+            tokens_received: tokens_received as i64,
+            ..Default::default()
+        };
+    }
+
+    /// Add a confirmed DCA scale-in to the buy leg
+    pub fn add_buy(&mut self, sol_in_lamports: i128, tokens_received: i128) {
+        self.sol_in_lamports += sol_in_lamports as i64;
+        self.tokens_received += tokens_received as i64;
+    }
+
+    /// Add a confirmed sell to the route, partial take-profits sell in several
+    pub fn record_sell(&mut self, tokens_sold: i128, sol_out_lamports: i128) {
+        self.tokens_sold += tokens_sold as i64;
+        self.sol_out_lamports += sol_out_lamports as i64;
+    }
+}
+
+impl std::fmt::Display for FundRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.9} SOL in → {} {} received → {} {} out → {:.9} SOL received",
+            self.sol_in_lamports as f64 / 1_000_000_000.0,
+            self.tokens_received,
+            self.mint,
+            self.tokens_sold,
+            self.mint,
+            self.sol_out_lamports as f64 / 1_000_000_000.0
+        )
+    }
+}
+
+/// Where a round trip's SOL went beyond the swap itself, in lamports. Pool
+/// fees come from the PumpSwap fill events; other AMMs only report network
+/// fee and tip.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct FeeBreakdown {
+    pub lp_fee_lamports: i64,
+    pub protocol_fee_lamports: i64,
+    pub coin_creator_fee_lamports: i64,
+    pub network_fee_lamports: i64,
+    pub tip_lamports: i64,
+}
+
+impl FeeBreakdown {
+    pub fn add(&mut self, other: &FeeBreakdown) {
+        self.lp_fee_lamports += other.lp_fee_lamports;
+        self.protocol_fee_lamports += other.protocol_fee_lamports;
+        self.coin_creator_fee_lamports += other.coin_creator_fee_lamports;
+        self.network_fee_lamports += other.network_fee_lamports;
+        self.tip_lamports += other.tip_lamports;
+    }
+}
+
+/// Realized slippage of a round trip's fills against the pool price when each
+/// swap was signalled, in percent of the expected output. Positive means the
+/// fill came out worse than expected.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct RealizedSlippage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_pct: Option<f64>,
+    /// Mean over the sells, partial take-profits close a position in several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_pct: Option<f64>,
+    #[serde(skip)]
+    exit_fills: u32,
+}
+
+impl RealizedSlippage {
+    /// Start a round trip from its entry fill
+    pub fn record_entry(&mut self, slippage_pct: f64) {
+        *self = Self {
+            entry_pct: Some(slippage_pct),
+            ..Default::default()
+        };
+    }
+
+    pub fn record_exit(&mut self, slippage_pct: f64) {
+        let fills = self.exit_fills as f64;
+        self.exit_pct = Some(
+            self.exit_pct
+                .map_or(slippage_pct, |mean| (mean * fills + slippage_pct) / (fills + 1.0)),
+        );
+        self.exit_fills += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entry_pct.is_none() && self.exit_pct.is_none()
+    }
+}
+
+/// Where a transaction landed: its slot and block time in unix seconds
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SlotTime {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+/// Time from the swap that signalled an entry to the bot's buy landing
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EntryLatency {
+    pub slots: u64,
+    /// From the block times, so in whole seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ms: Option<i64>,
+    /// Over the bot's `slow_entry_slots` or `slow_entry_ms` when it landed,
+    /// a race the bot lost
+    #[serde(default)]
+    pub slow: bool,
+}
+
+impl EntryLatency {
+    /// None when the fill doesn't come after the signal
+    pub fn between(signal: SlotTime, fill: SlotTime) -> Option<Self> {
+        let slots = fill.slot.checked_sub(signal.slot)?;
+        let ms = signal
+            .block_time
+            .zip(fill.block_time)
+            .map(|(signalled, landed)| (landed - signalled).max(0) * 1000);
+        Some(Self { slots, ms, slow: false })
+    }
+
+    /// Flags the entry slow past either threshold, zero disables each
+    pub fn judged(self, max_slots: u64, max_ms: u64) -> Self {
+        let slow_slots = max_slots > 0 && self.slots > max_slots;
+        let slow_ms = max_ms > 0 && self.ms.is_some_and(|ms| ms > max_ms as i64);
+        Self { slow: slow_slots || slow_ms, ..self }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct TradeData {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
@@ -13,10 +161,25 @@ pub struct TradeData {
     pub fees_sol: f64,
     pub roi_pct: f64,
     pub program_runtime_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<FundRoute>,
+    /// Strategy the trade was entered with, see `BotSettings::strategy_tag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_breakdown: Option<FeeBreakdown>,
+    /// Pool the round trip traded, missing on trades saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slippage: Option<RealizedSlippage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_latency: Option<EntryLatency>,
     pub created_at: DateTime,
 }
 
 impl TradeData {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_id: String,
         profit_sol: f64,
@@ -24,6 +187,12 @@ impl TradeData {
         fees_sol: f64,
         roi_pct: f64,
         program_runtime_ms: i64,
+        route: Option<FundRoute>,
+        tag: Option<String>,
+        fee_breakdown: Option<FeeBreakdown>,
+        pool_id: Option<String>,
+        slippage: Option<RealizedSlippage>,
+        entry_latency: Option<EntryLatency>,
     ) -> Self {
         Self {
             id: None,
@@ -34,6 +203,12 @@ impl TradeData {
             fees_sol,
             roi_pct,
             program_runtime_ms,
+            route,
+            tag,
+            fee_breakdown,
+            pool_id,
+            slippage,
+            entry_latency,
             created_at: DateTime::now(),
         }
     }
@@ -49,6 +224,20 @@ pub struct TradeDataResponse {
     pub fees_sol: f64,
     pub roi_pct: f64,
     pub program_runtime_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<FundRoute>,
+    /// Strategy the trade was entered with, see `BotSettings::strategy_tag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_breakdown: Option<FeeBreakdown>,
+    /// Pool the round trip traded, missing on trades saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slippage: Option<RealizedSlippage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_latency: Option<EntryLatency>,
     pub created_at: DateTime,
 }
 
@@ -63,6 +252,12 @@ impl From<TradeData> for TradeDataResponse {
             fees_sol: trade.fees_sol,
             roi_pct: trade.roi_pct,
             program_runtime_ms: trade.program_runtime_ms,
+            route: trade.route,
+            tag: trade.tag,
+            fee_breakdown: trade.fee_breakdown,
+            pool_id: trade.pool_id,
+            slippage: trade.slippage,
+            entry_latency: trade.entry_latency,
             created_at: trade.created_at,
         }
     }
@@ -5,7 +5,8 @@ use solana_sdk::signature::{Keypair, Signer};
 
 use crate::backend::auth::password_service::PasswordService;
 
-#[derive(Debug, Serialize, Clone)]
+/// `private_key` is stored encrypted, see `WalletCipher`
+#[derive(Serialize, Clone)]
 pub struct User {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
@@ -14,10 +15,31 @@ pub struct User {
     pub password_hash: String,
     pub private_key: String,
     pub public_key: String,
+    /// Keep this user's trades out of the public leaderboard
+    pub hide_from_leaderboard: bool,
+    /// User whose approval sensitive wallet operations also need, none
+    /// when they run on this user's approval alone
+    pub co_signer_id: Option<String>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
 
+impl std::fmt::Debug for User {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("email", &self.email)
+            .field("username", &self.username)
+            .field("private_key", &"<redacted>")
+            .field("public_key", &self.public_key)
+            .field("hide_from_leaderboard", &self.hide_from_leaderboard)
+            .field("co_signer_id", &self.co_signer_id)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .finish()
+    }
+}
+
 impl<'de> Deserialize<'de> for User {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -32,6 +54,10 @@ impl<'de> Deserialize<'de> for User {
             password_hash: String,
             private_key: String,
             public_key: String,
+            #[serde(default)]
+            hide_from_leaderboard: bool,
+            #[serde(default)]
+            co_signer_id: Option<String>,
             created_at: Option<DateTime>,
             updated_at: Option<DateTime>,
         }
@@ -44,6 +70,8 @@ impl<'de> Deserialize<'de> for User {
             password_hash: helper.password_hash,
             private_key: helper.private_key,
             public_key: helper.public_key,
+            hide_from_leaderboard: helper.hide_from_leaderboard,
+            co_signer_id: helper.co_signer_id,
             created_at: helper.created_at.unwrap_or_else(DateTime::now),
             updated_at: helper.updated_at.unwrap_or_else(DateTime::now),
         })
@@ -67,6 +95,8 @@ impl User {
             password_hash,
             private_key,
             public_key,
+            hide_from_leaderboard: false,
+            co_signer_id: None,
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
         })
@@ -91,6 +121,17 @@ impl User {
         }
         self.updated_at = DateTime::now();
     }
+
+    pub fn set_leaderboard_visibility(&mut self, hidden: bool) {
+        self.hide_from_leaderboard = hidden;
+        self.updated_at = DateTime::now();
+    }
+
+    pub fn set_co_signer(&mut self, co_signer_id: Option<String>) {
+        self.co_signer_id = co_signer_id;
+        self.updated_at = DateTime::now();
+    }
+
     /// Generate or retrieve a private key for a user
     /// This function handles the private key management securely
     /// Returns (private_key, public_key) tuple
@@ -109,12 +150,28 @@ impl User {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdatePrivacyRequest {
+    pub hide_from_leaderboard: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCoSignerRequest {
+    /// `null` removes the co-signer
+    pub co_signer_id: Option<String>,
+    /// The current co-signer's approval, when one is set
+    #[serde(default)]
+    pub approval_id: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UserResponse {
     pub id: String,
     pub email: String,
     pub username: String,
     pub public_key: String,
+    pub hide_from_leaderboard: bool,
+    pub co_signer_id: Option<String>,
     pub created_at: DateTime,
 }
 
@@ -125,7 +182,32 @@ impl From<User> for UserResponse {
             email: user.email,
             username: user.username,
             public_key: user.public_key,
+            hide_from_leaderboard: user.hide_from_leaderboard,
+            co_signer_id: user.co_signer_id,
             created_at: user.created_at,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses_never_carry_the_private_key() {
+        let mut user = User::new(
+            "trader@example.com".to_string(),
+            "trader".to_string(),
+            "hash".to_string(),
+            "secret-private-key".to_string(),
+            "public-key".to_string(),
+        )
+        .unwrap();
+        user.id = Some(ObjectId::new());
+
+        assert!(!format!("{:?}", user).contains("secret-private-key"));
+        let response = serde_json::to_string(&UserResponse::from(user)).unwrap();
+        assert!(!response.contains("private_key"));
+        assert!(!response.contains("secret-private-key"));
+    }
+}
@@ -0,0 +1,15 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::backend::{
+    db::connection::AppDatabase,
+    handlers::admin::{get_reconcile, repair_reconcile, run_self_test},
+};
+
+pub fn admin_routes() -> Router<AppDatabase> {
+    Router::new()
+        .route("/reconcile", get(get_reconcile).post(repair_reconcile))
+        .route("/self-test", post(run_self_test))
+}
@@ -1,8 +1,11 @@
-use axum::{routing::put, routing::get, Router};
+use axum::{routing::put, routing::get, routing::post, Router};
 
 use crate::backend::{
     db::connection::AppDatabase,
-    handlers::bot::{get_user_bots, start_bot, stop_bot, update_mev_config, update_trading_params},
+    handlers::bot::{
+        dry_run_bot, get_bot_failures, get_bot_logs, get_bot_position, get_bot_status, get_user_bots,
+        panic_sell, preview_buy, resume_bot, start_bot, stop_bot, update_mev_config, update_trading_params,
+    },
 };
 
 pub fn bot_routes() -> Router<AppDatabase> {
@@ -12,4 +15,12 @@ pub fn bot_routes() -> Router<AppDatabase> {
         .route("/mev", put(update_mev_config))
         .route("/start", get(start_bot))
         .route("/stop", get(stop_bot))
+        .route("/panic-sell", post(panic_sell))
+        .route("/resume", get(resume_bot))
+        .route("/status", get(get_bot_status))
+        .route("/position", get(get_bot_position))
+        .route("/preview", get(preview_buy))
+        .route("/dry-run", get(dry_run_bot))
+        .route("/logs", get(get_bot_logs))
+        .route("/failures", get(get_bot_failures))
 }
@@ -1,14 +1,27 @@
 use axum::{response::Json, routing::get, Router};
 use serde_json::json;
 
+use crate::utils::self_test::{latest_self_test, ServiceState};
+
 pub fn health_routes() -> Router {
     Router::new().route("/", get(health_check))
 }
 
 pub async fn health_check() -> Json<serde_json::Value> {
+    let services = latest_self_test();
+    let status = if services
+        .iter()
+        .any(|check| check.state == ServiceState::Unauthorized)
+    {
+        "degraded"
+    } else {
+        "ok"
+    };
+
     Json(json!({
-        "status": "ok",
+        "status": status,
         "message": "User Authentication API is running",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "services": services
     }))
 }
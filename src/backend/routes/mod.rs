@@ -3,3 +3,6 @@ pub mod health;
 pub mod users;
 pub mod bot;
 pub mod trade;
+pub mod admin;
+pub mod position;
+pub mod pool;
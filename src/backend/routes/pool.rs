@@ -0,0 +1,7 @@
+use axum::{routing::get, Router};
+
+use crate::backend::{db::connection::AppDatabase, handlers::pool::get_pool_price};
+
+pub fn pool_routes() -> Router<AppDatabase> {
+    Router::new().route("/:pool_address/price", get(get_pool_price))
+}
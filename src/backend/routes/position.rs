@@ -0,0 +1,7 @@
+use axum::{routing::get, Router};
+
+use crate::backend::{db::connection::AppDatabase, handlers::bot::get_open_positions};
+
+pub fn position_routes() -> Router<AppDatabase> {
+    Router::new().route("/", get(get_open_positions))
+}
@@ -5,11 +5,20 @@ use axum::{
 
 use crate::backend::{
     db::connection::AppDatabase,
-    handlers::trade::{get_trade_data, get_user_trades},
+    handlers::trade::{
+        get_global_trade_stats, get_leaderboard, get_pool_stats, get_tag_stats, get_trade_data,
+        get_trade_report, get_trade_stats, get_user_trades,
+    },
 };
 
 pub fn trade_routes() -> Router<AppDatabase> {
     Router::new()
         .route("/data", get(get_trade_data))
         .route("/user/:user_id", get(get_user_trades))
+        .route("/stats", get(get_trade_stats))
+        .route("/stats/global", get(get_global_trade_stats))
+        .route("/stats/tags", get(get_tag_stats))
+        .route("/stats/pools", get(get_pool_stats))
+        .route("/leaderboard", get(get_leaderboard))
+        .route("/report", get(get_trade_report))
 }
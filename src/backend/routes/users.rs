@@ -1,10 +1,14 @@
-use axum::{routing::get, Router};
+use axum::{routing::{get, post, put}, Router};
 use crate::backend::{
     db::connection::AppDatabase,
-    handlers::users::get_current_user,
+    handlers::users::{approve, get_current_user, request_approval, update_co_signer, update_privacy},
 };
 
 pub fn user_routes() -> Router<AppDatabase> {
     Router::new()
         .route("/me", get(get_current_user))
+        .route("/me/privacy", put(update_privacy))
+        .route("/me/co-signer", put(update_co_signer))
+        .route("/me/approvals", post(request_approval))
+        .route("/approvals/:id/approve", post(approve))
 }
@@ -21,6 +21,7 @@ pub async fn start_backend_server() -> Result<(), Box<dyn std::error::Error + Se
                 .unwrap_or_else(|_| EnvFilter::new(&config.rust_log)),
         )
         .with(tracing_subscriber::fmt::layer())
+        .with(crate::backend::user_logs::UserLogLayer)
         .try_init();
 
     println!("Tracing initialized");
@@ -37,6 +38,44 @@ pub async fn start_backend_server() -> Result<(), Box<dyn std::error::Error + Se
         }
     };
 
+    // Encrypt private keys stored before they were encrypted at rest, the
+    // cipher failing here stops startup rather than the first signup
+    let cipher = match crate::backend::crypto::wallet_cipher() {
+        Ok(cipher) => cipher,
+        Err(e) => {
+            eprintln!("Failed to initialize wallet encryption: {}", e);
+            return Err(e.into());
+        }
+    };
+    match crate::backend::db::user_repository::UserRepository::new(db.clone())
+        .encrypt_plaintext_keys(cipher)
+        .await
+    {
+        Ok(encrypted) => println!("Encrypted {} plaintext private keys", encrypted),
+        Err(e) => eprintln!("Failed to encrypt plaintext private keys: {}", e),
+    }
+
+    // Reload positions that were still open when the process last stopped
+    match crate::backend::services::bot_service::BotService::new(db.clone())
+        .restore_positions()
+        .await
+    {
+        Ok(restored) => println!("Restored {} open positions", restored),
+        Err(e) => eprintln!("Failed to restore open positions: {}", e),
+    }
+
+    // Start the scheduled start/stop task
+    tokio::spawn(
+        crate::backend::services::scheduler_service::SchedulerService::new(
+            db.clone(),
+            std::sync::Arc::new(crate::backend::services::scheduler_service::SystemClock),
+        )
+        .run(),
+    );
+
+    // Start the entry_percent auto-tuning task
+    tokio::spawn(crate::backend::services::tuning_service::AutoTuneService::new(db.clone()).run());
+
     // Create application
     let app = crate::backend::app::create_app(db);
     println!("Application created");
@@ -0,0 +1,234 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::backend::{
+    db::bot_repository::BotRepository,
+    db::connection::AppDatabase,
+    error::AppResult,
+    models::bot::BotSettings,
+    services::bot_service::UserBotData,
+};
+use crate::utils::positions::{positions_where, PoolInfoMap};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct IdleBot {
+    pub user_id: String,
+    pub name: String,
+    pub pool_address: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OrphanPosition {
+    pub pool_id: String,
+    pub user_id: String,
+    pub is_bought: bool,
+}
+
+/// Drift between `bot_settings`, `USER_LIST` and `REAL_POOL_INFO`
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ReconcileReport {
+    /// Bots in the DB whose user is not in `USER_LIST`
+    pub bots_not_running: Vec<IdleBot>,
+    /// Users in `USER_LIST` with no bot in the DB
+    pub users_without_bot: Vec<String>,
+    /// Positions in `REAL_POOL_INFO` whose user is not in `USER_LIST`
+    pub orphan_positions: Vec<OrphanPosition>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct RepairResult {
+    pub drift: ReconcileReport,
+    pub removed_users: Vec<String>,
+    pub removed_positions: Vec<OrphanPosition>,
+    /// Holding tokens, left in place so they can still be sold
+    pub kept_open_positions: Vec<OrphanPosition>,
+}
+
+pub fn find_drift(
+    db_bots: &[BotSettings],
+    users: &[UserBotData],
+    positions: &PoolInfoMap,
+) -> ReconcileReport {
+    let db_users: HashSet<&str> = db_bots.iter().map(|bot| bot.user_id.as_str()).collect();
+    let running: HashSet<&str> = users.iter().map(|user| user.user_id.as_str()).collect();
+
+    let bots_not_running = db_bots
+        .iter()
+        .filter(|bot| !running.contains(bot.user_id.as_str()))
+        .map(|bot| IdleBot {
+            user_id: bot.user_id.clone(),
+            name: bot.name.clone(),
+            pool_address: bot.pool_address.clone(),
+        })
+        .collect();
+
+    let users_without_bot = users
+        .iter()
+        .filter(|user| !db_users.contains(user.user_id.as_str()))
+        .map(|user| user.user_id.clone())
+        .collect();
+
+    let mut orphan_positions: Vec<OrphanPosition> = positions
+        .iter()
+        .flat_map(|infos| {
+            infos
+                .iter()
+                .filter(|info| !running.contains(info.user_bot_data.user_id.as_str()))
+                .map(|info| OrphanPosition {
+                    pool_id: infos.key().clone(),
+                    user_id: info.user_bot_data.user_id.clone(),
+                    is_bought: info.is_bought,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    orphan_positions.sort_by(|a, b| (&a.pool_id, &a.user_id).cmp(&(&b.pool_id, &b.user_id)));
+
+    ReconcileReport {
+        bots_not_running,
+        users_without_bot,
+        orphan_positions,
+    }
+}
+
+/// Removes in-memory state the DB no longer backs. Anything still holding
+/// tokens is kept, and idle DB bots are only reported: starting them is the
+/// user's call.
+pub fn repair_drift(
+    report: ReconcileReport,
+    users: &mut Vec<UserBotData>,
+    positions: &PoolInfoMap,
+) -> RepairResult {
+    let holding: HashSet<String> = positions_where(positions, |info| info.is_bought)
+        .into_iter()
+        .map(|info| info.user_bot_data.user_id)
+        .collect();
+
+    let removed_users: Vec<String> = report
+        .users_without_bot
+        .iter()
+        .filter(|user_id| !holding.contains(*user_id))
+        .cloned()
+        .collect();
+    users.retain(|user| !removed_users.contains(&user.user_id));
+
+    let (kept_open_positions, removed_positions): (Vec<_>, Vec<_>) = report
+        .orphan_positions
+        .iter()
+        .cloned()
+        .partition(|position| position.is_bought);
+
+    positions.retain(|pool_id, infos| {
+        infos.retain(|info| {
+            let orphan = removed_positions
+                .iter()
+                .any(|p| &p.pool_id == pool_id && p.user_id == info.user_bot_data.user_id);
+            let stale_user = !info.is_bought && removed_users.contains(&info.user_bot_data.user_id);
+            !orphan && !stale_user
+        });
+        !infos.is_empty()
+    });
+
+    RepairResult {
+        drift: report,
+        removed_users,
+        removed_positions,
+        kept_open_positions,
+    }
+}
+
+pub struct AdminService {
+    bot_repo: BotRepository,
+}
+
+impl AdminService {
+    pub fn new(database: AppDatabase) -> Self {
+        Self {
+            bot_repo: BotRepository::new(database),
+        }
+    }
+
+    pub async fn reconcile_report(&self) -> AppResult<ReconcileReport> {
+        let db_bots = self.bot_repo.find_all().await?;
+        let users = crate::statics::USER_LIST.read().await;
+
+        Ok(find_drift(&db_bots, &users, &crate::statics::REAL_POOL_INFO))
+    }
+
+    pub async fn repair(&self) -> AppResult<RepairResult> {
+        let db_bots = self.bot_repo.find_all().await?;
+        let mut users = crate::statics::USER_LIST.write().await;
+        let positions = &crate::statics::REAL_POOL_INFO;
+
+        let report = find_drift(&db_bots, &users, positions);
+        let result = repair_drift(report, &mut users, positions);
+
+        println!(
+            "🩺 Reconcile: removed {} users and {} positions, kept {} open positions",
+            result.removed_users.len(),
+            result.removed_positions.len(),
+            result.kept_open_positions.len()
+        );
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::services::bot_service::RealPoolInfo;
+
+    fn user(user_id: &str, pool_id: &str) -> UserBotData {
+        UserBotData {
+            pool_id: pool_id.to_string(),
+            user_id: user_id.to_string(),
+            private_key: String::new(),
+            public_key: String::new(),
+            bot_setting: BotSettings::new(user_id.to_string(), "bot".to_string(), pool_id.to_string()),
+        }
+    }
+
+    #[test]
+    fn drift_is_reported_and_repaired() {
+        let db_bots = vec![
+            BotSettings::new("running".to_string(), "bot".to_string(), "pool-a".to_string()),
+            BotSettings::new("idle".to_string(), "bot".to_string(), "pool-b".to_string()),
+        ];
+        let mut users = vec![user("running", "pool-a"), user("deleted", "pool-a")];
+
+        let mut ghost_holding = RealPoolInfo::new(user("ghost-holding", "pool-c"));
+        ghost_holding.is_bought = true;
+        let positions = PoolInfoMap::from_iter([
+            (
+                "pool-a".to_string(),
+                vec![
+                    RealPoolInfo::new(user("running", "pool-a")),
+                    RealPoolInfo::new(user("deleted", "pool-a")),
+                ],
+            ),
+            (
+                "pool-c".to_string(),
+                vec![RealPoolInfo::new(user("ghost", "pool-c")), ghost_holding],
+            ),
+        ]);
+
+        let report = find_drift(&db_bots, &users, &positions);
+        assert_eq!(report.bots_not_running.len(), 1);
+        assert_eq!(report.bots_not_running[0].user_id, "idle");
+        assert_eq!(report.users_without_bot, vec!["deleted".to_string()]);
+        let orphans: Vec<_> = report.orphan_positions.iter().map(|p| p.user_id.as_str()).collect();
+        assert_eq!(orphans, vec!["ghost", "ghost-holding"]);
+
+        let result = repair_drift(report, &mut users, &positions);
+        assert_eq!(result.removed_users, vec!["deleted".to_string()]);
+        assert_eq!(result.removed_positions.len(), 1);
+        assert_eq!(result.kept_open_positions[0].user_id, "ghost-holding");
+
+        let after = find_drift(&db_bots, &users, &positions);
+        assert!(after.users_without_bot.is_empty());
+        assert_eq!(after.orphan_positions, result.kept_open_positions);
+        // Idle bots are never started by a repair
+        assert_eq!(after.bots_not_running, result.drift.bots_not_running);
+    }
+}
@@ -0,0 +1,185 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Wallet operations that need the co-signer's approval when one is set
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveOperation {
+    RotateWallet,
+    EmergencyWithdrawal,
+    /// Replacing or removing the co-signer itself
+    ChangeCoSigner,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PendingApproval {
+    pub id: String,
+    pub operation: SensitiveOperation,
+    pub owner_id: String,
+    /// Co-signer at request time, whose approval the operation needs
+    pub co_signer_id: String,
+    /// Users that approved so far, the owner first
+    pub approvals: Vec<String>,
+    pub created_at_ms: u64,
+}
+
+impl PendingApproval {
+    /// Opening a request counts as the owner's approval
+    pub fn new(operation: SensitiveOperation, owner_id: String, co_signer_id: String, now_ms: u64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            operation,
+            approvals: vec![owner_id.clone()],
+            owner_id,
+            co_signer_id,
+            created_at_ms: now_ms,
+        }
+    }
+
+    pub fn status(&self, now_ms: u64, window_ms: u64) -> ApprovalStatus {
+        if self.approvals.contains(&self.co_signer_id) {
+            ApprovalStatus::Approved
+        } else if now_ms.saturating_sub(self.created_at_ms) > window_ms {
+            ApprovalStatus::Expired
+        } else {
+            ApprovalStatus::Pending
+        }
+    }
+
+    /// Records `approver`'s approval. Only the co-signer can add the second
+    /// signature, and only inside the window.
+    pub fn approve(&mut self, approver: &str, now_ms: u64, window_ms: u64) -> Result<ApprovalStatus, String> {
+        match self.status(now_ms, window_ms) {
+            ApprovalStatus::Expired => return Err("Approval window has expired".to_string()),
+            ApprovalStatus::Approved => return Ok(ApprovalStatus::Approved),
+            ApprovalStatus::Pending => {}
+        }
+        if self.co_signer_id != approver {
+            return Err("Only the co-signer can approve this operation".to_string());
+        }
+        self.approvals.push(approver.to_string());
+        Ok(self.status(now_ms, window_ms))
+    }
+}
+
+static PENDING_APPROVALS: Lazy<Mutex<HashMap<String, PendingApproval>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+fn window_ms() -> u64 {
+    crate::config::MULTISIG_APPROVAL_WINDOW.as_millis() as u64
+}
+
+/// Opens an approval request for the owner's operation, dropping expired ones
+pub fn open_approval(operation: SensitiveOperation, owner_id: &str, co_signer_id: String) -> PendingApproval {
+    let now = now_ms();
+    let pending = PendingApproval::new(operation, owner_id.to_string(), co_signer_id, now);
+
+    let mut approvals = PENDING_APPROVALS.lock().unwrap_or_else(|e| e.into_inner());
+    approvals.retain(|_, approval| approval.status(now, window_ms()) != ApprovalStatus::Expired);
+    approvals.insert(pending.id.clone(), pending.clone());
+    pending
+}
+
+pub fn approve(approval_id: &str, approver: &str) -> Result<PendingApproval, String> {
+    let mut approvals = PENDING_APPROVALS.lock().unwrap_or_else(|e| e.into_inner());
+    let approval = approvals
+        .get_mut(approval_id)
+        .ok_or_else(|| "Approval request not found".to_string())?;
+    approval.approve(approver, now_ms(), window_ms())?;
+    Ok(approval.clone())
+}
+
+/// Consumes an approved request for `operation`, for the operation's handler
+/// to call before it runs. Each approval authorizes a single run, and only
+/// while `co_signer_id` is still the owner's co-signer.
+pub fn take_approved(
+    approval_id: &str,
+    owner_id: &str,
+    co_signer_id: &str,
+    operation: SensitiveOperation,
+) -> Result<(), String> {
+    let mut approvals = PENDING_APPROVALS.lock().unwrap_or_else(|e| e.into_inner());
+    let approval = approvals
+        .get(approval_id)
+        .filter(|a| a.owner_id == owner_id && a.co_signer_id == co_signer_id && a.operation == operation)
+        .ok_or_else(|| "Approval request not found".to_string())?;
+    match approval.status(now_ms(), window_ms()) {
+        ApprovalStatus::Approved => {
+            approvals.remove(approval_id);
+            Ok(())
+        }
+        ApprovalStatus::Pending => Err("Operation is waiting for the co-signer's approval".to_string()),
+        ApprovalStatus::Expired => Err("Approval window has expired".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: u64 = 300_000;
+
+    #[test]
+    fn co_signer_must_approve_within_the_window() {
+        let mut approval = PendingApproval::new(
+            SensitiveOperation::EmergencyWithdrawal,
+            "owner".to_string(),
+            "co-signer".to_string(),
+            1_000,
+        );
+        assert_eq!(approval.status(1_000, WINDOW), ApprovalStatus::Pending);
+
+        // Neither the owner twice nor a stranger counts as a second signature
+        assert!(approval.approve("owner", 2_000, WINDOW).is_err());
+        assert!(approval.approve("stranger", 2_000, WINDOW).is_err());
+        assert_eq!(approval.status(2_000, WINDOW), ApprovalStatus::Pending);
+
+        assert_eq!(approval.approve("co-signer", 3_000, WINDOW), Ok(ApprovalStatus::Approved));
+    }
+
+    #[test]
+    fn an_approval_runs_its_operation_once() {
+        let pending = open_approval(SensitiveOperation::ChangeCoSigner, "owner", "co-signer".to_string());
+        let take = |operation| take_approved(&pending.id, "owner", "co-signer", operation);
+
+        assert_eq!(
+            take(SensitiveOperation::ChangeCoSigner),
+            Err("Operation is waiting for the co-signer's approval".to_string())
+        );
+        approve(&pending.id, "co-signer").unwrap();
+
+        // Not for another operation, nor once the co-signer changed
+        assert!(take(SensitiveOperation::EmergencyWithdrawal).is_err());
+        assert!(take_approved(&pending.id, "owner", "someone else", SensitiveOperation::ChangeCoSigner).is_err());
+
+        assert_eq!(take(SensitiveOperation::ChangeCoSigner), Ok(()));
+        assert!(take(SensitiveOperation::ChangeCoSigner).is_err());
+    }
+
+    #[test]
+    fn late_approvals_are_refused() {
+        let mut approval = PendingApproval::new(
+            SensitiveOperation::RotateWallet,
+            "owner".to_string(),
+            "co-signer".to_string(),
+            1_000,
+        );
+        let late = 1_000 + WINDOW + 1;
+        assert_eq!(approval.status(late, WINDOW), ApprovalStatus::Expired);
+        assert!(approval.approve("co-signer", late, WINDOW).is_err());
+        assert_eq!(approval.approvals, vec!["owner".to_string()]);
+    }
+}
@@ -6,7 +6,7 @@ use crate::backend::{
     services::bot_service::BotService,
     error::{AppError, AppResult},
     models::{
-        auth::{AuthResponse, SigninRequest, SignupRequest},
+        auth::{AuthResponse, RefreshRequest, SigninRequest, SignupRequest, TokenResponse},
         user::User,
         bot::BotSettings,
     },
@@ -21,13 +21,13 @@ pub struct AuthService {
 }
 
 impl AuthService {
-    pub fn new(database: AppDatabase) -> Self {
-        Self {
+    pub fn new(database: AppDatabase) -> AppResult<Self> {
+        Ok(Self {
             user_repo: UserRepository::new(database.clone()),
             bot_repo: BotRepository::new(database.clone()),
-            jwt_service: JwtService::new(),
+            jwt_service: JwtService::new()?,
             bot_service: BotService::new(database.clone()),
-        }
+        })
     }
 
     /// Register a new user (signup)
@@ -80,13 +80,18 @@ impl AuthService {
 
         let created_bot = self.bot_repo.create(initial_bot.clone()).await?;
 
-        info!("✅ Initial bot created for user: {} - Bot ID: {}", user_id, created_bot.id.unwrap().to_hex());
+        let bot_id = created_bot
+            .id
+            .map(|id| id.to_hex())
+            .ok_or_else(|| AppError::internal("Bot ID missing after creation"))?;
+        info!("✅ Initial bot created for user: {} - Bot ID: {}", user_id, bot_id);
 
         // Generate JWT token
         let token = self
             .jwt_service
             .create_token(&user_id)
             .map_err(|e| AppError::internal(format!("Failed to create JWT: {}", e)))?;
+        let refresh_token = self.jwt_service.create_refresh_token(&user_id)?;
 
         info!(
             "✅ New user signed up: {} ({}) - Public Key: {}",
@@ -95,6 +100,7 @@ impl AuthService {
 
         Ok(AuthResponse {
             token,
+            refresh_token,
             user: created_user.into(),
             bot: created_bot.into(),
         })
@@ -128,13 +134,31 @@ impl AuthService {
             .jwt_service
             .create_token(&user_id)
             .map_err(|e| AppError::internal(format!("Failed to create JWT: {}", e)))?;
+        let refresh_token = self.jwt_service.create_refresh_token(&user_id)?;
 
         info!("✅ User signed in: {}", user.email);
 
         Ok(AuthResponse {
             token,
+            refresh_token,
             user: user.into(),
             bot: bots.first().cloned().unwrap_or_default(),
         })
     }
+
+    /// Issue a new access token for a valid refresh token
+    pub async fn refresh(&self, request: RefreshRequest) -> AppResult<TokenResponse> {
+        let claims = self.jwt_service.verify_refresh_token(&request.refresh_token)?;
+
+        // A deleted user can't renew its session
+        if self.user_repo.find_by_id(&claims.sub).await?.is_none() {
+            return Err(AppError::auth("User no longer exists"));
+        }
+
+        Ok(TokenResponse {
+            token: self.jwt_service.create_token(&claims.sub)?,
+            expires_in: self.jwt_service.access_ttl_secs() as u64,
+            ..TokenResponse::default()
+        })
+    }
 }
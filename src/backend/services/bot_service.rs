@@ -1,19 +1,61 @@
 use crate::backend::{
+    crypto::wallet_cipher,
     db::bot_repository::BotRepository,
     db::connection::AppDatabase,
+    db::pool_price_repository::PoolPriceRepository,
+    db::position_repository::PositionRepository,
     db::user_repository::UserRepository,
     error::{AppError, AppResult},
-    models::bot::{BotSettings, BotSettingsResponse},
+    models::bot::{
+        BotPositionResponse, BotSettings, BotSettingsResponse, BotStatusResponse, BuyPreviewResponse,
+        DryRunReport, EntryReference, ExitParams, FeeBudget, MarketRegime, OpenPositionsResponse, PanicSellResponse,
+        PreviewOverrides, UpdateTradingParamsRequest,
+    },
+    models::pool_price::PoolPrice,
+    models::position::StoredPosition,
+    models::trade::{EntryLatency, FeeBreakdown, FundRoute, RealizedSlippage, SlotTime, TradeData},
 };
-use solana_sdk::instruction::Instruction;
+use crate::utils::{
+    blockhash::{USD1, WSOL},
+    dry_run::dry_run,
+    fee::{
+        break_even_price, lamports_to_sol, min_viable_buy_sol, projected_tx_fee_sol, round_trip_cost_sol,
+        swap_fee_rate,
+    },
+    pool_reserves::fetch_reserves_by_pool,
+    positions::{
+        get_or_insert_position, position, positions_where, remove_user_positions, update_position,
+        update_user_positions, PoolInfoMap,
+    },
+    price::FixedPrice,
+    price_history::PriceHistory,
+    rent::check_buy_balance,
+    swap_quote::buy_min_out,
+    token_balance::{fetch_held_tokens, HeldTokens},
+};
+use dashmap::DashMap;
+use solana_sdk::{instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, system_instruction};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use std::collections::HashMap;
 use tracing::info;
 
+/// Lookback and follow-up swaps used by `RealPoolInfo::sandwich_suspected`
+const SANDWICH_WINDOW_SECS: i64 = 60;
+const SANDWICH_MAX_GAP: usize = 3;
+
+const USD1_DECIMALS: i32 = 6;
+
 pub struct BotService {
     bot_repo: BotRepository,
     user_repo: UserRepository,
+    pool_price_repo: PoolPriceRepository,
+    position_repo: PositionRepository,
 }
 
-#[derive(Debug, Clone)]
+/// A running bot's user, `private_key` is held decrypted in memory only
+#[derive(Clone)]
 pub struct UserBotData {
     pub pool_id: String,
     pub user_id: String,
@@ -22,456 +64,2999 @@ pub struct UserBotData {
     pub bot_setting: BotSettings,
 }
 
+impl std::fmt::Debug for UserBotData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserBotData")
+            .field("pool_id", &self.pool_id)
+            .field("user_id", &self.user_id)
+            .field("private_key", &"<redacted>")
+            .field("public_key", &self.public_key)
+            .field("bot_setting", &self.bot_setting)
+            .finish()
+    }
+}
+
+/// Decrypts a user's stored private key for signing
+fn decrypt_private_key(stored: &str) -> AppResult<String> {
+    wallet_cipher()
+        .and_then(|cipher| cipher.decrypt(stored))
+        .map_err(|e| AppError::internal(format!("Failed to read the wallet key: {}", e)))
+}
+
+/// Adds a confirmed swap's `lamports` of fees and tip to the user's budget
+/// in `budgets`, starting from the `persisted` one the first time this run
+pub fn record_fee_spend(
+    budgets: &DashMap<String, FeeBudget>,
+    user_id: &str,
+    persisted: FeeBudget,
+    lamports: u64,
+    now_ms: i64,
+) -> FeeBudget {
+    let mut budget = budgets.entry(user_id.to_string()).or_insert(persisted);
+    budget.record(lamports, now_ms);
+    *budget
+}
+
+/// The user's fee budget, the live one once a fee was paid this run
+pub fn current_fee_budget(budgets: &DashMap<String, FeeBudget>, user_id: &str, persisted: FeeBudget) -> FeeBudget {
+    budgets.get(user_id).map_or(persisted, |budget| *budget)
+}
+
+/// The user's bot out of what the repository found, a 404 when it was
+/// never created or has been deleted
+fn user_bot(bots: &[BotSettings]) -> AppResult<&BotSettings> {
+    bots.first().ok_or_else(|| AppError::not_found("Bot not found"))
+}
+
+/// Which side of the trade a position's swap instructions are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SwapAction {
+    #[default]
+    Buy,
+    Sell,
+}
+
+impl SwapAction {
+    /// A sell while the position is held, otherwise a buy
+    pub fn from_held(held: bool) -> Self {
+        if held {
+            SwapAction::Sell
+        } else {
+            SwapAction::Buy
+        }
+    }
+}
+
+/// What `BotService::deactivate` did with a bot
+#[derive(Debug)]
+pub enum Deactivation {
+    AlreadyStopped,
+    /// Its position is being sold, the bot stops once that settles
+    Exiting,
+    /// Removed, with the bot it took off `USER_LIST`
+    Stopped(Option<Box<UserBotData>>),
+}
+
+/// What a price update does with a held position
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitSignal {
+    /// `stop_bot` or `panic_sell` asked for the position to be sold now
+    ForceExit,
+    /// A fired DCA level is still waiting for the processors to build its buy
+    DcaWaiting(usize),
+    /// A fired DCA level's buy is built and can go out
+    DcaBuy(usize),
+    /// The price has dropped to a DCA level that hasn't fired yet
+    DcaLevel(usize),
+    /// Take-profit tiers reached, the share of the position they sell and
+    /// whether selling it closes the position
+    TakeProfitTiers { tiers: Vec<usize>, fraction: f64, closes: bool },
+    TakeProfit,
+    /// Drop (%) from the peak past `trailing_stop_pct`
+    TrailingStop(f64),
+    /// At or below the stop level, fixed or moved to break-even
+    StopLoss,
+    AutoExit,
+}
+
+impl ExitSignal {
+    /// Whether the signal sells, as opposed to scaling in
+    pub fn sells(&self) -> bool {
+        !matches!(self, ExitSignal::DcaWaiting(_) | ExitSignal::DcaBuy(_) | ExitSignal::DcaLevel(_))
+    }
+
+    /// Whether the signal is a take-profit, single or tiered
+    pub fn takes_profit(&self) -> bool {
+        matches!(self, ExitSignal::TakeProfit | ExitSignal::TakeProfitTiers { .. })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RealPoolInfo {
     pub pool_price: f64,
     pub user_bot_data: UserBotData,
     pub latest_pool_price: f64,
-    pub swap_buy_ixs: Vec<Instruction>,
+    /// Price the entry drop of `latest_pool_price` is measured against, per
+    /// the bot's `entry_reference`. Every recorded price advances it, however
+    /// often the monitor gets to evaluate them.
+    pub entry_reference_price: Option<f64>,
+    /// Swap instructions to submit on the next signal
+    pub pending_swap_ixs: Vec<Instruction>,
+    /// Whether `pending_swap_ixs` buy or sell
+    pub pending_action: SwapAction,
+    /// Side of the last accepted submission, which its confirmed fill is recorded as
+    pub submitted_action: Option<SwapAction>,
     pub is_bought: bool,
     pub bought_price: Option<f64>,
     pub bought_at: Option<i64>,
     pub initial_wsol_balance: Option<f64>,
     pub signature: Option<String>,
     pub start_time: Option<std::time::Instant>,
+    /// Wall-clock time `start_time` was taken, in milliseconds, for storage
+    pub started_at: i64,
     pub last_profit_sol: Option<f64>,
     pub last_input_lamports_delta: Option<i128>,
     pub last_output_lamports_delta: Option<i128>,
     pub last_roi_pct: Option<f64>,
     pub last_duration: Option<std::time::Duration>,
-    pub fee: f64,
+    /// Network fees of the position's confirmed transactions, in lamports
+    pub fee_lamports: i64,
+    /// Entry input and network fees the round trip's sells were charged so
+    /// far, and the profit they made, in lamports
+    pub charged_input_lamports: i128,
+    pub charged_fee_lamports: i64,
+    pub round_trip_profit_lamports: i128,
+    pub price_history: PriceHistory,
+    pub observations: u64,
+    pub paused: bool,
+    pub route: FundRoute,
+    /// Set by `stop_bot` and `panic_sell` to sell the open position immediately
+    pub force_exit: bool,
+    /// An exit sell has been claimed and hasn't settled yet, so neither a
+    /// repeated `panic_sell`, the sweep nor a concurrent price update submits
+    /// another, see `claim_exit`
+    pub selling_in_progress: bool,
+    /// Highest price seen while holding the current position
+    pub peak_price: Option<f64>,
+    /// The stop loss has moved up to break-even for the rest of the
+    /// position, see `move_stop_to_be_at_pct`
+    pub stop_moved_to_be: bool,
+    /// Program of the pool, known once a swap on it has been seen
+    pub pool_program: Option<Pubkey>,
+    /// When the last position closed, in milliseconds, for `reentry_cooldown_secs`
+    pub last_exit_at: Option<i64>,
+    /// Tokens this position still holds, reduced by each confirmed sell
+    pub held_token_amount: Option<u64>,
+    /// Tokens received by this position's confirmed buy
+    pub bought_token_amount: Option<u64>,
+    /// Which `take_profit_tiers` have sold so far for the current position
+    pub fired_tiers: Vec<bool>,
+    /// Tiers the claimed sell is for, fired again by the next price update
+    /// if it fails
+    pub exit_tiers: Vec<usize>,
+    /// Share of the bought amount the next sell takes, none sells all that is held
+    pub sell_fraction: Option<f64>,
+    /// Why the next sell goes out, set on the copy `submit_exit` submits
+    pub exit_reason: Option<ExitSignal>,
+    /// Price of the position's first buy, DCA levels are measured from it
+    pub initial_bought_price: Option<f64>,
+    /// Which `dca_levels` have fired for the current position
+    pub filled_levels: Vec<bool>,
+    /// DCA level waiting for its buy instructions to be built and submitted
+    pub pending_dca_level: Option<usize>,
+    /// DCA level the held swap instructions buy for, none for entries and sells
+    pub swap_ixs_dca_level: Option<usize>,
+    /// Pool's quote-side (SOL/USD1) reserve after each observed swap
+    pub quote_reserve_history: PriceHistory,
+    /// Fees paid by the current round trip's confirmed fills
+    pub fee_breakdown: FeeBreakdown,
+    /// Signature whose fill has been recorded, by the gRPC stream or the
+    /// confirmation poller, so the other doesn't record it again
+    pub recorded_fill: Option<String>,
+    /// Pool price when the pending swap was submitted, fills are measured against it
+    pub signal_price: Option<f64>,
+    /// Slippage the current round trip's confirmed fills realized
+    pub slippage: RealizedSlippage,
+    /// Where the last observed swap on the pool landed
+    pub last_swap_at: Option<SlotTime>,
+    /// Where the swap that signalled the pending submission landed
+    pub signal_at: Option<SlotTime>,
+    /// How long the current position's entry took to land after its signal
+    pub entry_latency: Option<EntryLatency>,
+    /// When exits failed to submit or land, within `STUCK_EXIT_WINDOW`
+    pub exit_failures: Vec<i64>,
+    /// When repeated exit failures flagged the position as stuck
+    pub stuck_since: Option<i64>,
 }
 
-impl BotService {
-    pub fn new(database: AppDatabase) -> Self {
+impl RealPoolInfo {
+    pub fn new(user_bot_data: UserBotData) -> Self {
         Self {
-            bot_repo: BotRepository::new(database.clone()),
-            user_repo: UserRepository::new(database.clone()),
+            pool_price: 0.0,
+            entry_reference_price: None,
+            user_bot_data,
+            latest_pool_price: 0.0,
+            pending_swap_ixs: vec![],
+            pending_action: SwapAction::default(),
+            submitted_action: None,
+            is_bought: false,
+            bought_price: None,
+            bought_at: None,
+            initial_wsol_balance: Some(0.0),
+            signature: None,
+            start_time: Some(std::time::Instant::now()),
+            started_at: chrono::Utc::now().timestamp_millis(),
+            last_profit_sol: None,
+            last_input_lamports_delta: None,
+            last_output_lamports_delta: None,
+            last_roi_pct: None,
+            last_duration: None,
+            fee_lamports: 0,
+            charged_input_lamports: 0,
+            charged_fee_lamports: 0,
+            round_trip_profit_lamports: 0,
+            price_history: PriceHistory::default(),
+            observations: 0,
+            paused: false,
+            route: FundRoute::default(),
+            force_exit: false,
+            selling_in_progress: false,
+            recorded_fill: None,
+            peak_price: None,
+            stop_moved_to_be: false,
+            pool_program: None,
+            last_exit_at: None,
+            held_token_amount: None,
+            bought_token_amount: None,
+            fired_tiers: Vec::new(),
+            exit_tiers: Vec::new(),
+            sell_fraction: None,
+            exit_reason: None,
+            initial_bought_price: None,
+            filled_levels: Vec::new(),
+            pending_dca_level: None,
+            swap_ixs_dca_level: None,
+            quote_reserve_history: PriceHistory::default(),
+            fee_breakdown: FeeBreakdown::default(),
+            signal_price: None,
+            slippage: RealizedSlippage::default(),
+            last_swap_at: None,
+            signal_at: None,
+            entry_latency: None,
+            exit_failures: Vec::new(),
+            stuck_since: None,
         }
     }
 
-    /// Create a new bot for a user
-    pub async fn create_bot(
-        &self,
-        user_id: String,
-        name: String,
-        pool_address: String,
-    ) -> AppResult<BotSettingsResponse> {
-        // Check if user already has a bot with this name
-        let existing_bots = self.bot_repo.find_by_user_id(&user_id).await?;
-        if existing_bots.iter().any(|bot| bot.name == name) {
-            return Err(AppError::conflict("Bot name already exists for this user"));
-        }
+    /// Reloads a position persisted before a restart. `start_time` is
+    /// rebuilt from the stored wall-clock time, as far as the clock allows.
+    pub fn restore(&mut self, stored: &StoredPosition, now_ms: i64) {
+        self.is_bought = stored.is_bought;
+        self.bought_price = stored.bought_price;
+        self.bought_at = stored.bought_at;
+        self.signature = stored.signature.clone();
+        self.last_input_lamports_delta = stored.last_input_lamports_delta.map(i128::from);
+        self.fee_lamports = stored.fee_lamports;
+        self.last_exit_at = stored.last_exit_at;
+        self.started_at = stored.started_at;
+        self.route = stored.route.clone();
+        self.entry_latency = stored.entry_latency;
+        self.held_token_amount = stored.held_token_amount.and_then(|amount| u64::try_from(amount).ok());
+        self.bought_token_amount = stored.bought_token_amount.and_then(|amount| u64::try_from(amount).ok());
+        let held_for = std::time::Duration::from_millis((now_ms - stored.started_at).max(0) as u64);
+        self.start_time = std::time::Instant::now().checked_sub(held_for).or(self.start_time);
+    }
 
-        let bot = BotSettings::new(user_id, name, pool_address);
-        let created_bot = self.bot_repo.create(bot).await?;
+    /// Claims the position's exit sell, false while another is in flight
+    pub fn claim_exit(&mut self) -> bool {
+        !std::mem::replace(&mut self.selling_in_progress, true)
+    }
 
-        info!(
-            "✅ New bot created: {} for user: {}",
-            created_bot.name, created_bot.user_id
-        );
+    /// Count a failed exit, returning true when it's the one that makes
+    /// `max_failures` within `window_ms` and flags the position stuck. Zero
+    /// `max_failures` never flags. The position stays open and its claim and
+    /// tiers are released, so the next price update retries the exit.
+    pub fn record_exit_failure(&mut self, now_ms: i64, max_failures: u32, window_ms: i64) -> bool {
+        self.selling_in_progress = false;
+        for tier in std::mem::take(&mut self.exit_tiers) {
+            if let Some(fired) = self.fired_tiers.get_mut(tier) {
+                *fired = false;
+            }
+        }
+        self.exit_failures.retain(|ts| now_ms - ts < window_ms);
+        self.exit_failures.push(now_ms);
+        if max_failures == 0 || self.stuck_since.is_some() || self.exit_failures.len() < max_failures as usize {
+            return false;
+        }
+        self.stuck_since = Some(now_ms);
+        true
+    }
 
-        Ok(created_bot.into())
+    /// A confirmed exit means the position isn't stuck anymore
+    pub fn clear_exit_failures(&mut self) {
+        self.exit_failures.clear();
+        self.stuck_since = None;
     }
 
-    /// Get all bots for a user
-    pub async fn get_user_bots(&self, user_id: &str) -> AppResult<Vec<BotSettingsResponse>> {
-        let bots = self.bot_repo.find_by_user_id(user_id).await?;
-        Ok(bots.into_iter().map(|bot| bot.into()).collect())
+    /// Seed the reference price from a persisted observation so the first
+    /// stream update can already be compared against something
+    pub fn warm_start(&mut self, price: f64, observed_at_ms: i64) {
+        if price <= 0.0 {
+            return;
+        }
+        self.pool_price = price;
+        self.entry_reference_price = Some(price);
+        self.price_history.push(observed_at_ms, price);
     }
 
-    /// Update bot trading parameters
-    pub async fn update_trading_params(
-        &self,
-        user_id: &str,
-        pool_address: Option<String>,
-        buy_sol_amount: Option<f64>,
-        buy_usd1_amount: Option<f64>,
-        entry_percent: Option<f64>,
-        entry_slippage: Option<f64>,
-        exit_slippage: Option<f64>,
-        stop_loss: Option<f64>,
-        take_profit: Option<f64>,
-        auto_exit: Option<u64>,
-    ) -> AppResult<BotSettingsResponse> {
-        let mut bot = self
-            .bot_repo
-            .find_by_user_id(user_id)
-            .await?
-            .first()
-            .ok_or_else(|| AppError::not_found("Bot not found"))?
-            .clone();
+    /// A fresh position for `user_bot_data`, warm started from the pool's
+    /// persisted price unless it's older than `max_age`
+    pub fn restored(
+        user_bot_data: UserBotData,
+        stored: Option<&PoolPrice>,
+        now_ms: i64,
+        max_age: std::time::Duration,
+    ) -> Self {
+        let mut info = Self::new(user_bot_data);
+        if let Some(stored) = stored {
+            let observed_at_ms = stored.updated_at.timestamp_millis();
+            if now_ms - observed_at_ms <= max_age.as_millis() as i64 {
+                println!("🔥 Warm starting pool {} from price {:.8}", stored.pool_address, stored.price);
+                info.warm_start(stored.price, observed_at_ms);
+            }
+        }
+        info
+    }
 
-        bot.update_trading_params(
-            pool_address,
-            buy_sol_amount,
-            buy_usd1_amount,
-            entry_percent,
-            entry_slippage,
-            exit_slippage,
-            stop_loss,
-            take_profit,
-            auto_exit,
-        );
+    /// Store a freshly observed pool price
+    pub fn record_price(&mut self, price: f64) {
+        self.record_price_at(price, chrono::Utc::now().timestamp_millis());
+    }
 
-        self.bot_repo.update(&bot).await?;
+    fn record_price_at(&mut self, price: f64, now_ms: i64) {
+        // The warm-start price stands in for the last one until a swap is seen
+        let previous = if self.latest_pool_price > 0.0 {
+            self.latest_pool_price
+        } else {
+            self.pool_price
+        };
+        self.latest_pool_price = price;
+        if self.is_bought && price > 0.0 {
+            self.peak_price = Some(self.peak_price.map_or(price, |peak| peak.max(price)));
+        }
+        self.price_history.push(now_ms, price);
+        self.entry_reference_price = self
+            .reference_price_for(previous, now_ms)
+            .filter(|reference| *reference > 0.0);
+        self.observations += 1;
+        if let Some(updates) = crate::statics::PRICE_UPDATES.get() {
+            let _ = updates.send(PriceUpdate {
+                pool_id: self.user_bot_data.pool_id.clone(),
+                user_id: self.user_bot_data.user_id.clone(),
+                price,
+                entry_reference_price: self.entry_reference_price,
+            });
+        }
+    }
 
-        info!("✅ Bot trading parameters updated: {}", bot.name);
+    /// Stores an accepted submission's signature and which side it was. A buy
+    /// while not yet bought opens the position, which is returned.
+    pub fn record_submission(&mut self, signature: &str, action: SwapAction) -> bool {
+        self.signature = Some(signature.to_string());
+        self.submitted_action = Some(action);
+        let has_bought = action == SwapAction::Buy && !self.is_bought;
+        if has_bought {
+            self.is_bought = true;
+        }
+        has_bought
+    }
 
-        Ok(bot.into())
+    /// Whether `signature` is this position's submitted swap
+    pub fn matches_submitted_signature(&self, signature: &str) -> bool {
+        self.signature.as_deref() == Some(signature)
     }
 
-    /// Update bot MEV configuration
-    pub async fn update_mev_config(
-        &self,
-        user_id: &str,
-        confirm_service: Option<String>,
-        cu: Option<u64>,
-        priority_fee: Option<u64>,
-        third_party_fee: Option<f64>,
-    ) -> AppResult<BotSettingsResponse> {
-        let mut bot = self
-            .bot_repo
-            .find_by_user_id(user_id)
-            .await?
-            .first()
-            .ok_or_else(|| AppError::not_found("Bot not found"))?
-            .clone();
+    /// Claims the fill of `signature` for recording, true only the first time
+    /// it is claimed and only for a signature this position submitted
+    pub fn claim_fill(&mut self, signature: &str) -> bool {
+        if self.recorded_fill.as_deref() == Some(signature) {
+            return false;
+        }
+        let submitted = self.matches_submitted_signature(signature);
+        if submitted {
+            self.recorded_fill = Some(signature.to_string());
+        }
+        submitted
+    }
 
-        bot.update_mev_config(confirm_service, cu, priority_fee, third_party_fee);
+    /// Rolls back an entry that never landed: `is_bought` flips on submit, so a
+    /// buy that failed or expired would otherwise leave a phantom position.
+    /// Only applies while `signature` is still the pending submission and
+    /// its fill hasn't been recorded.
+    pub fn abandon_unfilled_entry(&mut self, signature: &str) -> bool {
+        let pending = self.matches_submitted_signature(signature);
+        if !pending || self.recorded_fill.as_deref() == Some(signature) || !self.is_bought {
+            return false;
+        }
+        self.is_bought = false;
+        self.signature = None;
+        self.bought_price = None;
+        self.bought_at = None;
+        true
+    }
 
-        self.bot_repo.update(&bot).await?;
+    /// Opens a paper position the way a confirmed buy of `input_lamports` at
+    /// the latest pool price would, with nothing sent. It holds
+    /// `input_lamports / price` paper tokens, so its sells are sized and
+    /// priced like real ones. Returns whether a position was opened.
+    pub fn record_paper_buy(&mut self, input_lamports: u64, now_ms: i64) -> bool {
+        let price = self.latest_pool_price;
+        if self.is_bought || price <= 0.0 {
+            return false;
+        }
+        self.is_bought = true;
+        self.bought_price = Some(price);
+        self.bought_at = Some(now_ms);
+        self.start_time = Some(std::time::Instant::now());
+        self.record_input_lamports(input_lamports as i128);
+        let tokens = (input_lamports as f64 / price).round() as u64;
+        self.held_token_amount = Some(tokens);
+        self.bought_token_amount = Some(tokens);
+        true
+    }
 
-        info!("✅ Bot MEV configuration updated: {}", bot.name);
+    /// Fills a paper sell of `tokens` at the latest pool price and records
+    /// its profit like a confirmed sell's. None without a paper position.
+    pub fn record_paper_sell(&mut self, tokens: u64) -> Option<(f64, f64)> {
+        let held = self.held_token_amount.filter(|_| self.is_bought)?;
+        let sold = tokens.min(held);
+        let output_lamports = (sold as f64 * self.latest_pool_price).round() as i128;
+        self.held_token_amount = Some(held - sold).filter(|held| *held > 0);
+        Some(self.record_exit_profit(output_lamports, Some(sold)))
+    }
 
-        Ok(bot.into())
+    /// Store the price and quote reserve left by an observed swap on
+    /// `program`'s pool landing `at`
+    pub fn record_swap(&mut self, program: Pubkey, price: f64, quote_reserve: f64, at: SlotTime) {
+        self.pool_program = Some(program);
+        self.quote_reserve_history
+            .push(chrono::Utc::now().timestamp_millis(), quote_reserve);
+        self.last_swap_at = Some(at);
+        self.record_price(price);
     }
 
-    /// Simple cleanup function that can be called without self
-    async fn simple_cleanup(user_id: &str) {
-        println!("🧹 Simple cleanup for user: {}", user_id);
-        
-        // Remove from USER_LIST with timeout
-        {
-            let user_list_result = tokio::time::timeout(
-                std::time::Duration::from_millis(500),
-                crate::statics::USER_LIST.write()
-            ).await;
-            
-            match user_list_result {
-                Ok(mut user_list) => {
-                    let initial_count = user_list.len();
-                    user_list.retain(|existing_user| existing_user.user_id != user_id);
-                    let final_count = user_list.len();
-                    if initial_count != final_count {
-                        println!("🧹 Simple cleanup: Removed {} entries from USER_LIST", initial_count - final_count);
-                    }
-                },
-                Err(_) => {
-                    println!("⚠️ USER_LIST write lock timeout, skipping cleanup");
-                }
-            }
+    /// Measure the entry buy that landed `at` against the swap that
+    /// signalled it. Scale-in fills keep the first entry's latency.
+    pub fn record_entry_fill(&mut self, at: SlotTime) {
+        if self.scaled_in() {
+            return;
         }
-        
-        // Remove from REAL_POOL_INFO with timeout
-        {
-            let real_pool_info_result = tokio::time::timeout(
-                std::time::Duration::from_millis(500),
-                crate::statics::REAL_POOL_INFO.write()
-            ).await;
-            
-            match real_pool_info_result {
-                Ok(mut real_pool_info) => {
-                    real_pool_info.retain(|_pool_id, pool_infos| {
-                        let initial_users = pool_infos.len();
-                        pool_infos.retain(|pool_info| pool_info.user_bot_data.user_id != user_id);
-                        let final_users = pool_infos.len();
-                        if initial_users != final_users {
-                            println!("🧹 Simple cleanup: Removed {} entries from pool", initial_users - final_users);
-                        }
-                        !pool_infos.is_empty()
-                    });
-                },
-                Err(_) => {
-                    println!("⚠️ REAL_POOL_INFO write lock timeout, skipping cleanup");
-                }
-            }
+        let settings = &self.user_bot_data.bot_setting;
+        self.entry_latency = self
+            .signal_at
+            .and_then(|signal| EntryLatency::between(signal, at))
+            .map(|latency| latency.judged(settings.slow_entry_slots, settings.slow_entry_ms));
+    }
+
+    /// Whether the pool's quote reserve is being pulled out fast enough to
+    /// look like a rug or coordinated dump rather than a dip: it fell
+    /// `drain_outflow_pct` from its high over the last `drain_window_secs`
+    /// across at least `drain_min_sells` reserve-reducing swaps.
+    pub fn is_draining(&self, now_ms: i64) -> bool {
+        let settings = &self.user_bot_data.bot_setting;
+        if !settings.drain_detection {
+            return false;
         }
-        println!("🧹 Simple cleanup completed for user: {}", user_id);
+        let since_ms = now_ms - (settings.drain_window_secs as i64) * 1000;
+        let (Some(high), Some(latest)) = (
+            self.quote_reserve_history.max_since(since_ms),
+            self.quote_reserve_history.latest(),
+        ) else {
+            return false;
+        };
+
+        let outflow_pct = (high - latest) / high * 100.0;
+        outflow_pct >= settings.drain_outflow_pct
+            && self.quote_reserve_history.declines_since(since_ms)
+                >= settings.drain_min_sells as usize
     }
 
-    pub async fn start_bot(&self, user_id: &str) -> AppResult<String> {
-        println!("🚀 Starting bot for user_id: {}", user_id);
-        
-        // Simple cleanup attempt with timeout
-        println!("🧹 Attempting quick cleanup...");
-        let user_id_clone = user_id.to_string();
-        let cleanup_result = tokio::time::timeout(
-            std::time::Duration::from_millis(200),
-            Self::simple_cleanup(&user_id_clone)
-        ).await;
-        
-        match cleanup_result {
-            Ok(_) => println!("✅ Quick cleanup completed"),
-            Err(_) => println!("⚠️ Quick cleanup timed out, continuing..."),
+    /// Whether the pool's recent swaps look like sandwiches: a quote reserve
+    /// jump of `sandwich_min_move_pct` from one swap, given back within the
+    /// next few swaps of the last minute
+    pub fn sandwich_suspected(&self, now_ms: i64) -> bool {
+        let settings = &self.user_bot_data.bot_setting;
+        if !settings.anti_sandwich {
+            return false;
         }
-        
-        // Check if user already exists in USER_LIST (quick check)
-        {
-            let user_list = crate::statics::USER_LIST.read().await;
-            if user_list.iter().any(|u| u.user_id == user_id) {
-                println!("⚠️ User {} already exists in USER_LIST, will be replaced", user_id);
-            }
+        let since_ms = now_ms - SANDWICH_WINDOW_SECS * 1000;
+        self.quote_reserve_history
+            .round_trips_since(since_ms, settings.sandwich_min_move_pct, SANDWICH_MAX_GAP)
+            > 0
+    }
+
+    /// Store the swap instructions to submit on the next signal and which
+    /// side they are. Observer bots never hold instructions, so nothing can
+    /// be sent on their behalf. `dca_level` marks instructions built to scale
+    /// in at that level.
+    pub fn set_swap_ixs(&mut self, ixs: Vec<Instruction>, action: SwapAction, dca_level: Option<usize>) {
+        if self.user_bot_data.bot_setting.observer {
+            self.pending_swap_ixs.clear();
+            return;
         }
-        
-        // Check if user exists in database
-        let user = match self.user_repo.find_by_id(user_id).await? {
-            Some(user) => {
-                println!("✅ User found: {}", user_id);
-                user
-            },
-            None => {
-                println!("❌ User not found: {}", user_id);
-                return Err(AppError::not_found("User not found"));
-            }
-        };
+        self.pending_swap_ixs = ixs;
+        self.pending_action = action;
+        self.swap_ixs_dca_level = dca_level;
+    }
 
-        // Check if bot exists
-        let bot = self.bot_repo.find_by_user_id(user_id).await?;
-        if bot.is_empty() {
-            println!("❌ No bot found for user: {}", user_id);
-            return Err(AppError::not_found("Bot not found"));
+    /// Whether the bot's mint lists let it enter a position in `mint`. An
+    /// excluded mint drops the pending entry, so instructions built before
+    /// the lists changed aren't submitted either.
+    pub fn entry_allowed(&mut self, mint: &str) -> bool {
+        if self.user_bot_data.bot_setting.mint_allowed(mint) {
+            return true;
         }
-        
-        let bot_settings = bot.first().unwrap();
-        let pool_id = bot_settings.pool_address.clone();
-        println!("✅ Bot found with pool_id: {}", pool_id);
+        if !self.is_bought {
+            self.pending_swap_ixs.clear();
+        }
+        false
+    }
 
-        // Create UserBotData and add to USER_LIST
-        let user_bot_data = UserBotData {
-            pool_id: pool_id.clone(),
-            user_id: user_id.to_string(),
-            private_key: user.private_key.clone(),
-            public_key: user.public_key.clone(),
-            bot_setting: bot_settings.clone(),
-        };
+    /// Settles an exit whose fill has been recorded: releases its claim, and
+    /// once nothing is held closes the position and its round trip. Returns
+    /// the round trip's trade record and whether the bot is stopping.
+    pub fn settle_exit(&mut self, now_ms: i64) -> Option<(TradeData, bool)> {
+        self.selling_in_progress = false;
+        self.exit_tiers.clear();
+        if self.held_token_amount.is_some() {
+            return None;
+        }
+        self.close_position(now_ms);
+        Some((self.finish_round_trip(), self.force_exit))
+    }
 
-        println!("USER_BOT_DATA: {:#?}", user_bot_data);
+    /// Closes the position after its sell but keeps the bot armed for the
+    /// next entry, noting the exit at `now_ms` for the re-entry cooldown.
+    /// Runs once the closing sell's fill is recorded, see `settle_exit`.
+    pub fn close_position(&mut self, now_ms: i64) {
+        self.is_bought = false;
+        self.peak_price = None;
+        self.stop_moved_to_be = false;
+        self.fired_tiers.clear();
+        self.filled_levels.clear();
+        self.pending_dca_level = None;
+        self.initial_bought_price = None;
+        self.last_exit_at = Some(now_ms);
+    }
 
-        // Add to USER_LIST (with fallback cleanup if needed)
-        {
-            let user_list_result = tokio::time::timeout(
-                std::time::Duration::from_millis(500),
-                crate::statics::USER_LIST.write()
-            ).await;
-            
-            match user_list_result {
-                Ok(mut user_list) => {
-                    // Remove any existing entries for this user (fallback)
-                    let initial_count = user_list.len();
-                    user_list.retain(|existing_user| existing_user.user_id != user_id);
-                    let final_count = user_list.len();
-                    if initial_count != final_count {
-                        println!("🧹 Fallback: Removed {} existing entries from USER_LIST", initial_count - final_count);
-                    }
-                    
-                    user_list.push(user_bot_data.clone());
-                    println!("✅ USER_LIST: Added user, total users: {}", user_list.len());
-                },
-                Err(_) => {
-                    println!("⚠️ Could not acquire USER_LIST write lock, trying alternative approach...");
-                    
-                    // Try to add in background
-                    let user_bot_data_clone = user_bot_data.clone();
-                    let user_id_clone = user_id.to_string();
-                    tokio::spawn(async move {
-                        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                        if let Ok(mut user_list) = crate::statics::USER_LIST.try_write() {
-                            user_list.retain(|existing_user| existing_user.user_id != user_id_clone);
-                            user_list.push(user_bot_data_clone);
-                            println!("✅ USER_LIST: Added user in background");
-                        }
-                    });
-                    println!("⚠️ USER_LIST: Will be added in background");
-                }
-            }
+    /// Whether `reentry_cooldown_secs` has passed since the last exit. Until
+    /// it has, a pending entry is dropped so it isn't submitted either.
+    pub fn reentry_cooldown_elapsed(&mut self, now_ms: i64) -> bool {
+        let cooling = self.cooling_down(now_ms);
+        if cooling && !self.is_bought {
+            self.pending_swap_ixs.clear();
         }
+        !cooling
+    }
 
-        // Create initial RealPoolInfo and add to REAL_POOL_INFO
-        println!("🔧 Creating RealPoolInfo...");
-        
-        // Try to get write lock with timeout
-        let real_pool_info_result = tokio::time::timeout(
-            std::time::Duration::from_millis(500),
-            crate::statics::REAL_POOL_INFO.write()
-        ).await;
-        
-        match real_pool_info_result {
-            Ok(mut real_pool_info) => {
-                println!("🔧 Got write lock on REAL_POOL_INFO");
-                
-                // Fallback cleanup: remove any existing entries for this user
-                real_pool_info.retain(|_pool_id, pool_infos| {
-                    let initial_users = pool_infos.len();
-                    pool_infos.retain(|pool_info| pool_info.user_bot_data.user_id != user_id);
-                    let final_users = pool_infos.len();
-                    if initial_users != final_users {
-                        println!("🧹 Fallback: Removed {} existing entries from pool", initial_users - final_users);
-                    }
-                    !pool_infos.is_empty() // Keep the pool entry only if it has remaining users
-                });
-                
-                println!("🔧 Creating RealPoolInfo struct...");
-                let initial_pool_info = RealPoolInfo {
-                    pool_price: 0.0,
-                    user_bot_data: user_bot_data.clone(),
-                    latest_pool_price: 0.0,
-                    swap_buy_ixs: vec![],
-                    is_bought: false,
-                    bought_price: None,
-                    bought_at: None,
-                    initial_wsol_balance: Some(0.0),
-                    signature: None,
-                    start_time: Some(std::time::Instant::now()),
-                    last_profit_sol: None,
-                    last_input_lamports_delta: None,
-                    last_output_lamports_delta: None,
-                    last_roi_pct: None,
-                    last_duration: None,
-                    fee: 0.01,
-                };
-                println!("🔧 RealPoolInfo created successfully");
-
-                if let Some(pool_infos) = real_pool_info.get_mut(&pool_id) {
-                    // Pool exists, add user
-                    pool_infos.push(initial_pool_info);
-                    println!("✅ REAL_POOL_INFO (existing pool): Added user, total users: {}", pool_infos.len());
-                } else {
-                    // Pool doesn't exist, create new pool entry
-                    real_pool_info.insert(pool_id, vec![initial_pool_info]);
-                    println!("✅ REAL_POOL_INFO (new pool): Created new pool with 1 user");
-                }
-            },
-            Err(_) => {
-                println!("⚠️ Could not acquire REAL_POOL_INFO write lock, trying alternative approach...");
-                
-                // Alternative: try to add without cleanup (let the system handle duplicates)
-                let initial_pool_info = RealPoolInfo {
-                    pool_price: 0.0,
-                    user_bot_data: user_bot_data.clone(),
-                    latest_pool_price: 0.0,
-                    swap_buy_ixs: vec![],
-                    is_bought: false,
-                    bought_price: None,
-                    bought_at: None,
-                    initial_wsol_balance: Some(0.0),
-                    signature: None,
-                    start_time: Some(std::time::Instant::now()),
-                    last_profit_sol: None,
-                    last_input_lamports_delta: None,
-                    last_output_lamports_delta: None,
-                    last_roi_pct: None,
-                    last_duration: None,
-                    fee: 0.01,
-                };
-                
-                // Try to add in background
-                let pool_id_clone = pool_id.clone();
-                let user_id_clone = user_id.to_string();
-                tokio::spawn(async move {
-                    // Wait a bit and try again
-                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                    if let Ok(mut real_pool_info) = crate::statics::REAL_POOL_INFO.try_write() {
-                        // Clean up first
-                        real_pool_info.retain(|_pool_id, pool_infos| {
-                            pool_infos.retain(|pool_info| pool_info.user_bot_data.user_id != user_id_clone);
-                            !pool_infos.is_empty()
-                        });
-                        
-                        // Add new entry
-                        if let Some(pool_infos) = real_pool_info.get_mut(&pool_id_clone) {
-                            pool_infos.push(initial_pool_info);
-                        } else {
-                            real_pool_info.insert(pool_id_clone, vec![initial_pool_info]);
-                        }
-                        println!("✅ REAL_POOL_INFO: Added user in background");
-                    }
-                });
-                println!("⚠️ REAL_POOL_INFO: Will be added in background");
-            }
+    /// Whether the last exit is still within `reentry_cooldown_secs` at `now_ms`
+    pub fn cooling_down(&self, now_ms: i64) -> bool {
+        let cooldown_ms = self.user_bot_data.bot_setting.reentry_cooldown_secs as i64 * 1000;
+        self.last_exit_at.is_some_and(|exit| now_ms - exit < cooldown_ms)
+    }
+
+    /// Whether the held instructions sell the position. An exit that fires
+    /// while a DCA buy is pending or held drops it, so the processors build
+    /// the sell on the next swap instead of the buy going out as the exit.
+    pub fn ready_to_sell(&mut self) -> bool {
+        let observer = self.user_bot_data.bot_setting.observer;
+        if observer || (self.pending_action == SwapAction::Sell && self.swap_ixs_dca_level.is_none()) {
+            return true;
         }
+        self.pending_dca_level = None;
+        if self.swap_ixs_dca_level.take().is_some() {
+            self.pending_swap_ixs.clear();
+        }
+        false
+    }
 
-        Ok("Started bot".to_string())
+    /// Whether the next swap built for this position is a buy
+    pub fn buying(&self) -> bool {
+        !self.is_bought || self.pending_dca_level.is_some()
     }
 
-    pub async fn stop_bot(&self, user_id: &str) -> AppResult<String> {
-        println!("🛑 stop_bot called for user_id: {}", user_id);
-        let mut is_bought = false;
-        let bot = self.bot_repo.find_by_user_id(user_id).await?;
-        let bot_settings = bot.first().unwrap();
-        let pool_id = bot_settings.pool_address.clone();
+    /// Whether the fill being confirmed is a buy, as its submission recorded
+    pub fn confirming_buy(&self) -> bool {
+        self.submitted_action == Some(SwapAction::Buy)
+    }
 
-        {
-            let mut real_pool_info = crate::statics::REAL_POOL_INFO.write().await;
-            if let Some(pool_info) = real_pool_info.get_mut(&pool_id) {
-                for info in pool_info {
-                    if info.user_bot_data.user_id.to_string() == user_id.to_string() {
-                        is_bought = info.is_bought;
-                    }
-                }
-            }
-        } // Write lock is dropped here
-        println!("IS_BOUGHT: {}", is_bought);
+    /// Whether a DCA level has fired for the current position, so buy fills
+    /// add to it instead of starting a new one
+    pub fn scaled_in(&self) -> bool {
+        self.filled_levels.iter().any(|filled| *filled)
+    }
 
-        if !is_bought {
-            // Remove from USER_LISTz
-            let mut user_list = crate::statics::USER_LIST.write().await;
-            if user_list
-                .iter()
-                .any(|user_bot_data| user_bot_data.user_id == user_id)
-            {
-                user_list.retain(|user_bot_data| user_bot_data.user_id != user_id);
+    /// First unfired DCA level the price has dropped to. Levels wait for the
+    /// first buy to land and fire one at a time; a level the pool never
+    /// reaches simply never deploys its SOL.
+    pub fn dca_level_due(&self, price: f64) -> Option<usize> {
+        let settings = &self.user_bot_data.bot_setting;
+        if self.pending_dca_level.is_some() || self.held_token_amount.is_none() {
+            return None;
+        }
+        let reference = self.initial_bought_price.or(self.bought_price)?;
+        if reference <= 0.0 {
+            return None;
+        }
+        let drop_pct = (reference - price) / reference * 100.0;
+        (0..settings.dca_levels.len().min(settings.dca_amounts.len())).find(|&level| {
+            !self.filled_levels.get(level).copied().unwrap_or(false)
+                && drop_pct >= settings.dca_levels[level]
+        })
+    }
+
+    /// Marks `level` as fired so the processors build its buy
+    pub fn fire_dca_level(&mut self, level: usize) {
+        self.filled_levels
+            .resize(self.user_bot_data.bot_setting.dca_levels.len(), false);
+        if let Some(filled) = self.filled_levels.get_mut(level) {
+            *filled = true;
+            self.initial_bought_price = self.initial_bought_price.or(self.bought_price);
+            self.pending_dca_level = Some(level);
+        }
+    }
+
+    /// Adds a confirmed scale-in to the position, averaging `bought_price`
+    /// weighted by the SOL each fill spent so TP/SL keep working
+    pub fn record_scale_in_fill(&mut self, sol_in_lamports: i128, tokens_received: i128, price: f64) {
+        let invested = self.route.sol_in_lamports as f64;
+        let added = sol_in_lamports as f64;
+        if let Some(average) = self.bought_price {
+            if invested > 0.0 && added > 0.0 && average > 0.0 && price > 0.0 {
+                self.bought_price = Some((invested + added) / (invested / average + added / price));
             }
+        }
+        self.route.add_buy(sol_in_lamports, tokens_received);
+        let tokens = u64::try_from(tokens_received).unwrap_or(0);
+        self.held_token_amount = Some(self.held_token_amount.unwrap_or(0) + tokens);
+        self.bought_token_amount = Some(self.bought_token_amount.unwrap_or(0) + tokens);
+    }
 
-            // Remove from REAL_POOL_INFO if it has data
-            {
-                let mut real_pool_info = crate::statics::REAL_POOL_INFO.write().await;
-                println!("REAL_POOL_INFO length: {}", real_pool_info.len());
-                println!("REAL_POOL_INFO keys: {:?}", real_pool_info.keys().collect::<Vec<_>>());
-                if real_pool_info.len() > 0 {
-                    real_pool_info.retain(|_pool_id, pool_infos| {
-                        pool_infos.retain(|pool_info| pool_info.user_bot_data.user_id != user_id);
-                        !pool_infos.is_empty() // Keep the pool entry only if it has remaining users
-                    });
-                }
+    /// Adds a confirmed transaction's network fee
+    pub fn record_network_fee(&mut self, lamports: u64) {
+        self.fee_lamports += lamports as i64;
+    }
+
+    /// Lamports spent by a confirmed buy, summed across scale-ins
+    pub fn record_input_lamports(&mut self, lamports: i128) {
+        self.last_input_lamports_delta = match self.last_input_lamports_delta {
+            Some(previous) if self.scaled_in() => Some(previous + lamports),
+            _ => Some(lamports),
+        };
+    }
+
+    /// Records the exit of a sell of `tokens_sold` that returned
+    /// `output_lamports`: profit is that minus its share of the SOL the buys
+    /// spent and the network fees not charged to an earlier sell, ROI is the
+    /// profit over that share. A partial sell's share is the fraction of the
+    /// bought tokens it sold, the sell leaving nothing held gets the rest.
+    /// Returns both, profit in SOL.
+    pub fn record_exit_profit(&mut self, output_lamports: i128, tokens_sold: Option<u64>) -> (f64, f64) {
+        self.last_output_lamports_delta = Some(output_lamports);
+        let entry_input = self.last_input_lamports_delta.unwrap_or(0);
+        let input_lamports = match (tokens_sold, self.bought_token_amount, self.held_token_amount) {
+            (Some(sold), Some(bought), Some(_)) if bought > 0 => {
+                entry_input * sold.min(bought) as i128 / bought as i128
             }
+            _ => entry_input - self.charged_input_lamports,
+        };
+        let fee_lamports = self.fee_lamports - self.charged_fee_lamports;
+        self.charged_input_lamports += input_lamports;
+        self.charged_fee_lamports = self.fee_lamports;
 
-            info!("✅ Bot stopped for user: {}", user_id);
-            return Ok("Stopped bot".to_string());
+        let profit_lamports = output_lamports - input_lamports - fee_lamports as i128;
+        self.round_trip_profit_lamports += profit_lamports;
+        let profit_sol = profit_lamports as f64 / 1_000_000_000.0;
+        let roi = if input_lamports > 0 {
+            profit_lamports as f64 / input_lamports as f64 * 100.0
         } else {
-            // Bot has bought tokens, need to sell them first
-            let mut real_pool_info = crate::statics::REAL_POOL_INFO.write().await;
-            println!("🔄 Bot stopping - triggering sell for user: {}", user_id);
-            if real_pool_info.len() > 0 {
-                if let Some(pool_info) = real_pool_info.get_mut(&pool_id) {
-                    for info in pool_info {
-                        if info.user_bot_data.user_id.to_string() == user_id {
-                            // Set auto_exit to 0 to trigger immediate sell
-                            info.user_bot_data.bot_setting.auto_exit = 0;
-                        }
-                    }
-                }
-            }
+            0.0
+        };
+        self.last_profit_sol = Some(profit_sol);
+        self.last_roi_pct = Some(roi);
+        (profit_sol, roi)
+    }
+
+    /// Closes the books on a round trip once its last sell is recorded:
+    /// returns its trade record and clears the fees, input, route and
+    /// slippage it accumulated so the next entry starts from nothing
+    pub fn finish_round_trip(&mut self) -> TradeData {
+        let fee_lamports = std::mem::take(&mut self.fee_lamports);
+        let route = std::mem::take(&mut self.route);
+        let slippage = std::mem::take(&mut self.slippage);
+        let input_lamports = self.last_input_lamports_delta.take().unwrap_or(0);
+        let profit_lamports = std::mem::take(&mut self.round_trip_profit_lamports);
+        self.charged_input_lamports = 0;
+        self.charged_fee_lamports = 0;
+        let roi = if input_lamports > 0 {
+            profit_lamports as f64 / input_lamports as f64 * 100.0
+        } else {
+            0.0
+        };
+        TradeData::new(
+            self.user_bot_data.user_id.clone(),
+            profit_lamports as f64 / 1_000_000_000.0,
+            fee_lamports,
+            lamports_to_sol(fee_lamports),
+            roi,
+            self.last_duration.map(|duration| duration.as_millis() as i64).unwrap_or(0),
+            (route.sol_out_lamports != 0).then_some(route),
+            Some(self.user_bot_data.bot_setting.strategy_tag()),
+            Some(std::mem::take(&mut self.fee_breakdown)),
+            Some(self.user_bot_data.pool_id.clone()),
+            (!slippage.is_empty()).then_some(slippage),
+            self.entry_latency.take(),
+        )
+    }
+
+    /// Whether enough swaps have been seen to trust the pool price for entries
+    pub fn has_min_observations(&self) -> bool {
+        self.observations >= self.user_bot_data.bot_setting.min_observations_before_trading as u64
+    }
+
+    /// Lamports a take-profit sell must return to cover its share of the buy
+    /// and this sell's fees, when `min_out_cost_floor` is enabled and the buy
+    /// is known. A tier sell covers its `sell_fraction` of the buy, the
+    /// closing sell whatever earlier sells haven't covered.
+    pub fn cost_basis_floor_lamports(&self) -> Option<u64> {
+        let settings = &self.user_bot_data.bot_setting;
+        if !settings.min_out_cost_floor {
+            return None;
+        }
+        let sol_in = self.last_input_lamports_delta.filter(|l| *l > 0)?;
+        let entry_share = match self.sell_fraction {
+            Some(fraction) => (sol_in as f64 * fraction).round() as i128,
+            None => sol_in - self.charged_input_lamports,
+        };
+        let sell_fee_sol = projected_tx_fee_sol(
+            settings.cu,
+            settings.priority_fee_micro_lamport,
+            settings.third_party_fee,
+        );
+        Some(entry_share.max(0) as u64 + (sell_fee_sol * 1_000_000_000.0).ceil() as u64)
+    }
+
+    /// Price change since the buy in percent, none without a bought price
+    pub fn unrealized_pnl_pct(&self) -> Option<f64> {
+        let bought_price = self.bought_price.filter(|p| *p > 0.0)?;
+        Some((self.latest_pool_price - bought_price) / bought_price * 100.0)
+    }
 
-            // let mut user_list = crate::statics::USER_LIST.write().await;
+    /// The position as the API reports it, with the exits in force at `now_ms`
+    pub fn position_response(&self, now_ms: i64) -> BotPositionResponse {
+        let settings = &self.user_bot_data.bot_setting;
+        let unrealized_pnl_pct = self.unrealized_pnl_pct();
+        let invested_sol = self
+            .last_input_lamports_delta
+            .map(|lamports| lamports as f64 / 1_000_000_000.0)
+            .unwrap_or(settings.buy_sol_amount);
 
-            // user_list.retain(|user_bot_data| user_bot_data.user_id != user_id);
-            // info!(
-            //     "🔄 Bot stopping - removing user from USER_LIST: {}",
-            //     user_id
-            // );
+        BotPositionResponse {
+            pool_id: self.user_bot_data.pool_id.clone(),
+            is_bought: self.is_bought,
+            bought_price: self.bought_price,
+            latest_pool_price: self.latest_pool_price,
+            unrealized_pnl_pct,
+            unrealized_pnl_sol: unrealized_pnl_pct.map(|pct| invested_sol * pct / 100.0),
+            bought_at: self.bought_at,
+            hold_secs: self.bought_at.map(|bought_at| (now_ms - bought_at).max(0) / 1000),
+            fee_lamports: self.fee_lamports,
+            take_profit_pct: self.take_profit_target(now_ms),
+            stop_loss_pct: -self.stop_level_pct(now_ms),
+            trailing_stop_pct: settings.trailing_stop_pct,
+            break_even_price: self.break_even_price(),
+            auto_exit_at: self
+                .bought_at
+                .filter(|_| settings.auto_exit > 0)
+                .map(|bought_at| bought_at + settings.auto_exit as i64 * 1000),
+        }
+    }
+
+    /// Pool price the open position has to reach to exit without a loss,
+    /// counting both legs' fees and tip, pool fees and the exit slippage
+    pub fn break_even_price(&self) -> Option<f64> {
+        let settings = &self.user_bot_data.bot_setting;
+        let leg_fee_sol = projected_tx_fee_sol(
+            settings.cu,
+            settings.priority_fee_micro_lamport,
+            settings.third_party_fee,
+        );
+        break_even_price(
+            self.bought_price?,
+            settings.buy_sol_amount,
+            2.0 * leg_fee_sol,
+            swap_fee_rate(self.pool_program.as_ref()),
+            settings.exit_slippage_for(&self.user_bot_data.pool_id),
+        )
+    }
 
-            return Ok("Stopped bot".to_string());
+    /// `break_even_price` as a change (%) from the bought price, 0% when
+    /// the exit slippage leaves no price to break even at
+    pub fn break_even_pct(&self) -> f64 {
+        match (self.bought_price.filter(|p| *p > 0.0), self.break_even_price()) {
+            (Some(bought_price), Some(break_even)) => (break_even - bought_price) / bought_price * 100.0,
+            _ => 0.0,
         }
     }
+
+    /// Regime of the pool's recent price path, when regime exits are configured
+    /// and there is enough movement to tell
+    pub fn market_regime(&self, now_ms: i64) -> Option<MarketRegime> {
+        let sets = self.user_bot_data.bot_setting.regime_exits?;
+        let since_ms = now_ms - (sets.window_secs as i64) * 1000;
+        let ratio = self.price_history.efficiency_ratio(since_ms)?;
+        Some(if ratio >= sets.trend_threshold {
+            MarketRegime::Trending
+        } else {
+            MarketRegime::Ranging
+        })
+    }
+
+    /// Take-profit / stop-loss currently in force
+    pub fn exit_params(&self, now_ms: i64) -> ExitParams {
+        self.user_bot_data
+            .bot_setting
+            .exit_params_for(self.market_regime(now_ms))
+    }
+
+    /// Take-profit (%) currently required to exit. Once the position has
+    /// peaked at `tp_decay_min_profit_pct`, the target decays linearly from
+    /// `take_profit` to break-even over `tp_decay_secs` after buying. Break-even
+    /// is 0% unless `break_even_tp_floor` asks for the cost-adjusted one.
+    pub fn take_profit_target(&self, now_ms: i64) -> f64 {
+        let settings = &self.user_bot_data.bot_setting;
+        let take_profit = self.exit_params(now_ms).take_profit;
+        if settings.tp_decay_secs == 0 || settings.tp_decay_min_profit_pct <= 0.0 {
+            return take_profit;
+        }
+
+        let (Some(bought_price), Some(bought_at), Some(peak)) =
+            (self.bought_price, self.bought_at, self.peak_price)
+        else {
+            return take_profit;
+        };
+        if bought_price <= 0.0
+            || (peak - bought_price) / bought_price * 100.0 < settings.tp_decay_min_profit_pct
+        {
+            return take_profit;
+        }
+
+        let floor = if settings.break_even_tp_floor {
+            self.break_even_pct().min(take_profit)
+        } else {
+            0.0
+        };
+
+        let held_secs = (now_ms - bought_at).max(0) as f64 / 1000.0;
+        let progress = (held_secs / settings.tp_decay_secs as f64).min(1.0);
+        take_profit - (take_profit - floor) * progress
+    }
+
+    /// Moves the stop to break-even once `percent_change` reaches
+    /// `move_stop_to_be_at_pct`. Returns `true` only on the update that moves it.
+    pub fn arm_break_even_stop(&mut self, percent_change: f64) -> bool {
+        let Some(threshold) = self.user_bot_data.bot_setting.move_stop_to_be_at_pct else {
+            return false;
+        };
+        let moves = !self.stop_moved_to_be && percent_change >= threshold;
+        self.stop_moved_to_be |= moves;
+        moves
+    }
+
+    /// Change (%) from the bought price at or below which the stop sells:
+    /// the stop loss in force, or once moved the slippage-adjusted
+    /// break-even plus its offset, capped at the level that moved it
+    pub fn stop_level_pct(&self, now_ms: i64) -> f64 {
+        let settings = &self.user_bot_data.bot_setting;
+        match settings.move_stop_to_be_at_pct {
+            Some(threshold) if self.stop_moved_to_be => {
+                (self.break_even_pct().max(0.0) + settings.break_even_stop_offset_pct).min(threshold)
+            }
+            _ => -self.exit_params(now_ms).stop_loss,
+        }
+    }
+
+    /// Drop (%) from the peak since entry when it has reached
+    /// `trailing_stop_pct`. The peak never counts below the entry price, so
+    /// before any gain this acts as a stop from entry.
+    pub fn trailing_stop_drawdown(&self, price: f64) -> Option<f64> {
+        let trailing_stop_pct = self.user_bot_data.bot_setting.trailing_stop_pct?;
+        let bought_price = self.bought_price.filter(|p| *p > 0.0)?;
+        let peak = self.peak_price.unwrap_or(bought_price).max(bought_price);
+        let drawdown = (peak - price) / peak * 100.0;
+        (drawdown >= trailing_stop_pct).then_some(drawdown)
+    }
+
+    /// Tokens to sell for this position. The ATA is shared by every position
+    /// in the same mint, so the sell is capped at what this position still
+    /// holds; a partial take-profit sells its fraction of the bought amount.
+    pub fn sell_amount(&self, ata_balance: u64) -> u64 {
+        let held = match self.held_token_amount {
+            Some(held) => held.min(ata_balance),
+            None => ata_balance,
+        };
+        match (self.sell_fraction, self.bought_token_amount) {
+            (Some(fraction), Some(bought)) => ((bought as f64 * fraction).round() as u64).min(held),
+            _ => held,
+        }
+    }
+
+    /// Take-profit tiers reached at `percent_change` that have not sold yet.
+    /// Returns their indexes and combined fraction, and whether selling them
+    /// closes the position.
+    pub fn take_profit_tiers_due(&self, percent_change: f64) -> Option<(Vec<usize>, f64, bool)> {
+        let tiers = &self.user_bot_data.bot_setting.take_profit_tiers;
+        let fired = |i: usize| self.fired_tiers.get(i).copied().unwrap_or(false);
+
+        let due: Vec<usize> = (0..tiers.len())
+            .filter(|&i| !fired(i) && percent_change >= tiers[i].0)
+            .collect();
+        if due.is_empty() {
+            return None;
+        }
+        let fraction: f64 = due.iter().map(|&i| tiers[i].1).sum();
+        let sold_before: f64 = (0..tiers.len()).filter(|&i| fired(i)).map(|i| tiers[i].1).sum();
+        let closes = sold_before + fraction >= 1.0 - 1e-9;
+        Some((due, fraction, closes))
+    }
+
+    /// Marks tiers as sold, see `take_profit_tiers_due`
+    pub fn fire_tiers(&mut self, tiers: &[usize]) {
+        let len = self.user_bot_data.bot_setting.take_profit_tiers.len();
+        self.fired_tiers.resize(len, false);
+        self.exit_tiers = tiers.to_vec();
+        for &i in tiers {
+            if let Some(fired) = self.fired_tiers.get_mut(i) {
+                *fired = true;
+            }
+        }
+    }
+
+    /// The exit or scale-in a held position at `price`, `percent_change`
+    /// from the bought price, calls for, most urgent first. Forced exits and
+    /// the stops come ahead of a pending DCA buy, the trailing stop ahead of
+    /// the static one. Tiers replace the single take profit but not the stops,
+    /// which keep guarding the rest of the position while no tier is due.
+    pub fn exit_signal(&self, price: f64, percent_change: f64, now_ms: i64) -> Option<ExitSignal> {
+        if self.force_exit {
+            return Some(ExitSignal::ForceExit);
+        }
+        if let Some(drawdown) = self.trailing_stop_drawdown(price) {
+            return Some(ExitSignal::TrailingStop(drawdown));
+        }
+        if percent_change <= self.stop_level_pct(now_ms) {
+            return Some(ExitSignal::StopLoss);
+        }
+        if let Some(level) = self.pending_dca_level {
+            return Some(if self.swap_ixs_dca_level == Some(level) {
+                ExitSignal::DcaBuy(level)
+            } else {
+                ExitSignal::DcaWaiting(level)
+            });
+        }
+        if let Some(level) = self.dca_level_due(price) {
+            return Some(ExitSignal::DcaLevel(level));
+        }
+        if self.user_bot_data.bot_setting.take_profit_tiers.is_empty() {
+            if percent_change >= self.take_profit_target(now_ms) {
+                return Some(ExitSignal::TakeProfit);
+            }
+        } else if let Some((tiers, fraction, closes)) = self.take_profit_tiers_due(percent_change) {
+            return Some(ExitSignal::TakeProfitTiers { tiers, fraction, closes });
+        }
+        self.auto_exit_due(now_ms).then_some(ExitSignal::AutoExit)
+    }
+
+    /// Whether the position has been held longer than `auto_exit` seconds.
+    /// An `auto_exit` of zero disables the timeout.
+    pub fn auto_exit_due(&self, now_ms: i64) -> bool {
+        let auto_exit = self.user_bot_data.bot_setting.auto_exit;
+        match self.bought_at {
+            Some(bought_at) if auto_exit > 0 => {
+                now_ms.saturating_sub(bought_at) > (auto_exit as i64).saturating_mul(1000)
+            }
+            _ => false,
+        }
+    }
+
+    /// Price the entry drop is measured against, per the bot's `entry_reference`,
+    /// given the price observed before the latest one
+    pub fn reference_price_for(&self, last_price: f64, now_ms: i64) -> Option<f64> {
+        let settings = &self.user_bot_data.bot_setting;
+        let since_ms = now_ms - (settings.entry_window_secs as i64) * 1000;
+
+        match settings.entry_reference {
+            EntryReference::LastPrice => Some(last_price),
+            EntryReference::WindowAverage => self.price_history.avg_over(since_ms),
+            EntryReference::SessionHigh => self.price_history.max_since(since_ms),
+        }
+    }
+}
+
+/// A price a processor recorded for one user's position
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceUpdate {
+    pub pool_id: String,
+    pub user_id: String,
+    pub price: f64,
+    /// The position's entry reference as of this price
+    pub entry_reference_price: Option<f64>,
+}
+
+/// Last price the monitor evaluated per position, so only actual changes
+/// reach the entry/exit logic
+#[derive(Debug, Default)]
+pub struct PriceChangeTracker {
+    last_prices: HashMap<(String, String), f64>,
+}
+
+impl PriceChangeTracker {
+    /// Returns `(old, new)` if `update` changes the position's price, compared
+    /// in fixed point so f64 rounding alone isn't a change. A position seen
+    /// for the first time compares against `seed_price`, its warm-start price.
+    pub fn observe(&mut self, update: &PriceUpdate, seed_price: f64) -> Option<(f64, f64)> {
+        let key = (update.pool_id.clone(), update.user_id.clone());
+        let old = self.last_prices.get(&key).copied().unwrap_or(seed_price);
+        let new = FixedPrice::from_f64(update.price);
+        if new.is_zero() || new == FixedPrice::from_f64(old) {
+            return None;
+        }
+        self.last_prices.insert(key, update.price);
+        Some((old, update.price))
+    }
+}
+
+/// Open positions past their `auto_exit` timeout or forced to exit, whose
+/// sell has not been claimed yet
+pub fn due_auto_exits(pools: &PoolInfoMap, now_ms: i64) -> Vec<RealPoolInfo> {
+    positions_where(pools, |info| {
+        info.is_bought && !info.selling_in_progress && (info.force_exit || info.auto_exit_due(now_ms))
+    })
+}
+
+/// Forces every open position of the user to exit. Positions whose forced
+/// sell is already requested are counted but not triggered again.
+pub fn trigger_panic_sell(pools: &PoolInfoMap, user_id: &str) -> PanicSellResponse {
+    let mut response = PanicSellResponse::default();
+    update_user_positions(pools, user_id, |info| {
+        if !info.is_bought {
+            return;
+        }
+        if info.force_exit || info.selling_in_progress {
+            response.already_selling += 1;
+        } else {
+            info.force_exit = true;
+            response.triggered += 1;
+        }
+    });
+    response
+}
+
+/// Snapshot of the user's open positions with their unrealized PnL summed
+pub fn open_positions(pools: &PoolInfoMap, user_id: &str, now_ms: i64) -> OpenPositionsResponse {
+    let positions: Vec<BotPositionResponse> =
+        positions_where(pools, |info| info.user_bot_data.user_id == user_id && info.is_bought)
+            .iter()
+            .map(|info| info.position_response(now_ms))
+            .collect();
+    let unrealized_pnl_sol = positions.iter().filter_map(|p| p.unrealized_pnl_sol).sum();
+    OpenPositionsResponse { positions, unrealized_pnl_sol }
+}
+
+/// Refuses a start while the user already holds `max_open_positions`
+/// bought positions, zero disables the limit
+pub fn check_open_position_limit(pools: &PoolInfoMap, settings: &BotSettings) -> AppResult<()> {
+    if settings.max_open_positions == 0 {
+        return Ok(());
+    }
+    let open = positions_where(pools, |info| {
+        info.user_bot_data.user_id == settings.user_id && info.is_bought
+    })
+    .len();
+    if open >= settings.max_open_positions as usize {
+        return Err(AppError::conflict(format!(
+            "{} open positions already, the limit is {}",
+            open, settings.max_open_positions
+        )));
+    }
+    Ok(())
+}
+
+/// What a buy sets up before its swap: the wallet's token account for each of
+/// the pool's `(mint, token program)` pairs and, on SOL pools, the transfer
+/// wrapping the buy amount. `check_buy_balance` prices these the same way it
+/// prices a real buy.
+pub fn entry_setup_instructions(
+    owner: &Pubkey,
+    mints: &[(Pubkey, Pubkey)],
+    quote_mint: &Pubkey,
+    settings: &BotSettings,
+) -> Vec<Instruction> {
+    let mut ixs: Vec<Instruction> = mints
+        .iter()
+        .map(|(mint, program)| create_associated_token_account_idempotent(owner, owner, mint, program))
+        .collect();
+    if *quote_mint == WSOL {
+        let wsol_ata = get_associated_token_address(owner, &WSOL);
+        let wrap_lamports = (settings.buy_sol_amount * LAMPORTS_PER_SOL as f64) as u64;
+        ixs.push(system_instruction::transfer(owner, &wsol_ata, wrap_lamports));
+    }
+    ixs
+}
+
+/// Refuses a USD1 buy the wallet's USD1 balance can't pay for, amounts in
+/// the token's base units
+pub fn check_quote_token_balance(held: u64, needed: u64) -> AppResult<()> {
+    if held < needed {
+        let scale = 10f64.powi(USD1_DECIMALS);
+        return Err(AppError::validation(format!(
+            "Wallet holds {:.2} USD1, a buy needs {:.2} USD1",
+            held as f64 / scale,
+            needed as f64 / scale
+        )));
+    }
+    Ok(())
+}
+
+/// Refuses a start when the wallet can't pay for a buy on the bot's pool:
+/// the amount in the pool's quote token, and in SOL the rent of the token
+/// accounts it lacks plus the transaction fee
+pub async fn check_entry_balance(owner: &Pubkey, settings: &BotSettings) -> AppResult<()> {
+    let pool = settings
+        .pool_address
+        .parse::<Pubkey>()
+        .map_err(|e| AppError::validation(format!("Invalid pool address: {}", e)))?;
+    let (vaults, _) = fetch_reserves_by_pool(&pool)
+        .await
+        .ok_or_else(|| AppError::internal(format!("Failed to read pool {}", pool)))?;
+    let quote_mint = if [WSOL, USD1].contains(&vaults.quote_mint) {
+        vaults.quote_mint
+    } else {
+        vaults.base_mint
+    };
+
+    // Each mint's token account lives under the mint's own token program
+    let mint_keys = [vaults.base_mint, vaults.quote_mint];
+    let mint_accounts = crate::config::RPC_CLIENT
+        .get_multiple_accounts(&mint_keys)
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to read pool mints: {}", e)))?;
+    let mints: Vec<(Pubkey, Pubkey)> = mint_keys
+        .into_iter()
+        .zip(&mint_accounts)
+        .map(|(mint, account)| (mint, account.as_ref().map(|a| a.owner).unwrap_or(spl_token::ID)))
+        .collect();
+
+    let fee_sol = projected_tx_fee_sol(settings.cu, settings.priority_fee_micro_lamport, settings.third_party_fee);
+    let ixs = entry_setup_instructions(owner, &mints, &quote_mint, settings);
+    check_buy_balance(owner, &ixs, fee_sol).await.map_err(AppError::validation)?;
+
+    if quote_mint == USD1 {
+        let held = match fetch_held_tokens(&get_associated_token_address(owner, &USD1)).await {
+            Ok(HeldTokens::Amount(amount)) => amount,
+            Ok(HeldTokens::NoAccount) => 0,
+            Err(e) => return Err(AppError::internal(format!("Failed to read USD1 balance: {}", e))),
+        };
+        let needed = (settings.buy_usd1_amount * 10f64.powi(USD1_DECIMALS)) as u64;
+        check_quote_token_balance(held, needed)?;
+    }
+    Ok(())
+}
+
+/// Refuses a `buy_sol_amount` whose round-trip fees, tips and rent cost more
+/// than `max_cost_pct` of it, as no realistic move pays that back. Zero
+/// disables the check.
+pub fn check_min_viable_trade(settings: &BotSettings, max_cost_pct: u64) -> AppResult<()> {
+    if max_cost_pct == 0 {
+        return Ok(());
+    }
+    let cost_sol = round_trip_cost_sol(settings.cu, settings.priority_fee_micro_lamport, settings.third_party_fee);
+    let min_buy_sol = min_viable_buy_sol(cost_sol, max_cost_pct as f64);
+    if settings.buy_sol_amount < min_buy_sol {
+        return Err(AppError::validation(format!(
+            "buy_sol_amount of {} SOL can't cover its {:.6} SOL round trip in fees, tips and rent, use at least {:.4} SOL",
+            settings.buy_sol_amount, cost_sol, min_buy_sol
+        )));
+    }
+    Ok(())
+}
+
+/// `check_min_viable_trade` for an edit, run only when it moves the buy size
+/// or the fees and tips, so bots saved before the check can still change
+/// unrelated settings
+pub fn check_min_viable_trade_change(previous: &BotSettings, settings: &BotSettings, max_cost_pct: u64) -> AppResult<()> {
+    let unchanged = previous.buy_sol_amount == settings.buy_sol_amount
+        && previous.cu == settings.cu
+        && previous.priority_fee_micro_lamport == settings.priority_fee_micro_lamport
+        && previous.third_party_fee == settings.third_party_fee;
+    if unchanged {
+        return Ok(());
+    }
+    check_min_viable_trade(settings, max_cost_pct)
+}
+
+impl BotService {
+    pub fn new(database: AppDatabase) -> Self {
+        Self {
+            bot_repo: BotRepository::new(database.clone()),
+            user_repo: UserRepository::new(database.clone()),
+            pool_price_repo: PoolPriceRepository::new(database.clone()),
+            position_repo: PositionRepository::new(database.clone()),
+        }
+    }
+
+    /// Create a new bot for a user
+    pub async fn create_bot(
+        &self,
+        user_id: String,
+        name: String,
+        pool_address: String,
+    ) -> AppResult<BotSettingsResponse> {
+        // Check if user already has a bot with this name
+        let existing_bots = self.bot_repo.find_by_user_id(&user_id).await?;
+        if existing_bots.iter().any(|bot| bot.name == name) {
+            return Err(AppError::conflict("Bot name already exists for this user"));
+        }
+
+        // The default buy size is too small to trade, it's checked once the
+        // owner sets it and again when the bot starts
+        let bot = BotSettings::new(user_id, name, pool_address);
+        let created_bot = self.bot_repo.create(bot).await?;
+
+        info!(
+            "✅ New bot created: {} for user: {}",
+            created_bot.name, created_bot.user_id
+        );
+
+        Ok(created_bot.into())
+    }
+
+    /// Get all bots for a user
+    pub async fn get_user_bots(&self, user_id: &str) -> AppResult<Vec<BotSettingsResponse>> {
+        let bots = self.bot_repo.find_by_user_id(user_id).await?;
+        Ok(bots.into_iter().map(|bot| bot.into()).collect())
+    }
+
+    /// Update bot trading parameters
+    pub async fn update_trading_params(
+        &self,
+        user_id: &str,
+        params: UpdateTradingParamsRequest,
+    ) -> AppResult<BotSettingsResponse> {
+        let mut bot = self
+            .bot_repo
+            .find_by_user_id(user_id)
+            .await?
+            .first()
+            .ok_or_else(|| AppError::not_found("Bot not found"))?
+            .clone();
+
+        let previous = bot.clone();
+        bot.update_trading_params(params);
+
+        if let (Some(start), Some(stop)) = (bot.scheduled_start, bot.scheduled_stop) {
+            if stop <= start {
+                return Err(AppError::validation("scheduled_stop must be after scheduled_start"));
+            }
+        }
+        if bot.dca_levels.len() != bot.dca_amounts.len() {
+            return Err(AppError::validation("dca_levels and dca_amounts must have the same length"));
+        }
+        bot.validate_tip_addr_idx().map_err(AppError::validation)?;
+        check_min_viable_trade_change(&previous, &bot, *crate::config::MAX_ROUND_TRIP_COST_PCT)?;
+        if bot.auto_tune_min_entry_percent > bot.auto_tune_max_entry_percent {
+            return Err(AppError::validation(
+                "auto_tune_min_entry_percent must not exceed auto_tune_max_entry_percent",
+            ));
+        }
+
+        self.bot_repo.update(&bot).await?;
+
+        info!("✅ Bot trading parameters updated: {}", bot.name);
+
+        Ok(bot.into())
+    }
+
+    /// Update bot MEV configuration
+    pub async fn update_mev_config(
+        &self,
+        user_id: &str,
+        confirm_service: Option<String>,
+        cu: Option<u64>,
+        priority_fee: Option<u64>,
+        third_party_fee: Option<f64>,
+    ) -> AppResult<BotSettingsResponse> {
+        let mut bot = self
+            .bot_repo
+            .find_by_user_id(user_id)
+            .await?
+            .first()
+            .ok_or_else(|| AppError::not_found("Bot not found"))?
+            .clone();
+
+        let previous = bot.clone();
+        bot.update_mev_config(confirm_service, cu, priority_fee, third_party_fee);
+        check_min_viable_trade_change(&previous, &bot, *crate::config::MAX_ROUND_TRIP_COST_PCT)?;
+
+        self.bot_repo.update(&bot).await?;
+
+        info!("✅ Bot MEV configuration updated: {}", bot.name);
+
+        Ok(bot.into())
+    }
+
+    /// Adds the bot's position to `REAL_POOL_INFO` and the bot to
+    /// `USER_LIST`, replacing positions left by an earlier run. False when
+    /// the user's bot is already running. The in-memory half of `start_bot`,
+    /// under the user's lifecycle lock.
+    pub async fn activate(pool_info: RealPoolInfo) -> bool {
+        let user_bot_data = pool_info.user_bot_data.clone();
+        let _lifecycle = crate::statics::BOT_LIFECYCLE_LOCKS.lock(&user_bot_data.user_id).await;
+        if Self::is_running(&user_bot_data.user_id).await {
+            return false;
+        }
+
+        let removed = remove_user_positions(&crate::statics::REAL_POOL_INFO, &user_bot_data.user_id);
+        if removed > 0 {
+            println!("🧹 Removed {} stale entries from REAL_POOL_INFO", removed);
+        }
+        // The position goes in first, so a processor seeing the bot finds it
+        get_or_insert_position(&crate::statics::REAL_POOL_INFO, &user_bot_data.pool_id, pool_info);
+        let mut user_list = crate::statics::USER_LIST.write().await;
+        user_list.push(user_bot_data);
+        println!("✅ USER_LIST: Added user, total users: {}", user_list.len());
+        true
+    }
+
+    /// Flags a held position to be sold, or removes the bot when nothing is
+    /// held. The in-memory half of `stop_bot`, under the user's lifecycle lock.
+    pub async fn deactivate(user_id: &str, pool_id: &str) -> Deactivation {
+        let _lifecycle = crate::statics::BOT_LIFECYCLE_LOCKS.lock(user_id).await;
+
+        let has_positions = !positions_where(&crate::statics::REAL_POOL_INFO, |info| {
+            info.user_bot_data.user_id == user_id
+        })
+        .is_empty();
+        if !has_positions && !Self::is_running(user_id).await {
+            return Deactivation::AlreadyStopped;
+        }
+
+        let is_bought = position(&crate::statics::REAL_POOL_INFO, pool_id, user_id).is_some_and(|info| info.is_bought);
+        println!("IS_BOUGHT: {}", is_bought);
+        if is_bought {
+            // Trigger an immediate sell on the next price update
+            update_position(&crate::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+                info.force_exit = true;
+            });
+            return Deactivation::Exiting;
+        }
+        Deactivation::Stopped(Self::unregister(user_id).await.map(Box::new))
+    }
+
+    /// Removes a stopping bot once its exit has settled or given up, under
+    /// the user's lifecycle lock
+    pub async fn release(user_id: &str) -> Option<UserBotData> {
+        let _lifecycle = crate::statics::BOT_LIFECYCLE_LOCKS.lock(user_id).await;
+        Self::unregister(user_id).await
+    }
+
+    /// Takes the user off `USER_LIST` and out of `REAL_POOL_INFO`, returning
+    /// the bot that was listed. Callers hold the user's lifecycle lock.
+    async fn unregister(user_id: &str) -> Option<UserBotData> {
+        let stopped = {
+            let mut user_list = crate::statics::USER_LIST.write().await;
+            let stopped = user_list
+                .iter()
+                .find(|user_bot_data| user_bot_data.user_id == user_id)
+                .cloned();
+            user_list.retain(|user_bot_data| user_bot_data.user_id != user_id);
+            stopped
+        };
+        let removed = remove_user_positions(&crate::statics::REAL_POOL_INFO, user_id);
+        if removed > 0 {
+            println!("🧹 Removed {} entries from REAL_POOL_INFO", removed);
+        }
+        stopped
+    }
+
+    /// Whether the user is in `USER_LIST`, which `start_bot` adds them to and
+    /// `stop_bot` removes them from
+    async fn is_running(user_id: &str) -> bool {
+        crate::statics::USER_LIST
+            .read()
+            .await
+            .iter()
+            .any(|user_bot_data| user_bot_data.user_id == user_id)
+    }
+
+    pub async fn start_bot(&self, user_id: &str) -> AppResult<String> {
+        println!("🚀 Starting bot for user_id: {}", user_id);
+        // Checked again under the lifecycle lock in `activate`
+        if Self::is_running(user_id).await {
+            println!("⚠️ Bot for user {} is already running", user_id);
+            return Ok("Bot already running".to_string());
+        }
+        
+        // Check if user exists in database
+        let user = match self.user_repo.find_by_id(user_id).await? {
+            Some(user) => {
+                println!("✅ User found: {}", user_id);
+                user
+            },
+            None => {
+                println!("❌ User not found: {}", user_id);
+                return Err(AppError::not_found("User not found"));
+            }
+        };
+
+        // Check if bot exists
+        let bot = self.bot_repo.find_by_user_id(user_id).await?;
+        let bot_settings = user_bot(&bot).inspect_err(|_| println!("❌ No bot found for user: {}", user_id))?;
+        if bot_settings.halted {
+            return Err(AppError::validation(format!(
+                "Bot halted after {} consecutive losing trades, resume it before starting",
+                bot_settings.consecutive_losses
+            )));
+        }
+        // Checked before the cleanup below drops the user's positions
+        check_open_position_limit(&crate::statics::REAL_POOL_INFO, bot_settings)?;
+        let owner = user
+            .public_key
+            .parse::<solana_sdk::pubkey::Pubkey>()
+            .map_err(|e| AppError::validation(format!("Invalid wallet public key: {}", e)))?;
+        check_entry_balance(&owner, bot_settings).await?;
+        check_min_viable_trade(bot_settings, *crate::config::MAX_ROUND_TRIP_COST_PCT)?;
+
+        let pool_id = bot_settings.pool_address.clone();
+        println!("✅ Bot found with pool_id: {}", pool_id);
+
+        crate::utils::processor_errors::reset_processor_errors(user_id);
+
+        // Create UserBotData and add to USER_LIST
+        let user_bot_data = UserBotData {
+            pool_id: pool_id.clone(),
+            user_id: user_id.to_string(),
+            private_key: decrypt_private_key(&user.private_key)?,
+            public_key: user.public_key.clone(),
+            bot_setting: bot_settings.clone(),
+        };
+
+        println!("USER_BOT_DATA: {:#?}", user_bot_data);
+
+        // Last persisted price for the pool, to warm start from if recent enough
+        let warm_price = self
+            .pool_price_repo
+            .find_by_pool_address(&pool_id)
+            .await
+            .unwrap_or_else(|e| {
+                println!("⚠️ Failed to load last pool price for {}: {}", pool_id, e);
+                None
+            });
+
+        let initial_pool_info = RealPoolInfo::restored(
+            user_bot_data,
+            warm_price.as_ref(),
+            chrono::Utc::now().timestamp_millis(),
+            *crate::config::POOL_PRICE_MAX_AGE,
+        );
+        if !Self::activate(initial_pool_info).await {
+            println!("⚠️ Bot for user {} is already running", user_id);
+            return Ok("Bot already running".to_string());
+        }
+        if let Err(e) = self.position_repo.delete_by_user_id(user_id).await {
+            println!("⚠️ Failed to delete stored positions for {}: {}", user_id, e);
+        }
+        println!("✅ REAL_POOL_INFO: Added user to pool {}", pool_id);
+
+        Ok("Started bot".to_string())
+    }
+
+    /// Record a closed trade for the loss circuit breaker, stopping the bot
+    /// when it trips
+    pub async fn record_trade_result(&self, user_id: &str, profit_sol: f64) -> AppResult<bool> {
+        let mut bot = self
+            .bot_repo
+            .find_by_user_id(user_id)
+            .await?
+            .first()
+            .ok_or_else(|| AppError::not_found("Bot not found"))?
+            .clone();
+
+        let tripped = bot.record_trade_result(profit_sol);
+        self.bot_repo.update_breaker(&bot).await?;
+
+        if tripped {
+            info!(
+                user_id,
+                "🧯 Circuit breaker tripped after {} consecutive losing trades, halting bot",
+                bot.consecutive_losses
+            );
+            self.stop_bot(user_id).await?;
+        }
+
+        Ok(tripped)
+    }
+
+    /// Persist the user's fee budget counters
+    pub async fn persist_fee_budget(&self, user_id: &str, budget: &FeeBudget) -> AppResult<()> {
+        self.bot_repo.update_fee_budget(user_id, budget).await?;
+        Ok(())
+    }
+
+    /// Write the user's position in `pool_id` to the `positions` collection
+    /// while it's bought or cooling down after an exit, deleting it once it's
+    /// neither or removed
+    pub async fn persist_position(&self, pool_id: &str, user_id: &str) -> AppResult<()> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let stored = position(&crate::statics::REAL_POOL_INFO, pool_id, user_id)
+            .filter(|info| info.is_bought || info.cooling_down(now_ms))
+            .map(|info| StoredPosition::from_position(&info));
+
+        match stored {
+            Some(stored) => self.position_repo.upsert(&stored).await?,
+            None => self.position_repo.delete(pool_id, user_id).await?,
+        }
+
+        Ok(())
+    }
+
+    /// Reload positions that were still bought or cooling down when the
+    /// process stopped into `REAL_POOL_INFO` and `USER_LIST`, so their exits
+    /// keep being evaluated and re-entries wait out the cooldown.
+    /// A position that fails to restore is logged and skipped. Returns how
+    /// many were restored.
+    pub async fn restore_positions(&self) -> AppResult<usize> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut restored = 0;
+
+        for stored in self.position_repo.find_restorable().await? {
+            match self.restore_position(&stored, now_ms).await {
+                Ok(true) => restored += 1,
+                Ok(false) => {}
+                Err(e) => println!(
+                    "⚠️ Failed to restore position of user {} on pool {}: {}",
+                    stored.user_id, stored.pool_id, e
+                ),
+            }
+        }
+
+        Ok(restored)
+    }
+
+    /// Restores one stored position, false when its bot is gone or its
+    /// cooldown has passed and it was dropped
+    async fn restore_position(&self, stored: &StoredPosition, now_ms: i64) -> AppResult<bool> {
+        let user = self.user_repo.find_by_id(&stored.user_id).await?;
+        let bot = self
+            .bot_repo
+            .find_by_user_id(&stored.user_id)
+            .await?
+            .into_iter()
+            .find(|bot| bot.pool_address == stored.pool_id);
+        let (Some(user), Some(bot_setting)) = (user, bot) else {
+            println!(
+                "⚠️ Dropping stored position of user {} on pool {}, its bot is gone",
+                stored.user_id, stored.pool_id
+            );
+            self.position_repo.delete(&stored.pool_id, &stored.user_id).await?;
+            return Ok(false);
+        };
+
+        let user_bot_data = UserBotData {
+            pool_id: stored.pool_id.clone(),
+            user_id: stored.user_id.clone(),
+            private_key: decrypt_private_key(&user.private_key)?,
+            public_key: user.public_key.clone(),
+            bot_setting,
+        };
+        let mut pool_info = RealPoolInfo::new(user_bot_data.clone());
+        pool_info.restore(stored, now_ms);
+        if !pool_info.is_bought && !pool_info.cooling_down(now_ms) {
+            self.position_repo.delete(&stored.pool_id, &stored.user_id).await?;
+            return Ok(false);
+        }
+        {
+            let mut user_list = crate::statics::USER_LIST.write().await;
+            user_list.retain(|existing_user| existing_user.user_id != stored.user_id);
+            user_list.push(user_bot_data);
+        }
+
+        remove_user_positions(&crate::statics::REAL_POOL_INFO, &stored.user_id);
+        get_or_insert_position(&crate::statics::REAL_POOL_INFO, &stored.pool_id, pool_info);
+        info!(
+            user_id = %stored.user_id,
+            "♻️ Restored open position on pool {} bought at {:?}",
+            stored.pool_id, stored.bought_price
+        );
+        Ok(true)
+    }
+
+    /// Current position of the user's bot, read from `REAL_POOL_INFO`
+    pub async fn get_status(&self, user_id: &str) -> AppResult<BotStatusResponse> {
+        let status = positions_where(&crate::statics::REAL_POOL_INFO, |info| info.user_bot_data.user_id == user_id)
+            .first()
+            .map(|info| BotStatusResponse {
+                running: true,
+                pool_id: info.user_bot_data.pool_id.clone(),
+                is_bought: info.is_bought,
+                bought_price: info.bought_price,
+                latest_pool_price: info.latest_pool_price,
+                break_even_price: if info.is_bought { info.break_even_price() } else { None },
+                stuck: info.stuck_since.is_some(),
+                stuck_since: info.stuck_since,
+                exit_failures: info.exit_failures.len() as u32,
+            });
+
+        Ok(status.unwrap_or_default())
+    }
+
+    /// Open position of the user's bot, read from `REAL_POOL_INFO`
+    pub async fn get_position(&self, user_id: &str) -> AppResult<BotPositionResponse> {
+        positions_where(&crate::statics::REAL_POOL_INFO, |info| {
+            info.user_bot_data.user_id == user_id && info.is_bought
+        })
+        .first()
+        .map(|info| info.position_response(chrono::Utc::now().timestamp_millis()))
+        .ok_or_else(|| AppError::not_found("No open position"))
+    }
+
+    /// Every open position of the user across pools, read from `REAL_POOL_INFO`
+    pub async fn get_open_positions(&self, user_id: &str) -> AppResult<OpenPositionsResponse> {
+        Ok(open_positions(
+            &crate::statics::REAL_POOL_INFO,
+            user_id,
+            chrono::Utc::now().timestamp_millis(),
+        ))
+    }
+
+    /// Quote a buy on the bot's pool against live reserves, trying `overrides`
+    /// in place of the stored amount and slippage
+    pub async fn preview_buy(&self, user_id: &str, overrides: PreviewOverrides) -> AppResult<BuyPreviewResponse> {
+        let bot = self
+            .bot_repo
+            .find_by_user_id(user_id)
+            .await?
+            .first()
+            .ok_or_else(|| AppError::not_found("Bot not found"))?
+            .clone();
+        let pool = bot
+            .pool_address
+            .parse()
+            .map_err(|_| AppError::validation("Bot pool address is invalid"))?;
+
+        let (vaults, reserves) = fetch_reserves_by_pool(&pool)
+            .await
+            .ok_or_else(|| AppError::internal("Failed to read the pool's reserves"))?;
+        let (sol_reserve, token_reserve) = if vaults.quote_mint == WSOL {
+            (reserves.quote, reserves.base)
+        } else if vaults.base_mint == WSOL {
+            (reserves.base, reserves.quote)
+        } else {
+            return Err(AppError::validation("Previews need a SOL pool"));
+        };
+
+        let (buy_sol_amount, slippage) = bot.preview_params(&overrides);
+        let amount_in = (buy_sol_amount * 1_000_000_000.0) as u64;
+        let (expected_tokens_out, min_tokens_out) =
+            buy_min_out(amount_in, sol_reserve, token_reserve, slippage);
+
+        Ok(BuyPreviewResponse {
+            pool_id: bot.pool_address,
+            buy_sol_amount,
+            slippage,
+            sol_reserve,
+            token_reserve,
+            expected_tokens_out,
+            min_tokens_out,
+        })
+    }
+
+    /// Pre-flight of the user's bot against the chain and its relays,
+    /// nothing is submitted and the bot doesn't need to be running
+    pub async fn dry_run(&self, user_id: &str) -> AppResult<DryRunReport> {
+        let bot = self
+            .bot_repo
+            .find_by_user_id(user_id)
+            .await?
+            .first()
+            .ok_or_else(|| AppError::not_found("Bot not found"))?
+            .clone();
+        let user = self
+            .user_repo
+            .find_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+        let wallet = user
+            .public_key
+            .parse()
+            .map_err(|_| AppError::validation("User wallet address is invalid"))?;
+
+        Ok(dry_run(&bot, &wallet).await)
+    }
+
+    /// Clear a tripped loss circuit breaker so the bot can be started again
+    pub async fn resume_bot(&self, user_id: &str) -> AppResult<BotSettingsResponse> {
+        let mut bot = self
+            .bot_repo
+            .find_by_user_id(user_id)
+            .await?
+            .first()
+            .ok_or_else(|| AppError::not_found("Bot not found"))?
+            .clone();
+
+        bot.consecutive_losses = 0;
+        bot.halted = false;
+        self.bot_repo.update_breaker(&bot).await?;
+
+        info!("✅ Bot resumed: {}", bot.name);
+
+        Ok(bot.into())
+    }
+
+    pub async fn stop_bot(&self, user_id: &str) -> AppResult<String> {
+        println!("🛑 stop_bot called for user_id: {}", user_id);
+
+        // A deleted bot is a 404 rather than "already stopped"
+        let bot = self.bot_repo.find_by_user_id(user_id).await?;
+        let pool_id = user_bot(&bot)?.pool_address.clone();
+
+        match Self::deactivate(user_id, &pool_id).await {
+            Deactivation::AlreadyStopped => {
+                println!("⚠️ Bot for user {} is already stopped", user_id);
+                Ok("Bot already stopped".to_string())
+            }
+            Deactivation::Exiting => {
+                println!("🔄 Bot stopping - triggering sell for user: {}", user_id);
+                Ok("Stopped bot".to_string())
+            }
+            Deactivation::Stopped(stopped) => {
+                // With nothing held there is no final exit to close the kept WSOL account
+                if let Some(bot) = stopped.filter(|_| *crate::config::KEEP_WSOL_OPEN) {
+                    tokio::spawn(async move {
+                        if let Err(e) = crate::service::submission::close_kept_wsol(&bot).await {
+                            println!("⚠️ Failed to close the kept WSOL account of user {}: {}", bot.user_id, e);
+                        }
+                    });
+                }
+                self.position_repo.delete_by_user_id(user_id).await?;
+
+                info!("✅ Bot stopped for user: {}", user_id);
+                Ok("Stopped bot".to_string())
+            }
+        }
+    }
+
+    /// Sells every open position of the user at the next price update or
+    /// sweep, whatever their take-profit and stop-loss. Like `stop_bot` the
+    /// bot stops once its position is sold.
+    pub async fn panic_sell(&self, user_id: &str) -> AppResult<PanicSellResponse> {
+        let response = trigger_panic_sell(&crate::statics::REAL_POOL_INFO, user_id);
+        println!(
+            "🚨 Panic sell for user {}: {} positions triggered, {} already selling",
+            user_id, response.triggered, response.already_selling
+        );
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::models::bot::RegimeExits;
+    use carbon_raydium_amm_v4_decoder::PROGRAM_ID as RAY_V4_PROGRAM_ID;
+
+    fn pool_info(reference: EntryReference, window_secs: u64) -> RealPoolInfo {
+        let mut bot_setting =
+            BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        bot_setting.entry_reference = reference;
+        bot_setting.entry_window_secs = window_secs;
+
+        let mut info = RealPoolInfo::new(UserBotData {
+            pool_id: "pool".to_string(),
+            user_id: "user".to_string(),
+            private_key: String::new(),
+            public_key: String::new(),
+            bot_setting,
+        });
+        // 60s ago the price peaked at 2.0, 20s ago it was 1.5, now 1.0
+        info.price_history.push(40_000, 2.0);
+        info.price_history.push(80_000, 1.5);
+        info.price_history.push(100_000, 1.0);
+        info
+    }
+
+    #[test]
+    fn only_price_changes_reach_the_monitor() {
+        let mut tracker = PriceChangeTracker::default();
+        let update = |user_id: &str, price: f64| PriceUpdate {
+            pool_id: "pool".to_string(),
+            user_id: user_id.to_string(),
+            price,
+            entry_reference_price: None,
+        };
+
+        // First sight compares against the warm-start price
+        assert_eq!(tracker.observe(&update("a", 1.0), 0.9), Some((0.9, 1.0)));
+        assert_eq!(tracker.observe(&update("a", 1.0), 0.9), None);
+        assert_eq!(tracker.observe(&update("a", 1.1), 0.9), Some((1.0, 1.1)));
+        // Rounding noise below the fixed-point resolution is no change
+        assert_eq!(tracker.observe(&update("a", 1.1 + 1e-15), 0.9), None);
+        // Positions are tracked separately
+        assert_eq!(tracker.observe(&update("b", 1.1), 0.0), Some((0.0, 1.1)));
+        assert_eq!(tracker.observe(&update("a", 0.0), 0.9), None);
+    }
+
+    #[test]
+    fn entries_wait_for_min_observations() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.user_bot_data.bot_setting.min_observations_before_trading = 3;
+
+        info.record_price(1.0);
+        info.record_price(0.5);
+        assert!(!info.has_min_observations());
+
+        info.record_price(0.6);
+        assert!(info.has_min_observations());
+    }
+
+    #[test]
+    fn a_restarted_bot_resumes_from_the_persisted_price() {
+        let max_age = std::time::Duration::from_secs(3_600);
+        let running = PoolInfoMap::default();
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.record_price_at(1.5, 101_000);
+        info.record_price_at(1.75, 102_000);
+        let user_bot_data = info.user_bot_data.clone();
+        get_or_insert_position(&running, "pool", info);
+
+        // What the persistence task would upsert before the process exits
+        let stored: Vec<PoolPrice> = crate::utils::positions::latest_pool_prices(&running)
+            .into_iter()
+            .map(|(pool_address, price)| PoolPrice {
+                id: None,
+                pool_address,
+                price,
+                updated_at: bson::DateTime::from_millis(102_000),
+            })
+            .collect();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].price, 1.75);
+
+        // After the restart only the stored document survives
+        let mut restored = RealPoolInfo::restored(user_bot_data.clone(), stored.first(), 110_000, max_age);
+        assert_eq!(restored.pool_price, 1.75);
+        assert_eq!(restored.entry_reference_price, Some(1.75));
+        assert_eq!(restored.price_history.len(), 1);
+
+        // The first swap after the restart is measured against it
+        restored.record_price_at(1.5, 111_000);
+        assert_eq!(restored.entry_reference_price, Some(1.75));
+
+        // A cold start has nothing to compare the first swap against, and a
+        // price older than the max age is treated as cold
+        let cold = RealPoolInfo::restored(user_bot_data.clone(), None, 110_000, max_age);
+        let stale = RealPoolInfo::restored(user_bot_data, stored.first(), 102_000 + 3_600_001, max_age);
+        for info in [cold, stale] {
+            assert_eq!(info.pool_price, 0.0);
+            assert_eq!(info.entry_reference_price, None);
+            assert!(info.price_history.is_empty());
+        }
+    }
+
+    #[test]
+    fn entry_reference_advances_with_every_recorded_price() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.price_history = PriceHistory::default();
+        info.record_price_at(1.0, 100_000);
+        assert_eq!(info.entry_reference_price, None);
+
+        // No monitor pass in between, each price still gets the one before it
+        info.record_price_at(0.9, 101_000);
+        assert_eq!(info.entry_reference_price, Some(1.0));
+        info.record_price_at(0.8, 102_000);
+        assert_eq!(info.entry_reference_price, Some(0.9));
+
+        info.user_bot_data.bot_setting.entry_reference = EntryReference::SessionHigh;
+        info.record_price_at(0.7, 103_000);
+        assert_eq!(info.entry_reference_price, Some(1.0));
+        // Prices fall out of the 30s window
+        info.record_price_at(0.75, 131_500);
+        assert_eq!(info.entry_reference_price, Some(0.8));
+    }
+
+    #[test]
+    fn entry_reference_respects_window() {
+        let now_ms = 100_000;
+
+        assert_eq!(
+            pool_info(EntryReference::SessionHigh, 30).reference_price_for(1.2, now_ms),
+            Some(1.5)
+        );
+        assert_eq!(
+            pool_info(EntryReference::SessionHigh, 90).reference_price_for(1.2, now_ms),
+            Some(2.0)
+        );
+        assert_eq!(
+            pool_info(EntryReference::WindowAverage, 30).reference_price_for(1.2, now_ms),
+            Some(1.25)
+        );
+        assert_eq!(
+            pool_info(EntryReference::WindowAverage, 10).reference_price_for(1.2, now_ms),
+            Some(1.0)
+        );
+        assert_eq!(
+            pool_info(EntryReference::LastPrice, 10).reference_price_for(1.2, now_ms),
+            Some(1.2)
+        );
+    }
+
+    #[test]
+    fn observer_never_holds_swap_instructions() {
+        let ix = Instruction::new_with_bytes(solana_sdk::pubkey::Pubkey::new_unique(), &[], vec![]);
+
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.set_swap_ixs(vec![ix.clone()], SwapAction::Buy, None);
+        assert_eq!(info.pending_swap_ixs.len(), 1);
+
+        info.user_bot_data.bot_setting.observer = true;
+        info.set_swap_ixs(vec![ix], SwapAction::Buy, None);
+        assert!(info.pending_swap_ixs.is_empty());
+    }
+
+    #[test]
+    fn entry_confirms_as_a_buy_after_the_sell_is_built() {
+        let ix = Instruction::new_with_bytes(solana_sdk::pubkey::Pubkey::new_unique(), &[], vec![]);
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        assert_eq!(SwapAction::from_held(info.is_bought), SwapAction::Buy);
+
+        info.set_swap_ixs(vec![ix.clone()], SwapAction::from_held(info.is_bought), None);
+        assert!(info.record_submission("entry", info.pending_action));
+        // The next swap builds the exit before the entry's fill comes in
+        info.set_swap_ixs(vec![ix], SwapAction::from_held(info.is_bought), None);
+        assert_eq!(info.pending_action, SwapAction::Sell);
+        assert!(info.confirming_buy());
+
+        assert!(!info.record_submission("exit", info.pending_action));
+        assert!(!info.confirming_buy());
+    }
+
+    #[test]
+    fn blacklisted_mint_leaves_no_entry_to_submit() {
+        let ix = Instruction::new_with_bytes(solana_sdk::pubkey::Pubkey::new_unique(), &[], vec![]);
+        let mint = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.set_swap_ixs(vec![ix], SwapAction::Buy, None);
+
+        assert!(info.entry_allowed(&mint));
+        assert_eq!(info.pending_swap_ixs.len(), 1);
+
+        info.user_bot_data.bot_setting.mint_blacklist = vec![mint.clone()];
+        assert!(!info.entry_allowed(&mint));
+        assert!(info.pending_swap_ixs.is_empty());
+    }
+
+    #[test]
+    fn auto_exit_zero_is_disabled_not_immediate() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.bought_at = Some(0);
+
+        info.user_bot_data.bot_setting.auto_exit = 0;
+        assert!(!info.auto_exit_due(i64::MAX));
+        assert!(!info.force_exit);
+
+        info.user_bot_data.bot_setting.auto_exit = 60;
+        assert!(!info.auto_exit_due(60_000));
+        assert!(info.auto_exit_due(60_001));
+    }
+
+    #[test]
+    fn take_profit_decays_after_spike_then_flatline() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        let settings = &mut info.user_bot_data.bot_setting;
+        settings.take_profit = 50.0;
+        settings.tp_decay_min_profit_pct = 10.0;
+        settings.tp_decay_secs = 100;
+
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        info.bought_at = Some(0);
+
+        // Spike to +20%, then flatline at +5%
+        for price in [1.1, 1.2, 1.05, 1.05, 1.05] {
+            info.record_price(price);
+        }
+        let change = 5.0;
+
+        assert_eq!(info.take_profit_target(20_000), 40.0);
+        assert!(change < info.take_profit_target(20_000));
+        assert!(change >= info.take_profit_target(95_000));
+        assert_eq!(info.take_profit_target(200_000), 0.0);
+
+        // Without reaching the minimum profit the target stays put
+        info.peak_price = Some(1.05);
+        assert_eq!(info.take_profit_target(95_000), 50.0);
+    }
+
+    #[test]
+    fn take_profit_decays_to_cost_adjusted_break_even() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        let settings = &mut info.user_bot_data.bot_setting;
+        settings.take_profit = 50.0;
+        settings.tp_decay_min_profit_pct = 10.0;
+        settings.tp_decay_secs = 100;
+        settings.break_even_tp_floor = true;
+        settings.buy_sol_amount = 1.0;
+        settings.exit_slippage = 1.0;
+
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        info.bought_at = Some(0);
+        info.peak_price = Some(1.2);
+
+        let be = info.break_even_price().unwrap();
+        assert!(be > 1.0 / (0.997 * 0.997 * 0.99) - 1e-12);
+        let floor_pct = (be - 1.0) * 100.0;
+        assert!((info.take_profit_target(200_000) - floor_pct).abs() < 1e-9);
+        assert!((info.take_profit_target(50_000) - (50.0 + floor_pct) / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn entries_are_suppressed_while_pool_drains() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        let settings = &mut info.user_bot_data.bot_setting;
+        settings.drain_detection = true;
+        settings.drain_window_secs = 30;
+        settings.drain_outflow_pct = 20.0;
+        settings.drain_min_sells = 3;
+
+        // Four sells in ten seconds pull the SOL reserve from 100 to 70
+        for (ts, reserve) in [(0, 100.0), (2_000, 90.0), (4_000, 82.0), (7_000, 75.0), (10_000, 70.0)] {
+            info.quote_reserve_history.push(ts, reserve);
+        }
+        assert!(info.is_draining(10_000));
+
+        // Once the window has moved past the drain, entries are allowed again
+        info.quote_reserve_history.push(45_000, 71.0);
+        assert!(!info.is_draining(45_000));
+
+        // A single large sell is a dip, not a drain
+        let mut dip = pool_info(EntryReference::LastPrice, 30);
+        dip.user_bot_data.bot_setting.drain_detection = true;
+        dip.quote_reserve_history.push(0, 100.0);
+        dip.quote_reserve_history.push(1_000, 70.0);
+        assert!(!dip.is_draining(1_000));
+
+        info.user_bot_data.bot_setting.drain_detection = false;
+        info.quote_reserve_history.push(46_000, 10.0);
+        assert!(!info.is_draining(46_000));
+    }
+
+    #[test]
+    fn exit_params_follow_the_regime() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.price_history = PriceHistory::default();
+        let settings = &mut info.user_bot_data.bot_setting;
+        settings.take_profit = 5.0;
+        settings.stop_loss = 5.0;
+
+        // Without regime sets the single default set applies
+        info.price_history.push(0, 1.0);
+        info.price_history.push(1_000, 1.1);
+        assert_eq!(info.exit_params(1_000), ExitParams { take_profit: 5.0, stop_loss: 5.0 });
+
+        let trending = ExitParams { take_profit: 30.0, stop_loss: 10.0 };
+        let ranging = ExitParams { take_profit: 3.0, stop_loss: 2.0 };
+        info.user_bot_data.bot_setting.regime_exits = Some(RegimeExits {
+            trending,
+            ranging,
+            window_secs: 60,
+            trend_threshold: 0.6,
+        });
+
+        // Steady climb: trending
+        for (i, price) in [1.2, 1.3, 1.4].into_iter().enumerate() {
+            info.price_history.push(2_000 + i as i64 * 1_000, price);
+        }
+        assert_eq!(info.market_regime(4_000), Some(MarketRegime::Trending));
+        assert_eq!(info.exit_params(4_000), trending);
+        assert_eq!(info.take_profit_target(4_000), 30.0);
+
+        // Chop back and forth: ranging
+        for (i, price) in [1.2, 1.4, 1.2, 1.4, 1.2, 1.4].into_iter().enumerate() {
+            info.price_history.push(5_000 + i as i64 * 1_000, price);
+        }
+        assert_eq!(info.market_regime(10_000), Some(MarketRegime::Ranging));
+        assert_eq!(info.exit_params(10_000), ranging);
+    }
+
+    #[test]
+    fn trailing_stop_ratchets_with_the_peak() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        assert_eq!(info.trailing_stop_drawdown(0.5), None);
+
+        info.user_bot_data.bot_setting.trailing_stop_pct = Some(10.0);
+        // Before any gain the stop sits 10% under entry
+        assert_eq!(info.trailing_stop_drawdown(0.95), None);
+        assert!(info.trailing_stop_drawdown(0.89).is_some());
+
+        // Peak ratchets up to 2.0, the stop follows to 1.8 (10% under it)
+        for price in [1.2, 2.0, 1.9] {
+            info.record_price(price);
+        }
+        assert_eq!(info.peak_price, Some(2.0));
+        assert_eq!(info.trailing_stop_drawdown(1.85), None);
+        let drawdown = info.trailing_stop_drawdown(1.7).unwrap();
+        assert!((drawdown - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stop_moves_to_break_even_after_the_threshold() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        info.user_bot_data.bot_setting.stop_loss = 10.0;
+        assert!(!info.arm_break_even_stop(50.0));
+        assert_eq!(info.stop_level_pct(0), -10.0);
+
+        info.user_bot_data.bot_setting.move_stop_to_be_at_pct = Some(5.0);
+        info.user_bot_data.bot_setting.break_even_stop_offset_pct = 0.5;
+        info.user_bot_data.bot_setting.buy_sol_amount = 1.0;
+        info.user_bot_data.bot_setting.exit_slippage = 1.0;
+        assert!(!info.arm_break_even_stop(4.0));
+        // A -2% dip before the move is inside the 10% stop loss
+        assert!(-2.0 > info.stop_level_pct(0));
+
+        // Rises past 5%, then dips back: the stop stays at the break-even
+        // after both legs' fees, pool fees and exit slippage, plus the offset
+        assert!(info.arm_break_even_stop(6.0));
+        assert!(!info.arm_break_even_stop(7.0));
+        assert!(!info.arm_break_even_stop(1.0));
+        assert!(info.stop_moved_to_be);
+        let break_even_pct = info.break_even_pct();
+        assert!(break_even_pct > 0.0);
+        assert!((info.stop_level_pct(0) - (break_even_pct + 0.5)).abs() < 1e-9);
+        assert_eq!(info.position_response(0).stop_loss_pct, -info.stop_level_pct(0));
+
+        // Priced with the pool's own fee once a swap on it has been seen
+        info.pool_program = Some(RAY_V4_PROGRAM_ID);
+        assert!(info.break_even_pct() < break_even_pct);
+
+        // Never above the level that moved it
+        info.user_bot_data.bot_setting.break_even_stop_offset_pct = 100.0;
+        assert_eq!(info.stop_level_pct(0), 5.0);
+    }
+
+    #[test]
+    fn no_buy_is_built_during_the_reentry_cooldown() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        let ix = Instruction::new_with_bytes(solana_sdk::pubkey::Pubkey::new_unique(), &[], vec![]);
+        // Without a cooldown the bot may enter again straight after an exit
+        info.close_position(1_000_000);
+        assert!(info.reentry_cooldown_elapsed(1_000_000));
+
+        info.user_bot_data.bot_setting.reentry_cooldown_secs = 30;
+        info.is_bought = true;
+        info.stop_moved_to_be = true;
+        info.close_position(2_000_000);
+        assert!(!info.is_bought && !info.stop_moved_to_be);
+        assert_eq!(info.last_exit_at, Some(2_000_000));
+
+        // A buy built while the pool keeps dropping is dropped, not held
+        info.set_swap_ixs(vec![ix.clone()], SwapAction::Buy, None);
+        assert!(!info.reentry_cooldown_elapsed(2_029_999));
+        assert!(info.pending_swap_ixs.is_empty());
+
+        assert!(info.cooling_down(2_029_999) && !info.cooling_down(2_030_000));
+        assert!(info.reentry_cooldown_elapsed(2_030_000));
+        info.set_swap_ixs(vec![ix], SwapAction::Buy, None);
+        assert!(info.reentry_cooldown_elapsed(2_030_001));
+        assert_eq!(info.pending_swap_ixs.len(), 1);
+    }
+
+    #[test]
+    fn tiered_positions_still_stop_out() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.user_bot_data.bot_setting.take_profit_tiers = vec![(30.0, 0.5), (80.0, 0.5)];
+        info.user_bot_data.bot_setting.stop_loss = 10.0;
+        info.user_bot_data.bot_setting.take_profit = 20.0;
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+
+        // Tiers replace the single take profit, nothing is due in between
+        assert_eq!(info.exit_signal(1.25, 25.0, 0), None);
+        assert_eq!(info.exit_signal(0.95, -5.0, 0), None);
+        assert_eq!(
+            info.exit_signal(1.35, 35.0, 0),
+            Some(ExitSignal::TakeProfitTiers { tiers: vec![0], fraction: 0.5, closes: false })
+        );
+
+        // Falling through the stop with no tier due sells what is held
+        assert_eq!(info.exit_signal(0.88, -12.0, 0), Some(ExitSignal::StopLoss));
+        info.fire_tiers(&[0]);
+        assert_eq!(info.exit_signal(0.88, -12.0, 0), Some(ExitSignal::StopLoss));
+
+        info.user_bot_data.bot_setting.trailing_stop_pct = Some(5.0);
+        info.peak_price = Some(1.2);
+        assert!(matches!(info.exit_signal(1.1, 10.0, 0), Some(ExitSignal::TrailingStop(_))));
+        // Through both stops, the trailing one is checked first
+        assert!(matches!(info.exit_signal(0.88, -12.0, 0), Some(ExitSignal::TrailingStop(_))));
+
+        info.user_bot_data.bot_setting.trailing_stop_pct = None;
+        info.user_bot_data.bot_setting.auto_exit = 60;
+        info.bought_at = Some(0);
+        assert_eq!(info.exit_signal(1.1, 10.0, 61_000), Some(ExitSignal::AutoExit));
+    }
+
+    #[test]
+    fn stop_loss_is_not_held_up_by_a_pending_dca_buy() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        let ix = Instruction::new_with_bytes(solana_sdk::pubkey::Pubkey::new_unique(), &[], vec![]);
+        info.user_bot_data.bot_setting.stop_loss = 20.0;
+        info.user_bot_data.bot_setting.dca_levels = vec![10.0];
+        info.user_bot_data.bot_setting.dca_amounts = vec![0.01];
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        info.held_token_amount = Some(1_000);
+        info.set_swap_ixs(vec![ix.clone()], SwapAction::Sell, None);
+        assert!(info.ready_to_sell());
+
+        assert_eq!(info.exit_signal(0.88, -12.0, 0), Some(ExitSignal::DcaLevel(0)));
+        info.fire_dca_level(0);
+        assert_eq!(info.exit_signal(0.88, -12.0, 0), Some(ExitSignal::DcaWaiting(0)));
+
+        // The price keeps falling through the stop before the buy is built
+        let signal = info.exit_signal(0.75, -25.0, 0).unwrap();
+        assert_eq!(signal, ExitSignal::StopLoss);
+        assert!(signal.sells());
+        info.set_swap_ixs(vec![ix.clone()], SwapAction::Buy, Some(0));
+        assert!(!info.ready_to_sell());
+        assert_eq!(info.pending_dca_level, None);
+        assert!(info.pending_swap_ixs.is_empty());
+
+        // The next swap builds the sell, which the stop then submits
+        info.set_swap_ixs(vec![ix], SwapAction::from_held(!info.buying()), None);
+        assert!(info.ready_to_sell());
+        assert_eq!(info.exit_signal(0.75, -25.0, 0), Some(ExitSignal::StopLoss));
+
+        info.force_exit = true;
+        assert_eq!(info.exit_signal(0.75, -25.0, 0), Some(ExitSignal::ForceExit));
+    }
+
+    #[test]
+    fn take_profit_tiers_sell_their_share_then_close() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.user_bot_data.bot_setting.take_profit_tiers = vec![(30.0, 0.5), (80.0, 0.5)];
+        info.bought_token_amount = Some(1_000);
+        info.held_token_amount = Some(1_000);
+
+        assert_eq!(info.take_profit_tiers_due(20.0), None);
+        let (due, fraction, closes) = info.take_profit_tiers_due(35.0).unwrap();
+        assert_eq!((due.clone(), fraction, closes), (vec![0], 0.5, false));
+        info.fire_tiers(&due);
+        info.sell_fraction = Some(fraction);
+        assert_eq!(info.sell_amount(1_000), 500);
+
+        // The first tier's sell landed, the second sells what is left
+        info.held_token_amount = Some(500);
+        assert_eq!(info.take_profit_tiers_due(50.0), None);
+        let (due, fraction, closes) = info.take_profit_tiers_due(90.0).unwrap();
+        assert_eq!((due, closes), (vec![1], true));
+        info.sell_fraction = Some(fraction);
+        // Never more than the position still holds, even when the ATA has more
+        info.held_token_amount = Some(400);
+        assert_eq!(info.sell_amount(5_000), 400);
+    }
+
+    #[test]
+    fn sandwiched_pools_suppress_entries() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        // Front-run buy, victim buy, back-run sell
+        for (ts, reserve) in [(10_000, 100.0), (11_000, 103.0), (12_000, 103.5), (13_000, 100.6)] {
+            info.quote_reserve_history.push(ts, reserve);
+        }
+        assert!(!info.sandwich_suspected(14_000));
+
+        info.user_bot_data.bot_setting.anti_sandwich = true;
+        assert!(info.sandwich_suspected(14_000));
+        // Falls out of the window a minute later
+        assert!(!info.sandwich_suspected(80_000));
+
+        info.user_bot_data.bot_setting.sandwich_min_move_pct = 5.0;
+        assert!(!info.sandwich_suspected(14_000));
+    }
+
+    #[test]
+    fn dca_levels_scale_in_and_average_the_entry() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.user_bot_data.bot_setting.dca_levels = vec![10.0, 20.0];
+        info.user_bot_data.bot_setting.dca_amounts = vec![1.0, 2.0];
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        assert!(!info.buying());
+
+        // Nothing fires until the first buy has landed
+        assert_eq!(info.dca_level_due(0.85), None);
+        info.route.record_buy("mint".to_string(), 1_000_000_000, 1_000);
+        info.held_token_amount = Some(1_000);
+
+        assert_eq!(info.dca_level_due(0.95), None);
+        assert_eq!(info.dca_level_due(0.85), Some(0));
+        info.fire_dca_level(0);
+        assert!(info.buying());
+        info.set_swap_ixs(Vec::new(), SwapAction::from_held(!info.buying()), Some(0));
+        info.record_submission("scale-in", info.pending_action);
+        assert!(info.confirming_buy());
+        assert_eq!(info.user_bot_data.bot_setting.dca_buy_sol_amount(info.pending_dca_level), 1.0);
+        // One level at a time
+        assert_eq!(info.dca_level_due(0.7), None);
+
+        // 1 SOL at 1.0 plus 1 SOL at 0.5 averages to 2 SOL / 3 tokens
+        info.pending_dca_level = None;
+        info.record_scale_in_fill(1_000_000_000, 2_000, 0.5);
+        assert!((info.bought_price.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(info.held_token_amount, Some(3_000));
+        assert_eq!(info.route.sol_in_lamports, 2_000_000_000);
+
+        // Levels stay measured from the first fill, not the new average
+        assert_eq!(info.dca_level_due(0.85), None);
+        assert_eq!(info.dca_level_due(0.79), Some(1));
+
+        // A partial take-profit keeps the position bought but confirms as a sell
+        info.set_swap_ixs(Vec::new(), SwapAction::from_held(!info.buying()), None);
+        assert!(!info.record_submission("tier", info.pending_action));
+        assert!(info.is_bought);
+        assert!(!info.confirming_buy());
+    }
+
+    #[test]
+    fn positions_sharing_a_mint_sell_their_own_amount() {
+        let ata_balance = 3_000;
+
+        let mut first = pool_info(EntryReference::LastPrice, 30);
+        first.held_token_amount = Some(1_000);
+        let mut second = pool_info(EntryReference::LastPrice, 30);
+        second.user_bot_data.pool_id = "other-pool".to_string();
+        second.held_token_amount = Some(2_000);
+
+        assert_eq!(first.sell_amount(ata_balance), 1_000);
+        assert_eq!(second.sell_amount(ata_balance), 2_000);
+        // After the first sells, the second still gets its full amount
+        assert_eq!(second.sell_amount(ata_balance - 1_000), 2_000);
+
+        // Unknown holding falls back to the ATA balance
+        let unknown = pool_info(EntryReference::LastPrice, 30);
+        assert_eq!(unknown.sell_amount(ata_balance), ata_balance);
+    }
+
+    #[tokio::test]
+    async fn recorded_prices_are_sent_to_the_monitor() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let _ = crate::statics::PRICE_UPDATES.set(tx);
+
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.user_bot_data.pool_id = "channel-pool".to_string();
+        info.record_price(1.25);
+
+        // Other tests record prices too, skip to this position's update
+        let update = tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            loop {
+                let update: PriceUpdate = rx.recv().await.unwrap();
+                if update.pool_id == "channel-pool" {
+                    return update;
+                }
+            }
+        })
+        .await
+        .expect("monitor should receive the recorded price");
+        assert_eq!(update.price, 1.25);
+    }
+
+    fn lifecycle_bot(user_id: &str, pool_id: &str) -> RealPoolInfo {
+        RealPoolInfo::new(UserBotData {
+            pool_id: pool_id.to_string(),
+            user_id: user_id.to_string(),
+            private_key: String::new(),
+            public_key: String::new(),
+            bot_setting: BotSettings::new(user_id.to_string(), "bot".to_string(), pool_id.to_string()),
+        })
+    }
+
+    async fn lifecycle_state(user_id: &str) -> (usize, usize) {
+        let listed = crate::statics::USER_LIST
+            .read()
+            .await
+            .iter()
+            .filter(|user_bot_data| user_bot_data.user_id == user_id)
+            .count();
+        let positioned = positions_where(&crate::statics::REAL_POOL_INFO, |info| info.user_bot_data.user_id == user_id).len();
+        (listed, positioned)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn interleaved_starts_and_stops_leave_the_bot_listed_with_its_position() {
+        let (user_id, pool_id) = ("lifecycle-user", "lifecycle-pool");
+
+        let tasks: Vec<_> = (0..200)
+            .map(|i| {
+                tokio::spawn(async move {
+                    if i % 3 != 0 {
+                        (BotService::activate(lifecycle_bot(user_id, pool_id)).await, false)
+                    } else {
+                        let stopped = matches!(
+                            BotService::deactivate(user_id, pool_id).await,
+                            Deactivation::Stopped(Some(_))
+                        );
+                        (false, stopped)
+                    }
+                })
+            })
+            .collect();
+        let (mut starts, mut stops) = (0, 0);
+        for task in tasks {
+            let (started, stopped) = task.await.unwrap();
+            starts += started as usize;
+            stops += stopped as usize;
+        }
+
+        // Repeated starts and stops were no-ops, so the rest alternated
+        let (listed, positioned) = lifecycle_state(user_id).await;
+        assert!(listed <= 1);
+        assert_eq!(listed, positioned);
+        assert!(starts > 0);
+        assert_eq!(starts - stops, listed);
+
+        // A held position is sold before the bot goes, release then removes it
+        if listed == 0 {
+            assert!(BotService::activate(lifecycle_bot(user_id, pool_id)).await);
+        }
+        update_position(&crate::statics::REAL_POOL_INFO, pool_id, user_id, |info| info.is_bought = true);
+        assert!(matches!(BotService::deactivate(user_id, pool_id).await, Deactivation::Exiting));
+        assert!(position(&crate::statics::REAL_POOL_INFO, pool_id, user_id).unwrap().force_exit);
+        assert!(!BotService::activate(lifecycle_bot(user_id, pool_id)).await);
+        assert!(BotService::release(user_id).await.is_some());
+        assert_eq!(lifecycle_state(user_id).await, (0, 0));
+        assert!(matches!(BotService::deactivate(user_id, pool_id).await, Deactivation::AlreadyStopped));
+    }
+
+    #[tokio::test]
+    async fn processor_cleanup_waits_for_a_start_in_progress() {
+        let user_id = "cleanup-user";
+        assert!(BotService::activate(lifecycle_bot(user_id, "cleanup-pool")).await);
+
+        let lifecycle = crate::statics::BOT_LIFECYCLE_LOCKS.lock(user_id).await;
+        let release = tokio::spawn(BotService::release(user_id));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!release.is_finished());
+        assert_eq!(lifecycle_state(user_id).await, (1, 1));
+
+        drop(lifecycle);
+        assert!(release.await.unwrap().is_some());
+        assert_eq!(lifecycle_state(user_id).await, (0, 0));
+    }
+
+    #[test]
+    fn sweep_only_returns_positions_past_auto_exit() {
+        let mut due = pool_info(EntryReference::LastPrice, 30);
+        due.user_bot_data.bot_setting.auto_exit = 10;
+        due.is_bought = true;
+        due.bought_at = Some(0);
+        let mut fresh = due.clone();
+        fresh.user_bot_data.user_id = "fresh".to_string();
+        fresh.bought_at = Some(50_000);
+        let pools = PoolInfoMap::from_iter([("pool".to_string(), vec![due, fresh])]);
+
+        let exits = due_auto_exits(&pools, 55_000);
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].bought_at, Some(0));
+    }
+
+    #[test]
+    fn panic_sell_triggers_each_open_position_once() {
+        let mut open = pool_info(EntryReference::LastPrice, 30);
+        open.is_bought = true;
+        let mut other_pool = open.clone();
+        other_pool.user_bot_data.pool_id = "other".to_string();
+        let mut watching = open.clone();
+        watching.is_bought = false;
+        watching.user_bot_data.pool_id = "watching".to_string();
+        let mut someone_else = open.clone();
+        someone_else.user_bot_data.user_id = "someone else".to_string();
+        let pools = PoolInfoMap::from_iter([
+            ("pool".to_string(), vec![open, someone_else]),
+            ("other".to_string(), vec![other_pool]),
+            ("watching".to_string(), vec![watching]),
+        ]);
+
+        let first = trigger_panic_sell(&pools, "user");
+        assert_eq!((first.triggered, first.already_selling), (2, 0));
+        assert!(!position(&pools, "pool", "someone else").unwrap().force_exit);
+        assert!(!position(&pools, "watching", "user").unwrap().force_exit);
+        // The sweep picks forced exits up without waiting for a price update
+        assert_eq!(due_auto_exits(&pools, 0).len(), 2);
+
+        update_position(&pools, "pool", "user", |info| info.selling_in_progress = true);
+        assert_eq!(due_auto_exits(&pools, 0).len(), 1);
+
+        let second = trigger_panic_sell(&pools, "user");
+        assert_eq!((second.triggered, second.already_selling), (0, 2));
+    }
+
+    #[test]
+    fn positions_snapshot_lists_open_positions_with_their_exits() {
+        let mut gaining = pool_info(EntryReference::LastPrice, 30);
+        gaining.user_bot_data.bot_setting.take_profit = 20.0;
+        gaining.user_bot_data.bot_setting.stop_loss = 10.0;
+        gaining.user_bot_data.bot_setting.auto_exit = 600;
+        gaining.is_bought = true;
+        gaining.bought_price = Some(1.0);
+        gaining.latest_pool_price = 1.1;
+        gaining.bought_at = Some(40_000);
+        gaining.last_input_lamports_delta = Some(2_000_000_000);
+        let mut losing = gaining.clone();
+        losing.user_bot_data.pool_id = "other".to_string();
+        losing.latest_pool_price = 0.95;
+        losing.last_input_lamports_delta = Some(1_000_000_000);
+        let mut watching = gaining.clone();
+        watching.user_bot_data.pool_id = "watching".to_string();
+        watching.is_bought = false;
+        let mut someone_else = gaining.clone();
+        someone_else.user_bot_data.user_id = "someone else".to_string();
+        let pools = PoolInfoMap::from_iter([
+            ("pool".to_string(), vec![gaining, someone_else]),
+            ("other".to_string(), vec![losing]),
+            ("watching".to_string(), vec![watching]),
+        ]);
+
+        let snapshot = open_positions(&pools, "user", 100_000);
+        let mut pool_ids: Vec<_> = snapshot.positions.iter().map(|p| p.pool_id.as_str()).collect();
+        pool_ids.sort();
+        assert_eq!(pool_ids, ["other", "pool"]);
+
+        let gaining = snapshot.positions.iter().find(|p| p.pool_id == "pool").unwrap();
+        assert!((gaining.unrealized_pnl_sol.unwrap() - 0.2).abs() < 1e-9);
+        assert_eq!(gaining.hold_secs, Some(60));
+        assert_eq!((gaining.take_profit_pct, gaining.stop_loss_pct), (20.0, 10.0));
+        assert_eq!(gaining.auto_exit_at, Some(640_000));
+
+        // +0.2 SOL on the 2 SOL position, -0.05 SOL on the 1 SOL one
+        assert!((snapshot.unrealized_pnl_sol - 0.15).abs() < 1e-9);
+        assert!(open_positions(&pools, "nobody", 100_000).positions.is_empty());
+    }
+
+    #[test]
+    fn each_round_trip_gets_its_own_trade_record() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+
+        // Buy 1 SOL worth, sell it for 1.1 SOL, 10_000 lamports of network fees
+        info.record_network_fee(5_000);
+        info.record_input_lamports(1_000_000_000);
+        info.route.record_buy("mint".to_string(), 1_000_000_000, 500);
+        info.record_network_fee(5_000);
+        info.route.record_sell(500, 1_100_000_000);
+        info.slippage.record_exit(0.5);
+        info.record_exit_profit(1_100_000_000, None);
+        info.close_position(1_000);
+
+        let first = info.finish_round_trip();
+        assert!((first.profit_sol - 0.09999).abs() < 1e-9);
+        assert_eq!(first.fees_lamports, 10_000);
+        assert_eq!(first.route.as_ref().map(|route| route.sol_out_lamports), Some(1_100_000_000));
+        assert_eq!(first.pool_id.as_deref(), Some("pool"));
+        assert!(first.slippage.is_some());
+
+        // The next round trip doesn't inherit the first one's fees or input
+        assert_eq!((info.fee_lamports, info.last_input_lamports_delta), (0, None));
+        info.record_network_fee(5_000);
+        info.record_input_lamports(1_000_000_000);
+        info.record_network_fee(5_000);
+        info.record_exit_profit(900_000_000, None);
+        let second = info.finish_round_trip();
+        assert!((second.profit_sol + 0.10001).abs() < 1e-9);
+        assert_eq!(second.fees_lamports, 10_000);
+        assert_eq!((second.route, second.slippage), (None, None));
+    }
+
+    #[test]
+    fn a_fill_is_recorded_once_and_only_unfilled_entries_roll_back() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        info.signature = Some("sent".to_string());
+
+        // The stream and the poller race, only the first records the fill
+        assert!(!info.claim_fill("someone else's"));
+        assert!(info.claim_fill("sent"));
+        assert!(!info.claim_fill("sent"));
+        assert!(!info.abandon_unfilled_entry("sent"));
+        assert!(info.is_bought);
+
+        info.signature = Some("next".to_string());
+        info.recorded_fill = None;
+        // A newer submission isn't the one that timed out
+        assert!(!info.abandon_unfilled_entry("sent"));
+        assert!(info.abandon_unfilled_entry("next"));
+        assert!(!info.is_bought);
+        assert_eq!((info.signature.as_ref(), info.bought_price), (None, None));
+    }
+
+    #[test]
+    fn network_fees_add_up_in_lamports() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        assert_eq!(info.fee_lamports, 0);
+
+        // Entry with a priority fee, then a plain exit
+        info.record_network_fee(105_000);
+        info.record_network_fee(5_000);
+        assert_eq!(info.fee_lamports, 110_000);
+        assert_eq!(lamports_to_sol(info.fee_lamports), 0.000_11);
+    }
+
+    #[test]
+    fn exit_profit_is_received_minus_spent_minus_fees() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        // 1 SOL in, 1.2 SOL back, 105_000 + 5_000 lamports of network fees
+        info.record_input_lamports(1_000_000_000);
+        info.record_network_fee(105_000);
+        info.record_network_fee(5_000);
+
+        let (profit_sol, roi) = info.record_exit_profit(1_200_000_000, None);
+        assert!((profit_sol - 0.19989).abs() < 1e-12);
+        assert!((roi - 19.989).abs() < 1e-9);
+        assert_eq!(info.last_output_lamports_delta, Some(1_200_000_000));
+        assert_eq!((info.last_profit_sol, info.last_roi_pct), (Some(profit_sol), Some(roi)));
+
+        // A losing exit: 0.5 SOL in, 0.45 SOL back, 10_000 lamports of fees
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.record_input_lamports(500_000_000);
+        info.record_network_fee(10_000);
+        let (profit_sol, roi) = info.record_exit_profit(450_000_000, None);
+        assert!((profit_sol + 0.05001).abs() < 1e-12);
+        assert!((roi + 10.002).abs() < 1e-9);
+
+        // Nothing spent on record, no ROI to speak of
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        assert_eq!(info.record_exit_profit(1_000, None).1, 0.0);
+    }
+
+    #[test]
+    fn partial_sells_are_charged_their_share_of_the_entry() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        // 1 SOL for 1_000 tokens, 10_000 lamports of entry fees
+        info.record_input_lamports(1_000_000_000);
+        info.record_network_fee(10_000);
+        info.bought_token_amount = Some(1_000);
+
+        // A tier sells 400 tokens for 0.5 SOL, 600 stay held
+        info.record_network_fee(5_000);
+        info.held_token_amount = Some(600);
+        let (profit_sol, roi) = info.record_exit_profit(500_000_000, Some(400));
+        assert!((profit_sol - 0.099985).abs() < 1e-12);
+        assert!((roi - 24.99625).abs() < 1e-9);
+
+        // The last 600 go for 0.6 SOL and are charged the rest of the entry
+        info.record_network_fee(5_000);
+        info.held_token_amount = None;
+        let (profit_sol, _) = info.record_exit_profit(600_000_000, Some(600));
+        assert!((profit_sol + 0.000005).abs() < 1e-12);
+
+        // The round trip's record adds both sells up
+        let trade = info.finish_round_trip();
+        assert!((trade.profit_sol - 0.09998).abs() < 1e-12);
+        assert!((trade.roi_pct - 9.998).abs() < 1e-9);
+    }
+
+    #[test]
+    fn paper_round_trips_are_priced_at_the_pool() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.latest_pool_price = 0.001;
+        assert!(info.record_paper_buy(1_000_000_000, 5_000));
+        assert!(!info.record_paper_buy(1_000_000_000, 6_000));
+        assert_eq!(info.bought_price, Some(0.001));
+        assert_eq!(info.bought_at, Some(5_000));
+        assert_eq!(info.held_token_amount, Some(1_000_000_000_000));
+
+        // Half sold at +20%, the other half back at the entry price
+        info.latest_pool_price = 0.0012;
+        let (profit_sol, roi) = info.record_paper_sell(500_000_000_000).unwrap();
+        assert!((profit_sol - 0.1).abs() < 1e-9);
+        assert!((roi - 20.0).abs() < 1e-9);
+        assert_eq!(info.held_token_amount, Some(500_000_000_000));
+
+        info.latest_pool_price = 0.001;
+        let (profit_sol, _) = info.record_paper_sell(u64::MAX).unwrap();
+        assert!(profit_sol.abs() < 1e-9);
+        assert_eq!(info.held_token_amount, None);
+        assert_eq!(info.record_paper_sell(u64::MAX), None);
+
+        let trade = info.finish_round_trip();
+        assert!((trade.profit_sol - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn open_position_round_trips_through_storage() {
+        let mut open = pool_info(EntryReference::LastPrice, 30);
+        open.is_bought = true;
+        open.bought_price = Some(1.25);
+        open.bought_at = Some(90_000);
+        open.signature = Some("sig".to_string());
+        open.last_input_lamports_delta = Some(100_000_000);
+        open.fee_lamports = 5_000;
+        open.last_exit_at = Some(20_000);
+        open.started_at = 40_000;
+        open.route.record_buy("mint".to_string(), 100_000_000, 1_000);
+        open.bought_token_amount = Some(1_000);
+        open.held_token_amount = Some(600);
+        open.entry_latency = Some(EntryLatency { slots: 4, ms: Some(2_000), slow: true });
+
+        // Through BSON and back, the way the positions collection stores it
+        let document = bson::to_document(&StoredPosition::from_position(&open)).unwrap();
+        let stored: StoredPosition = bson::from_document(document).unwrap();
+
+        let mut restored = pool_info(EntryReference::LastPrice, 30);
+        restored.restore(&stored, 100_000);
+        assert!(restored.is_bought);
+        assert_eq!(restored.bought_price, Some(1.25));
+        assert_eq!(restored.bought_at, Some(90_000));
+        assert_eq!(restored.signature.as_deref(), Some("sig"));
+        assert_eq!(restored.last_input_lamports_delta, Some(100_000_000));
+        assert_eq!(restored.fee_lamports, 5_000);
+        assert_eq!(restored.last_exit_at, Some(20_000));
+        assert_eq!(restored.started_at, 40_000);
+        assert_eq!(restored.route, open.route);
+        assert_eq!((restored.held_token_amount, restored.bought_token_amount), (Some(600), Some(1_000)));
+        assert_eq!(restored.entry_latency, open.entry_latency);
+
+        // Held for a minute before the restart, best effort
+        let held = restored.start_time.unwrap().elapsed();
+        assert!(held >= std::time::Duration::from_secs(60), "held for {:?}", held);
+    }
+
+    #[test]
+    fn unrealized_pnl_is_measured_from_the_bought_price() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.latest_pool_price = 1.5;
+        assert_eq!(info.unrealized_pnl_pct(), None);
+
+        info.bought_price = Some(1.2);
+        assert!((info.unrealized_pnl_pct().unwrap() - 25.0).abs() < 1e-9);
+        info.latest_pool_price = 0.9;
+        assert!((info.unrealized_pnl_pct().unwrap() + 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn repeated_exit_failures_flag_the_position_stuck_once() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.is_bought = true;
+
+        // Failures spread wider than the window never add up
+        assert!(!info.record_exit_failure(0, 3, 60_000));
+        assert!(!info.record_exit_failure(50_000, 3, 60_000));
+        assert!(!info.record_exit_failure(100_000, 3, 60_000));
+        assert_eq!(info.stuck_since, None);
+
+        assert!(info.record_exit_failure(105_000, 3, 60_000));
+        assert_eq!(info.stuck_since, Some(105_000));
+        // Already stuck, so no second alert
+        assert!(!info.record_exit_failure(120_000, 3, 60_000));
+
+        info.clear_exit_failures();
+        assert_eq!(info.stuck_since, None);
+        assert!(info.exit_failures.is_empty());
+        assert!(!info.record_exit_failure(130_000, 0, 60_000));
+    }
+
+    #[test]
+    fn failed_exits_keep_the_position_open_until_it_is_flagged_stuck() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.user_bot_data.bot_setting.stop_loss = 10.0;
+        info.user_bot_data.bot_setting.take_profit_tiers = vec![(30.0, 0.5), (80.0, 0.5)];
+        info.is_bought = true;
+        info.bought_price = Some(1.0);
+        info.held_token_amount = Some(1_000);
+
+        // A tier sell that fails is fired again by the next price update
+        let Some(ExitSignal::TakeProfitTiers { tiers, .. }) = info.exit_signal(1.35, 35.0, 0) else {
+            panic!("tier not due");
+        };
+        assert!(info.claim_exit());
+        info.fire_tiers(&tiers);
+        assert!(!info.record_exit_failure(0, 3, 60_000));
+        assert!(matches!(info.exit_signal(1.35, 35.0, 0), Some(ExitSignal::TakeProfitTiers { .. })));
+        assert!(info.is_bought);
+
+        // Every failed stop-loss sell leaves the position open and is retried
+        for (attempt, now_ms) in [(2, 10_000), (3, 20_000)] {
+            assert_eq!(info.exit_signal(0.85, -15.0, now_ms), Some(ExitSignal::StopLoss));
+            assert!(info.claim_exit());
+            // Nothing else is submitted while the sell is in flight
+            assert!(!info.claim_exit());
+            let flagged = info.record_exit_failure(now_ms, 3, 60_000);
+            assert_eq!(flagged, attempt == 3);
+            assert!(info.is_bought);
+            assert_eq!(info.held_token_amount, Some(1_000));
+            assert!(!info.selling_in_progress);
+        }
+        assert_eq!(info.stuck_since, Some(20_000));
+        assert_eq!(info.exit_failures.len(), 3);
+
+        // The sell that finally lands closes it
+        assert_eq!(info.exit_signal(0.85, -15.0, 30_000), Some(ExitSignal::StopLoss));
+        assert!(info.claim_exit());
+        info.clear_exit_failures();
+        info.held_token_amount = None;
+        let (_, stopping) = info.settle_exit(30_000).unwrap();
+        assert!(!stopping);
+        assert!(!info.is_bought);
+        assert!(!info.selling_in_progress);
+        assert_eq!(info.stuck_since, None);
+        assert_eq!(info.last_exit_at, Some(30_000));
+    }
+
+    #[test]
+    fn a_partial_sell_settles_without_closing_the_position() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        info.user_bot_data.bot_setting.take_profit_tiers = vec![(30.0, 0.5), (80.0, 0.5)];
+        info.is_bought = true;
+        info.held_token_amount = Some(1_000);
+
+        assert!(info.claim_exit());
+        info.fire_tiers(&[0]);
+        info.held_token_amount = Some(500);
+        assert!(info.settle_exit(1_000).is_none());
+        assert!(info.is_bought);
+        assert!(!info.selling_in_progress);
+        // A later failure doesn't unfire the tier that sold
+        assert!(!info.record_exit_failure(2_000, 3, 60_000));
+        assert_eq!(info.fired_tiers, vec![true, false]);
+    }
+
+    #[test]
+    fn entry_latency_runs_from_the_signalling_swap_to_the_fill() {
+        let mut info = pool_info(EntryReference::LastPrice, 30);
+        let signal = SlotTime { slot: 1_000, block_time: Some(1_700_000_000) };
+        info.record_swap(RAY_V4_PROGRAM_ID, 1.0, 50.0, signal);
+        // The submission remembers the swap it reacted to
+        info.signal_at = info.last_swap_at;
+        info.record_swap(RAY_V4_PROGRAM_ID, 1.1, 49.0, SlotTime { slot: 1_001, block_time: Some(1_700_000_000) });
+
+        info.record_entry_fill(SlotTime { slot: 1_003, block_time: Some(1_700_000_001) });
+        assert_eq!(info.entry_latency, Some(EntryLatency { slots: 3, ms: Some(1_000), slow: false }));
+        // Past either of the bot's thresholds it's a lost race
+        info.user_bot_data.bot_setting.slow_entry_slots = 2;
+        info.record_entry_fill(SlotTime { slot: 1_003, block_time: Some(1_700_000_001) });
+        assert!(info.entry_latency.unwrap().slow);
+        info.user_bot_data.bot_setting.slow_entry_slots = 3;
+        info.user_bot_data.bot_setting.slow_entry_ms = 500;
+        info.record_entry_fill(SlotTime { slot: 1_003, block_time: Some(1_700_000_001) });
+        assert!(info.entry_latency.unwrap().slow);
+        info.user_bot_data.bot_setting.slow_entry_ms = 1_000;
+        info.record_entry_fill(SlotTime { slot: 1_003, block_time: Some(1_700_000_001) });
+        assert!(!info.entry_latency.unwrap().slow);
+
+        // A DCA fill doesn't replace the entry's latency
+        info.filled_levels = vec![true];
+        info.record_entry_fill(SlotTime { slot: 1_050, block_time: Some(1_700_000_020) });
+        assert_eq!(info.entry_latency.unwrap().slots, 3);
+
+        // Block times are optional, a fill before its signal isn't measured
+        let signal = SlotTime { slot: 2_000, block_time: None };
+        assert_eq!(
+            EntryLatency::between(signal, SlotTime { slot: 2_002, block_time: Some(1) }),
+            Some(EntryLatency { slots: 2, ms: None, slow: false })
+        );
+        assert_eq!(EntryLatency::between(signal, SlotTime { slot: 1_999, block_time: None }), None);
+    }
+
+    #[test]
+    fn start_is_refused_at_the_open_position_limit() {
+        let pools = PoolInfoMap::new();
+        for pool_id in ["pool-a", "pool-b"] {
+            let mut info = pool_info(EntryReference::LastPrice, 30);
+            info.is_bought = pool_id == "pool-a";
+            get_or_insert_position(&pools, pool_id, info);
+        }
+        let mut settings = BotSettings::new("user".to_string(), "bot".to_string(), "pool-c".to_string());
+
+        // Only the bought position counts
+        settings.max_open_positions = 2;
+        assert!(check_open_position_limit(&pools, &settings).is_ok());
+        settings.max_open_positions = 1;
+        assert!(matches!(check_open_position_limit(&pools, &settings), Err(AppError::Conflict(_))));
+
+        settings.max_open_positions = 0;
+        assert!(check_open_position_limit(&pools, &settings).is_ok());
+        settings.user_id = "other".to_string();
+        settings.max_open_positions = 1;
+        assert!(check_open_position_limit(&pools, &settings).is_ok());
+    }
+
+    #[test]
+    fn start_is_refused_when_the_wallet_cannot_pay_for_a_buy() {
+        use crate::utils::rent::{ata_creations, lamports_transferred_from, required_buy_lamports, TOKEN_ACCOUNT_RENT_LAMPORTS};
+
+        let mut settings = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        settings.buy_sol_amount = 0.5;
+        settings.buy_usd1_amount = 20.0;
+        let owner = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let fee_sol = 0.000_015;
+
+        // SOL pool: the buy amount is wrapped, and both token accounts cost rent
+        let mints = [(token, Pubkey::new_unique()), (WSOL, spl_token::ID)];
+        let ixs = entry_setup_instructions(&owner, &mints, &WSOL, &settings);
+        assert_eq!(ata_creations(&ixs).len(), 2);
+        assert_eq!(lamports_transferred_from(&ixs, &owner), 500_000_000);
+        assert_eq!(
+            required_buy_lamports(lamports_transferred_from(&ixs, &owner), ata_creations(&ixs).len(), fee_sol),
+            500_000_000 + 2 * TOKEN_ACCOUNT_RENT_LAMPORTS + 15_000
+        );
+
+        // USD1 pool: no SOL goes into the buy, the amount is checked in USD1
+        let mints = [(token, spl_token::ID), (USD1, spl_token::ID)];
+        let ixs = entry_setup_instructions(&owner, &mints, &USD1, &settings);
+        assert_eq!(lamports_transferred_from(&ixs, &owner), 0);
+        assert_eq!(ata_creations(&ixs).len(), 2);
+        assert!(check_quote_token_balance(20_000_000, 20_000_000).is_ok());
+        assert!(matches!(check_quote_token_balance(19_999_999, 20_000_000), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn buys_too_small_to_cover_their_round_trip_are_refused() {
+        let mut settings = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        settings.third_party_fee = 0.001;
+        settings.cu = 200_000;
+        settings.priority_fee_micro_lamport = 50_000;
+        // Two 0.001015 SOL submissions and 0.00203928 SOL of rent
+        let cost_sol = round_trip_cost_sol(settings.cu, settings.priority_fee_micro_lamport, settings.third_party_fee);
+        assert!((cost_sol - 0.004_069_28).abs() < 1e-12);
+
+        // The 0.001 SOL default pays four times its size to trade
+        let Err(AppError::Validation(message)) = check_min_viable_trade(&settings, 10) else {
+            panic!("the default buy size was accepted");
+        };
+        assert!(message.contains("use at least 0.0407 SOL"));
+
+        settings.buy_sol_amount = 0.041;
+        assert!(check_min_viable_trade(&settings, 10).is_ok());
+        settings.buy_sol_amount = 0.001;
+        assert!(check_min_viable_trade(&settings, 0).is_ok());
+    }
+
+    #[test]
+    fn edits_only_check_the_buy_size_when_they_move_its_cost() {
+        let previous = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        // The default size can't pay for its round trip, other edits still save
+        let mut settings = previous.clone();
+        settings.take_profit = 50.0;
+        assert!(check_min_viable_trade_change(&previous, &settings, 10).is_ok());
+        assert!(check_min_viable_trade(&settings, 10).is_err());
+
+        settings.third_party_fee = 0.0002;
+        assert!(check_min_viable_trade_change(&previous, &settings, 10).is_err());
+        settings.buy_sol_amount = 1.0;
+        assert!(check_min_viable_trade_change(&previous, &settings, 10).is_ok());
+    }
+
+    #[test]
+    fn stopping_a_deleted_bot_is_a_404() {
+        use axum::{http::StatusCode, response::IntoResponse};
+
+        let err = user_bot(&[]).unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+        let bots = [BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string())];
+        assert_eq!(user_bot(&bots).unwrap().pool_address, "pool");
+    }
 }
@@ -0,0 +1,232 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::{collections::BTreeMap, env, fmt::Write, sync::atomic::Ordering};
+
+use crate::backend::{
+    db::connection::AppDatabase,
+    db::trade_repository::{TradeRepository, TradeStats},
+};
+use crate::utils::processor_errors::PROCESSOR_ERRORS_TOTAL;
+
+/// Label value used for everything past the cardinality cap
+pub const OVERFLOW_LABEL: &str = "other";
+
+/// Upper bound on distinct label sets per metric family
+pub static METRICS_MAX_LABEL_SETS: Lazy<usize> = Lazy::new(|| {
+    env::var("METRICS_MAX_LABEL_SETS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(200)
+});
+
+/// Open position count for one user on one pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionSample {
+    pub user_id: String,
+    pub pool_id: String,
+    pub open: u64,
+}
+
+pub struct MetricsService {
+    trade_repo: TradeRepository,
+}
+
+impl MetricsService {
+    pub fn new(database: AppDatabase) -> Self {
+        Self {
+            trade_repo: TradeRepository::new(database),
+        }
+    }
+
+    /// Render the labeled per-user metrics in Prometheus text format
+    pub async fn render(&self) -> Result<String> {
+        let positions = Self::open_positions().await;
+        let user_stats = self.trade_repo.get_stats_by_user().await?;
+
+        let mut out = render_metrics(&positions, &user_stats, *METRICS_MAX_LABEL_SETS);
+        let _ = writeln!(
+            out,
+            "# HELP bot_processor_errors_total Errors returned by processor tasks"
+        );
+        let _ = writeln!(out, "# TYPE bot_processor_errors_total counter");
+        let _ = writeln!(
+            out,
+            "bot_processor_errors_total {}",
+            PROCESSOR_ERRORS_TOTAL.load(Ordering::Relaxed)
+        );
+
+        Ok(out)
+    }
+
+    async fn open_positions() -> Vec<PositionSample> {
+        crate::utils::positions::positions_where(&crate::statics::REAL_POOL_INFO, |_| true)
+            .into_iter()
+            .map(|info| PositionSample {
+                user_id: info.user_bot_data.user_id,
+                pool_id: info.user_bot_data.pool_id,
+                open: info.is_bought as u64,
+            })
+            .collect()
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Renders the metric families, folding label sets past `max_label_sets`
+/// into an `other` bucket so churny tenants can't blow up cardinality.
+pub fn render_metrics(
+    positions: &[PositionSample],
+    user_stats: &[(String, TradeStats)],
+    max_label_sets: usize,
+) -> String {
+    let mut out = String::new();
+
+    // Largest positions keep their own labels, the long tail is aggregated
+    let mut positions = positions.to_vec();
+    positions.sort_by(|a, b| b.open.cmp(&a.open).then_with(|| a.user_id.cmp(&b.user_id)));
+    let mut position_series: BTreeMap<(String, String), u64> = BTreeMap::new();
+    for (i, sample) in positions.into_iter().enumerate() {
+        let key = if i < max_label_sets {
+            (sample.user_id, sample.pool_id)
+        } else {
+            (OVERFLOW_LABEL.to_string(), OVERFLOW_LABEL.to_string())
+        };
+        *position_series.entry(key).or_default() += sample.open;
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP bot_positions_open Open positions per user and pool"
+    );
+    let _ = writeln!(out, "# TYPE bot_positions_open gauge");
+    for ((user_id, pool_id), open) in &position_series {
+        let _ = writeln!(
+            out,
+            "bot_positions_open{{user_id=\"{}\",pool=\"{}\"}} {}",
+            escape_label(user_id),
+            escape_label(pool_id),
+            open
+        );
+    }
+
+    // Most active traders keep their own labels
+    let mut user_stats = user_stats.to_vec();
+    user_stats.sort_by(|a, b| {
+        b.1.total_trades
+            .cmp(&a.1.total_trades)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    let mut user_series: BTreeMap<String, TradeStats> = BTreeMap::new();
+    for (i, (user_id, stats)) in user_stats.into_iter().enumerate() {
+        let key = if i < max_label_sets {
+            user_id
+        } else {
+            OVERFLOW_LABEL.to_string()
+        };
+        let entry = user_series.entry(key).or_default();
+        entry.total_trades += stats.total_trades;
+        entry.total_profit += stats.total_profit;
+        entry.profitable_trades += stats.profitable_trades;
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP bot_realized_pnl_sol Realized profit in SOL per user"
+    );
+    let _ = writeln!(out, "# TYPE bot_realized_pnl_sol gauge");
+    for (user_id, stats) in &user_series {
+        let _ = writeln!(
+            out,
+            "bot_realized_pnl_sol{{user_id=\"{}\"}} {}",
+            escape_label(user_id),
+            stats.total_profit
+        );
+    }
+
+    let _ = writeln!(out, "# HELP bot_trades_total Completed trades per user");
+    let _ = writeln!(out, "# TYPE bot_trades_total counter");
+    for (user_id, stats) in &user_series {
+        let _ = writeln!(
+            out,
+            "bot_trades_total{{user_id=\"{}\"}} {}",
+            escape_label(user_id),
+            stats.total_trades
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP bot_win_rate_pct Share of profitable trades per user"
+    );
+    let _ = writeln!(out, "# TYPE bot_win_rate_pct gauge");
+    for (user_id, stats) in &user_series {
+        let win_rate = if stats.total_trades > 0 {
+            (stats.profitable_trades as f64 / stats.total_trades as f64) * 100.0
+        } else {
+            0.0
+        };
+        let _ = writeln!(
+            out,
+            "bot_win_rate_pct{{user_id=\"{}\"}} {}",
+            escape_label(user_id),
+            win_rate
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(total_trades: u32, total_profit: f64, profitable_trades: u32) -> TradeStats {
+        TradeStats {
+            total_trades,
+            total_profit,
+            profitable_trades,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn long_tail_is_folded_into_other() {
+        let positions = vec![
+            PositionSample {
+                user_id: "a".into(),
+                pool_id: "p1".into(),
+                open: 1,
+            },
+            PositionSample {
+                user_id: "b".into(),
+                pool_id: "p2".into(),
+                open: 1,
+            },
+            PositionSample {
+                user_id: "c".into(),
+                pool_id: "p3".into(),
+                open: 0,
+            },
+        ];
+        let user_stats = vec![
+            ("a".to_string(), stats(10, 1.0, 5)),
+            ("b".to_string(), stats(4, -0.5, 1)),
+            ("c".to_string(), stats(2, 0.25, 2)),
+        ];
+
+        let rendered = render_metrics(&positions, &user_stats, 1);
+
+        assert!(rendered.contains("bot_positions_open{user_id=\"a\",pool=\"p1\"} 1"));
+        assert!(rendered.contains("bot_positions_open{user_id=\"other\",pool=\"other\"} 1"));
+        assert!(rendered.contains("bot_realized_pnl_sol{user_id=\"a\"} 1"));
+        assert!(rendered.contains("bot_realized_pnl_sol{user_id=\"other\"} -0.25"));
+        assert!(rendered.contains("bot_trades_total{user_id=\"other\"} 6"));
+        assert!(rendered.contains("bot_win_rate_pct{user_id=\"other\"} 50"));
+        assert!(!rendered.contains("user_id=\"b\""));
+    }
+}
@@ -2,3 +2,9 @@ pub mod auth_service;
 pub mod user_service;
 pub mod bot_service;
 pub mod trade_service;
+pub mod metrics_service;
+pub mod scheduler_service;
+pub mod admin_service;
+pub mod approval_service;
+pub mod tuning_service;
+pub mod pool_service;
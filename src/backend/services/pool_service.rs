@@ -0,0 +1,173 @@
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use solana_sdk::pubkey::Pubkey;
+use std::time::{Duration, Instant};
+
+use crate::backend::{
+    error::{AppError, AppResult},
+    models::pool_price::PoolPriceResponse,
+};
+use crate::config::POOL_PRICE_QUERY_CACHE_TTL;
+use crate::utils::{
+    blockhash::{USD1, WSOL},
+    pool_reserves::{fetch_reserves_by_pool, PoolReserves, PoolVaults},
+    price::FixedPrice,
+    token_decimals::token_decimals,
+};
+
+const SOL_DECIMALS: u8 = 9;
+const USD1_DECIMALS: u8 = 6;
+
+static POOL_PRICE_QUOTES: Lazy<DashMap<Pubkey, (PoolPriceResponse, Instant)>> = Lazy::new(DashMap::new);
+
+/// The token mint of a SOL or USD1 pool, with its quote currency's mint,
+/// name and decimals
+fn quote_side(vaults: &PoolVaults) -> Option<(Pubkey, Pubkey, &'static str, u8)> {
+    [(WSOL, "SOL", SOL_DECIMALS), (USD1, "USD1", USD1_DECIMALS)]
+        .into_iter()
+        .find_map(|(mint, currency, decimals)| {
+            if vaults.quote_mint == mint {
+                Some((vaults.base_mint, mint, currency, decimals))
+            } else if vaults.base_mint == mint {
+                Some((vaults.quote_mint, mint, currency, decimals))
+            } else {
+                None
+            }
+        })
+}
+
+/// Prices a pool from its reserves, whichever side the quote currency is on
+pub fn price_from_reserves(
+    pool: &Pubkey,
+    vaults: &PoolVaults,
+    reserves: &PoolReserves,
+    token_decimals: u8,
+) -> AppResult<PoolPriceResponse> {
+    let (token_mint, quote_mint, quote_currency, quote_decimals) =
+        quote_side(vaults).ok_or_else(|| AppError::validation("Pool isn't paired with SOL or USD1"))?;
+    let (quote_reserve, token_reserve) = if vaults.quote_mint == quote_mint {
+        (reserves.quote, reserves.base)
+    } else {
+        (reserves.base, reserves.quote)
+    };
+
+    let price = FixedPrice::from_reserves(quote_reserve as u128, quote_decimals, token_reserve as u128, token_decimals)
+        .filter(|price| !price.is_zero())
+        .ok_or_else(|| AppError::not_found("Pool has no liquidity yet"))?
+        .to_f64();
+
+    Ok(PoolPriceResponse {
+        pool_address: pool.to_string(),
+        token_mint: token_mint.to_string(),
+        quote_mint: quote_mint.to_string(),
+        quote_currency: quote_currency.to_string(),
+        price,
+        price_sol: (quote_mint == WSOL).then_some(price),
+        token_decimals,
+        quote_decimals,
+        token_reserve,
+        quote_reserve,
+        liquidity: quote_reserve as f64 / 10f64.powi(quote_decimals as i32),
+    })
+}
+
+/// A cached answer for `pool` younger than `ttl`
+fn cached_quote(
+    cache: &DashMap<Pubkey, (PoolPriceResponse, Instant)>,
+    pool: &Pubkey,
+    ttl: Duration,
+) -> Option<PoolPriceResponse> {
+    let entry = cache.get(pool)?;
+    let (quote, fetched_at) = entry.value();
+    (fetched_at.elapsed() < ttl).then(|| quote.clone())
+}
+
+/// Current price of any pool the bot can trade, read from the chain without
+/// a running bot. Answers are cached for `POOL_PRICE_QUERY_CACHE_TTL`.
+pub async fn pool_price(pool_address: &str) -> AppResult<PoolPriceResponse> {
+    let pool: Pubkey = pool_address
+        .parse()
+        .map_err(|_| AppError::validation("Pool address is invalid"))?;
+    if let Some(quote) = cached_quote(&POOL_PRICE_QUOTES, &pool, *POOL_PRICE_QUERY_CACHE_TTL) {
+        return Ok(quote);
+    }
+
+    let (vaults, reserves) = fetch_reserves_by_pool(&pool)
+        .await
+        .ok_or_else(|| AppError::not_found("Pool not found or not initialized"))?;
+    let (token_mint, ..) =
+        quote_side(&vaults).ok_or_else(|| AppError::validation("Pool isn't paired with SOL or USD1"))?;
+    let decimals = token_decimals(std::iter::empty(), &token_mint)
+        .await
+        .ok_or_else(|| AppError::internal("Failed to read the token's decimals"))?;
+
+    let quote = price_from_reserves(&pool, &vaults, &reserves, decimals)?;
+    POOL_PRICE_QUOTES.insert(pool, (quote.clone(), Instant::now()));
+    Ok(quote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vaults(base_mint: Pubkey, quote_mint: Pubkey) -> PoolVaults {
+        PoolVaults { base_mint, quote_mint, base_vault: Pubkey::new_unique(), quote_vault: Pubkey::new_unique() }
+    }
+
+    #[test]
+    fn prices_come_from_the_reserves_on_either_side() {
+        let pool = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+
+        // 40 SOL against 1M tokens of a 6-decimal mint
+        let reserves = PoolReserves { base: 1_000_000_000_000, quote: 40_000_000_000 };
+        let quote = price_from_reserves(&pool, &vaults(token, WSOL), &reserves, 6).unwrap();
+        assert_eq!(quote.quote_currency, "SOL");
+        assert_eq!(quote.token_mint, token.to_string());
+        assert!((quote.price - 0.000_04).abs() < 1e-15);
+        assert_eq!(quote.price_sol, Some(quote.price));
+        assert_eq!((quote.token_reserve, quote.quote_reserve), (1_000_000_000_000, 40_000_000_000));
+        assert_eq!(quote.liquidity, 40.0);
+
+        // WSOL as the base, as some CPMM pools pair it
+        let flipped = PoolReserves { base: reserves.quote, quote: reserves.base };
+        let inverted = price_from_reserves(&pool, &vaults(WSOL, token), &flipped, 6).unwrap();
+        assert_eq!(inverted.price, quote.price);
+
+        let usd1 = PoolReserves { base: 1_000_000_000_000, quote: 5_000_000_000 };
+        let quote = price_from_reserves(&pool, &vaults(token, USD1), &usd1, 6).unwrap();
+        assert_eq!((quote.quote_currency.as_str(), quote.price_sol), ("USD1", None));
+        assert!((quote.price - 0.005).abs() < 1e-15);
+        assert_eq!(quote.liquidity, 5_000.0);
+    }
+
+    #[test]
+    fn empty_or_unpaired_pools_are_refused() {
+        let pool = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+
+        let empty = PoolReserves { base: 0, quote: 0 };
+        let err = price_from_reserves(&pool, &vaults(token, WSOL), &empty, 6).unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        let reserves = PoolReserves { base: 1_000, quote: 1_000 };
+        let err = price_from_reserves(&pool, &vaults(token, Pubkey::new_unique()), &reserves, 6).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+    }
+
+    #[test]
+    fn cached_quotes_expire() {
+        let cache = DashMap::new();
+        let pool = Pubkey::new_unique();
+        let reserves = PoolReserves { base: 1_000_000, quote: 2_000_000_000 };
+        let quote = price_from_reserves(&pool, &vaults(Pubkey::new_unique(), WSOL), &reserves, 6).unwrap();
+
+        assert_eq!(cached_quote(&cache, &pool, Duration::from_secs(2)), None);
+        cache.insert(pool, (quote.clone(), Instant::now()));
+        assert_eq!(cached_quote(&cache, &pool, Duration::from_secs(2)), Some(quote.clone()));
+        assert_eq!(cached_quote(&cache, &pool, Duration::ZERO), None);
+
+        cache.insert(pool, (quote, Instant::now() - Duration::from_secs(3)));
+        assert_eq!(cached_quote(&cache, &pool, Duration::from_secs(2)), None);
+    }
+}
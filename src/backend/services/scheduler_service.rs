@@ -0,0 +1,301 @@
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use std::{env, sync::Arc, time::Duration};
+
+use crate::backend::{
+    db::bot_repository::BotRepository,
+    db::connection::AppDatabase,
+    models::bot::BotSettings,
+    services::bot_service::BotService,
+};
+
+/// How often pending schedules are checked
+pub static SCHEDULER_INTERVAL: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(
+        env::var("SCHEDULER_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(10),
+    )
+});
+
+/// Source of the current time, injectable so schedules can be tested
+pub trait Clock: Send + Sync {
+    fn now_unix(&self) -> i64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_unix(&self) -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduledAction {
+    Start,
+    Stop,
+}
+
+/// Action due for a bot at `now`. A stop that is due wins over a start, so
+/// a bot whose whole window has already passed is never started.
+pub fn due_action(bot: &BotSettings, running: bool, now: i64) -> Option<ScheduledAction> {
+    let stop_due = bot.scheduled_stop.is_some_and(|stop| now >= stop);
+    let start_due = bot.scheduled_start.is_some_and(|start| now >= start);
+
+    if stop_due {
+        running.then_some(ScheduledAction::Stop)
+    } else if start_due && !running {
+        Some(ScheduledAction::Start)
+    } else {
+        None
+    }
+}
+
+/// Clears the schedule entries of `bot` that have fired or expired at `now`.
+/// The entry whose action `failed` is kept, so the next tick retries it.
+/// Returns whether anything was cleared.
+pub fn clear_fired(bot: &mut BotSettings, now: i64, failed: Option<ScheduledAction>) -> bool {
+    let mut changed = false;
+    if bot.scheduled_start.is_some_and(|start| now >= start) && failed != Some(ScheduledAction::Start) {
+        bot.scheduled_start = None;
+        changed = true;
+    }
+    if bot.scheduled_stop.is_some_and(|stop| now >= stop) && failed != Some(ScheduledAction::Stop) {
+        bot.scheduled_stop = None;
+        changed = true;
+    }
+    changed
+}
+
+/// The bots the scheduler acts on, injectable so a tick can be tested
+#[async_trait]
+pub trait ScheduledBots: Send + Sync {
+    /// Bots with a start or stop scheduled
+    async fn scheduled(&self) -> anyhow::Result<Vec<BotSettings>>;
+    async fn is_running(&self, user_id: &str) -> bool;
+    async fn start(&self, user_id: &str) -> anyhow::Result<()>;
+    async fn stop(&self, user_id: &str) -> anyhow::Result<()>;
+    async fn save_schedule(&self, bot: &BotSettings) -> anyhow::Result<()>;
+}
+
+/// The stored bots, started and stopped through `BotService`
+pub struct DatabaseBots {
+    database: AppDatabase,
+    bot_repo: BotRepository,
+}
+
+#[async_trait]
+impl ScheduledBots for DatabaseBots {
+    async fn scheduled(&self) -> anyhow::Result<Vec<BotSettings>> {
+        Ok(self.bot_repo.find_scheduled().await?)
+    }
+
+    async fn is_running(&self, user_id: &str) -> bool {
+        let user_list = crate::statics::USER_LIST.read().await;
+        user_list.iter().any(|user| user.user_id == user_id)
+    }
+
+    async fn start(&self, user_id: &str) -> anyhow::Result<()> {
+        BotService::new(self.database.clone()).start_bot(user_id).await?;
+        Ok(())
+    }
+
+    async fn stop(&self, user_id: &str) -> anyhow::Result<()> {
+        BotService::new(self.database.clone()).stop_bot(user_id).await?;
+        Ok(())
+    }
+
+    async fn save_schedule(&self, bot: &BotSettings) -> anyhow::Result<()> {
+        Ok(self.bot_repo.update_schedule(bot).await?)
+    }
+}
+
+/// Starts and stops bots at their scheduled times. Schedules are one-shot:
+/// each is cleared once it fires (or expires), so a manual stop isn't
+/// undone. A start or stop that fails keeps its schedule and is retried.
+pub struct SchedulerService {
+    bots: Arc<dyn ScheduledBots>,
+    clock: Arc<dyn Clock>,
+}
+
+impl SchedulerService {
+    pub fn new(database: AppDatabase, clock: Arc<dyn Clock>) -> Self {
+        let bots = DatabaseBots {
+            bot_repo: BotRepository::new(database.clone()),
+            database,
+        };
+        Self::with_bots(Arc::new(bots), clock)
+    }
+
+    pub fn with_bots(bots: Arc<dyn ScheduledBots>, clock: Arc<dyn Clock>) -> Self {
+        Self { bots, clock }
+    }
+
+    pub async fn run(self) {
+        loop {
+            if let Err(e) = self.tick().await {
+                eprintln!("Scheduler tick failed: {}", e);
+            }
+            tokio::time::sleep(*SCHEDULER_INTERVAL).await;
+        }
+    }
+
+    async fn tick(&self) -> anyhow::Result<()> {
+        let now = self.clock.now_unix();
+
+        for mut bot in self.bots.scheduled().await? {
+            let running = self.bots.is_running(&bot.user_id).await;
+
+            let failed = match due_action(&bot, running, now) {
+                Some(ScheduledAction::Start) => {
+                    println!("⏰ Scheduled start for user {}", bot.user_id);
+                    self.bots.start(&bot.user_id).await.err().map(|e| {
+                        eprintln!("Scheduled start failed for user {}, retrying next tick: {}", bot.user_id, e);
+                        ScheduledAction::Start
+                    })
+                }
+                Some(ScheduledAction::Stop) => {
+                    println!("⏰ Scheduled stop for user {}", bot.user_id);
+                    self.bots.stop(&bot.user_id).await.err().map(|e| {
+                        eprintln!("Scheduled stop failed for user {}, retrying next tick: {}", bot.user_id, e);
+                        ScheduledAction::Stop
+                    })
+                }
+                None => None,
+            };
+
+            if clear_fired(&mut bot, now, failed) {
+                self.bots.save_schedule(&bot).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex,
+    };
+
+    struct FakeClock(AtomicI64);
+
+    impl Clock for FakeClock {
+        fn now_unix(&self) -> i64 {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn start_and_stop_fire_at_configured_times() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        bot.scheduled_start = Some(1_000);
+        bot.scheduled_stop = Some(2_000);
+
+        let clock = FakeClock(AtomicI64::new(999));
+        assert_eq!(due_action(&bot, false, clock.now_unix()), None);
+
+        clock.0.store(1_000, Ordering::Relaxed);
+        assert_eq!(due_action(&bot, false, clock.now_unix()), Some(ScheduledAction::Start));
+        assert_eq!(due_action(&bot, true, clock.now_unix()), None);
+
+        clock.0.store(2_000, Ordering::Relaxed);
+        assert_eq!(due_action(&bot, true, clock.now_unix()), Some(ScheduledAction::Stop));
+        // Window already over: never started
+        assert_eq!(due_action(&bot, false, clock.now_unix()), None);
+    }
+
+    /// One bot whose starts fail while `start_fails` is set
+    struct FakeBots {
+        bot: Mutex<BotSettings>,
+        running: Mutex<bool>,
+        start_fails: Mutex<bool>,
+        starts: AtomicI64,
+    }
+
+    #[async_trait]
+    impl ScheduledBots for FakeBots {
+        async fn scheduled(&self) -> anyhow::Result<Vec<BotSettings>> {
+            let bot = self.bot.lock().unwrap().clone();
+            let scheduled = bot.scheduled_start.is_some() || bot.scheduled_stop.is_some();
+            Ok(if scheduled { vec![bot] } else { Vec::new() })
+        }
+
+        async fn is_running(&self, _user_id: &str) -> bool {
+            *self.running.lock().unwrap()
+        }
+
+        async fn start(&self, _user_id: &str) -> anyhow::Result<()> {
+            self.starts.fetch_add(1, Ordering::Relaxed);
+            if *self.start_fails.lock().unwrap() {
+                anyhow::bail!("wallet can't pay for a buy");
+            }
+            *self.running.lock().unwrap() = true;
+            Ok(())
+        }
+
+        async fn stop(&self, _user_id: &str) -> anyhow::Result<()> {
+            *self.running.lock().unwrap() = false;
+            Ok(())
+        }
+
+        async fn save_schedule(&self, bot: &BotSettings) -> anyhow::Result<()> {
+            *self.bot.lock().unwrap() = bot.clone();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn a_failed_start_keeps_its_schedule_until_it_succeeds() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        bot.scheduled_start = Some(1_000);
+        bot.scheduled_stop = Some(2_000);
+        let bots = Arc::new(FakeBots {
+            bot: Mutex::new(bot),
+            running: Mutex::new(false),
+            start_fails: Mutex::new(true),
+            starts: AtomicI64::new(0),
+        });
+        let clock = Arc::new(FakeClock(AtomicI64::new(1_000)));
+        let scheduler = SchedulerService::with_bots(bots.clone(), clock.clone());
+
+        // The start fails: it stays scheduled and the next tick retries it
+        scheduler.tick().await.unwrap();
+        scheduler.tick().await.unwrap();
+        assert_eq!(bots.starts.load(Ordering::Relaxed), 2);
+        assert_eq!(bots.bot.lock().unwrap().scheduled_start, Some(1_000));
+        assert!(!*bots.running.lock().unwrap());
+
+        // Once it succeeds the start is cleared, the stop still pending
+        *bots.start_fails.lock().unwrap() = false;
+        scheduler.tick().await.unwrap();
+        assert!(*bots.running.lock().unwrap());
+        assert_eq!(bots.bot.lock().unwrap().scheduled_start, None);
+        assert_eq!(bots.bot.lock().unwrap().scheduled_stop, Some(2_000));
+
+        clock.0.store(2_000, Ordering::Relaxed);
+        scheduler.tick().await.unwrap();
+        assert!(!*bots.running.lock().unwrap());
+        assert_eq!(bots.bot.lock().unwrap().scheduled_stop, None);
+        assert_eq!(bots.starts.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn only_the_failed_action_stays_scheduled() {
+        let mut bot = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        bot.scheduled_start = Some(1_000);
+        bot.scheduled_stop = Some(2_000);
+
+        assert!(!clear_fired(&mut bot, 999, None));
+        // The stop failed: the passed start goes, the stop stays
+        assert!(clear_fired(&mut bot, 2_000, Some(ScheduledAction::Stop)));
+        assert_eq!((bot.scheduled_start, bot.scheduled_stop), (None, Some(2_000)));
+        assert!(clear_fired(&mut bot, 2_000, None));
+        assert_eq!(bot.scheduled_stop, None);
+    }
+}
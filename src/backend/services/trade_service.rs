@@ -1,21 +1,48 @@
-use anyhow::Result;
+use serde::Serialize;
+use std::hash::{Hash, Hasher};
 use crate::backend::{
     db::connection::AppDatabase,
-    db::trade_repository::{TradeRepository},
-    models::trade::{TradeData, TradeDataResponse},
+    db::trade_repository::{
+        LeaderboardSort, PoolStats, TagStats, TradeReport, TradeRepository, TradeStats,
+    },
+    db::user_repository::UserRepository,
+    error::AppResult,
+    models::trade::{EntryLatency, FeeBreakdown, FundRoute, RealizedSlippage, TradeData, TradeDataResponse},
 };
 
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderboardEntry {
+    pub rank: u64,
+    pub trader: String,
+    #[serde(flatten)]
+    pub stats: TradeStats,
+}
+
+/// Public name for a user on the leaderboard. Keyed with the JWT secret so
+/// it is stable within a deployment but can't be mapped back to a user id.
+pub fn anonymized_trader_id(user_id: &str) -> String {
+    let secret = std::env::var("JWT_SECRET")
+        .unwrap_or_else(|_| "your-secret-key-change-in-production".to_string());
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    secret.hash(&mut hasher);
+    user_id.hash(&mut hasher);
+    format!("trader-{:016x}", hasher.finish())
+}
+
 pub struct TradeService {
     trade_repo: TradeRepository,
+    user_repo: UserRepository,
 }
 
 impl TradeService {
     pub fn new(database: AppDatabase) -> Self {
         Self {
-            trade_repo: TradeRepository::new(database),
+            trade_repo: TradeRepository::new(database.clone()),
+            user_repo: UserRepository::new(database),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_trade_data(
         &self,
         user_id: String,
@@ -24,7 +51,13 @@ impl TradeService {
         fees_sol: f64,
         roi_pct: f64,
         program_runtime_ms: i64,
-    ) -> Result<TradeDataResponse> {
+        route: Option<FundRoute>,
+        tag: Option<String>,
+        fee_breakdown: Option<FeeBreakdown>,
+        pool_id: Option<String>,
+        slippage: Option<RealizedSlippage>,
+        entry_latency: Option<EntryLatency>,
+    ) -> AppResult<TradeDataResponse> {
         println!("💾 Saving trade data for user: {}", user_id);
 
         // Create trade data
@@ -35,9 +68,20 @@ impl TradeService {
             fees_sol,
             roi_pct,
             program_runtime_ms,
+            route,
+            tag,
+            fee_breakdown,
+            pool_id,
+            slippage,
+            entry_latency,
         );
 
-        // Save to database
+        self.record_trade(trade_data).await
+    }
+
+    /// Saves a round trip's trade record as the bot built it
+    pub async fn record_trade(&self, trade_data: TradeData) -> AppResult<TradeDataResponse> {
+        let user_id = trade_data.user_id.clone();
         let saved_trade = self.trade_repo.create(trade_data).await?;
         let trade_response = TradeDataResponse::from(saved_trade);
 
@@ -45,15 +89,93 @@ impl TradeService {
         Ok(trade_response)
     }
 
-    pub async fn get_user_trades(&self, user_id: &str) -> Result<Vec<TradeDataResponse>> {
-        let trades = self.trade_repo.find_by_user_id(user_id).await?;
+    pub async fn get_user_trades(
+        &self,
+        user_id: &str,
+        tag: Option<&str>,
+    ) -> AppResult<Vec<TradeDataResponse>> {
+        let trades = match tag {
+            Some(tag) => self.trade_repo.find_by_user_id_and_tag(user_id, tag).await?,
+            None => self.trade_repo.find_by_user_id(user_id).await?,
+        };
         let responses: Vec<TradeDataResponse> = trades.into_iter().map(TradeDataResponse::from).collect();
         Ok(responses)
     }
 
-    pub async fn get_recent_trades(&self, limit: i64) -> Result<Vec<TradeDataResponse>> {
+    pub async fn get_recent_trades(&self, limit: i64) -> AppResult<Vec<TradeDataResponse>> {
         let trades = self.trade_repo.find_recent(limit).await?;
         let responses: Vec<TradeDataResponse> = trades.into_iter().map(TradeDataResponse::from).collect();
         Ok(responses)
     }
+
+    /// Totals and win rate of the user's trades, or of every trade for `None`
+    pub async fn get_stats(&self, user_id: Option<&str>) -> AppResult<TradeStats> {
+        Ok(self.trade_repo.get_stats(user_id, None, None).await?)
+    }
+
+    /// Performance of the user's trades broken down by strategy tag
+    pub async fn get_tag_stats(&self, user_id: &str) -> AppResult<Vec<TagStats>> {
+        Ok(self.trade_repo.get_stats_by_tag(Some(user_id)).await?)
+    }
+
+    /// Performance, average realized slippage and entry latency of the user's
+    /// trades per pool
+    pub async fn get_pool_stats(&self, user_id: &str) -> AppResult<Vec<PoolStats>> {
+        Ok(self.trade_repo.get_stats_by_pool(Some(user_id)).await?)
+    }
+
+    /// Performance report of the user's trades, optionally limited to a period
+    pub async fn get_report(
+        &self,
+        user_id: &str,
+        from_ms: Option<i64>,
+        to_ms: Option<i64>,
+    ) -> AppResult<TradeReport> {
+        Ok(self.trade_repo.get_report(user_id, from_ms, to_ms).await?)
+    }
+
+    /// One page (1-based) of the leaderboard over the last `days`, zero for all time
+    pub async fn get_leaderboard(
+        &self,
+        sort: LeaderboardSort,
+        days: u64,
+        page: u64,
+        per_page: u64,
+    ) -> AppResult<Vec<LeaderboardEntry>> {
+        let since_ms = if days == 0 {
+            0
+        } else {
+            chrono::Utc::now().timestamp_millis() - (days * 24 * 60 * 60 * 1000) as i64
+        };
+        let skip = (page.max(1) - 1) * per_page;
+        let hidden = self.user_repo.find_hidden_from_leaderboard().await?;
+
+        let ranked = self
+            .trade_repo
+            .get_leaderboard(since_ms, &hidden, sort, skip, per_page as i64)
+            .await?;
+
+        Ok(ranked
+            .into_iter()
+            .enumerate()
+            .map(|(i, (user_id, stats))| LeaderboardEntry {
+                rank: skip + i as u64 + 1,
+                trader: anonymized_trader_id(&user_id),
+                stats,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trader_ids_are_stable_and_hide_the_user_id() {
+        let id = anonymized_trader_id("66f0c0ffee0123456789abcd");
+        assert_eq!(id, anonymized_trader_id("66f0c0ffee0123456789abcd"));
+        assert_ne!(id, anonymized_trader_id("66f0c0ffee0123456789abce"));
+        assert!(!id.contains("66f0c0ffee"));
+    }
 }
@@ -0,0 +1,78 @@
+use crate::backend::{
+    db::bot_repository::BotRepository,
+    db::connection::AppDatabase,
+    db::trade_repository::TradeRepository,
+    models::bot::AUTO_TUNE_MIN_TRADES,
+};
+use crate::config::AUTO_TUNE_INTERVAL;
+use crate::utils::positions::update_user_positions;
+
+/// Periodically re-tunes `entry_percent` for bots that opted in, from the
+/// trades they closed since the previous pass
+pub struct AutoTuneService {
+    bot_repo: BotRepository,
+    trade_repo: TradeRepository,
+}
+
+impl AutoTuneService {
+    pub fn new(database: AppDatabase) -> Self {
+        Self {
+            bot_repo: BotRepository::new(database.clone()),
+            trade_repo: TradeRepository::new(database),
+        }
+    }
+
+    pub async fn run(self) {
+        loop {
+            tokio::time::sleep(*AUTO_TUNE_INTERVAL).await;
+            if let Err(e) = self.tick().await {
+                eprintln!("Auto-tune tick failed: {}", e);
+            }
+        }
+    }
+
+    async fn tick(&self) -> anyhow::Result<()> {
+        for mut bot in self.bot_repo.find_auto_tuned().await? {
+            let trades = self
+                .trade_repo
+                .find_by_user_id_since(&bot.user_id, bot.auto_tuned_at)
+                .await?;
+            if trades.len() < AUTO_TUNE_MIN_TRADES {
+                continue;
+            }
+
+            let previous = bot.entry_percent;
+            let profits: Vec<f64> = trades.iter().map(|trade| trade.profit_sol).collect();
+            let tuned = bot.tune_entry_percent(&profits);
+            bot.auto_tuned_at = trades.last().map(|trade| trade.created_at.timestamp_millis());
+            self.bot_repo.update_entry_tuning(&bot).await?;
+
+            if let Some(entry_percent) = tuned {
+                println!(
+                    "🎛️ Auto-tuned entry_percent for user {} over {} trades: {} -> {}",
+                    bot.user_id,
+                    trades.len(),
+                    previous,
+                    entry_percent
+                );
+                apply_entry_percent(&bot.user_id, entry_percent).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Hand the tuned value to the running bot so the next entry uses it
+async fn apply_entry_percent(user_id: &str, entry_percent: f64) {
+    {
+        let mut user_list = crate::statics::USER_LIST.write().await;
+        for user in user_list.iter_mut().filter(|user| user.user_id == user_id) {
+            user.bot_setting.entry_percent = entry_percent;
+        }
+    }
+
+    update_user_positions(&crate::statics::REAL_POOL_INFO, user_id, |info| {
+        info.user_bot_data.bot_setting.entry_percent = entry_percent;
+    });
+}
@@ -2,7 +2,8 @@ use axum::http::HeaderMap;
 use crate::backend::{
     db::connection::AppDatabase,
     error::{AppError, AppResult},
-    models::user::UserResponse,
+    models::user::{User, UserResponse},
+    services::approval_service::{self, PendingApproval, SensitiveOperation},
     db::user_repository::UserRepository,
     auth::jwt_service::JwtService,
 };
@@ -13,14 +14,85 @@ pub struct UserService {
 }
 
 impl UserService {
-    pub fn new(database: AppDatabase) -> Self {
-        Self {
+    pub fn new(database: AppDatabase) -> AppResult<Self> {
+        Ok(Self {
             user_repo: UserRepository::new(database),
-            jwt_service: JwtService::new(),
-        }
+            jwt_service: JwtService::new()?,
+        })
     }
 
     pub async fn get_current_user(&self, headers: HeaderMap) -> AppResult<UserResponse> {
+        Ok(self.current_user(&headers).await?.into())
+    }
+
+    /// Show or hide the current user on the leaderboard
+    pub async fn update_privacy(
+        &self,
+        headers: HeaderMap,
+        hide_from_leaderboard: bool,
+    ) -> AppResult<UserResponse> {
+        let mut user = self.current_user(&headers).await?;
+        user.set_leaderboard_visibility(hide_from_leaderboard);
+        self.user_repo.update(&user).await?;
+
+        Ok(user.into())
+    }
+
+    /// Set or clear the user whose approval sensitive wallet operations need.
+    /// Replacing or removing a co-signer takes their `approval_id`.
+    pub async fn update_co_signer(
+        &self,
+        headers: HeaderMap,
+        co_signer_id: Option<String>,
+        approval_id: Option<String>,
+    ) -> AppResult<UserResponse> {
+        let mut user = self.current_user(&headers).await?;
+        authorize(&user, SensitiveOperation::ChangeCoSigner, approval_id.as_deref())?;
+        if let Some(co_signer_id) = &co_signer_id {
+            if user.id.map(|id| id.to_hex()).as_ref() == Some(co_signer_id) {
+                return Err(AppError::validation("Co-signer must be a different user"));
+            }
+            self.user_repo
+                .find_by_id(co_signer_id)
+                .await?
+                .ok_or_else(|| AppError::not_found("Co-signer not found"))?;
+        }
+        user.set_co_signer(co_signer_id);
+        self.user_repo.update(&user).await?;
+
+        Ok(user.into())
+    }
+
+    /// Open an approval request for a sensitive operation on the current user's wallet
+    pub async fn request_approval(
+        &self,
+        headers: HeaderMap,
+        operation: SensitiveOperation,
+    ) -> AppResult<PendingApproval> {
+        let user = self.current_user(&headers).await?;
+        let owner_id = user
+            .id
+            .map(|id| id.to_hex())
+            .ok_or_else(|| AppError::internal("User has no id"))?;
+        let co_signer_id = user
+            .co_signer_id
+            .ok_or_else(|| AppError::validation("No co-signer is set, the operation needs no approval"))?;
+
+        Ok(approval_service::open_approval(operation, &owner_id, co_signer_id))
+    }
+
+    /// Add the current user's approval as co-signer
+    pub async fn approve(&self, headers: HeaderMap, approval_id: &str) -> AppResult<PendingApproval> {
+        let user = self.current_user(&headers).await?;
+        let approver = user
+            .id
+            .map(|id| id.to_hex())
+            .ok_or_else(|| AppError::internal("User has no id"))?;
+
+        approval_service::approve(approval_id, &approver).map_err(AppError::validation)
+    }
+
+    async fn current_user(&self, headers: &HeaderMap) -> AppResult<User> {
         // Extract token from Authorization header
         let auth_header = headers
             .get("authorization")
@@ -39,6 +111,60 @@ impl UserService {
             .await?
             .ok_or_else(|| AppError::not_found("User associated with token not found"))?;
 
-        Ok(user.into())
+        Ok(user)
+    }
+}
+
+/// Lets `user` run a sensitive `operation`: right away without a co-signer,
+/// otherwise by consuming the co-signer's `approval_id` for it
+pub fn authorize(user: &User, operation: SensitiveOperation, approval_id: Option<&str>) -> AppResult<()> {
+    let Some(co_signer_id) = &user.co_signer_id else {
+        return Ok(());
+    };
+    let owner_id = user
+        .id
+        .map(|id| id.to_hex())
+        .ok_or_else(|| AppError::internal("User has no id"))?;
+    let approval_id =
+        approval_id.ok_or_else(|| AppError::auth("The co-signer's approval is required, pass its approval_id"))?;
+    approval_service::take_approved(approval_id, &owner_id, co_signer_id, operation).map_err(AppError::auth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_co_signed_user_needs_an_approval_per_operation() {
+        let mut user = User::new(
+            "owner@example.com".to_string(),
+            "owner".to_string(),
+            "password".to_string(),
+            "private-key".to_string(),
+            "public-key".to_string(),
+        )
+        .unwrap();
+        user.id = Some(bson::oid::ObjectId::new());
+        let owner_id = user.id.unwrap().to_hex();
+
+        // Nobody to ask yet
+        assert!(authorize(&user, SensitiveOperation::ChangeCoSigner, None).is_ok());
+
+        user.set_co_signer(Some("co-signer".to_string()));
+        assert!(matches!(
+            authorize(&user, SensitiveOperation::ChangeCoSigner, None),
+            Err(AppError::Auth(_))
+        ));
+
+        let pending = approval_service::open_approval(
+            SensitiveOperation::ChangeCoSigner,
+            &owner_id,
+            "co-signer".to_string(),
+        );
+        assert!(authorize(&user, SensitiveOperation::ChangeCoSigner, Some(&pending.id)).is_err());
+        approval_service::approve(&pending.id, "co-signer").unwrap();
+        assert!(authorize(&user, SensitiveOperation::ChangeCoSigner, Some(&pending.id)).is_ok());
+        // Spent
+        assert!(authorize(&user, SensitiveOperation::ChangeCoSigner, Some(&pending.id)).is_err());
     }
 }
@@ -0,0 +1,152 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::backend::auth::jwt_service::{extract_token_from_header, JwtService};
+use crate::config::{API_THROTTLE_BURST, API_THROTTLE_PER_SEC};
+
+static USER_BUCKETS: Lazy<Mutex<HashMap<String, TokenBucket>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn full(capacity: u64, now: Instant) -> Self {
+        Self {
+            tokens: capacity as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Takes one token, or returns how long until one is available
+    pub fn try_take(&mut self, now: Instant, capacity: u64, per_sec: u64) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec as f64).min(capacity as f64);
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if per_sec == 0 {
+            Err(Duration::from_secs(1))
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_sec as f64))
+        }
+    }
+}
+
+fn jwt_subject(headers: &HeaderMap) -> Option<String> {
+    let auth_header = headers.get("authorization")?.to_str().ok()?;
+    let token = extract_token_from_header(auth_header)?;
+    JwtService::new().ok()?.verify_token(token).ok().map(|claims| claims.sub)
+}
+
+/// Per-user token bucket keyed by the JWT subject, `API_THROTTLE_BURST=0`
+/// turns it off. Requests without a valid token pass through so the handler
+/// can reject them as usual.
+pub async fn throttle_user(request: Request, next: Next) -> Response {
+    let capacity = *API_THROTTLE_BURST;
+    if capacity == 0 {
+        return next.run(request).await;
+    }
+    let Some(user_id) = jwt_subject(request.headers()) else {
+        return next.run(request).await;
+    };
+
+    let now = Instant::now();
+    let verdict = {
+        let mut buckets = USER_BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+        buckets
+            .entry(user_id)
+            .or_insert_with(|| TokenBucket::full(capacity, now))
+            .try_take(now, capacity, *API_THROTTLE_PER_SEC)
+    };
+
+    match verdict {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let retry_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            let body = Json(json!({
+                "error": StatusCode::TOO_MANY_REQUESTS.as_str(),
+                "message": "Too many requests, slow down"
+            }));
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_secs.to_string())],
+                body,
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(3, start);
+
+        for _ in 0..3 {
+            assert!(bucket.try_take(start, 3, 2).is_ok());
+        }
+        let wait = bucket.try_take(start, 3, 2).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+
+        // Half a second at 2/s earns one token back, never more than the burst
+        assert!(bucket.try_take(start + Duration::from_millis(500), 3, 2).is_ok());
+        assert!(bucket.try_take(start + Duration::from_millis(500), 3, 2).is_err());
+
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(bucket.try_take(later, 3, 2).is_ok());
+        }
+        assert!(bucket.try_take(later, 3, 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn a_user_past_the_burst_gets_429_with_retry_after() {
+        let app = Router::new()
+            .route("/bots", get(|| async { "ok" }))
+            .route_layer(middleware::from_fn(throttle_user));
+        let token = JwtService::new().unwrap().create_token("throttled-user").unwrap();
+        let request = || {
+            Request::builder()
+                .uri("/bots")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for _ in 0..*API_THROTTLE_BURST {
+            let response = app.clone().oneshot(request()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        // Unauthenticated requests are left for the handler to reject
+        let anonymous = Request::builder().uri("/bots").body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(anonymous).await.unwrap().status(), StatusCode::OK);
+    }
+}
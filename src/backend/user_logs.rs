@@ -0,0 +1,155 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    fmt::{self, Write},
+    sync::Mutex,
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// Recent log lines kept per user
+pub static USER_LOG_BUFFER_SIZE: Lazy<usize> = Lazy::new(|| {
+    env::var("USER_LOG_BUFFER_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(200)
+});
+
+static USER_LOGS: Lazy<Mutex<HashMap<String, VecDeque<UserLogLine>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UserLogLine {
+    pub ts: String,
+    pub level: String,
+    pub message: String,
+}
+
+/// Most recent `limit` log lines for the user, oldest first
+pub fn recent_user_logs(user_id: &str, limit: usize) -> Vec<UserLogLine> {
+    let logs = USER_LOGS.lock().unwrap_or_else(|e| e.into_inner());
+    logs.get(user_id)
+        .map(|lines| {
+            let skip = lines.len().saturating_sub(limit);
+            lines.iter().skip(skip).cloned().collect()
+        })
+        .unwrap_or_default()
+}
+
+fn push_user_log(user_id: &str, line: UserLogLine, capacity: usize) {
+    let mut logs = USER_LOGS.lock().unwrap_or_else(|e| e.into_inner());
+    let lines = logs.entry(user_id.to_string()).or_default();
+    lines.push_back(line);
+    while lines.len() > capacity {
+        lines.pop_front();
+    }
+}
+
+/// Collects the `user_id` field and renders the rest of an event
+#[derive(Default)]
+struct LogVisitor {
+    user_id: Option<String>,
+    message: String,
+    fields: String,
+}
+
+impl Visit for LogVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "user_id" {
+            self.user_id = Some(value.to_string());
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "user_id" => self.user_id = Some(format!("{:?}", value).trim_matches('"').to_string()),
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+/// User id recorded on a span, stored in its extensions
+struct SpanUserId(String);
+
+/// Tracing layer that copies events tagged with a `user_id` (on the event
+/// itself or an enclosing span) into that user's bounded log buffer.
+pub struct UserLogLayer;
+
+impl<S> Layer<S> for UserLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut visitor = LogVisitor::default();
+        attrs.record(&mut visitor);
+        if let (Some(user_id), Some(span)) = (visitor.user_id, ctx.span(id)) {
+            span.extensions_mut().insert(SpanUserId(user_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = LogVisitor::default();
+        event.record(&mut visitor);
+
+        let user_id = visitor.user_id.or_else(|| {
+            ctx.event_scope(event)?.find_map(|span| {
+                span.extensions()
+                    .get::<SpanUserId>()
+                    .map(|user| user.0.clone())
+            })
+        });
+        let Some(user_id) = user_id else {
+            return;
+        };
+
+        push_user_log(
+            &user_id,
+            UserLogLine {
+                ts: chrono::Utc::now().to_rfc3339(),
+                level: event.metadata().level().to_string(),
+                message: format!("{}{}", visitor.message, visitor.fields),
+            },
+            *USER_LOG_BUFFER_SIZE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn user_events_are_routed_and_bounded() {
+        let subscriber = tracing_subscriber::registry().with(UserLogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("bot", user_id = %"log-user-a");
+            let _guard = span.enter();
+            for i in 0..(*USER_LOG_BUFFER_SIZE + 5) {
+                tracing::info!(i, "price update");
+            }
+            tracing::warn!(user_id = %"log-user-b", "entry paused");
+        });
+
+        let a = recent_user_logs("log-user-a", usize::MAX);
+        assert_eq!(a.len(), *USER_LOG_BUFFER_SIZE);
+        assert_eq!(a.last().unwrap().message, format!("price update i={}", *USER_LOG_BUFFER_SIZE + 4));
+
+        let b = recent_user_logs("log-user-b", 10);
+        assert_eq!(b.len(), 1);
+        assert_eq!(b[0].level, "WARN");
+        assert_eq!(b[0].message, "entry paused");
+    }
+}
@@ -1,16 +1,22 @@
 use tokio::sync::OnceCell;
 use std::env;
 
-use crate::service::{Jito, Nozomi, ZeroSlot};
+use crate::service::{Jito, Nozomi, SubmissionService, ZSlotRegionsType, ZeroSlot, ZSLOT_REGIONS};
 
 pub static NOZOMI_CLIENT: OnceCell<Nozomi> = OnceCell::const_new();
 pub static ZSLOT_CLIENT: OnceCell<ZeroSlot> = OnceCell::const_new();
+/// One ZERO_SLOT client per region, for bots that pin `zslot_region`
+pub static ZSLOT_REGION_CLIENTS: OnceCell<Vec<ZeroSlot>> = OnceCell::const_new();
 pub static JITO_CLIENT: OnceCell<Jito> = OnceCell::const_new();
 
 pub async fn init_nozomi() {
     let _ = dotenv::dotenv().ok();
 
-    let nozomi_api_key = env::var("NOZOMI_API_KEY").unwrap();
+    // Nozomi is optional, bots set to it report the client as not initialized
+    let Ok(nozomi_api_key) = env::var("NOZOMI_API_KEY") else {
+        println!("NOZOMI_API_KEY not set, NOZOMI confirm service disabled");
+        return;
+    };
 
     let nozomi = Nozomi::new_auto(nozomi_api_key).await;
     nozomi.health_check(50);
@@ -20,12 +26,43 @@ pub async fn init_nozomi() {
 pub async fn init_zslot() {
     let _ = dotenv::dotenv().ok();
 
-    let zslot_api_key = env::var("ZSLOT_API_KEY").unwrap();
+    // ZERO_SLOT is optional, bots set to it report the client as not initialized
+    let Ok(zslot_api_key) = env::var("ZSLOT_API_KEY") else {
+        println!("ZSLOT_API_KEY not set, ZERO_SLOT confirm service disabled");
+        return;
+    };
+
+    let region_clients = ZSLOT_REGIONS
+        .iter()
+        .map(|endpoint| ZeroSlot::with_endpoint(endpoint.clone(), zslot_api_key.clone()))
+        .collect();
+    ZSLOT_REGION_CLIENTS.set(region_clients).unwrap();
 
     let zslot = ZeroSlot::new_auto(zslot_api_key).await;
     ZSLOT_CLIENT.set(zslot).unwrap();
 }
 
+/// Client for the bot's `zslot_region`, the fastest region when it is empty
+pub fn zslot_client_for(region: &str) -> Option<&'static ZeroSlot> {
+    let Some(region) = ZSlotRegionsType::from_name(region) else {
+        return ZSLOT_CLIENT.get();
+    };
+    ZSLOT_REGION_CLIENTS
+        .get()?
+        .iter()
+        .find(|client| client.endpoint.relayer == region)
+}
+
+/// Initialized client for a `confirm_service` name
+pub fn submission_service(service: &str, zslot_region: &str) -> Option<&'static dyn SubmissionService> {
+    match service {
+        "JITO" => JITO_CLIENT.get().map(|client| client as &'static dyn SubmissionService),
+        "NOZOMI" => NOZOMI_CLIENT.get().map(|client| client as &'static dyn SubmissionService),
+        "ZERO_SLOT" => zslot_client_for(zslot_region).map(|client| client as &'static dyn SubmissionService),
+        _ => None,
+    }
+}
+
 pub async fn init_jito() {
     let _ = dotenv::dotenv().ok();
     
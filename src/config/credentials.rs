@@ -1,6 +1,6 @@
 use once_cell::sync::Lazy;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair};
 use std::{env, sync::Arc};
 
 pub static RPC_ENDPOINT: Lazy<String> = Lazy::new(|| {
@@ -21,3 +21,30 @@ pub static RPC_CLIENT: Lazy<Arc<RpcClient>> = Lazy::new(|| {
         CommitmentConfig::processed(),
     ))
 });
+
+/// RPC clients polled for blockhashes: `RPC_ENDPOINT` plus any in the comma
+/// separated `BLOCKHASH_RPC_ENDPOINTS`, signing takes the freshest
+pub static BLOCKHASH_RPC_CLIENTS: Lazy<Vec<Arc<RpcClient>>> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    let mut clients = vec![RPC_CLIENT.clone()];
+    let extra = env::var("BLOCKHASH_RPC_ENDPOINTS").unwrap_or_default();
+    for endpoint in extra.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        clients.push(Arc::new(RpcClient::new_with_commitment(
+            endpoint.to_string(),
+            CommitmentConfig::processed(),
+        )));
+    }
+    clients
+});
+
+/// Wallet that pays for and owns the pools' lookup tables, base58 in
+/// `POOL_ALT_PAYER_KEY`. Users' trading wallets never pay a table's rent, so
+/// without it no table is created.
+pub static POOL_ALT_PAYER: Lazy<Option<Keypair>> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    let key = env::var("POOL_ALT_PAYER_KEY").ok()?;
+    let bytes = bs58::decode(key.trim()).into_vec().ok()?;
+    Keypair::from_bytes(&bytes).ok()
+});
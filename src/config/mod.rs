@@ -1,5 +1,7 @@
 pub mod clients;
 pub mod credentials;
+pub mod trading;
 
 pub use clients::*;
 pub use credentials::*;
+pub use trading::*;
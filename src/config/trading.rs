@@ -0,0 +1,227 @@
+use once_cell::sync::Lazy;
+use std::{env, time::Duration};
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    let _ = dotenv::dotenv().ok();
+
+    env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+pub static POOL_STATUS_CHECK_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("POOL_STATUS_CHECK")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+/// Token-2022 pools are refused unless explicitly allowed
+pub static ALLOW_TOKEN_2022: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("ALLOW_TOKEN_2022")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+/// Create an address lookup table of each pool's static swap accounts and
+/// sign swaps against it. `POOL_ALT_PAYER_KEY` pays the tables' rent.
+pub static POOL_ALT_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("POOL_ALT_ENABLED")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+/// Check the wallet covers the buy, token account rent, fees and tip before submitting
+pub static ATA_RENT_PREFLIGHT_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("ATA_RENT_PREFLIGHT")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+pub static POOL_STATUS_CACHE_TTL: Lazy<Duration> =
+    Lazy::new(|| Duration::from_millis(env_u64("POOL_STATUS_CACHE_MS", 5_000)));
+
+/// How long a `GET /pools/{pool}/price` answer is served from cache, zero
+/// reads the reserves on every request
+pub static POOL_PRICE_QUERY_CACHE_TTL: Lazy<Duration> =
+    Lazy::new(|| Duration::from_millis(env_u64("POOL_PRICE_QUERY_CACHE_MS", 2_000)));
+
+/// How often last-known pool prices are persisted, zero disables it
+pub static POOL_PRICE_PERSIST_INTERVAL: Lazy<Duration> =
+    Lazy::new(|| Duration::from_secs(env_u64("POOL_PRICE_PERSIST_SECS", 30)));
+
+/// Persisted prices older than this are ignored on warm start
+pub static POOL_PRICE_MAX_AGE: Lazy<Duration> =
+    Lazy::new(|| Duration::from_secs(env_u64("POOL_PRICE_MAX_AGE_SECS", 3_600)));
+
+/// Interval of the sweep that fires auto-exits on pools that stopped trading,
+/// price changes themselves are evaluated as they arrive
+pub static AUTO_EXIT_SWEEP_INTERVAL: Lazy<Duration> =
+    Lazy::new(|| Duration::from_millis(env_u64("AUTO_EXIT_SWEEP_MS", 5_000)));
+
+/// Most tasks one PumpSwap instruction spawns, its users are batched into
+/// them. Zero gives each user its own task.
+pub static MAX_TASKS_PER_TRANSACTION: Lazy<usize> =
+    Lazy::new(|| env_u64("MAX_TASKS_PER_TRANSACTION", 0) as usize);
+
+/// Consecutive processor errors before a bot's entries are paused, zero disables it
+pub static PROCESSOR_ERROR_PAUSE_THRESHOLD: Lazy<u32> =
+    Lazy::new(|| env_u64("PROCESSOR_ERROR_PAUSE_THRESHOLD", 0) as u32);
+
+/// Fetch the error and logs of submitted transactions that fail on-chain
+pub static FAILED_TX_DIAGNOSTICS_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("FAILED_TX_DIAGNOSTICS")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+/// Price PumpSwap pools from the reserves their swap events report, rather
+/// than from the vault balances
+pub static PUMPSWAP_EVENT_RESERVES_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("PUMPSWAP_EVENT_RESERVES")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+/// Failed transactions kept per user for `/bots/failures`
+pub static FAILED_TX_BUFFER_SIZE: Lazy<usize> =
+    Lazy::new(|| env_u64("FAILED_TX_BUFFER_SIZE", 20) as usize);
+
+/// Serve the public per-user leaderboard on `/trades/leaderboard`
+pub static LEADERBOARD_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("LEADERBOARD_ENABLED")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+/// Process a transaction once per watched pool, however many swap legs it has there
+pub static DEDUPE_SWAP_LEGS: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("DEDUPE_SWAP_LEGS")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+/// Probe the relays, RPC node and Geyser endpoint once on startup
+pub static STARTUP_SELF_TEST_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("STARTUP_SELF_TEST")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+/// How long a co-signer has to approve a sensitive wallet operation
+pub static MULTISIG_APPROVAL_WINDOW: Lazy<Duration> =
+    Lazy::new(|| Duration::from_secs(env_u64("MULTISIG_APPROVAL_WINDOW_SECS", 300)));
+
+/// Highest share of a buy, in percent, its round-trip fees, tips and rent may
+/// cost before the size is refused as unable to profit. Zero disables the check.
+pub static MAX_ROUND_TRIP_COST_PCT: Lazy<u64> = Lazy::new(|| env_u64("MAX_ROUND_TRIP_COST_PCT", 10));
+
+/// Requests a user can burst to `/bots` and `/trades`, zero disables the throttle
+pub static API_THROTTLE_BURST: Lazy<u64> = Lazy::new(|| env_u64("API_THROTTLE_BURST", 30));
+
+/// Requests per second each user's bucket refills at
+pub static API_THROTTLE_PER_SEC: Lazy<u64> = Lazy::new(|| env_u64("API_THROTTLE_PER_SEC", 5));
+
+/// How often bots with `auto_tune_entry` have their entry_percent re-tuned
+pub static AUTO_TUNE_INTERVAL: Lazy<Duration> =
+    Lazy::new(|| Duration::from_secs(env_u64("AUTO_TUNE_INTERVAL_SECS", 300)));
+
+/// Check Geyser accepts `X_TOKEN` before the pipeline subscribes
+pub static GEYSER_AUTH_CHECK_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("GEYSER_AUTH_CHECK")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+/// Connection attempts before an unreachable Geyser endpoint stops startup,
+/// a refused token stops it on the first
+pub static GEYSER_CONNECT_ATTEMPTS: Lazy<u32> =
+    Lazy::new(|| env_u64("GEYSER_CONNECT_ATTEMPTS", 5).max(1) as u32);
+
+/// Price samples kept across every pool's history, each buffer's share
+/// shrinks as more pools are watched
+pub static PRICE_HISTORY_MAX_SAMPLES: Lazy<usize> =
+    Lazy::new(|| env_u64("PRICE_HISTORY_MAX_SAMPLES", 2_000_000) as usize);
+
+/// Pools with no price observed for this long have their history dropped
+pub static PRICE_HISTORY_IDLE: Lazy<Duration> =
+    Lazy::new(|| Duration::from_secs(env_u64("PRICE_HISTORY_IDLE_SECS", 600)));
+
+/// Nothing is submitted unless this is explicitly true, bots paper trade
+/// instead; each bot must also set `live_trading_acknowledged`
+pub static LIVE_TRADING_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("LIVE_TRADING_ENABLED")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+/// Read reserves from the decoded pool state and vault accounts in one
+/// request rather than a balance call per vault
+pub static POOL_ACCOUNT_RESERVES_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("POOL_ACCOUNT_RESERVES")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+});
+
+/// Failed exit attempts within `STUCK_EXIT_WINDOW` that flag a position as
+/// stuck, zero disables it
+pub static STUCK_EXIT_FAILURES: Lazy<u32> = Lazy::new(|| env_u64("STUCK_EXIT_FAILURES", 3) as u32);
+
+pub static STUCK_EXIT_WINDOW: Lazy<Duration> =
+    Lazy::new(|| Duration::from_secs(env_u64("STUCK_EXIT_WINDOW_SECS", 600)));
+
+/// Where stuck-position alerts are POSTed, unset only logs them
+pub static STUCK_ALERT_WEBHOOK_URL: Lazy<Option<String>> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("STUCK_ALERT_WEBHOOK_URL").ok().filter(|url| !url.is_empty())
+});
+
+/// Keep the WSOL account open across a session's trades instead of closing
+/// and re-creating it each round trip, it closes on a stopped bot's final exit
+pub static KEEP_WSOL_OPEN: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("KEEP_WSOL_OPEN")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+/// Follow each swap with a Lighthouse assertion that its output account grew
+/// by at least the swap's minimum, on top of the AMM's own min-out check
+pub static MIN_OUTPUT_ASSERTION_ENABLED: Lazy<bool> = Lazy::new(|| {
+    let _ = dotenv::dotenv().ok();
+
+    env::var("MIN_OUTPUT_ASSERTION")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+/// Attempts at reading a position's token balance before its sell is given up,
+/// a token account that doesn't exist isn't retried
+pub static TOKEN_BALANCE_RPC_ATTEMPTS: Lazy<u32> =
+    Lazy::new(|| env_u64("TOKEN_BALANCE_RPC_ATTEMPTS", 3).max(1) as u32);
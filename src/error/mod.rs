@@ -16,6 +16,7 @@
 //! - `InsufficientFunds`: Insufficient funds for a transaction.
 //! - `SimulationError`: Transaction simulation failed.
 //! - `RateLimitExceeded`: Rate limit exceeded.
+//! - `UnsupportedTokenProgram`: A mint is owned by a token program the builders can't handle.
 
 use serde_json::Error;
 use solana_client::{
@@ -92,6 +93,9 @@ pub enum ClientError {
     InvalidEventType,
 
     ChannelClosed,
+
+    /// Mint is owned by a token program the instruction builders don't support
+    UnsupportedTokenProgram(String),
 }
 
 impl std::fmt::Display for ClientError {
@@ -128,6 +132,7 @@ impl std::fmt::Display for ClientError {
             Self::Duplicate(msg) => write!(f, "Duplicate event: {}", msg),
             Self::InvalidEventType => write!(f, "Invalid event type"),
             Self::ChannelClosed => write!(f, "Channel closed"),
+            Self::UnsupportedTokenProgram(msg) => write!(f, "Unsupported token program: {}", msg),
         }
     }
 }
@@ -13,7 +13,7 @@ use solana_sdk::{
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::instruction::sync_native;
 
-use crate::utils::blockhash::WSOL;
+use crate::utils::{blockhash::WSOL, wsol::close_wsol_ix};
 
 pub trait BuyInstructionAccountsExt {
     fn get_buy_ix(&self, buy_params: Buy) -> Instruction;
@@ -70,18 +70,7 @@ impl BuyInstructionAccountsExt for BuyInstructionAccounts {
     }
 
     fn get_close_wsol(&self) -> Instruction {
-        let wsol_ata = get_associated_token_address(&self.user, &WSOL);
-
-        let create_ata_ix = spl_token::instruction::close_account(
-            &spl_token::ID,
-            &wsol_ata,
-            &self.user,
-            &self.user,
-            &[],
-        )
-        .unwrap();
-
-        create_ata_ix
+        close_wsol_ix(&self.user)
     }
 
     fn get_buy_ix(&self, buy_params: Buy) -> Instruction {
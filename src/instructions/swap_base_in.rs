@@ -10,7 +10,7 @@ use solana_sdk::{
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::instruction::sync_native;
 
-use crate::utils::blockhash::WSOL;
+use crate::utils::{blockhash::WSOL, wsol::close_wsol_ix};
 
 pub trait SwapBaseInInstructionAccountsExt {
     fn get_swap_base_in_ix(&self, buy_exact_in_param: SwapBaseIn) -> Instruction;
@@ -19,18 +19,18 @@ pub trait SwapBaseInInstructionAccountsExt {
         base_mint: Pubkey,
         quote_mint: Pubkey,
     ) -> Vec<Instruction>;
-    fn get_create_ata_ix(&self) -> Instruction;
+    fn get_create_ata_ix(&self, mint: Pubkey) -> Instruction;
     fn get_wrap_sol(&self, pubkey: Pubkey, buy_exact_in_param: SwapBaseIn) -> Vec<Instruction>;
     fn get_close_wsol(&self, pubkey: Pubkey) -> Instruction;
 }
 
 impl SwapBaseInInstructionAccountsExt for SwapBaseInInstructionAccounts {
-    fn get_create_ata_ix(&self) -> Instruction {
+    fn get_create_ata_ix(&self, mint: Pubkey) -> Instruction {
         let create_ata_ix =
             spl_associated_token_account::instruction::create_associated_token_account(
                 &self.user_source_owner,
                 &self.user_source_owner,
-                &self.user_source_owner,
+                &mint,
                 &self.token_program,
             );
 
@@ -71,13 +71,7 @@ impl SwapBaseInInstructionAccountsExt for SwapBaseInInstructionAccounts {
     }
 
     fn get_close_wsol(&self, pubkey: Pubkey) -> Instruction {
-        let wsol_ata = get_associated_token_address(&pubkey.clone(), &WSOL);
-
-        let create_ata_ix =
-            spl_token::instruction::close_account(&spl_token::ID, &wsol_ata, &pubkey.clone(), &pubkey.clone(), &[])
-                .unwrap();
-
-        create_ata_ix
+        close_wsol_ix(&pubkey)
     }
 
     fn get_swap_base_in_ix(&self, buy_exact_in_param: SwapBaseIn) -> Instruction {
@@ -138,3 +132,42 @@ impl SwapBaseInInstructionAccountsExt for SwapBaseInInstructionAccounts {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_ata_ix_is_for_the_given_mint() {
+        let accounts = SwapBaseInInstructionAccounts {
+            token_program: spl_token::ID,
+            amm: Pubkey::new_unique(),
+            amm_authority: Pubkey::new_unique(),
+            amm_open_orders: Pubkey::new_unique(),
+            amm_target_orders: None,
+            pool_coin_token_account: Pubkey::new_unique(),
+            pool_pc_token_account: Pubkey::new_unique(),
+            serum_program: Pubkey::new_unique(),
+            serum_market: Pubkey::new_unique(),
+            serum_bids: Pubkey::new_unique(),
+            serum_asks: Pubkey::new_unique(),
+            serum_event_queue: Pubkey::new_unique(),
+            serum_coin_vault_account: Pubkey::new_unique(),
+            serum_pc_vault_account: Pubkey::new_unique(),
+            serum_vault_signer: Pubkey::new_unique(),
+            user_source_token_account: Pubkey::new_unique(),
+            user_destination_token_account: Pubkey::new_unique(),
+            user_source_owner: Pubkey::new_unique(),
+        };
+        let mint = Pubkey::new_unique();
+
+        let ix = accounts.get_create_ata_ix(mint);
+
+        // payer, associated account, wallet, mint, system program, token program
+        let owner = accounts.user_source_owner;
+        assert_eq!(ix.accounts[1].pubkey, get_associated_token_address(&owner, &mint));
+        assert_eq!(ix.accounts[2].pubkey, owner);
+        assert_eq!(ix.accounts[3].pubkey, mint);
+        assert_eq!(ix.accounts[5].pubkey, spl_token::ID);
+    }
+}
@@ -1,14 +1,16 @@
 use carbon_raydium_amm_v4_decoder::{
     PROGRAM_ID as RAYDIUM_V4_PROGRAM_ID,
-    instructions::swap_base_out::{SwapBaseOut, SwapBaseOutInstructionAccounts},
+    instructions::{
+        swap_base_in::SwapBaseInInstructionAccounts,
+        swap_base_out::{SwapBaseOut, SwapBaseOutInstructionAccounts},
+    },
 };
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
 };
-use spl_associated_token_account::get_associated_token_address;
 
-use crate::utils::blockhash::WSOL;
+use crate::utils::wsol::close_wsol_ix;
 
 pub trait SwapBaseOutInstructionAccountsExt {
     fn get_swap_base_out_ix(&self, buy_exact_in_param: SwapBaseOut) -> Instruction;
@@ -17,17 +19,18 @@ pub trait SwapBaseOutInstructionAccountsExt {
         base_mint: Pubkey,
         quote_mint: Pubkey,
     ) -> Vec<Instruction>;
-    fn get_create_ata_ix(&self) -> Instruction;
+    fn get_create_ata_ix(&self, mint: Pubkey) -> Instruction;
     fn get_close_wsol(&self, pubkey: Pubkey) -> Instruction;
+    fn to_swap_base_in_accounts(&self) -> SwapBaseInInstructionAccounts;
 }
 
 impl SwapBaseOutInstructionAccountsExt for SwapBaseOutInstructionAccounts {
-    fn get_create_ata_ix(&self) -> Instruction {
+    fn get_create_ata_ix(&self, mint: Pubkey) -> Instruction {
         let create_ata_ix =
             spl_associated_token_account::instruction::create_associated_token_account(
                 &self.user_source_owner,
                 &self.user_source_owner,
-                &self.user_source_owner,
+                &mint,
                 &self.token_program,
             );
 
@@ -59,17 +62,35 @@ impl SwapBaseOutInstructionAccountsExt for SwapBaseOutInstructionAccounts {
     }
 
     fn get_close_wsol(&self, pubkey: Pubkey) -> Instruction {
-        let wsol_ata = get_associated_token_address(&pubkey.clone(), &WSOL);
-
-        let create_ata_ix =
-            spl_token::instruction::close_account(&spl_token::ID, &wsol_ata, &pubkey.clone(), &pubkey.clone(), &[])
-                .unwrap();
+        close_wsol_ix(&pubkey)
+    }
 
-        create_ata_ix
+    /// Base-in and base-out swaps take the same accounts, only the amounts differ
+    fn to_swap_base_in_accounts(&self) -> SwapBaseInInstructionAccounts {
+        SwapBaseInInstructionAccounts {
+            token_program: self.token_program,
+            amm: self.amm,
+            amm_authority: self.amm_authority,
+            amm_open_orders: self.amm_open_orders,
+            amm_target_orders: self.amm_target_orders,
+            pool_coin_token_account: self.pool_coin_token_account,
+            pool_pc_token_account: self.pool_pc_token_account,
+            serum_program: self.serum_program,
+            serum_market: self.serum_market,
+            serum_bids: self.serum_bids,
+            serum_asks: self.serum_asks,
+            serum_event_queue: self.serum_event_queue,
+            serum_coin_vault_account: self.serum_coin_vault_account,
+            serum_pc_vault_account: self.serum_pc_vault_account,
+            serum_vault_signer: self.serum_vault_signer,
+            user_source_token_account: self.user_source_token_account,
+            user_destination_token_account: self.user_destination_token_account,
+            user_source_owner: self.user_source_owner,
+        }
     }
 
     fn get_swap_base_out_ix(&self, buy_exact_in_param: SwapBaseOut) -> Instruction {
-        let discriminator = [9];
+        let discriminator = [11];
         let mut data = Vec::new();
 
         data.extend_from_slice(&discriminator);
@@ -126,3 +147,79 @@ impl SwapBaseOutInstructionAccountsExt for SwapBaseOutInstructionAccounts {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use carbon_core::{deserialize::ArrangeAccounts, instruction::InstructionDecoder};
+    use carbon_raydium_amm_v4_decoder::{instructions::RaydiumAmmV4Instruction, RaydiumAmmV4Decoder};
+    use spl_associated_token_account::get_associated_token_address;
+
+    fn sample_accounts() -> SwapBaseOutInstructionAccounts {
+        SwapBaseOutInstructionAccounts {
+            token_program: spl_token::ID,
+            amm: Pubkey::new_unique(),
+            amm_authority: Pubkey::new_unique(),
+            amm_open_orders: Pubkey::new_unique(),
+            amm_target_orders: None,
+            pool_coin_token_account: Pubkey::new_unique(),
+            pool_pc_token_account: Pubkey::new_unique(),
+            serum_program: Pubkey::new_unique(),
+            serum_market: Pubkey::new_unique(),
+            serum_bids: Pubkey::new_unique(),
+            serum_asks: Pubkey::new_unique(),
+            serum_event_queue: Pubkey::new_unique(),
+            serum_coin_vault_account: Pubkey::new_unique(),
+            serum_pc_vault_account: Pubkey::new_unique(),
+            serum_vault_signer: Pubkey::new_unique(),
+            user_source_token_account: Pubkey::new_unique(),
+            user_destination_token_account: Pubkey::new_unique(),
+            user_source_owner: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn create_ata_ix_is_for_the_given_mint() {
+        let accounts = sample_accounts();
+        let mint = Pubkey::new_unique();
+
+        let ix = accounts.get_create_ata_ix(mint);
+
+        // payer, associated account, wallet, mint, system program, token program
+        let owner = accounts.user_source_owner;
+        assert_eq!(ix.accounts[1].pubkey, get_associated_token_address(&owner, &mint));
+        assert_eq!(ix.accounts[2].pubkey, owner);
+        assert_eq!(ix.accounts[3].pubkey, mint);
+        assert_eq!(ix.accounts[5].pubkey, spl_token::ID);
+    }
+
+    #[test]
+    fn swap_base_out_decodes_into_the_swap_base_in_accounts() {
+        let accounts = sample_accounts();
+        let params = SwapBaseOut {
+            max_amount_in: 1_000_000_000,
+            amount_out: 42_000_000,
+        };
+        let ix = accounts.get_swap_base_out_ix(params.clone());
+
+        let decoded = RaydiumAmmV4Decoder.decode_instruction(&ix).unwrap();
+        let RaydiumAmmV4Instruction::SwapBaseOut(data) = decoded.data else {
+            panic!("decoded as {:?}", decoded.data);
+        };
+        assert_eq!(data, params);
+
+        let arranged = SwapBaseOut::arrange_accounts(&decoded.accounts)
+            .unwrap()
+            .to_swap_base_in_accounts();
+        assert_eq!(arranged.amm, accounts.amm);
+        assert_eq!(arranged.amm_authority, accounts.amm_authority);
+        assert_eq!(arranged.pool_coin_token_account, accounts.pool_coin_token_account);
+        assert_eq!(arranged.pool_pc_token_account, accounts.pool_pc_token_account);
+        assert_eq!(arranged.user_source_token_account, accounts.user_source_token_account);
+        assert_eq!(
+            arranged.user_destination_token_account,
+            accounts.user_destination_token_account
+        );
+        assert_eq!(arranged.user_source_owner, accounts.user_source_owner);
+    }
+}
@@ -1,3 +1,6 @@
+// `doc!` in BotRepository::update expands one level per field
+#![recursion_limit = "256"]
+
 pub mod backend;
 pub mod config;
 pub mod error;
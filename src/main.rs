@@ -14,7 +14,9 @@ use {
         PumpSwapDecoder, PROGRAM_ID as PUMPSWAP_PROGRAM_ID,
     },
     carbon_raydium_amm_v4_decoder::{
-        instructions::{swap_base_in::SwapBaseIn, RaydiumAmmV4Instruction},
+        instructions::{
+            swap_base_in::SwapBaseIn, swap_base_out::SwapBaseOut, RaydiumAmmV4Instruction,
+        },
         RaydiumAmmV4Decoder, PROGRAM_ID as RAY_V4_PROGRAM_ID,
     },
     carbon_raydium_cpmm_decoder::{
@@ -29,21 +31,54 @@ use {
     },
     carbon_yellowstone_grpc_datasource::YellowstoneGrpcGeyserClient,
     raydium_amm_monitor::{
-        backend::server::start_backend_server,
-        config::{init_jito, init_nozomi, init_zslot, JITO_CLIENT, RPC_CLIENT},
+        backend::{
+            db::pool_price_repository::PoolPriceRepository, models::bot::{FeeBudget, RELAY_SERVICES}, models::trade::{RealizedSlippage, SlotTime},
+            server::start_backend_server, services::bot_service::{current_fee_budget, ExitSignal, due_auto_exits, record_fee_spend, PriceChangeTracker, SwapAction},
+        },
+        config::{
+            init_jito, init_nozomi, init_zslot, ALLOW_TOKEN_2022, BLOCKHASH_RPC_CLIENTS,
+            AUTO_EXIT_SWEEP_INTERVAL, GEYSER_AUTH_CHECK_ENABLED, GEYSER_CONNECT_ATTEMPTS, KEEP_WSOL_OPEN, LIVE_TRADING_ENABLED, MAX_TASKS_PER_TRANSACTION, MIN_OUTPUT_ASSERTION_ENABLED, POOL_ALT_ENABLED, PUMPSWAP_EVENT_RESERVES_ENABLED, POOL_PRICE_PERSIST_INTERVAL,
+            PRICE_HISTORY_IDLE, PRICE_HISTORY_MAX_SAMPLES, RPC_CLIENT,
+            STARTUP_SELF_TEST_ENABLED, submission_service,
+        },
         instructions::{
             buy::BuyInstructionAccountsExt, sell::SellInstructionAccountsExt,
             swap_base_in::SwapBaseInInstructionAccountsExt,
+            swap_base_out::SwapBaseOutInstructionAccountsExt,
             buy_exact_in::BuyExactInInstructionAccountsExt,
             sell_exact_in::SellExactInInstructionAccountsExt,
             swap_base_input::SwapBaseInputInstructionAccountsExt,
         },
-        service::Tips,
+        service::{submit_through, submit_to_all, tip_all_and_sign, tip_and_sign, SubmissionService},
         utils::{
-            blockhash::{get_slot, recent_blockhash_handler, USD1, WSOL},
-            build_and_sign::build_and_sign,
-            parse::get_coin_pc_mint,
-            swap_quote::sol_token_quote,
+            alt::{ensure_pool_table, retire_unused_tables, POOL_TABLE_SWEEP_INTERVAL},
+            blockhash::{latest_blockhash, recent_blockhash_handler, USD1, WSOL},
+            confirm::{confirm_signature, fetch_transaction_metadata, Confirmation},
+            parse::{get_coin_pc_mint, owner_token_delta, token_account_flow, VaultBalances},
+            pool_status::pool_swap_enabled,
+            pool_reserves::pool_reserves,
+            positions::{
+                get_or_insert_position, position, position_changed, read_position,
+                trim_price_histories, update_position,
+            },
+            geyser_auth::wait_for_geyser,
+            fee::{fill_fee_breakdown, projected_tx_fee_sol},
+            min_output::{raise_min_output, swap_min_output, widen_slippage, with_min_output_assertion},
+            processor_errors::report_processor_result,
+            rent::check_buy_balance,
+            self_test::run_self_test,
+            simulation_failure::{classify_simulation_failure, SimulationFailure},
+            token_balance::sellable_tokens,
+            token_decimals::{reserve_price, token_decimals},
+            price::FixedPrice,
+            fill_event::{event_pool_reserves, launchpad_curve_reserves, user_fill},
+            swap_legs::first_pool_leg,
+            swap_quote::{launchpad_min_out, price_impact_pct, realized_slippage_pct, sell_min_out_with_cost_floor, sol_token_quote},
+            token_program::check_token_programs,
+            stuck::record_exit_failure,
+            tx_failures::diagnose_submitted_transaction,
+            tx_users::{task_batches, users_for_instruction},
+            wsol::apply_wsol_close,
         },
     },
     serde_json::json,
@@ -54,7 +89,7 @@ use {
         commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey,
         transaction::VersionedTransaction,
     },
-    solana_transaction_status_client_types::UiTransactionEncoding,
+    solana_transaction_status_client_types::{TransactionTokenBalance, UiTransactionEncoding},
     spl_associated_token_account::get_associated_token_address,
     std::{
         collections::{HashMap, HashSet},
@@ -63,6 +98,7 @@ use {
         time::Duration,
     },
     tokio::{sync::RwLock, time::sleep},
+    tracing::Instrument,
     yellowstone_grpc_proto::geyser::{CommitmentLevel, SubscribeRequestFilterTransactions},
 };
 
@@ -149,77 +185,159 @@ pub async fn main() -> CarbonResult<()> {
         }
     }
 
-    // init_nozomi().await;
-    // init_zslot().await;
+    init_nozomi().await;
+    init_zslot().await;
     init_jito().await;
 
+    if !*LIVE_TRADING_ENABLED {
+        println!("🔒 LIVE_TRADING_ENABLED is not set, every bot paper trades");
+    }
+
+    if *STARTUP_SELF_TEST_ENABLED {
+        run_self_test().await;
+    }
+
     // {
     //     let mut start_time_guard = START_TIME.write().await;
     //     *start_time_guard = Some(std::time::Instant::now());
     //     println!("Start time: {:?}", *start_time_guard);
     // }
-    tokio::spawn({
-        async move {
+    for (source, rpc_client) in BLOCKHASH_RPC_CLIENTS.iter().enumerate() {
+        let rpc_client = rpc_client.clone();
+        tokio::spawn(async move {
             loop {
-                recent_blockhash_handler(RPC_CLIENT.clone()).await;
+                recent_blockhash_handler(source, rpc_client.clone()).await;
             }
-        }
-    });
+        });
+    }
 
     // NOTE: Workaround, that solving issue https://github.com/rustls/rustls/issues/1877
     rustls::crypto::aws_lc_rs::default_provider()
         .install_default()
         .expect("Can't set crypto provider to aws_lc_rs");
 
-    // Spawn a task to monitor pool price changes when REAL_POOL_INFO length changes
+    // Evaluate every price a processor records as it arrives
+    let (price_tx, mut price_rx) = tokio::sync::mpsc::unbounded_channel();
+    let _ = raydium_amm_monitor::statics::PRICE_UPDATES.set(price_tx);
     tokio::spawn(async move {
-        loop {
-            // Get a snapshot of the data to avoid deadlocks
-            let pool_data = {
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                real_pool_info.clone() // Clone the data to release the read lock
+        let mut tracker = PriceChangeTracker::default();
+        while let Some(update) = price_rx.recv().await {
+            let Some(mut pool_info) = position(
+                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                &update.pool_id,
+                &update.user_id,
+            ) else {
+                continue;
+            };
+            // Later prices may already be recorded, evaluate this one against its own reference
+            pool_info.entry_reference_price = update.entry_reference_price;
+            let Some((old_price, new_price)) = tracker.observe(&update, pool_info.pool_price) else {
+                continue;
             };
+            println!(
+                "📊 Price change detected for pool {}: {:.8} -> {:.8}",
+                update.pool_id, old_price, new_price
+            );
 
-            for (pool_id, pool_infos) in pool_data.iter() {
-                for pool_info in pool_infos {
-                    let pool_price = pool_info.pool_price;
-                    let latest_price = pool_info.latest_pool_price;
+            display_pool_price_change(old_price, new_price, pool_info).await;
+        }
+    });
 
-                    // Only process if we have a valid price change
-                    if latest_price > 0.0 && pool_price != latest_price {
-                        println!("📊 Price change detected for pool {}: {:.8} -> {:.8}", 
-                            pool_id, pool_price, latest_price);
-                        
-                        let pool_info_clone = pool_info.clone();
-                        let old_price = pool_price;
-                        let new_price = latest_price;
+    // Persist every position state transition so open positions survive a restart
+    let (position_tx, mut position_rx) = tokio::sync::mpsc::unbounded_channel::<(String, String)>();
+    let _ = raydium_amm_monitor::statics::POSITION_CHANGES.set(position_tx);
+    tokio::spawn(async move {
+        let uri = std::env::var("MONGODB_URI").expect("MONGODB_URI not set");
+        let client = match Client::with_uri_str(uri).await {
+            Ok(client) => client,
+            Err(e) => {
+                println!("⚠️ Position persistence disabled: {}", e);
+                return;
+            }
+        };
+        let bot_service =
+            raydium_amm_monitor::backend::services::bot_service::BotService::new(client.database("trading"));
 
-                        // Update the pool_price to match latest_price before processing
-                        {
-                            let mut real_pool_info_write =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                            if let Some(pool_infos) = real_pool_info_write.get_mut(pool_id) {
-                                for info in pool_infos {
-                                    if info.user_bot_data.user_id.to_string()
-                                        == pool_info.user_bot_data.user_id.to_string()
-                                    {
-                                        info.pool_price = new_price;
-                                    }
-                                }
-                            }
-                        } // Write lock is automatically dropped here
+        while let Some((pool_id, user_id)) = position_rx.recv().await {
+            if let Err(e) = bot_service.persist_position(&pool_id, &user_id).await {
+                println!("⚠️ Failed to persist position of {} on pool {}: {}", user_id, pool_id, e);
+            }
+        }
+    });
 
-                        // Process the price change with updated data
-                        display_pool_price_change(old_price, new_price, pool_info_clone.clone())
-                            .await;
-                    }
-                }
+    // Slow sweep so auto-exit still fires on pools that stopped trading, it
+    // also keeps price history memory within its budget
+    tokio::spawn(async move {
+        loop {
+            sleep(*AUTO_EXIT_SWEEP_INTERVAL).await;
+            let now_ms = Utc::now().timestamp_millis();
+            trim_price_histories(
+                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                *PRICE_HISTORY_MAX_SAMPLES,
+                now_ms - PRICE_HISTORY_IDLE.as_millis() as i64,
+            );
+            let due = due_auto_exits(&raydium_amm_monitor::statics::REAL_POOL_INFO, now_ms);
+            for pool_info in due {
+                let price = pool_info.latest_pool_price;
+                display_pool_price_change(price, price, pool_info).await;
             }
-            // Check for price changes every 400ms
-            tokio::time::sleep(Duration::from_millis(400)).await;
         }
     });
 
+    // Periodically persist the last-known price per pool so bots can warm start
+    if !POOL_PRICE_PERSIST_INTERVAL.is_zero() {
+        tokio::spawn(async move {
+            let uri = std::env::var("MONGODB_URI").expect("MONGODB_URI not set");
+            let client = match Client::with_uri_str(uri).await {
+                Ok(client) => client,
+                Err(e) => {
+                    println!("⚠️ Pool price persistence disabled: {}", e);
+                    return;
+                }
+            };
+            let pool_price_repo = PoolPriceRepository::new(client.database("trading"));
+
+            loop {
+                tokio::time::sleep(*POOL_PRICE_PERSIST_INTERVAL).await;
+
+                let latest_prices = raydium_amm_monitor::utils::positions::latest_pool_prices(
+                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                );
+                for (pool_id, price) in latest_prices {
+                    if let Err(e) = pool_price_repo.upsert(&pool_id, price).await {
+                        println!("⚠️ Failed to persist price for pool {}: {}", pool_id, e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Deactivate lookup tables of pools no bot trades anymore, and close them
+    // once they can be, so their rent returns to the payer
+    if *POOL_ALT_ENABLED {
+        tokio::spawn(async move {
+            loop {
+                sleep(POOL_TABLE_SWEEP_INTERVAL).await;
+                let database = match trading_db().await {
+                    Ok(database) => database.clone(),
+                    Err(e) => {
+                        println!("⚠️ Lookup table sweep skipped: {}", e);
+                        continue;
+                    }
+                };
+                let pools_in_use: HashSet<String> = raydium_amm_monitor::statics::USER_LIST
+                    .read()
+                    .await
+                    .iter()
+                    .map(|bot| bot.pool_id.clone())
+                    .collect();
+                if let Err(e) = retire_unused_tables(database, &pools_in_use).await {
+                    println!("⚠️ Lookup table sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
     let transaction_filter = SubscribeRequestFilterTransactions {
         vote: Some(false),
         failed: Some(false),
@@ -242,9 +360,23 @@ pub async fn main() -> CarbonResult<()> {
         transaction_filter,
     );
 
+    let geyser_url = env::var("GEYSER_URL").unwrap_or_default();
+    let x_token = env::var("X_TOKEN").ok();
+
+    // The datasource only logs a refused subscription and then sits idle, so
+    // a bad token has to be caught before it subscribes
+    if *GEYSER_AUTH_CHECK_ENABLED {
+        if let Err(e) = wait_for_geyser(&geyser_url, x_token.clone(), *GEYSER_CONNECT_ATTEMPTS).await {
+            eprintln!("❌ Not starting the monitor: {}", e);
+            eprintln!("   Check GEYSER_URL and X_TOKEN, or set GEYSER_AUTH_CHECK=false to skip this check");
+            return Err(carbon_core::error::Error::FailedToConsumeDatasource(e.to_string()));
+        }
+        println!("✅ Geyser accepted X_TOKEN");
+    }
+
     let yellowstone_grpc = YellowstoneGrpcGeyserClient::new(
-        env::var("GEYSER_URL").unwrap_or_default(),
-        env::var("X_TOKEN").ok(),
+        geyser_url,
+        x_token,
         Some(CommitmentLevel::Processed),
         HashMap::new(),
         transaction_filters,
@@ -272,34 +404,148 @@ pub async fn main() -> CarbonResult<()> {
     Ok(())
 }
 
+/// Whether the position's held instructions are its sell. Otherwise a
+/// pending DCA buy is dropped and the exit waits for the processors to build
+/// the sell on the pool's next swap.
+fn exit_ixs_ready(pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo) -> bool {
+    let ready = update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        &pool_info.user_bot_data.pool_id,
+        &pool_info.user_bot_data.user_id,
+        |info| info.ready_to_sell(),
+    )
+    .unwrap_or(false);
+    if !ready {
+        println!(
+            "⏳ Exit for user {} waits for its sell instructions to be built",
+            pool_info.user_bot_data.user_id
+        );
+    }
+    ready
+}
+
+/// Raises a take-profit sell's minimum output to the position's cost basis,
+/// whichever processor built it. The cost is in lamports, so only sells
+/// paying out to the owner's WSOL account are floored.
+fn with_cost_floor(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    owner: &Pubkey,
+    swap_ixs: Vec<Instruction>,
+) -> Vec<Instruction> {
+    let Some(reason) = pool_info.exit_reason.as_ref().filter(|_| pool_info.pending_action == SwapAction::Sell) else {
+        return swap_ixs;
+    };
+    let Some((output, slippage_min_out)) = swap_ixs.iter().find_map(swap_min_output) else {
+        return swap_ixs;
+    };
+    if output != get_associated_token_address(owner, &WSOL) {
+        return swap_ixs;
+    }
+    let min_out = sell_min_out_with_cost_floor(slippage_min_out, pool_info.cost_basis_floor_lamports(), reason);
+    raise_min_output(&swap_ixs, min_out).unwrap_or(swap_ixs)
+}
+
+/// Claims the position's exit sell, false while another is in flight
+fn claim_exit(pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo) -> bool {
+    update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        &pool_info.user_bot_data.pool_id,
+        &pool_info.user_bot_data.user_id,
+        |info| info.claim_exit(),
+    ) == Some(true)
+}
+
+/// Submits a claimed exit sell. One that isn't accepted counts towards the
+/// position's stuck-exit count right away and leaves the position open for
+/// the next price update to retry; an accepted one is settled by its fill or
+/// its confirmation, see `close_round_trip` and `confirm_submission`.
+async fn submit_exit(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    reason: &ExitSignal,
+    label: &str,
+) -> bool {
+    let mut exit = pool_info.clone();
+    exit.exit_reason = Some(reason.clone());
+    let failure = match build_and_submit_swap_transaction(exit).await {
+        Ok(result) => {
+            tracing::info!("✅ {} transaction result: {:?}", label, result);
+            // Only an accepted live submission carries `has_bought`
+            if result.get("has_bought").is_some() {
+                return true;
+            }
+            result["message"].as_str().unwrap_or("not accepted").to_string()
+        }
+        Err(err) => {
+            tracing::error!("❌ {} transaction failed: {}", label, err);
+            err.to_string()
+        }
+    };
+    record_exit_failure(&pool_info.user_bot_data.pool_id, &pool_info.user_bot_data.user_id, &failure).await;
+    false
+}
+
 async fn display_pool_price_change(
     old: f64,
     new: f64,
-    pool_info: raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    mut pool_info: raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
 ) {
     if old > 0.0 && new > 0.0 {
+        let span = tracing::info_span!(
+            "bot",
+            user_id = %pool_info.user_bot_data.user_id,
+            pool = %pool_info.user_bot_data.pool_id
+        );
         tokio::spawn({
             let bought = pool_info.is_bought;
 
             async move {
                 if !bought {
                     // We haven't bought yet - looking for entry signal
+                    if pool_info.paused {
+                        tracing::info!(
+                            "⏸️ Entries paused for user {} after repeated processor errors",
+                            pool_info.user_bot_data.user_id
+                        );
+                        return;
+                    }
+                    if pool_info.is_draining(Utc::now().timestamp_millis()) {
+                        tracing::info!(
+                            "🚰 Entry suppressed on pool {}: quote reserve is being drained",
+                            pool_info.user_bot_data.pool_id
+                        );
+                        return;
+                    }
+                    if pool_info.sandwich_suspected(Utc::now().timestamp_millis()) {
+                        tracing::info!(
+                            "🥪 Entry suppressed on pool {}: recent swaps look like sandwiches",
+                            pool_info.user_bot_data.pool_id
+                        );
+                        return;
+                    }
+                    if !pool_info.has_min_observations() {
+                        println!(
+                            "⏳ Waiting for price history on pool {}: {}/{} swaps observed",
+                            pool_info.user_bot_data.pool_id,
+                            pool_info.observations,
+                            pool_info.user_bot_data.bot_setting.min_observations_before_trading
+                        );
+                        return;
+                    }
                     let new_clone = new.clone();
-                    // Calculate percentage drop from old price
-                    let percent_drop = if old > 0.0 {
-                        ((old - new_clone) / old) * 100.0
-                    } else {
-                        0.0
-                    };
-                    
+                    // Reference price depends on the bot's entry_reference (last price by default)
+                    let reference = pool_info.entry_reference_price.unwrap_or(old);
+                    // Calculate percentage drop from the reference price
+                    let percent_drop =
+                        -FixedPrice::from_f64(new_clone).percent_change_from(FixedPrice::from_f64(reference));
+
                     println!(
-                        "📊 ENTRY CHECK: old = {:.8}, new = {:.8}, drop = {:+.4}% (threshold: {}%)",
-                        old, new_clone, percent_drop, pool_info.user_bot_data.bot_setting.entry_percent
+                        "📊 ENTRY CHECK: reference = {:.8}, new = {:.8}, drop = {:+.4}% (threshold: {}%)",
+                        reference, new_clone, percent_drop, pool_info.user_bot_data.bot_setting.entry_percent
                     );
                     
                     // Check if price dropped enough to trigger entry
                     if percent_drop >= pool_info.user_bot_data.bot_setting.entry_percent {
-                        println!(
+                        tracing::info!(
                             "🚀 ENTRY SIGNAL: Price dropped {:.4}% (threshold: {}%) - BUYING!",
                             percent_drop, pool_info.user_bot_data.bot_setting.entry_percent
                         );
@@ -309,28 +555,27 @@ async fn display_pool_price_change(
                         let current_time = Utc::now().timestamp_millis();
                         
                         // Update bought price and timestamp
-                        {
-                            let mut real_pool_info =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                            if let Some(pool_infos) = real_pool_info.get_mut(&pool_info_for_spawn.user_bot_data.pool_id.clone()) {
-                                for info in pool_infos {
-                                    if info.user_bot_data.user_id.to_string()
-                                        == pool_info_for_spawn.user_bot_data.user_id.to_string()
-                                    {
-                                        info.bought_price = Some(new_price_clone);
-                                        info.bought_at = Some(current_time);
-                                        println!("✅ Updated bought_price: {:.8}, bought_at: {}", new_price_clone, current_time);
-                                    }
-                                }
-                            }
-                        }
+                        update_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            &pool_info_for_spawn.user_bot_data.pool_id,
+                            &pool_info_for_spawn.user_bot_data.user_id,
+                            |info| {
+                                info.bought_price = Some(new_price_clone);
+                                info.bought_at = Some(current_time);
+                                println!("✅ Updated bought_price: {:.8}, bought_at: {}", new_price_clone, current_time);
+                            },
+                        );
+                        position_changed(
+                            &pool_info_for_spawn.user_bot_data.pool_id,
+                            &pool_info_for_spawn.user_bot_data.user_id,
+                        );
 
                         match build_and_submit_swap_transaction(pool_info_for_spawn.clone()).await {
                             Ok(result) => {
-                                log::info!("✅ BUY transaction result: {:?}", result);
+                                tracing::info!("✅ BUY transaction result: {:?}", result);
                             }
                             Err(err) => {
-                                log::error!("❌ BUY transaction failed: {}", err);
+                                tracing::error!("❌ BUY transaction failed: {}", err);
                             }
                         }
                     }
@@ -341,11 +586,8 @@ async fn display_pool_price_change(
                     
                     if let Some(bought_price_val) = bought_price {
                         // Calculate percentage change from bought price
-                        let percent_change = if bought_price_val > 0.0 {
-                            ((new_clone - bought_price_val) / bought_price_val) * 100.0
-                        } else {
-                            0.0
-                        };
+                        let percent_change =
+                            FixedPrice::from_f64(new_clone).percent_change_from(FixedPrice::from_f64(bought_price_val));
                         
                         println!(
                             "📈 EXIT CHECK: bought = {:.8}, current = {:.8}, change = {:+.4}% (TP: {}%, SL: {}%)",
@@ -354,84 +596,138 @@ async fn display_pool_price_change(
                             pool_info.user_bot_data.bot_setting.stop_loss
                         );
                         
-                        let bought_at = pool_info.bought_at;
                         let current_time = Utc::now().timestamp_millis();
+
+                        // Far enough in profit, the stop moves up to break-even for good
+                        let moved = update_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            &pool_info.user_bot_data.pool_id,
+                            &pool_info.user_bot_data.user_id,
+                            |info| info.arm_break_even_stop(percent_change),
+                        );
+                        if moved == Some(true) {
+                            pool_info.stop_moved_to_be = true;
+                            tracing::info!("🔒 BREAK-EVEN STOP: Price increased {:.4}% - stop moved to {:+.4}%",
+                                percent_change, pool_info.stop_level_pct(current_time));
+                        }
                         
-                        // Check take profit condition
-                        if percent_change >= pool_info.user_bot_data.bot_setting.take_profit {
-                            println!("🎯 TAKE PROFIT: Price increased {:.4}% (threshold: {}%) - SELLING!",
-                                percent_change, pool_info.user_bot_data.bot_setting.take_profit);
-                            
-                            match build_and_submit_swap_transaction(pool_info.clone()).await {
-                                Ok(result) => {
-                                    log::info!("✅ TAKE PROFIT transaction result: {:?}", result);
-                                }
-                                Err(err) => {
-                                    log::error!("❌ TAKE PROFIT transaction failed: {}", err);
-                                }
-                            }
-                            
-                            // Clean up bot state after selling
-                            set_bot_after_sell(&pool_info).await;
+                        let Some(signal) = pool_info.exit_signal(new_clone, percent_change, current_time) else {
+                            return;
+                        };
+                        if signal.sells() && !exit_ixs_ready(&pool_info) {
+                            return;
                         }
-                        // Check stop loss condition
-                        else if percent_change <= -pool_info.user_bot_data.bot_setting.stop_loss {
-                            println!("🛑 STOP LOSS: Price decreased {:.4}% (threshold: {}%) - SELLING!",
-                                percent_change.abs(), pool_info.user_bot_data.bot_setting.stop_loss);
-                            
-                            match build_and_submit_swap_transaction(pool_info.clone()).await {
-                                Ok(result) => {
-                                    log::info!("✅ STOP LOSS transaction result: {:?}", result);
+                        let reason = signal.clone();
+                        match signal {
+                            // Stop bot and panic sell, ahead of TP/SL so the bot always stops
+                            ExitSignal::ForceExit => {
+                                // Claim the sell, so a concurrent price update or sweep doesn't submit it too
+                                if !claim_exit(&pool_info) {
+                                    return;
                                 }
-                                Err(err) => {
-                                    log::error!("❌ STOP LOSS transaction failed: {}", err);
+                                tracing::info!("🛑 IMMEDIATE SELL: Stop bot triggered!");
+                                let submitted = submit_exit(&pool_info, &reason, "IMMEDIATE SELL").await;
+                                save_position_duration(&pool_info.user_bot_data.pool_id, &pool_info.user_bot_data.user_id);
+
+                                // A submitted sell finishes stopping the bot once it settles, so
+                                // its round trip is recorded first, see `close_round_trip`
+                                if !submitted {
+                                    cleanup_bot_after_stop(&pool_info).await;
                                 }
                             }
-                            
-                            // Clean up bot state after selling
-                            set_bot_after_sell(&pool_info).await;
-                        }
-                        // Check immediate sell (stop bot)
-                        else if pool_info.user_bot_data.bot_setting.auto_exit == 0 {
-                            println!("🛑 IMMEDIATE SELL: Stop bot triggered!");
-                            
-                            match build_and_submit_swap_transaction(pool_info.clone()).await {
-                                Ok(result) => {
-                                    log::info!("✅ IMMEDIATE SELL transaction result: {:?}", result);
-                                }
-                                Err(err) => {
-                                    log::error!("❌ IMMEDIATE SELL transaction failed: {}", err);
-                                }
+                            // A fired DCA level waits for the processors to build its buy
+                            ExitSignal::DcaWaiting(level) => {
+                                println!("⏳ Waiting for DCA level {} buy instructions", level);
                             }
-                            
-                            // Clean up bot state after selling
-                            cleanup_bot_after_stop(&pool_info).await;
-                        }
-                        // Check auto exit timeout
-                        else if let Some(bought_at_time) = bought_at {
-                            let auto_exit_ms = match (pool_info.user_bot_data.bot_setting.auto_exit * 1000).try_into() {
-                                Ok(ms) => ms,
-                                Err(_) => {
-                                    println!("Warning: AUTO_EXIT conversion failed, using default 1000ms");
-                                    1000i64
-                                }
-                            };
-                            
-                            if current_time - bought_at_time > auto_exit_ms {
-                                println!("⏰ AUTO EXIT: Timeout after {} seconds - SELLING!",
-                                    pool_info.user_bot_data.bot_setting.auto_exit);
-                                
+                            ExitSignal::DcaBuy(level) => {
+                                tracing::info!("📥 DCA level {}: buying {} SOL more at {:.8}",
+                                    level, pool_info.user_bot_data.bot_setting.dca_buy_sol_amount(Some(level)), new_clone);
+
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    &pool_info.user_bot_data.pool_id,
+                                    &pool_info.user_bot_data.user_id,
+                                    |info| info.pending_dca_level = None,
+                                );
+
                                 match build_and_submit_swap_transaction(pool_info.clone()).await {
                                     Ok(result) => {
-                                        log::info!("✅ AUTO EXIT transaction result: {:?}", result);
+                                        tracing::info!("✅ DCA BUY transaction result: {:?}", result);
                                     }
                                     Err(err) => {
-                                        log::error!("❌ AUTO EXIT transaction failed: {}", err);
+                                        tracing::error!("❌ DCA BUY transaction failed: {}", err);
                                     }
                                 }
-                                
-                                // Clean up bot state after selling
-                                set_bot_after_sell(&pool_info).await;
+                            }
+                            // A deeper drop since the first fill scales in
+                            ExitSignal::DcaLevel(level) => {
+                                tracing::info!("📉 DCA level {} reached ({}% below the first fill) - preparing buy",
+                                    level, pool_info.user_bot_data.bot_setting.dca_levels[level]);
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    &pool_info.user_bot_data.pool_id,
+                                    &pool_info.user_bot_data.user_id,
+                                    |info| info.fire_dca_level(level),
+                                );
+                            }
+                            // Partial take-profit tiers, which replace the single take profit
+                            ExitSignal::TakeProfitTiers { tiers, fraction, closes } => {
+                                if !claim_exit(&pool_info) {
+                                    return;
+                                }
+                                tracing::info!("🎯 TAKE PROFIT TIER {:?}: Price increased {:.4}% - SELLING {:.0}% of the position!",
+                                    tiers, percent_change, fraction * 100.0);
+
+                                // Mark the tiers before submitting, a failed sell fires them again
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    &pool_info.user_bot_data.pool_id,
+                                    &pool_info.user_bot_data.user_id,
+                                    |info| info.fire_tiers(&tiers),
+                                );
+
+                                let mut tier_sell = pool_info.clone();
+                                // The closing tier sells everything still held
+                                tier_sell.sell_fraction = (!closes).then_some(fraction);
+                                submit_exit(&tier_sell, &reason, "TAKE PROFIT TIER").await;
+                            }
+                            // Take profit, possibly decayed towards break-even
+                            ExitSignal::TakeProfit => {
+                                if !claim_exit(&pool_info) {
+                                    return;
+                                }
+                                tracing::info!("🎯 TAKE PROFIT: Price increased {:.4}% (threshold: {:.4}%) - SELLING!",
+                                    percent_change, pool_info.take_profit_target(current_time));
+                                submit_exit(&pool_info, &reason, "TAKE PROFIT").await;
+                            }
+                            // Trailing stop, ahead of the fixed stop loss
+                            ExitSignal::TrailingStop(drawdown) => {
+                                if !claim_exit(&pool_info) {
+                                    return;
+                                }
+                                tracing::info!("📉 TRAILING STOP: Price {:.4}% below peak {:.8} (threshold: {:?}%) - SELLING!",
+                                    drawdown, pool_info.peak_price.unwrap_or(bought_price_val),
+                                    pool_info.user_bot_data.bot_setting.trailing_stop_pct);
+                                submit_exit(&pool_info, &reason, "TRAILING STOP").await;
+                            }
+                            // Stop loss per the current market regime, or break-even once the stop has moved
+                            ExitSignal::StopLoss => {
+                                if !claim_exit(&pool_info) {
+                                    return;
+                                }
+                                tracing::info!("🛑 {}: Price changed {:+.4}% (threshold: {:+.4}%) - SELLING!",
+                                    if pool_info.stop_moved_to_be { "BREAK-EVEN STOP" } else { "STOP LOSS" },
+                                    percent_change, pool_info.stop_level_pct(current_time));
+                                submit_exit(&pool_info, &reason, "STOP LOSS").await;
+                            }
+                            // Auto exit timeout (auto_exit = 0 disables it)
+                            ExitSignal::AutoExit => {
+                                if !claim_exit(&pool_info) {
+                                    return;
+                                }
+                                tracing::info!("⏰ AUTO EXIT: Timeout after {} seconds - SELLING!",
+                                    pool_info.user_bot_data.bot_setting.auto_exit);
+                                submit_exit(&pool_info, &reason, "AUTO EXIT").await;
                             }
                         }
                     } else {
@@ -439,6 +735,7 @@ async fn display_pool_price_change(
                     }
                 }
             }
+            .instrument(span)
         });
     }
 }
@@ -453,278 +750,1029 @@ async fn build_and_submit_swap_transaction(
     // Print current timestamp and consumed time from start
     println!("Submitting tx --> Current time: {:#?}", Utc::now());
 
-    let pool_info_clone = pool_info.clone();
-    let buy_ixs = {
-        let buy_ixs_guard = pool_info_clone.swap_buy_ixs;
-        buy_ixs_guard.clone()
-    };
+    let settings = &pool_info.user_bot_data.bot_setting;
+    if !settings.submits_live(*LIVE_TRADING_ENABLED) {
+        if !settings.observer {
+            tracing::info!(
+                "🔒 Paper trading for user {}: live trading is {}",
+                pool_info.user_bot_data.user_id,
+                if *LIVE_TRADING_ENABLED { "not acknowledged for this bot" } else { "disabled" }
+            );
+        }
+        return Ok(observe_swap(&pool_info).await);
+    }
 
-    if buy_ixs.is_empty() {
+    let swap_ixs = pool_info.pending_swap_ixs.clone();
+    if swap_ixs.is_empty() {
         println!("No swap instructions to submit.");
         return Ok(json!({ "result": "error", "message": "No swap instructions to submit" }));
     }
 
-    let results = match pool_info.user_bot_data.bot_setting.confirm_service.as_str() {
-        // "NOZOMI" => {
-        //     let nozomi = match NOZOMI_CLIENT.get() {
-        //         Some(client) => client,
-        //         None => {
-        //             println!("Error: Nozomi client not initialized");
-        //             return;
-        //         }
-        //     };
-
-        //     let ixs = nozomi.add_tip_ix(Tips {
-        //         cu: Some(pool_info.user_bot_data.bot_setting.cu),
-        //         priority_fee_micro_lamport: Some(pool_info.user_bot_data.bot_setting.priority_fee_micro_lamport),
-        //         payer: pool_info.user_bot_data.public_key.parse::<Pubkey>().clone().unwrap(),
-        //         pure_ix: buy_ixs.clone(),
-        //         tip_addr_idx: 1,
-        //         tip_sol_amount: pool_info.user_bot_data.bot_setting.third_party_fee,
-        //     });
-
-        //     let recent_blockhash = get_slot();
-
-        //     let encoded_tx = build_and_sign(ixs, recent_blockhash, None);
-
-        //     match nozomi.send_transaction(&encoded_tx).await {
-        //         Ok(data) => {
-        //             // Extract signature from the result
-        //             pool_info.signature =
-        //                 Some(data["result"].as_str().unwrap_or_default().to_string());
-        //             json!({ "result": data })
-        //         }
-        //         Err(err) => {
-        //             json!({ "result": "error", "message": err.to_string() })
-        //         }
-        //     }
-        // }
-        // "ZERO_SLOT" => {
-        //     let zero_slot = match ZSLOT_CLIENT.get() {
-        //         Some(client) => client,
-        //         None => {
-        //             println!("Error: ZSlot client not initialized");
-        //             return;
-        //         }
-        //     };
-
-        //     let ixs = zero_slot.add_tip_ix(Tips {
-        //         cu: Some(pool_info.user_bot_data.bot_setting.cu),
-        //         priority_fee_micro_lamport: Some(
-        //             pool_info
-        //                 .user_bot_data
-        //                 .bot_setting
-        //                 .priority_fee_micro_lamport,
-        //         ),
-        //         payer: pool_info
-        //             .user_bot_data
-        //             .public_key
-        //             .parse::<Pubkey>()
-        //             .clone()
-        //             .unwrap(),
-        //         pure_ix: buy_ixs,
-        //         tip_addr_idx: 1,
-        //         tip_sol_amount: pool_info.user_bot_data.bot_setting.third_party_fee,
-        //     });
-
-        //     let recent_blockhash = get_slot();
-
-        //     let encoded_tx = build_and_sign(
-        //         ixs,
-        //         recent_blockhash,
-        //         None,
-        //         pool_info
-        //             .user_bot_data
-        //             .public_key
-        //             .parse::<Pubkey>()
-        //             .clone()
-        //             .unwrap(),
-        //         keypair,
-        //     );
-
-        //     match zero_slot.send_transaction(&encoded_tx).await {
-        //         Ok(data) => {
-        //             // Extract signature from the result
-        //             pool_info.signature =
-        //                 Some(data["result"].as_str().unwrap_or_default().to_string());
-        //             json!({ "result": data })
-        //         }
-        //         Err(err) => {
-        //             json!({ "result": "error", "message": err.to_string() })
-        //         }
-        //     }
-        // }
-        "JITO" => {
-            let jito = match JITO_CLIENT.get() {
-                Some(client) => client,
-                None => {
-                    println!("Error: Jito client not initialized");
-                    return Ok(
-                        json!({ "result": "error", "message": "Jito client not initialized" }),
-                    );
-                }
-            };
+    // A forced exit (stop_bot, panic_sell) is the session's last trade
+    let owner = pool_info.user_bot_data.public_key.parse::<Pubkey>()?;
+    let swap_ixs = apply_wsol_close(swap_ixs, &owner, *KEEP_WSOL_OPEN, pool_info.force_exit);
+    let swap_ixs = with_cost_floor(&pool_info, &owner, swap_ixs);
+    let swap_ixs = if *MIN_OUTPUT_ASSERTION_ENABLED {
+        with_min_output_assertion(swap_ixs).await
+    } else {
+        swap_ixs
+    };
 
-            let ixs = jito.add_tip_ix(Tips {
-                cu: Some(pool_info.user_bot_data.bot_setting.cu),
-                priority_fee_micro_lamport: Some(
-                    pool_info
-                        .user_bot_data
-                        .bot_setting
-                        .priority_fee_micro_lamport,
-                ),
-                payer: pool_info
-                    .user_bot_data
-                    .public_key
-                    .parse::<Pubkey>()
-                    .clone()
-                    .unwrap(),
-                pure_ix: buy_ixs,
-                tip_addr_idx: 4,
-                tip_sol_amount: pool_info.user_bot_data.bot_setting.third_party_fee,
-            });
+    // Entries only: a position in hand is always let out
+    if pool_info.pending_action == SwapAction::Buy {
+        let budget = current_fee_budget(
+            &raydium_amm_monitor::statics::FEE_BUDGETS,
+            &pool_info.user_bot_data.user_id,
+            settings.fee_budget,
+        );
+        if let Some(reason) = settings.fee_budget_exhausted(&budget, Utc::now().timestamp_millis()) {
+            tracing::warn!("⛽ Pausing entries for user {}: {}", pool_info.user_bot_data.user_id, reason);
+            return Ok(json!({ "result": "error", "message": format!("Fee budget exhausted: {}", reason) }));
+        }
+    }
 
-            let recent_blockhash = get_slot();
-
-            let encoded_tx = build_and_sign(
-                ixs,
-                recent_blockhash,
-                None,
-                pool_info
-                    .user_bot_data
-                    .public_key
-                    .parse::<Pubkey>()
-                    .clone()
-                    .unwrap(),
-                keypair,
+    // Buys only: sells spend tokens, not the wallet's SOL
+    if pool_info.pending_action == SwapAction::Buy {
+        let payer = pool_info.user_bot_data.public_key.parse::<Pubkey>()?;
+        let projected_fee = projected_tx_fee_sol(
+            settings.cu,
+            settings.priority_fee_micro_lamport,
+            settings.third_party_fee,
+        );
+        if let Err(reason) = check_buy_balance(&payer, &swap_ixs, projected_fee).await {
+            tracing::warn!(
+                "🪫 Skipping buy for user {}: {}",
+                pool_info.user_bot_data.user_id, reason
             );
+            return Ok(json!({ "result": "error", "message": format!("Insufficient balance: {}", reason) }));
+        }
+    }
+
+    if *POOL_ALT_ENABLED {
+        match trading_db().await {
+            Ok(database) => ensure_pool_table(&pool_info.user_bot_data.pool_id, &swap_ixs, database.clone()),
+            Err(e) => println!("⚠️ Lookup tables unavailable: {}", e),
+        }
+    }
+
+    update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        &pool_info.user_bot_data.pool_id,
+        &pool_info.user_bot_data.user_id,
+        |info| {
+            info.signal_price = Some(pool_info.latest_pool_price);
+            info.signal_at = pool_info.last_swap_at;
+        },
+    );
+
+    if !pool_info.user_bot_data.bot_setting.multi_relay.is_empty() {
+        let relays = pool_info.user_bot_data.bot_setting.multi_relay.clone();
+        let results = submit_to_relays(&pool_info, swap_ixs, &relays).await;
+        println!(
+            "Multi-relay submitting --> : {:#?}\nPeriod from start: {:?}",
+            results,
+            start.elapsed()
+        );
+        return results;
+    }
+
+    let service = pool_info.user_bot_data.bot_setting.confirm_service.as_str();
+    let Some(client) = submission_service(service, &pool_info.user_bot_data.bot_setting.zslot_region) else {
+        let message = if RELAY_SERVICES.contains(&service) {
+            format!("{} client not initialized", service)
+        } else {
+            "unknown confirmation service".to_string()
+        };
+        println!("Error: {}", message);
+        return Ok(json!({ "result": "error", "message": message }));
+    };
+    let results = simulate_and_submit(&pool_info, client, keypair, swap_ixs).await;
+
+    println!(
+        "Transaction submitting --> : {:#?}\nCurrent time: {:#?}\nPeriod from start: {:?}",
+        results,
+        Utc::now(),
+        start.elapsed()
+    );
+
+    results
+}
+
+/// Simulates the swap as `client` would sign it and submits it through
+/// `client`, watching the signature it accepts
+async fn simulate_and_submit(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    client: &dyn SubmissionService,
+    keypair: Keypair,
+    swap_ixs: Vec<Instruction>,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    let (recent_blockhash, last_valid_block_height) = latest_blockhash();
+    let sign = |swap_ixs: &[Instruction]| {
+        tip_and_sign(client, settings, keypair.insecure_clone(), swap_ixs.to_vec(), recent_blockhash)
+    };
+
+    let (swap_ixs, units_consumed) = match simulate_swap(pool_info, swap_ixs, sign).await? {
+        Ok(simulated) => simulated,
+        Err(failure) => return Ok(failure),
+    };
+    let accepted = submit_through(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        pool_info,
+        client,
+        keypair,
+        swap_ixs,
+        units_consumed,
+        recent_blockhash,
+    )
+    .await;
+    match accepted {
+        Ok(accepted) => {
+            watch_submission(pool_info, accepted.signature, accepted.has_bought, last_valid_block_height);
+            Ok(json!({ "result": accepted.response, "has_bought": accepted.has_bought }))
+        }
+        Err(failure) => Ok(failure),
+    }
+}
+
+/// Signs the swap once with a tip for each relay in `relays` and sends it
+/// through all of them at once, watching the signature the first accepts
+async fn submit_to_relays(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    swap_ixs: Vec<Instruction>,
+    relays: &[String],
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    let (recent_blockhash, last_valid_block_height) = latest_blockhash();
+    let keypair = Keypair::from_base58_string(&pool_info.user_bot_data.private_key);
+
+    let clients: Vec<(&str, &dyn SubmissionService)> = relays
+        .iter()
+        .filter_map(|relay| match submission_service(relay, &settings.zslot_region) {
+            Some(client) => Some((relay.as_str(), client)),
+            None => {
+                println!("⚠️ {} client not initialized, left out of the relay race", relay);
+                None
+            }
+        })
+        .collect();
+    let sign = |swap_ixs: &[Instruction]| {
+        let services: Vec<&dyn SubmissionService> = clients.iter().map(|(_, client)| *client).collect();
+        tip_all_and_sign(&services, settings, keypair.insecure_clone(), swap_ixs.to_vec(), recent_blockhash)
+    };
+
+    let (swap_ixs, units_consumed) = match simulate_swap(pool_info, swap_ixs, sign).await? {
+        Ok(simulated) => simulated,
+        Err(failure) => return Ok(failure),
+    };
+    let accepted = submit_to_all(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        pool_info,
+        &clients,
+        keypair,
+        swap_ixs,
+        units_consumed,
+        recent_blockhash,
+    )
+    .await;
+    match accepted {
+        Ok(accepted) => {
+            watch_submission(pool_info, accepted.signature, accepted.has_bought, last_valid_block_height);
+            Ok(json!({
+                "result": accepted.response,
+                "has_bought": accepted.has_bought,
+                "relay_errors": accepted.relay_errors,
+            }))
+        }
+        Err(failure) => Ok(failure),
+    }
+}
 
-            // Simulate transaction before sending
+/// Simulates the swap as `sign` signs it. While the simulation fails on
+/// slippage, the swap's limit is widened by the bot's `slippage_retry_step`
+/// and simulated again, up to `max_slippage_retries` times. Returns the swap
+/// that passed with the compute units it consumed, or the error response.
+async fn simulate_swap(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    mut swap_ixs: Vec<Instruction>,
+    sign: impl Fn(&[Instruction]) -> anyhow::Result<String>,
+) -> Result<Result<(Vec<Instruction>, Option<u64>), serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    let pool_id = &pool_info.user_bot_data.pool_id;
+    let slippage = match pool_info.pending_action {
+        SwapAction::Buy => settings.entry_slippage_for(pool_id),
+        SwapAction::Sell => settings.exit_slippage_for(pool_id),
+    };
+    let original_ixs = swap_ixs.clone();
+    let mut retries = 0;
+
+    loop {
+        let encoded_tx = match sign(&swap_ixs) {
+            Ok(encoded_tx) => encoded_tx,
+            Err(err) => return Ok(Err(json!({ "result": "error", "message": err.to_string() }))),
+        };
+        match simulate_encoded_transaction(pool_info, &encoded_tx).await? {
+            Ok(units_consumed) => return Ok(Ok((swap_ixs, units_consumed))),
+            Err((failure, SimulationFailure::Slippage)) if retries < settings.max_slippage_retries => {
+                retries += 1;
+                let widened_pct = slippage + settings.slippage_retry_step * retries as f64;
+                let Some(widened_ixs) = widen_slippage(&original_ixs, slippage, widened_pct) else {
+                    return Ok(Err(failure));
+                };
+                tracing::warn!(
+                    "📉 Simulation for user {} exceeded its slippage, retrying at {:.2}% ({}/{})",
+                    pool_info.user_bot_data.user_id, widened_pct, retries, settings.max_slippage_retries
+                );
+                swap_ixs = widened_ixs;
+            }
+            Err((failure, _)) => return Ok(Err(failure)),
+        }
+    }
+}
+
+/// Simulates a signed transaction before it is sent to a relay. Returns the
+/// compute units it consumed, or the error response to hand back when the
+/// simulation fails along with what failed it.
+async fn simulate_encoded_transaction(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    encoded_tx: &str,
+) -> Result<Result<Option<u64>, (serde_json::Value, SimulationFailure)>, Box<dyn std::error::Error + Send + Sync>> {
+    // Simulate transaction before sending
+    log::info!(
+        "Simulating transaction for pool: {}",
+        pool_info.user_bot_data.pool_id
+    );
+
+    // Parse the encoded transaction for simulation
+    let transaction_bytes = base64::decode(encoded_tx)
+        .map_err(|e| format!("Failed to decode transaction: {}", e))?;
+    let transaction: VersionedTransaction = bincode::deserialize(&transaction_bytes)
+        .map_err(|e| format!("Failed to deserialize transaction: {}", e))?;
+
+    match simulate_transaction(&transaction).await {
+        Ok(simulation_result) => {
+            log::info!("=== TRANSACTION SIMULATION RESULTS ===");
+            log::info!("Pool ID: {}", pool_info.user_bot_data.pool_id);
+            log::info!("User ID: {}", pool_info.user_bot_data.user_id);
             log::info!(
-                "Simulating transaction for pool: {}",
-                pool_info.user_bot_data.pool_id
+                "Estimated compute units: {}",
+                simulation_result.units_consumed.unwrap_or(0)
             );
+            log::info!("Simulation successful: {}", simulation_result.err.is_none());
 
-            // Parse the encoded transaction for simulation
-            let transaction_bytes = base64::decode(&encoded_tx)
-                .map_err(|e| format!("Failed to decode transaction: {}", e))?;
-            let transaction: VersionedTransaction = bincode::deserialize(&transaction_bytes)
-                .map_err(|e| format!("Failed to deserialize transaction: {}", e))?;
-
-            println!("transaction: {:#?}", transaction);
-
-            match simulate_transaction(&transaction).await {
-                Ok(simulation_result) => {
-                    log::info!("=== TRANSACTION SIMULATION RESULTS ===");
-                    log::info!("Pool ID: {}", pool_info.user_bot_data.pool_id);
-                    log::info!("User ID: {}", pool_info.user_bot_data.user_id);
-                    log::info!(
-                        "Estimated compute units: {}",
-                        simulation_result.units_consumed.unwrap_or(0)
-                    );
-                    log::info!("Simulation successful: {}", simulation_result.err.is_none());
+            if let Some(logs) = &simulation_result.logs {
+                log::info!("Simulation logs ({} entries):", logs.len());
+                for (i, log_entry) in logs.iter().enumerate() {
+                    log::info!("  [{}] {}", i + 1, log_entry);
+                }
+            }
 
-                    if let Some(logs) = simulation_result.logs {
-                        log::info!("Simulation logs ({} entries):", logs.len());
-                        for (i, log_entry) in logs.iter().enumerate() {
-                            log::info!("  [{}] {}", i + 1, log_entry);
-                        }
-                    }
+            if let Some(accounts) = simulation_result.accounts {
+                log::info!("Account changes: {} accounts modified", accounts.len());
+            }
 
-                    if let Some(accounts) = simulation_result.accounts {
-                        log::info!("Account changes: {} accounts modified", accounts.len());
-                    }
+            log::info!("=== END SIMULATION RESULTS ===");
 
-                    log::info!("=== END SIMULATION RESULTS ===");
+            // Check if simulation failed
+            if let Some(err) = &simulation_result.err {
+                log::error!("Transaction simulation failed: {:?}", err);
+                let failure = classify_simulation_failure(err, simulation_result.logs.as_deref().unwrap_or_default());
+                return Ok(Err((
+                    json!({ "result": "simulation_error", "message": format!("Simulation failed: {:?}", err) }),
+                    failure,
+                )));
+            }
 
-                    // Check if simulation failed
-                    if simulation_result.err.is_some() {
-                        log::error!("Transaction simulation failed: {:?}", simulation_result.err);
-                        return Ok(
-                            json!({ "result": "simulation_error", "message": format!("Simulation failed: {:?}", simulation_result.err) }),
-                        );
+            Ok(Ok(simulation_result.units_consumed))
+        }
+        Err(err) => {
+            log::error!("Failed to simulate transaction: {}", err);
+            Ok(Err((
+                json!({ "result": "simulation_error", "message": format!("Simulation error: {}", err) }),
+                SimulationFailure::Other,
+            )))
+        }
+    }
+}
+
+/// Starts watching a signature recorded on the user's position for on-chain
+/// failures and confirmation until its blockhash, valid through
+/// `last_valid_block_height`, expires
+fn watch_submission(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    signature: String,
+    has_bought: bool,
+    last_valid_block_height: u64,
+) {
+    tokio::spawn(diagnose_submitted_transaction(
+        pool_info.user_bot_data.user_id.clone(),
+        pool_info.user_bot_data.pool_id.clone(),
+        signature.clone(),
+    ));
+    position_changed(&pool_info.user_bot_data.pool_id, &pool_info.user_bot_data.user_id);
+
+    tokio::spawn(confirm_submission(
+        pool_info.user_bot_data.pool_id.clone(),
+        pool_info.user_bot_data.user_id.clone(),
+        signature,
+        has_bought,
+        last_valid_block_height,
+        Duration::from_secs(pool_info.user_bot_data.bot_setting.confirm_timeout_secs),
+    ));
+}
+
+/// Fallback for fills the gRPC stream never delivers: polls the submission
+/// until it confirms and records its fill from RPC unless the stream already
+/// has. An entry is rolled back only once it can no longer land: it failed
+/// or its blockhash expired unconfirmed. An exit that isn't confirmed and
+/// recorded in time is counted as a failed exit, leaving the position open.
+async fn confirm_submission(
+    pool_id: String,
+    user_id: String,
+    signature: String,
+    is_entry: bool,
+    last_valid_block_height: u64,
+    timeout: Duration,
+) {
+    let Ok(parsed) = signature.parse::<solana_sdk::signature::Signature>() else {
+        return;
+    };
+
+    // Whether the signature is still the position's pending exit sell
+    let pending_exit = || {
+        !is_entry
+            && read_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, &pool_id, &user_id, |info| {
+                info.matches_submitted_signature(&signature)
+                    && !info.confirming_buy()
+                    && info.recorded_fill.as_deref() != Some(signature.as_str())
+            }) == Some(true)
+    };
+
+    match confirm_signature(&parsed, last_valid_block_height, timeout).await {
+        Confirmation::Confirmed(confirmed) => {
+            match fetch_transaction_metadata(&confirmed).await {
+                Ok(transaction) => {
+                    if record_confirmed_fill(&pool_id, &user_id, &transaction).await {
+                        println!("📡 Recorded fill of {} from RPC, the stream missed it", confirmed);
                     }
                 }
-                Err(err) => {
-                    log::error!("Failed to simulate transaction: {}", err);
-                    return Ok(
-                        json!({ "result": "simulation_error", "message": format!("Simulation error: {}", err) }),
-                    );
+                Err(e) => println!("⚠️ Failed to fetch confirmed transaction {}: {}", confirmed, e),
+            }
+            // An exit settles only through its recorded fill, so one whose
+            // fill can't be recorded is retried like a failed one
+            if pending_exit() {
+                record_exit_failure(&pool_id, &user_id, "confirmed, but its fill couldn't be recorded").await;
+            }
+        }
+        outcome => {
+            let rolled_back = is_entry
+                && outcome.never_lands()
+                && update_position(
+                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                    &pool_id,
+                    &user_id,
+                    |info| info.abandon_unfilled_entry(&signature),
+                ) == Some(true);
+            if rolled_back {
+                tracing::warn!(
+                    "↩️ Entry of user {} on pool {} did not land ({:?}), position rolled back",
+                    user_id, pool_id, outcome
+                );
+                position_changed(&pool_id, &user_id);
+            }
+            if !is_entry {
+                // An exit that failed, was dropped or went unconfirmed counts towards
+                // flagging the position stuck and is retried by the next price update
+                if pending_exit() {
+                    let reason = outcome
+                        .failure_reason()
+                        .unwrap_or_else(|| format!("unconfirmed after {:?}", timeout));
+                    record_exit_failure(&pool_id, &user_id, &reason).await;
+                }
+                // A stopping bot's forced exit that won't fill still stops it
+                let stopping = position(&raydium_amm_monitor::statics::REAL_POOL_INFO, &pool_id, &user_id)
+                    .filter(|info| info.force_exit);
+                if let Some(pool_info) = stopping {
+                    cleanup_bot_after_stop(&pool_info).await;
                 }
             }
+        }
+    }
+}
 
-            match jito.send_transaction(&encoded_tx).await {
-                Ok(data) => {
-                    let mut has_bought = false;
-                    // Extract signature from the result
-                    {
-                        let user_id = pool_info.user_bot_data.user_id.clone();
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_infos = real_pool_info
-                            .get_mut(&pool_info.user_bot_data.pool_id.clone())
-                            .unwrap();
-                        for info in pool_infos {
-                            if info.user_bot_data.user_id.to_string() == user_id {
-                                info.signature =
-                                    Some(data["result"].as_str().unwrap_or_default().to_string());
-                                has_bought = !info.is_bought;
-                                if (has_bought) {
-                                    info.is_bought = has_bought;
-                                }
-                            }
-                        }
-                    }
-                    Ok(json!({ "result": data, "has_bought": has_bought }))
-                }
-                Err(err) => Ok(json!({ "result": "error", "message": err.to_string() })),
+/// Observer mode stand-in for a submission: logs what would have been sent
+/// and flips the virtual position so exits keep being evaluated.
+async fn observe_swap(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+) -> serde_json::Value {
+    let pool_id = &pool_info.user_bot_data.pool_id;
+    let user_id = &pool_info.user_bot_data.user_id;
+    if !pool_info.is_bought {
+        let input_lamports = (pool_info.user_bot_data.bot_setting.buy_sol_amount * 1_000_000_000.0) as u64;
+        tracing::info!(
+            "👀 OBSERVER: would BUY {} SOL on pool {} for user {} at {:.8}",
+            pool_info.user_bot_data.bot_setting.buy_sol_amount,
+            pool_id,
+            user_id,
+            pool_info.latest_pool_price
+        );
+        let has_bought = update_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+            info.record_paper_buy(input_lamports, Utc::now().timestamp_millis())
+        })
+        .unwrap_or(false);
+        position_changed(pool_id, user_id);
+        return json!({ "result": "observer", "side": "BUY", "has_bought": has_bought });
+    }
+
+    // Only exits sell, a DCA level's buy isn't paper traded
+    if pool_info.exit_reason.is_none() {
+        return json!({ "result": "observer", "side": "BUY" });
+    }
+    tracing::info!(
+        "👀 OBSERVER: would SELL position on pool {} for user {} at {:.8}",
+        pool_id,
+        user_id,
+        pool_info.latest_pool_price
+    );
+    let tokens = pool_info.sell_amount(u64::MAX);
+    let Some((profit_sol, roi)) = update_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+        info.record_paper_sell(tokens)
+    })
+    .flatten() else {
+        return json!({ "result": "observer", "side": "SELL" });
+    };
+    println!("Paper profit: {} SOL, ROI: {}%", profit_sol, roi);
+    tokio::spawn(record_trade_result(user_id.to_string(), profit_sol));
+    // The same close path a confirmed sell takes, which finishes a forced exit's stop
+    close_round_trip(pool_id, user_id).await;
+
+    json!({ "result": "observer", "side": "SELL", "has_bought": false })
+}
+
+/// The token side of a pool's mint pair, the one that isn't WSOL or USD1
+fn traded_mint(a: Pubkey, b: Pubkey) -> Pubkey {
+    if a == WSOL || a == USD1 {
+        b
+    } else {
+        a
+    }
+}
+
+/// Whether the user's mint lists let the bot enter a position in `mint`
+fn entry_mint_allowed(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    mint: &Pubkey,
+) -> bool {
+    let allowed = update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        &pool_info.user_bot_data.pool_id,
+        &pool_info.user_bot_data.user_id,
+        |info| info.entry_allowed(&mint.to_string()),
+    )
+    .unwrap_or(false);
+    if !allowed {
+        println!(
+            "🚫 {} is excluded by the mint lists of user {}, skipping the buy",
+            mint, pool_info.user_bot_data.user_id
+        );
+    }
+    allowed
+}
+
+/// Whether the pool's `mints` are classic SPL tokens, per the owning
+/// programs in the transaction's token `balances`. The swap builders only
+/// derive classic token accounts, so anything else would fail on-chain.
+fn entry_token_programs_supported(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    balances: &[impl AsRef<[TransactionTokenBalance]>],
+    mints: &[Pubkey],
+) -> bool {
+    let balances = balances
+        .iter()
+        .flat_map(|balances| balances.as_ref())
+        .map(|balance| (balance.mint.as_str(), balance.program_id.as_str()));
+    match check_token_programs(balances, mints, *ALLOW_TOKEN_2022) {
+        Ok(()) => true,
+        Err(e) => {
+            println!("⛔ {} on pool {}, skipping the buy", e, pool_info.user_bot_data.pool_id);
+            false
+        }
+    }
+}
+
+/// Whether the bot's `reentry_cooldown_secs` has passed since its last exit
+fn entry_cooldown_elapsed(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+) -> bool {
+    let elapsed = update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        &pool_info.user_bot_data.pool_id,
+        &pool_info.user_bot_data.user_id,
+        |info| info.reentry_cooldown_elapsed(Utc::now().timestamp_millis()),
+    )
+    .unwrap_or(false);
+    if !elapsed {
+        println!(
+            "🧊 User {} exited pool {} within reentry_cooldown_secs {}, skipping the buy",
+            pool_info.user_bot_data.user_id,
+            pool_info.user_bot_data.pool_id,
+            pool_info.user_bot_data.bot_setting.reentry_cooldown_secs
+        );
+    }
+    elapsed
+}
+
+/// Whether the pool's WSOL side, `sol_reserve_lamports`, is deep enough for
+/// the bot to enter a position it can get out of
+fn entry_liquidity_sufficient(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    sol_reserve_lamports: f64,
+) -> bool {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    let sol_reserve = sol_reserve_lamports / 1_000_000_000.0;
+    let sufficient = settings.liquidity_sufficient(sol_reserve);
+    if !sufficient {
+        println!(
+            "🫗 Pool {} holds {:.4} SOL, below min_pool_liquidity_sol {:.4}, skipping the buy",
+            pool_info.user_bot_data.pool_id, sol_reserve, settings.min_pool_liquidity_sol
+        );
+    }
+    sufficient
+}
+
+/// Whether a buy of `amount_in` quote units moves the pool's price within the
+/// bot's `max_price_impact_pct`
+fn entry_price_impact_acceptable(
+    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
+    amount_in: u64,
+    token_reserve: u64,
+    quote_reserve: u64,
+) -> bool {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    let impact = price_impact_pct(amount_in, token_reserve, quote_reserve);
+    let acceptable = settings.price_impact_acceptable(impact);
+    if !acceptable {
+        println!(
+            "🌊 Buy of {} quote units on pool {} would move the price {:.2}%, above max_price_impact_pct {:.2}, skipping it",
+            amount_in, pool_info.user_bot_data.pool_id, impact, settings.max_price_impact_pct
+        );
+    }
+    acceptable
+}
+
+fn slot_time(transaction: &carbon_core::transaction::TransactionMetadata) -> SlotTime {
+    SlotTime {
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+    }
+}
+
+/// Measures how long the entry buy took to land after the swap that signalled it
+fn record_entry_latency(
+    pool_id: &str,
+    user_id: &str,
+    transaction: &carbon_core::transaction::TransactionMetadata,
+) {
+    update_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+        info.record_entry_fill(slot_time(transaction));
+        if let Some(latency) = info.entry_latency {
+            let race = if latency.slow { ", slower than the bot's slow-entry threshold" } else { "" };
+            println!("⏱️ Entry landed {} slots after its signal{}", latency.slots, race);
+        }
+    });
+}
+
+/// One confirmed swap of a position, as its fill is recorded
+struct FillLeg<'a> {
+    transaction: &'a carbon_core::transaction::TransactionMetadata,
+    owner: &'a Pubkey,
+    /// The pool's quote mint, WSOL or USD1, and its decimals
+    quote_mint: Pubkey,
+    quote_decimals: u8,
+    /// What the swap moved through the owner's quote account, negative on a buy
+    quote_flow: i128,
+    is_buy: bool,
+    /// Executed price from the swap's event, when it emitted one
+    fill_price: Option<f64>,
+}
+
+/// Adds one confirmed swap to the user's fund route, logging the full
+/// route once the sell leg lands. Returns the tokens a sell sold.
+async fn record_fund_route_leg(pool_id: &str, user_id: &str, leg: &FillLeg<'_>) -> Option<u64> {
+    let FillLeg { transaction, owner, quote_mint, quote_decimals, quote_flow: quote_delta, is_buy, fill_price } = *leg;
+    // The quote amount the swap paid or received
+    let lamports_delta = quote_delta.abs();
+    let pre_token_balances = transaction.meta.pre_token_balances.as_deref();
+    let post_token_balances = transaction.meta.post_token_balances.as_deref();
+    let Some((mint, token_delta)) = owner_token_delta(
+        pre_token_balances.unwrap_or_default(),
+        post_token_balances.unwrap_or_default(),
+        &owner.to_string(),
+        &quote_mint.to_string(),
+    ) else {
+        println!("No token balance change found for {}, route not updated", owner);
+        return None;
+    };
+    let decimals = post_token_balances
+        .unwrap_or_default()
+        .iter()
+        .find(|balance| balance.mint == mint)
+        .map(|balance| balance.ui_token_amount.decimals);
+
+    update_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+        let slippage = info.signal_price.zip(decimals).and_then(|(price, decimals)| {
+            realized_slippage_pct(price, quote_delta, quote_decimals, token_delta, decimals, is_buy)
+        });
+        if let Some(slippage) = slippage {
+            println!("📐 Realized slippage: {:.3}%", slippage);
+        }
+
+        if is_buy && info.scaled_in() {
+            let price = fill_price.unwrap_or(info.latest_pool_price);
+            info.record_scale_in_fill(lamports_delta, token_delta, price);
+            println!("➕ Scaled in, average bought price now {:?}", info.bought_price);
+        } else if is_buy {
+            info.route.record_buy(mint, lamports_delta, token_delta);
+            info.slippage = RealizedSlippage::default();
+            info.clear_exit_failures();
+            if let Some(slippage) = slippage {
+                info.slippage.record_entry(slippage);
+            }
+            info.held_token_amount = u64::try_from(token_delta).ok();
+            info.bought_token_amount = info.held_token_amount;
+            // The executed price replaces the signal price set at entry
+            if let Some(price) = fill_price {
+                println!("🎯 Entry filled at {:.10}, signal price was {:?}", price, info.bought_price);
+                info.bought_price = Some(price);
+            }
+        } else {
+            info.clear_exit_failures();
+            info.route.record_sell(-token_delta, lamports_delta);
+            if let Some(slippage) = slippage {
+                info.slippage.record_exit(slippage);
             }
+            // A partial take-profit leaves the rest of the position held
+            let sold = u64::try_from(-token_delta).unwrap_or(0);
+            info.held_token_amount = info
+                .held_token_amount
+                .map(|held| held.saturating_sub(sold))
+                .filter(|held| *held > 0);
+            println!("🧾 Fund route: {}", info.route);
+        }
+    });
+    u64::try_from(-token_delta).ok().filter(|_| !is_buy)
+}
+
+/// Adds one confirmed swap's fees to the position's breakdown, starting a
+/// fresh breakdown on the entry buy.
+async fn record_fill_fees(
+    pool_id: &str,
+    user_id: &str,
+    transaction: &carbon_core::transaction::TransactionMetadata,
+    owner: &Pubkey,
+    is_buy: bool,
+) {
+    let event_data = transaction
+        .meta
+        .inner_instructions
+        .iter()
+        .flatten()
+        .flat_map(|inner| inner.instructions.iter())
+        .map(|inner| inner.instruction.data.as_slice());
+
+    let spent = update_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+        let tip_lamports = (info.user_bot_data.bot_setting.third_party_fee
+            * solana_sdk::native_token::LAMPORTS_PER_SOL as f64) as u64;
+        let fees = fill_fee_breakdown(event_data, owner, transaction.meta.fee, tip_lamports);
+        if is_buy && !info.scaled_in() {
+            info.fee_breakdown = fees;
+        } else {
+            info.fee_breakdown.add(&fees);
+        }
+        let lamports = fees.lp_fee_lamports
+            + fees.protocol_fee_lamports
+            + fees.coin_creator_fee_lamports
+            + fees.network_fee_lamports
+            + fees.tip_lamports;
+        (u64::try_from(lamports).unwrap_or(0), info.user_bot_data.bot_setting.fee_budget)
+    });
+
+    if let Some((lamports, persisted)) = spent {
+        let budget = record_fee_spend(
+            &raydium_amm_monitor::statics::FEE_BUDGETS,
+            user_id,
+            persisted,
+            lamports,
+            Utc::now().timestamp_millis(),
+        );
+        tokio::spawn(persist_fee_budget(user_id.to_string(), budget));
+    }
+}
+
+/// The shared `trading` database handle, connecting on the first call
+async fn trading_db() -> Result<&'static mongodb::Database, Box<dyn std::error::Error + Send + Sync>> {
+    raydium_amm_monitor::statics::TRADING_DB
+        .get_or_try_init(|| async {
+            let uri = std::env::var("MONGODB_URI").map_err(|_| "MONGODB_URI not set")?;
+            let client = Client::with_uri_str(uri).await?;
+            Ok(client.database("trading"))
+        })
+        .await
+}
+
+/// Write the user's fee budget counters back to their bot
+async fn persist_fee_budget(user_id: String, budget: FeeBudget) {
+    let persisted = match trading_db().await {
+        Ok(database) => {
+            raydium_amm_monitor::backend::services::bot_service::BotService::new(database.clone())
+                .persist_fee_budget(&user_id, &budget)
+                .await
+                .map_err(|e| e.to_string())
         }
-        _ => Ok(json!({ "result": "error", "message": "unknown confirmation service" })),
+        Err(e) => Err(e.to_string()),
     };
+    if let Err(e) = persisted {
+        println!("⚠️ Failed to persist fee budget of user {}: {}", user_id, e);
+    }
+}
 
-    println!(
-        "Transaction submitting --> : {:#?}\nCurrent time: {:#?}\nPeriod from start: {:?}",
-        results,
-        Utc::now(),
-        start.elapsed()
+/// Price the owner's PumpSwap fill executed at, decoded from its event CPI.
+/// `None` for the other programs' swaps, which keep the reserve-ratio price.
+fn event_fill_price(
+    transaction: &carbon_core::transaction::TransactionMetadata,
+    owner: &Pubkey,
+    wsol_ata: &Pubkey,
+) -> Option<f64> {
+    let event_data = transaction
+        .meta
+        .inner_instructions
+        .iter()
+        .flatten()
+        .flat_map(|inner| inner.instructions.iter())
+        .map(|inner| inner.instruction.data.as_slice());
+    let fill = user_fill(event_data, owner)?;
+
+    let owner = owner.to_string();
+    let wsol = WSOL.to_string();
+    let decimals = transaction
+        .meta
+        .post_token_balances
+        .iter()
+        .flatten()
+        .chain(transaction.meta.pre_token_balances.iter().flatten())
+        .find(|balance| balance.owner == owner && balance.mint != wsol)?
+        .ui_token_amount
+        .decimals;
+    fill.price_sol(decimals, fill.user_quote_token_account == *wsol_ata)
+}
+
+/// Post and pre reserves of a PumpSwap pool's base and quote vaults, shaped
+/// like `get_coin_pc_mint`'s. Read from the swap events when they're enabled
+/// and the transaction emitted one for the pool, from the vault balances
+/// otherwise.
+fn pumpswap_reserves(
+    transaction: &carbon_core::transaction::TransactionMetadata,
+    pool: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    base_vault: Pubkey,
+    quote_vault: Pubkey,
+    account_keys: &[Pubkey],
+) -> VaultBalances {
+    if *PUMPSWAP_EVENT_RESERVES_ENABLED {
+        let event_data = transaction
+            .meta
+            .inner_instructions
+            .iter()
+            .flatten()
+            .flat_map(|inner| inner.instructions.iter())
+            .map(|inner| inner.instruction.data.as_slice());
+        if let Some((before, after)) = event_pool_reserves(event_data, pool) {
+            let side = |amount: u64, mint: &Pubkey| Some((amount.to_string(), mint.to_string()));
+            return (
+                side(after.base, base_mint),
+                side(after.quote, quote_mint),
+                side(before.base, base_mint),
+                side(before.quote, quote_mint),
+            );
+        }
+    }
+    get_coin_pc_mint(
+        transaction.meta.post_token_balances.as_ref().unwrap_or(&vec![]),
+        transaction.meta.pre_token_balances.as_ref().unwrap_or(&vec![]),
+        base_vault,
+        quote_vault,
+        *pool,
+        account_keys,
+    )
+}
+
+/// Records a confirmed fill of the position's submitted swap: the SOL it
+/// moved, its fees and fund route leg, and for sells the profit and ROI.
+/// Both the gRPC stream and the confirmation poller call this, whichever sees
+/// the transaction first records it. Returns whether this call did.
+async fn record_confirmed_fill(
+    pool_id: &str,
+    user_id: &str,
+    transaction: &carbon_core::transaction::TransactionMetadata,
+) -> bool {
+    let signature = transaction.signature.to_string();
+    let Some(public_key) = read_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        pool_id,
+        user_id,
+        |info| info.user_bot_data.public_key.parse::<Pubkey>().ok(),
+    )
+    .flatten() else {
+        return false;
+    };
+
+    let mut account_keys: Vec<Pubkey> = transaction.message.static_account_keys().to_vec();
+    account_keys.extend(&transaction.meta.loaded_addresses.writable);
+    account_keys.extend(&transaction.meta.loaded_addresses.readonly);
+    // What the swap moved through the WSOL or USD1 account. Wrapping, rent
+    // and a balance left in the account aren't part of it
+    let Some((quote_mint, quote_flow, quote_decimals)) = swap_quote_leg(transaction, &account_keys, &public_key) else {
+        return false;
+    };
+    // Profit and its cost basis are kept in lamports, a USD1 pool's fills
+    // only add to its route, fees and held tokens
+    let quote_is_sol = quote_mint == WSOL;
+    // Claimed only once it can be recorded, so a skipped transaction is left
+    // for the other path
+    let claimed = update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        pool_id,
+        user_id,
+        |info| info.claim_fill(&signature),
+    )
+    .unwrap_or(false);
+    if !claimed {
+        return false;
+    }
+
+    let has_bought = read_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        pool_id,
+        user_id,
+        |info| info.confirming_buy(),
+    )
+    .unwrap_or(false);
+    let metadata_fee = transaction.meta.fee;
+    println!("Transaction signature confirmed: {}", signature);
+    println!("IS_BOUGHT STATE: {}", has_bought);
+    println!("Transaction fee: {}", metadata_fee);
+    update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        pool_id,
+        user_id,
+        |info| info.record_network_fee(metadata_fee),
     );
+    let quote_account = get_associated_token_address(&public_key, &quote_mint);
+    let leg = FillLeg {
+        transaction,
+        owner: &public_key,
+        quote_mint,
+        quote_decimals,
+        quote_flow,
+        is_buy: has_bought,
+        fill_price: if has_bought && quote_is_sol {
+            event_fill_price(transaction, &public_key, &quote_account)
+        } else {
+            None
+        },
+    };
 
-    results
+    if has_bought {
+        // Just bought: the quote left its account
+        let input_lamports_delta = -quote_flow;
+        if quote_is_sol {
+            update_position(
+                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                pool_id,
+                user_id,
+                |info| info.record_input_lamports(input_lamports_delta),
+            );
+            println!("Input SOL: {}", input_lamports_delta as f64 / 1_000_000_000.0);
+        }
+        record_fund_route_leg(pool_id, user_id, &leg).await;
+        position_changed(pool_id, user_id);
+        record_fill_fees(pool_id, user_id, transaction, &public_key, true).await;
+        record_entry_latency(pool_id, user_id, transaction);
+    } else {
+        // Just sold: the quote came into its account
+        let output_lamports_delta = quote_flow;
+        let tokens_sold = record_fund_route_leg(pool_id, user_id, &leg).await;
+        record_fill_fees(pool_id, user_id, transaction, &public_key, false).await;
+        if quote_is_sol {
+            println!("Output SOL: {}", output_lamports_delta as f64 / 1_000_000_000.0);
+            let Some((profit_sol, roi)) = update_position(
+                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                pool_id,
+                user_id,
+                |info| info.record_exit_profit(output_lamports_delta, tokens_sold),
+            ) else {
+                return true;
+            };
+            println!("Profit: {}", profit_sol);
+            println!("ROI: {}", roi);
+            tokio::spawn(record_trade_result(user_id.to_string(), profit_sol));
+        }
+        close_round_trip(pool_id, user_id).await;
+    }
+    true
+}
+
+/// Settles a recorded sell. Once it leaves nothing held, closes the
+/// position, saves the round trip's trade record and finishes stopping a
+/// bot whose forced exit it was. The bot stays armed for its next entry
+/// once the cooldown passes.
+async fn close_round_trip(pool_id: &str, user_id: &str) {
+    // Every DEX's exits land here, so the hold time is kept for PumpSwap too
+    save_position_duration(pool_id, user_id);
+    let Some((trade, stopping)) = update_position(
+        &raydium_amm_monitor::statics::REAL_POOL_INFO,
+        pool_id,
+        user_id,
+        |info| info.settle_exit(Utc::now().timestamp_millis()),
+    )
+    .flatten() else {
+        return;
+    };
+    position_changed(pool_id, user_id);
+    tokio::spawn(save_trade_record(trade));
+
+    if stopping {
+        if let Some(pool_info) = position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id) {
+            cleanup_bot_after_stop(&pool_info).await;
+        }
+    }
+}
+
+/// What the transaction's swap moved through the owner's quote account
+/// `quote_account`, see `token_account_flow`
+fn swap_quote_flow(
+    transaction: &carbon_core::transaction::TransactionMetadata,
+    account_keys: &[Pubkey],
+    quote_account: &Pubkey,
+) -> i128 {
+    let inner = transaction
+        .meta
+        .inner_instructions
+        .iter()
+        .flatten()
+        .flat_map(|inner| inner.instructions.iter())
+        .map(|inner| &inner.instruction);
+    token_account_flow(transaction.message.instructions().iter().chain(inner), account_keys, quote_account)
 }
 
-async fn save_trade_metrics(
+/// The swap's flow through the owner's quote account, its WSOL or USD1
+/// account, with that quote mint and its decimals
+fn swap_quote_leg(
+    transaction: &carbon_core::transaction::TransactionMetadata,
+    account_keys: &[Pubkey],
+    owner: &Pubkey,
+) -> Option<(Pubkey, i128, u8)> {
+    [(WSOL, 9), (USD1, 6)].into_iter().find_map(|(mint, decimals)| {
+        let quote_account = get_associated_token_address(owner, &mint);
+        let flow = swap_quote_flow(transaction, account_keys, &quote_account);
+        (flow != 0).then_some((mint, flow, decimals))
+    })
+}
+
+/// Feed a closed trade into the bot's loss circuit breaker
+async fn record_trade_result(
     user_id: String,
     profit_sol: f64,
-    total_fees_lamports_f64: f64,
-    roi_pct: f64,
-    duration_ms: i64,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Get database connection from backend
     let uri = std::env::var("MONGODB_URI").expect("MONGODB_URI not set");
     let options = ClientOptions::parse(uri).await?;
     let client = Client::with_options(options)?;
-    let database = client.database("trading"); // Use same database as backend
-
-    // Create trade service
-    let trade_service =
-        raydium_amm_monitor::backend::services::trade_service::TradeService::new(database);
-
-    // Save trade data using backend service
-    let _trade_response = trade_service
-        .save_trade_data(
-            user_id.clone(),
-            profit_sol,
-            total_fees_lamports_f64 as i64,
-            total_fees_lamports_f64 / 1_000_000_000.0,
-            roi_pct,
-            duration_ms,
-        )
-        .await?;
+    let database = client.database("trading");
+
+    let bot_service =
+        raydium_amm_monitor::backend::services::bot_service::BotService::new(database);
+    bot_service.record_trade_result(&user_id, profit_sol).await?;
 
-    println!("✅ Trade metrics saved for user: {}", user_id);
     Ok(())
 }
+
+/// Save a round trip's trade record
+async fn save_trade_record(trade: raydium_amm_monitor::backend::models::trade::TradeData) {
+    let user_id = trade.user_id.clone();
+    let saved = match trading_db().await {
+        Ok(database) => raydium_amm_monitor::backend::services::trade_service::TradeService::new(database.clone())
+            .record_trade(trade)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match saved {
+        Ok(()) => println!("✅ Trade metrics saved for user: {}", user_id),
+        Err(e) => println!("⚠️ Failed to save trade metrics of user {}: {}", user_id, e),
+    }
+}
 pub struct RaydiumV4Process;
 pub struct RaydiumCpmmProcess;
 pub struct PumpSwapProcess;
@@ -774,9 +1822,12 @@ impl Processor for RaydiumV4Process {
 
             // Spawn each task without waiting for completion
             tokio::spawn(async move {
-                let _ =
+                let user_id = user_bot_data_clone.user_id.clone();
+                let pool_id = user_bot_data_clone.pool_id.clone();
+                let result =
                     Self::process_user_data(metadata_clone, instruction_clone, user_bot_data_clone)
                         .await;
+                report_processor_result(&user_id, &pool_id, result);
             });
         }
 
@@ -808,90 +1859,46 @@ impl RaydiumV4Process {
             Err(_) => return Ok(()),
         };
 
-        if !account_keys.contains(&pool_address) {
-            return Ok(());
-        }
-
-        let initial_pool_info = raydium_amm_monitor::backend::services::bot_service::RealPoolInfo {
-            user_bot_data: user_bot_data.clone(),
-            pool_price: 0.0,
-            latest_pool_price: 0.0,
-            swap_buy_ixs: vec![],
-            is_bought: false,
-            bought_price: None,
-            bought_at: None,
-            initial_wsol_balance: Some(0.0),
-            signature: None,
-            start_time: Some(std::time::Instant::now()),
-            last_profit_sol: None,
-            last_input_lamports_delta: None,
-            last_output_lamports_delta: None,
-            last_roi_pct: None,
-            last_duration: None,
-            fee: 0.01,
-        };
-
-        let mut pool_info = initial_pool_info.clone();
-
-        // Check if pool exists and get existing pool info
-        let pool_exists = {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            real_pool_info.contains_key(pool_id)
-        };
-
-        if !pool_exists {
-            println!("Initial pool info inserted");
-            let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-            real_pool_info.insert(pool_id.to_string(), vec![initial_pool_info.clone()]);
-            drop(real_pool_info);
-        } else {
-            // Check if user already exists in this pool
-            let user_exists = {
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    pool_infos
-                        .iter()
-                        .any(|info| info.user_bot_data.user_id.to_string() == user_id.clone())
-                } else {
-                    false
-                }
-            };
-
-            if !user_exists {
-                println!("Adding user to existing pool");
-                let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                if let Some(pool_infos) = real_pool_info.get_mut(pool_id) {
-                    pool_infos.push(initial_pool_info.clone());
-                }
-                drop(real_pool_info);
-            } else {
-                println!("User already exists in pool, getting existing info");
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    for info in pool_infos {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            println!("pool_id: {}", info.user_bot_data.bot_setting.pool_address);
-                            pool_info = info.clone();
-                            break;
-                        }
-                    }
-                }
-                drop(real_pool_info);
-            }
+        if !account_keys.contains(&pool_address) {
+            return Ok(());
         }
 
-        println!("real_pool_info dropped");
+        let initial_pool_info =
+            raydium_amm_monitor::backend::services::bot_service::RealPoolInfo::new(
+                user_bot_data.clone(),
+            );
+
+        // The user's position, added on the first transaction seen for the pool
+        let pool_info = get_or_insert_position(
+            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+            pool_id,
+            initial_pool_info,
+        );
+
+        if !pool_info.is_bought && !pool_swap_enabled(&pool_address).await {
+            return Ok(());
+        }
 
         let instruction_clone: DecodedInstruction<RaydiumAmmV4Instruction> = instruction.clone();
 
         let _buy_ixs = match &instruction.data {
-            RaydiumAmmV4Instruction::SwapBaseIn(_swap_base_in_data) => {
-                if let Some(mut arranged) =
-                    SwapBaseIn::arrange_accounts(&instruction_clone.accounts)
-                {
+            // Base-out swaps move the pool's reserves just the same and take the
+            // same accounts; our own swap is always built as a base-in one
+            RaydiumAmmV4Instruction::SwapBaseIn(_) | RaydiumAmmV4Instruction::SwapBaseOut(_) => {
+                let arranged = match &instruction.data {
+                    RaydiumAmmV4Instruction::SwapBaseOut(_) => {
+                        SwapBaseOut::arrange_accounts(&instruction_clone.accounts)
+                            .map(|accounts| accounts.to_swap_base_in_accounts())
+                    }
+                    _ => SwapBaseIn::arrange_accounts(&instruction_clone.accounts),
+                };
+                if let Some(mut arranged) = arranged {
                     if arranged.amm.to_string() != pool_id.to_string() {
                         return Ok(());
                     }
+                    if !first_pool_leg(&metadata.transaction_metadata.signature, pool_id, user_id) {
+                        return Ok(());
+                    }
                     let post_token_balance = metadata
                         .transaction_metadata
                         .meta
@@ -966,196 +1973,163 @@ impl RaydiumV4Process {
                         let mint_decimal: u8;
 
                         if input_mint == WSOL {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == output_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
-
-                            let pool_price_sol = if post_output_reserve_val > 0.0 {
-                                (post_input_reserve_val / 10f64.powf(9 as f64))
-                                    / (post_output_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if output reserve is zero
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &output_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
                             };
+
+                            let pool_price_sol = reserve_price(&input_reserve, 9, &output_reserve, mint_decimal);
                             println!("signature : {}", metadata.transaction_metadata.signature);
                             println!("pool_price_sol 1: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(RAY_V4_PROGRAM_ID, pool_price_sol, post_input_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
                         } else {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == input_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
-
-                            let pool_price_sol = if post_input_reserve_val > 0.0 {
-                                (post_output_reserve_val / 10f64.powf(9 as f64))
-                                    / (post_input_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if input reserve is zero
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &input_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
                             };
+
+                            let pool_price_sol = reserve_price(&output_reserve, 9, &input_reserve, mint_decimal);
                             println!("signature : {}", metadata.transaction_metadata.signature);
                             println!("pool_price_sol 2: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(RAY_V4_PROGRAM_ID, pool_price_sol, post_output_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
                         }
 
-                        arranged.user_source_owner = pool_info
-                            .user_bot_data
-                            .public_key
-                            .parse::<Pubkey>()
-                            .clone()
-                            .unwrap();
+                        // Observer bots only track the price, no swap instructions are built
+                        if pool_info.user_bot_data.bot_setting.observer {
+                            return Ok(());
+                        }
+
+                        let Ok(owner) = pool_info.user_bot_data.public_key.parse::<Pubkey>() else {
+                            println!("Invalid public key for user {}, skipping", user_id);
+                            return Ok(());
+                        };
+                        arranged.user_source_owner = owner;
 
                         let mut has_bought = false;
-                        {
-                            let real_pool_info =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                            let pool_info = real_pool_info.get(pool_id).unwrap();
-                            for info in pool_info {
-                                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                    has_bought = info.is_bought;
-                                }
-                            }
+                        let mut dca_level = None;
+                        read_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            pool_id,
+                            user_id,
+                            |info| {
+                                // A pending DCA level buys more while the position is held
+                                has_bought = !info.buying();
+                                dca_level = info.pending_dca_level;
+                            },
+                        );
+
+                        if !has_bought && !entry_cooldown_elapsed(&pool_info) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_mint_allowed(&pool_info, &traded_mint(input_mint, output_mint)) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_token_programs_supported(&pool_info, &full_token_balances, &[input_mint, output_mint]) {
+                            return Ok(());
+                        }
+                        let (sol_reserve, token_reserve) = if input_mint == WSOL {
+                            (post_input_reserve_val, post_output_reserve_val)
+                        } else {
+                            (post_output_reserve_val, post_input_reserve_val)
+                        };
+                        if !has_bought && !entry_liquidity_sufficient(&pool_info, sol_reserve) {
+                            return Ok(());
                         }
 
                         if !has_bought {
                             if input_mint == WSOL {
                                 arranged.user_source_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &input_mint,
                                 );
                                 arranged.user_destination_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &output_mint,
                                     );
                             } else {
                                 arranged.user_source_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &output_mint,
                                 );
                                 arranged.user_destination_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &input_mint,
                                     );
                             }
                         } else {
                             if input_mint == WSOL {
                                 arranged.user_source_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &output_mint,
                                 );
                                 arranged.user_destination_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &input_mint,
                                     );
                             } else {
                                 arranged.user_source_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &input_mint,
                                 );
                                 arranged.user_destination_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &output_mint,
                                     );
                             }
                         }
 
-                        let entry_slippage = pool_info.user_bot_data.bot_setting.entry_slippage;
-                        let exit_slippage = pool_info.user_bot_data.bot_setting.exit_slippage;
+                        let entry_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .entry_slippage_for(&pool_info.user_bot_data.pool_id);
+                        let exit_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .exit_slippage_for(&pool_info.user_bot_data.pool_id);
                         let buy_sol_amount =
-                            pool_info.user_bot_data.bot_setting.buy_sol_amount.clone();
+                            pool_info.user_bot_data.bot_setting.dca_buy_sol_amount(dca_level);
 
                         let amount_in = if !has_bought {
                             (buy_sol_amount * 10_f64.powf(9.0)) as u64
                         } else {
-                            let token_balance = match RPC_CLIENT
-                                .get_token_account_balance_with_commitment(
-                                    &arranged.user_source_token_account,
-                                    CommitmentConfig::processed(),
-                                )
-                                .await
-                            {
-                                Ok(response) => response.value.amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
-                            };
-
-                            let token_amount = match token_balance.parse::<u64>() {
-                                Ok(amount) => amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
+                            let Some(token_amount) = sellable_tokens(&arranged.user_source_token_account).await? else {
+                                return Ok(());
                             };
 
-                            token_amount as u64
+                            // Only sell this position, even if another one shares the mint/ATA
+                            pool_info.sell_amount(token_amount)
                         };
+                        if !has_bought
+                            && !entry_price_impact_acceptable(&pool_info, amount_in, token_reserve as u64, sol_reserve as u64)
+                        {
+                            return Ok(());
+                        }
 
                         let output_reserve_val = match output_reserve.parse::<f64>() {
                             Ok(val) => val,
@@ -1211,12 +2185,7 @@ impl RaydiumV4Process {
                         // Wrap SOL if buying WSOL
                         if !has_bought {
                             let wsol_ix = arranged.get_wrap_sol(
-                                pool_info
-                                    .user_bot_data
-                                    .public_key
-                                    .parse::<Pubkey>()
-                                    .clone()
-                                    .unwrap(),
+                                owner,
                                 buy_exact_in_param.clone(),
                             );
                             ix.extend(wsol_ix);
@@ -1228,26 +2197,17 @@ impl RaydiumV4Process {
                         // Wrap SOL if buying WSOL
                         if !has_bought {
                             let wsol_close = arranged.get_close_wsol(
-                                pool_info
-                                    .user_bot_data
-                                    .public_key
-                                    .parse::<Pubkey>()
-                                    .clone()
-                                    .unwrap(),
+                                owner,
                             );
                             ix.push(wsol_close.clone());
                         }
 
-                        {
-                            let mut real_pool_info =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                            let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                            for info in pool_info {
-                                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                    info.swap_buy_ixs = ix.clone();
-                                }
-                            }
-                        }
+                        update_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            pool_id,
+                            user_id,
+                            |info| info.set_swap_ixs(ix.clone(), SwapAction::from_held(has_bought), dca_level),
+                        );
                     }
                 }
             }
@@ -1257,165 +2217,7 @@ impl RaydiumV4Process {
             }
         };
 
-        let mut sent_signature = None;
-        {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            let pool_info = real_pool_info.get(pool_id).unwrap();
-            for info in pool_info {
-                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                    sent_signature = info.signature.clone();
-                }
-            }
-        }
-        let metadata_signature = metadata.transaction_metadata.signature.to_string();
-        let metadata_fee: u64 = metadata.transaction_metadata.meta.fee;
-        let mut public_key = None;
-        {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            let pool_info = real_pool_info.get(pool_id).unwrap();
-            for info in pool_info {
-                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                    public_key = info.user_bot_data.public_key.parse::<Pubkey>().ok();
-                }
-            }
-        }
-        let wsol_ata = get_associated_token_address(&public_key.unwrap(), &WSOL);
-        let Some(idx) = account_keys.iter().position(|key| key == &wsol_ata) else {
-            return Ok(());
-        };
-        // let wsol_lamports = metadata.transaction_metadata.meta.pre_balances[idx] - metadata.transaction_metadata.meta.post_balances[idx];
-        if let Some(sig) = sent_signature {
-            if sig == metadata_signature {
-                let mut has_bought = false;
-                {
-                    let mut real_pool_info =
-                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                    for info in pool_info {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            has_bought = info.is_bought;
-                        }
-                    }
-                }
-                println!("Transaction signature confirmed: {}", sig);
-                println!("IS_BOUGHT STATE: {}", has_bought);
-                println!("Transaction fee: {}", metadata_fee);
-                {
-                    let mut real_pool_info =
-                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                    for info in pool_info {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            info.fee += metadata_fee as f64;
-                        }
-                    }
-                }
-                // println!("metadata: {:#?}", metadata);
-                // Compute SOL deltas using signed math and convert lamports -> SOL
-                let pre_lamports = metadata
-                    .transaction_metadata
-                    .meta
-                    .pre_balances
-                    .get(idx)
-                    .copied()
-                    .unwrap_or(0) as i128;
-                let post_lamports = metadata
-                    .transaction_metadata
-                    .meta
-                    .post_balances
-                    .get(idx)
-                    .copied()
-                    .unwrap_or(0) as i128;
-
-                // let input_lamports_delta: i128 = 0; // lamports spent (buy)
-                // let output_lamports_delta: i128 = 0; // lamports received (sell)
-
-                if has_bought {
-                    // Just bought: SOL decreased
-                    let input_lamports_delta = pre_lamports - post_lamports;
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_input_lamports_delta = Some(input_lamports_delta);
-                            }
-                        }
-                    }
-                    let input_sol = input_lamports_delta as f64 / 1_000_000_000.0;
-                    println!("Input SOL: {}", input_sol);
-                } else {
-                    // Just sold: SOL increased
-                    let output_lamports_delta = post_lamports - pre_lamports;
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_output_lamports_delta = Some(output_lamports_delta);
-                            }
-                        }
-                    }
-                    let output_sol = output_lamports_delta as f64 / 1_000_000_000.0;
-                    println!("Output SOL: {}", output_sol);
-                    let mut last_output_lamports_delta = None;
-                    {
-                        let real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                        let pool_info = real_pool_info.get(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                last_output_lamports_delta = info.last_output_lamports_delta;
-                            }
-                        }
-                    }
-                    let profit_sol =
-                        (output_lamports_delta - last_output_lamports_delta.unwrap_or(0)) as f64
-                            / 1_000_000_000.0;
-                    println!("Profit: {}", profit_sol);
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_profit_sol = Some(profit_sol);
-                            }
-                        }
-                    }
-                    let mut last_input_lamports: Option<i128> = None;
-                    {
-                        let real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                        let pool_info = real_pool_info.get(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                last_input_lamports = info.last_input_lamports_delta;
-                            }
-                        }
-                    }
-                    let input_sol = last_input_lamports.unwrap_or(0) as f64 / 1_000_000_000.0;
-                    let roi = if input_sol > 0.0 {
-                        (profit_sol / input_sol) * 100.0
-                    } else {
-                        0.0
-                    };
-                    println!("ROI: {}", roi);
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_roi_pct = Some(roi);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        record_confirmed_fill(pool_id, user_id, &metadata.transaction_metadata).await;
         Ok(())
     }
 }
@@ -1464,9 +2266,12 @@ impl Processor for RaydiumCpmmProcess {
 
             // Spawn each task without waiting for completion
             tokio::spawn(async move {
-                let _ =
+                let user_id = user_bot_data_clone.user_id.clone();
+                let pool_id = user_bot_data_clone.pool_id.clone();
+                let result =
                     Self::process_user_data(metadata_clone, instruction_clone, user_bot_data_clone)
                         .await;
+                report_processor_result(&user_id, &pool_id, result);
             });
         }
 
@@ -1502,76 +2307,22 @@ impl RaydiumCpmmProcess {
             return Ok(());
         }
 
-        let initial_pool_info = raydium_amm_monitor::backend::services::bot_service::RealPoolInfo {
-            user_bot_data: user_bot_data.clone(),
-            pool_price: 0.0,
-            latest_pool_price: 0.0,
-            swap_buy_ixs: vec![],
-            is_bought: false,
-            bought_price: None,
-            bought_at: None,
-            initial_wsol_balance: Some(0.0),
-            signature: None,
-            start_time: Some(std::time::Instant::now()),
-            last_profit_sol: None,
-            last_input_lamports_delta: None,
-            last_output_lamports_delta: None,
-            last_roi_pct: None,
-            last_duration: None,
-            fee: 0.01,
-        };
-
-        let mut pool_info = initial_pool_info.clone();
-
-        // Check if pool exists and get existing pool info
-        let pool_exists = {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            real_pool_info.contains_key(pool_id)
-        };
+        let initial_pool_info =
+            raydium_amm_monitor::backend::services::bot_service::RealPoolInfo::new(
+                user_bot_data.clone(),
+            );
 
-        if !pool_exists {
-            println!("Initial pool info inserted");
-            let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-            real_pool_info.insert(pool_id.to_string(), vec![initial_pool_info.clone()]);
-            drop(real_pool_info);
-        } else {
-            // Check if user already exists in this pool
-            let user_exists = {
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    pool_infos
-                        .iter()
-                        .any(|info| info.user_bot_data.user_id.to_string() == user_id.clone())
-                } else {
-                    false
-                }
-            };
+        // The user's position, added on the first transaction seen for the pool
+        let pool_info = get_or_insert_position(
+            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+            pool_id,
+            initial_pool_info,
+        );
 
-            if !user_exists {
-                println!("Adding user to existing pool");
-                let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                if let Some(pool_infos) = real_pool_info.get_mut(pool_id) {
-                    pool_infos.push(initial_pool_info.clone());
-                }
-                drop(real_pool_info);
-            } else {
-                println!("User already exists in pool, getting existing info");
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    for info in pool_infos {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            println!("pool_id: {}", info.user_bot_data.bot_setting.pool_address);
-                            pool_info = info.clone();
-                            break;
-                        }
-                    }
-                }
-                drop(real_pool_info);
-            }
+        if !pool_info.is_bought && !pool_swap_enabled(&pool_address).await {
+            return Ok(());
         }
 
-        println!("real_pool_info dropped");
-
         let instruction_clone: DecodedInstruction<RaydiumCpmmInstruction> = instruction.clone();
 
         let _buy_ixs = match &instruction.data {
@@ -1582,6 +2333,9 @@ impl RaydiumCpmmProcess {
                     if arranged.pool_state.to_string() != pool_id.to_string() {
                         return Ok(());
                     }
+                    if !first_pool_leg(&metadata.transaction_metadata.signature, pool_id, user_id) {
+                        return Ok(());
+                    }
                     let post_token_balance = metadata
                         .transaction_metadata
                         .meta
@@ -1606,8 +2360,8 @@ impl RaydiumCpmmProcess {
                         get_coin_pc_mint(
                             post_token_balance.as_ref().unwrap_or(&vec![]),
                             pre_token_balances.as_ref().unwrap_or(&vec![]),
-                            arranged.input_token_account,
-                            arranged.output_token_account,
+                            arranged.input_vault,
+                            arranged.output_vault,
                             arranged.authority,
                             &account_keys,
                         );
@@ -1656,195 +2410,149 @@ impl RaydiumCpmmProcess {
                         let mint_decimal: u8;
 
                         if input_mint == USD1 {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == output_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
-
-                            let pool_price_sol = if post_output_reserve_val > 0.0 {
-                                (post_input_reserve_val / 10f64.powf(6 as f64))
-                                    / (post_output_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if output reserve is zero
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &output_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
                             };
+
+                            let pool_price_sol = reserve_price(&input_reserve, 6, &output_reserve, mint_decimal);
                             println!("signature : {}", metadata.transaction_metadata.signature);
                             println!("pool_price_sol 1: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(CPMM_PROGRAM_ID, pool_price_sol, post_input_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
                         } else {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == input_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
-
-                            let pool_price_sol = if post_input_reserve_val > 0.0 {
-                                (post_output_reserve_val / 10f64.powf(6 as f64))
-                                    / (post_input_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if input reserve is zero
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &input_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
                             };
+
+                            let pool_price_sol = reserve_price(&output_reserve, 6, &input_reserve, mint_decimal);
                             println!("signature : {}", metadata.transaction_metadata.signature);
                             println!("pool_price_sol 2: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(CPMM_PROGRAM_ID, pool_price_sol, post_output_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
                         }
 
-                        arranged.payer = pool_info
-                            .user_bot_data
-                            .public_key
-                            .parse::<Pubkey>()
-                            .clone()
-                            .unwrap();
+                        // Observer bots only track the price, no swap instructions are built
+                        if pool_info.user_bot_data.bot_setting.observer {
+                            return Ok(());
+                        }
+
+                        let Ok(owner) = pool_info.user_bot_data.public_key.parse::<Pubkey>() else {
+                            println!("Invalid public key for user {}, skipping", user_id);
+                            return Ok(());
+                        };
+                        arranged.payer = owner;
 
                         let mut has_bought = false;
-                        {
-                            let real_pool_info =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                            let pool_info = real_pool_info.get(pool_id).unwrap();
-                            for info in pool_info {
-                                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                    has_bought = info.is_bought;
-                                }
-                            }
+                        let mut dca_level = None;
+                        read_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            pool_id,
+                            user_id,
+                            |info| {
+                                // A pending DCA level buys more while the position is held
+                                has_bought = !info.buying();
+                                dca_level = info.pending_dca_level;
+                            },
+                        );
+
+                        if !has_bought && !entry_cooldown_elapsed(&pool_info) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_mint_allowed(&pool_info, &traded_mint(input_mint, output_mint)) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_token_programs_supported(&pool_info, &full_token_balances, &[input_mint, output_mint]) {
+                            return Ok(());
                         }
 
                         if !has_bought {
                             if input_mint == USD1 {
                                 arranged.input_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &input_mint,
                                 );
                                 arranged.output_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &output_mint,
                                     );
                             } else {
                                 arranged.input_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &output_mint,
                                 );
                                 arranged.output_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &input_mint,
                                     );
                             }
                         } else {
                             if input_mint == USD1 {
                                 arranged.input_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &output_mint,
                                 );
                                 arranged.output_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &input_mint,
                                     );
                             } else {
                                 arranged.input_token_account = get_associated_token_address(
-                                    &pool_info
-                                        .user_bot_data
-                                        .public_key
-                                        .parse::<Pubkey>()
-                                        .clone()
-                                        .unwrap(),
+                                    &owner,
                                     &input_mint,
                                 );
                                 arranged.output_token_account =
                                     get_associated_token_address(
-                                        &pool_info
-                                            .user_bot_data
-                                            .public_key
-                                            .parse::<Pubkey>()
-                                            .clone()
-                                            .unwrap(),
+                                        &owner,
                                         &output_mint,
                                     );
                             }
                         }
 
-                        let entry_slippage = pool_info.user_bot_data.bot_setting.entry_slippage;
-                        let exit_slippage = pool_info.user_bot_data.bot_setting.exit_slippage;
+                        let entry_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .entry_slippage_for(&pool_info.user_bot_data.pool_id);
+                        let exit_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .exit_slippage_for(&pool_info.user_bot_data.pool_id);
                         let buy_sol_amount =
-                            pool_info.user_bot_data.bot_setting.buy_sol_amount.clone();
+                            pool_info.user_bot_data.bot_setting.dca_buy_sol_amount(dca_level);
 
                         let amount_in = if !has_bought {
                             (buy_sol_amount * 10_f64.powf(6.0)) as u64
                         } else {
-                            let token_balance = match RPC_CLIENT
-                                .get_token_account_balance_with_commitment(
-                                    &arranged.input_token_account,
-                                    CommitmentConfig::processed(),
-                                )
-                                .await
-                            {
-                                Ok(response) => response.value.amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
-                            };
-
-                            let token_amount = match token_balance.parse::<u64>() {
-                                Ok(amount) => amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
+                            let Some(token_amount) = sellable_tokens(&arranged.input_token_account).await? else {
+                                return Ok(());
                             };
 
-                            token_amount as u64
+                            // Only sell this position, even if another one shares the mint/ATA
+                            pool_info.sell_amount(token_amount)
                         };
 
                         let output_reserve_val = match output_reserve.parse::<f64>() {
@@ -1864,6 +2572,16 @@ impl RaydiumCpmmProcess {
                                 return Ok(());
                             }
                         };
+                        if !has_bought
+                            && !entry_price_impact_acceptable(
+                                &pool_info,
+                                amount_in,
+                                output_reserve_val as u64,
+                                input_reserve_val as u64,
+                            )
+                        {
+                            return Ok(());
+                        }
 
                         // Calculate amount_out by entry_slippage/exit slippage when buying
                         // Add safety check to prevent division by zero
@@ -1883,202 +2601,40 @@ impl RaydiumCpmmProcess {
                             }
                         } else {
                             println!("Warning: Invalid reserve values for amount_out calculation");
-                            return Ok(());
-                        };
-
-                        let swap_base_input_param = SwapBaseInput {
-                            amount_in,
-                            minimum_amount_out: amount_out as u64,
-                        };
-
-                        let mut ix: Vec<Instruction> = vec![];
-
-                        let create_ata_ix =
-                            arranged.get_create_idempotent_ata_ix(input_mint, output_mint);
-
-                        ix.extend(create_ata_ix);
-
-                        let swap_ix = arranged.get_swap_base_input_ix(swap_base_input_param);
-                        ix.push(swap_ix);
-
-                        {
-                            let mut real_pool_info =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                            let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                            for info in pool_info {
-                                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                    info.swap_buy_ixs = ix.clone();
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            _ => {
-                println!("");
-            }
-        };
-
-        let mut sent_signature = None;
-        {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            let pool_info = real_pool_info.get(pool_id).unwrap();
-            for info in pool_info {
-                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                    sent_signature = info.signature.clone();
-                }
-            }
-        }
-        let metadata_signature = metadata.transaction_metadata.signature.to_string();
-        let metadata_fee: u64 = metadata.transaction_metadata.meta.fee;
-        let mut public_key = None;
-        {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            let pool_info = real_pool_info.get(pool_id).unwrap();
-            for info in pool_info {
-                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                    public_key = info.user_bot_data.public_key.parse::<Pubkey>().ok();
-                }
-            }
-        }
-        let wsol_ata = get_associated_token_address(&public_key.unwrap(), &WSOL);
-        let Some(idx) = account_keys.iter().position(|key| key == &wsol_ata) else {
-            return Ok(());
-        };
-        // let wsol_lamports = metadata.transaction_metadata.meta.pre_balances[idx] - metadata.transaction_metadata.meta.post_balances[idx];
-        if let Some(sig) = sent_signature {
-            if sig == metadata_signature {
-                let mut has_bought = false;
-                {
-                    let mut real_pool_info =
-                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                    for info in pool_info {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            has_bought = info.is_bought;
-                        }
-                    }
-                }
-                println!("Transaction signature confirmed: {}", sig);
-                println!("IS_BOUGHT STATE: {}", has_bought);
-                println!("Transaction fee: {}", metadata_fee);
-                {
-                    let mut real_pool_info =
-                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                    for info in pool_info {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            info.fee += metadata_fee as f64;
-                        }
-                    }
-                }
-                // println!("metadata: {:#?}", metadata);
-                // Compute SOL deltas using signed math and convert lamports -> SOL
-                let pre_lamports = metadata
-                    .transaction_metadata
-                    .meta
-                    .pre_balances
-                    .get(idx)
-                    .copied()
-                    .unwrap_or(0) as i128;
-                let post_lamports = metadata
-                    .transaction_metadata
-                    .meta
-                    .post_balances
-                    .get(idx)
-                    .copied()
-                    .unwrap_or(0) as i128;
-
-                // let input_lamports_delta: i128 = 0; // lamports spent (buy)
-                // let output_lamports_delta: i128 = 0; // lamports received (sell)
-
-                if has_bought {
-                    // Just bought: SOL decreased
-                    let input_lamports_delta = pre_lamports - post_lamports;
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_input_lamports_delta = Some(input_lamports_delta);
-                            }
-                        }
-                    }
-                    let input_sol = input_lamports_delta as f64 / 1_000_000.0;
-                    println!("Input SOL: {}", input_sol);
-                } else {
-                    // Just sold: SOL increased
-                    let output_lamports_delta = post_lamports - pre_lamports;
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_output_lamports_delta = Some(output_lamports_delta);
-                            }
-                        }
-                    }
-                    let output_sol = output_lamports_delta as f64 / 1_000_000.0;
-                    println!("Output SOL: {}", output_sol);
-                    let mut last_output_lamports_delta = None;
-                    {
-                        let real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                        let pool_info = real_pool_info.get(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                last_output_lamports_delta = info.last_output_lamports_delta;
-                            }
-                        }
-                    }
-                    let profit_sol =
-                        (output_lamports_delta - last_output_lamports_delta.unwrap_or(0)) as f64
-                            / 1_000_000.0;
-                    println!("Profit: {}", profit_sol);
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_profit_sol = Some(profit_sol);
-                            }
-                        }
-                    }
-                    let mut last_input_lamports: Option<i128> = None;
-                    {
-                        let real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                        let pool_info = real_pool_info.get(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                last_input_lamports = info.last_input_lamports_delta;
-                            }
-                        }
-                    }
-                    let input_sol = last_input_lamports.unwrap_or(0) as f64 / 1_000_000.0;
-                    let roi = if input_sol > 0.0 {
-                        (profit_sol / input_sol) * 100.0
-                    } else {
-                        0.0
-                    };
-                    println!("ROI: {}", roi);
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_roi_pct = Some(roi);
-                            }
-                        }
+                            return Ok(());
+                        };
+
+                        let swap_base_input_param = SwapBaseInput {
+                            amount_in,
+                            minimum_amount_out: amount_out as u64,
+                        };
+
+                        let mut ix: Vec<Instruction> = vec![];
+
+                        let create_ata_ix =
+                            arranged.get_create_idempotent_ata_ix(input_mint, output_mint);
+
+                        ix.extend(create_ata_ix);
+
+                        let swap_ix = arranged.get_swap_base_input_ix(swap_base_input_param);
+                        ix.push(swap_ix);
+
+                        update_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            pool_id,
+                            user_id,
+                            |info| info.set_swap_ixs(ix.clone(), SwapAction::from_held(has_bought), dca_level),
+                        );
                     }
                 }
             }
-        }
+
+            _ => {
+                println!("");
+            }
+        };
+
+        record_confirmed_fill(pool_id, user_id, &metadata.transaction_metadata).await;
         Ok(())
     }
 }
@@ -2125,9 +2681,12 @@ impl Processor for RaydiumLaunchpadProcess {
             let user_bot_data_clone = user_bot_data.clone();
 
             tokio::spawn(async move {
-                let _ =
+                let user_id = user_bot_data_clone.user_id.clone();
+                let pool_id = user_bot_data_clone.pool_id.clone();
+                let result =
                     Self::process_user_data(metadata_clone, instruction_clone, user_bot_data_clone)
                         .await;
+                report_processor_result(&user_id, &pool_id, result);
             });
         }
         Ok(())
@@ -2162,76 +2721,22 @@ impl RaydiumLaunchpadProcess {
             return Ok(());
         }
 
-        let initial_pool_info = raydium_amm_monitor::backend::services::bot_service::RealPoolInfo {
-            user_bot_data: user_bot_data.clone(),
-            pool_price: 0.0,
-            latest_pool_price: 0.0,
-            swap_buy_ixs: vec![],
-            is_bought: false,
-            bought_price: None,
-            bought_at: None,
-            initial_wsol_balance: Some(0.0),
-            signature: None,
-            start_time: Some(std::time::Instant::now()),
-            last_profit_sol: None,
-            last_input_lamports_delta: None,
-            last_output_lamports_delta: None,
-            last_roi_pct: None,
-            last_duration: None,
-            fee: 0.01,
-        };
-
-        let mut pool_info = initial_pool_info.clone();
-
-        // Check if pool exists and get existing pool info
-        let pool_exists = {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            real_pool_info.contains_key(pool_id)
-        };
+        let initial_pool_info =
+            raydium_amm_monitor::backend::services::bot_service::RealPoolInfo::new(
+                user_bot_data.clone(),
+            );
 
-        if !pool_exists {
-            println!("Initial pool info inserted");
-            let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-            real_pool_info.insert(pool_id.to_string(), vec![initial_pool_info.clone()]);
-            drop(real_pool_info);
-        } else {
-            // Check if user already exists in this pool
-            let user_exists = {
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    pool_infos
-                        .iter()
-                        .any(|info| info.user_bot_data.user_id.to_string() == user_id.clone())
-                } else {
-                    false
-                }
-            };
+        // The user's position, added on the first transaction seen for the pool
+        let pool_info = get_or_insert_position(
+            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+            pool_id,
+            initial_pool_info,
+        );
 
-            if !user_exists {
-                println!("Adding user to existing pool");
-                let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                if let Some(pool_infos) = real_pool_info.get_mut(pool_id) {
-                    pool_infos.push(initial_pool_info.clone());
-                }
-                drop(real_pool_info);
-            } else {
-                println!("User already exists in pool, getting existing info");
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    for info in pool_infos {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            println!("pool_id: {}", info.user_bot_data.bot_setting.pool_address);
-                            pool_info = info.clone();
-                            break;
-                        }
-                    }
-                }
-                drop(real_pool_info);
-            }
+        if !pool_info.is_bought && !pool_swap_enabled(&pool_address).await {
+            return Ok(());
         }
 
-        println!("real_pool_info dropped");
-
         let instruction_clone: DecodedInstruction<RaydiumLaunchpadInstruction> =
             instruction.clone();
 
@@ -2243,6 +2748,9 @@ impl RaydiumLaunchpadProcess {
                     if arranged.pool_state.to_string() != pool_id.to_string() {
                         return Ok(());
                     }
+                    if !first_pool_leg(&metadata.transaction_metadata.signature, pool_id, user_id) {
+                        return Ok(());
+                    }
                     println!("launchpad arranged BuyExactIn");
                     let post_token_balance = metadata
                         .transaction_metadata
@@ -2306,16 +2814,6 @@ impl RaydiumLaunchpadProcess {
                         let output_mint = Pubkey::from_str_const(&output_mint);
 
                         if input_mint == USD1 {
-                            let post_output_reserve_val = match output_reserve.parse::<f64>() {
-                                Ok(val) => val,
-                                Err(_) => {
-                                    println!(
-                                        "Warning: Invalid output_reserve value: {}",
-                                        output_reserve
-                                    );
-                                    return Ok(());
-                                }
-                            };
                             let post_input_reserve_val = match input_reserve.parse::<f64>() {
                                 Ok(val) => val,
                                 Err(_) => {
@@ -2327,130 +2825,141 @@ impl RaydiumLaunchpadProcess {
                                 }
                             };
 
-                            let mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == output_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
-
-                            let pool_price_sol = if post_output_reserve_val > 0.0 {
-                                (post_input_reserve_val / 10f64.powf(6 as f64))
-                                    / (post_output_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if output reserve is zero
+                            let mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &output_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
                             };
+
+                            let pool_price_sol = reserve_price(&input_reserve, 6, &output_reserve, mint_decimal);
                             println!("signature : {}", metadata.transaction_metadata.signature);
                             println!("pool_price_sol launchpad: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(LAUNCHPAD_PROGRAM_ID, pool_price_sol, post_input_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
+
+                            // Observer bots only track the price, no swap instructions are built
+                            if pool_info.user_bot_data.bot_setting.observer {
+                                return Ok(());
                             }
 
-                            arranged.payer = pool_info
-                                .user_bot_data
-                                .public_key
-                                .parse::<Pubkey>()
-                                .unwrap();
+                            let Ok(owner) = pool_info.user_bot_data.public_key.parse::<Pubkey>() else {
+                                println!("Invalid public key for user {}, skipping", user_id);
+                                return Ok(());
+                            };
+                            arranged.payer = owner;
 
                             let mut has_bought = false;
-                            {
-                                let real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                                let pool_info = real_pool_info.get(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        has_bought = info.is_bought;
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| {
+                                    has_bought = info.is_bought;
+                                    if info.pending_dca_level.take().is_some() {
+                                        tracing::warn!("DCA scale-in is not supported on USD1 launchpad pools, skipping level");
                                     }
-                                }
+                                },
+                            );
+
+                            if !has_bought && !entry_cooldown_elapsed(&pool_info) {
+                                return Ok(());
+                            }
+                            if !has_bought && !entry_mint_allowed(&pool_info, &traded_mint(input_mint, output_mint)) {
+                                return Ok(());
+                            }
+                            if !has_bought && !entry_token_programs_supported(&pool_info, &full_token_balances, &[input_mint, output_mint]) {
+                                return Ok(());
                             }
 
                             arranged.user_base_token = get_associated_token_address(
-                                &pool_info
-                                    .user_bot_data
-                                    .public_key
-                                    .parse::<Pubkey>()
-                                    .unwrap(),
+                                &owner,
                                 &input_mint,
                             );
                             arranged.user_quote_token = get_associated_token_address(
-                                &pool_info
-                                    .user_bot_data
-                                    .public_key
-                                    .parse::<Pubkey>()
-                                    .unwrap(),
+                                &owner,
                                 &input_mint,
                             );
 
-                            let entry_slippage = pool_info.user_bot_data.bot_setting.entry_slippage;
-                            let exit_slippage = pool_info.user_bot_data.bot_setting.exit_slippage;
+                            let entry_slippage = pool_info
+                                .user_bot_data
+                                .bot_setting
+                                .entry_slippage_for(&pool_info.user_bot_data.pool_id);
+                            let exit_slippage = pool_info
+                                .user_bot_data
+                                .bot_setting
+                                .exit_slippage_for(&pool_info.user_bot_data.pool_id);
                             let buy_usd1_amount =
                                 pool_info.user_bot_data.bot_setting.buy_usd1_amount.clone();
 
                             let amount_in = if !has_bought {
                                 (buy_usd1_amount * 10_f64.powf(6.0)) as u64
                             } else {
-                                let token_balance = match RPC_CLIENT
-                                    .get_token_account_balance_with_commitment(
-                                        &arranged.user_quote_token,
-                                        CommitmentConfig::processed(),
-                                    )
-                                    .await
-                                {
-                                    Ok(response) => response.value.amount,
-                                    Err(_) => {
-                                        return Ok(());
-                                    }
+                                // The position's tokens are the swap's output mint, not the USD1 it pays with
+                                let token_account = get_associated_token_address(&owner, &output_mint);
+                                let Some(token_amount) = sellable_tokens(&token_account).await? else {
+                                    return Ok(());
                                 };
 
-                                let token_amount = match token_balance.parse::<u64>() {
-                                    Ok(amount) => amount,
-                                    Err(_) => {
-                                        return Ok(());
-                                    }
-                                };
+                                // Only sell this position, even if another one shares the mint/ATA
+                                pool_info.sell_amount(token_amount)
+                            };
+                            // The curve's reserves, not the vault balances, set the price the swap fills at
+                            let event_data = metadata
+                                .transaction_metadata
+                                .meta
+                                .inner_instructions
+                                .iter()
+                                .flatten()
+                                .flat_map(|inner| inner.instructions.iter())
+                                .map(|inner| inner.instruction.data.as_slice());
+                            let Some(curve) = launchpad_curve_reserves(event_data, &pool_address) else {
+                                println!("No trade event for launchpad pool {}, skipping", pool_id);
+                                return Ok(());
+                            };
+                            if !has_bought && !entry_price_impact_acceptable(&pool_info, amount_in, curve.base, curve.quote) {
+                                return Ok(());
+                            }
 
-                                token_amount as u64
+                            let minimum_amount_out = if has_bought {
+                                launchpad_min_out(amount_in, curve, false, exit_slippage)
+                            } else {
+                                launchpad_min_out(amount_in, curve, true, entry_slippage)
                             };
+                            let share_fee_rate = 0;
+
+                            let mut instructions = vec![];
 
-                             let minimum_amount_out = 0;
-                             let share_fee_rate = 0;
-
-                             let mut instructions = vec![];
-                             
-                             if has_bought {
-                                 let sell_ix = arranged.get_sell_ix(SellExactIn {
-                                     amount_in: amount_in,
-                                     minimum_amount_out: minimum_amount_out,
-                                     share_fee_rate: share_fee_rate,
-                                 });
-                                 instructions.push(sell_ix);
-                             } else {
-                                 let buy_ix = arranged.get_buy_ix(BuyExactIn {
-                                     amount_in: amount_in,
-                                     minimum_amount_out: minimum_amount_out,
-                                     share_fee_rate: share_fee_rate,
-                                 });
-                                 instructions.push(buy_ix);
-                             }
-
-                             {
-                                 let mut real_pool_info =
-                                     raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                 let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                 for info in pool_info {
-                                     if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                         info.swap_buy_ixs = instructions.clone();
-                                     }
-                                 }
-                             }
+                            if has_bought {
+                                let sell_ix = arranged.get_sell_ix(SellExactIn {
+                                    amount_in: amount_in,
+                                    minimum_amount_out: minimum_amount_out,
+                                    share_fee_rate: share_fee_rate,
+                                });
+                                instructions.push(sell_ix);
+                            } else {
+                                let buy_ix = arranged.get_buy_ix(BuyExactIn {
+                                    amount_in: amount_in,
+                                    minimum_amount_out: minimum_amount_out,
+                                    share_fee_rate: share_fee_rate,
+                                });
+                                instructions.push(buy_ix);
+                            }
+
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), None),
+                            );
                         }
                     }
                 } else {
@@ -2463,6 +2972,9 @@ impl RaydiumLaunchpadProcess {
                     if arranged.pool_state.to_string() != pool_id.to_string() {
                         return Ok(());
                     }
+                    if !first_pool_leg(&metadata.transaction_metadata.signature, pool_id, user_id) {
+                        return Ok(());
+                    }
                     println!("launchpad arranged SellExactIn");
                     let post_token_balance = metadata
                         .transaction_metadata
@@ -2533,112 +3045,120 @@ impl RaydiumLaunchpadProcess {
                                     return Ok(());
                                 }
                             };
-                            let post_input_reserve_val = match input_reserve.parse::<f64>() {
-                                Ok(val) => val,
-                                Err(_) => {
-                                    println!("Warning: Invalid input_reserve value: {}", input_reserve);
-                                    return Ok(());
-                                }
-                            };
 
-                            let mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == output_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
-
-                            let pool_price_sol = if post_input_reserve_val > 0.0 {
-                                (post_output_reserve_val / 10f64.powf(6 as f64))
-                                    / (post_input_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if output reserve is zero
+                            // The token is the input side here, USD1 is priced against it
+                            let mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &input_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
                             };
+
+                            let pool_price_sol = reserve_price(&output_reserve, 6, &input_reserve, mint_decimal);
                             println!("signature : {}", metadata.transaction_metadata.signature);
                             println!("pool_price_sol launchpad: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(LAUNCHPAD_PROGRAM_ID, pool_price_sol, post_output_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
+
+                            // Observer bots only track the price, no swap instructions are built
+                            if pool_info.user_bot_data.bot_setting.observer {
+                                return Ok(());
                             }
 
-                            arranged.payer = pool_info
-                                .user_bot_data
-                                .public_key
-                                .parse::<Pubkey>()
-                                .unwrap();
+                            let Ok(owner) = pool_info.user_bot_data.public_key.parse::<Pubkey>() else {
+                                println!("Invalid public key for user {}, skipping", user_id);
+                                return Ok(());
+                            };
+                            arranged.payer = owner;
 
                             arranged.user_base_token = get_associated_token_address(
-                                &pool_info
-                                    .user_bot_data
-                                    .public_key
-                                    .parse::<Pubkey>()
-                                    .unwrap(),
+                                &owner,
                                 &input_mint,
                             );
                             arranged.user_quote_token = get_associated_token_address(
-                                &pool_info
-                                    .user_bot_data
-                                    .public_key
-                                    .parse::<Pubkey>()
-                                    .unwrap(),
+                                &owner,
                                 &input_mint,
                             );
 
                             let mut has_bought = false;
-                            {
-                                let real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                                let pool_info = real_pool_info.get(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        has_bought = info.is_bought;
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| {
+                                    has_bought = info.is_bought;
+                                    if info.pending_dca_level.take().is_some() {
+                                        tracing::warn!("DCA scale-in is not supported on USD1 launchpad pools, skipping level");
                                     }
-                                }
+                                },
+                            );
+
+                            if !has_bought && !entry_cooldown_elapsed(&pool_info) {
+                                return Ok(());
+                            }
+                            if !has_bought && !entry_mint_allowed(&pool_info, &traded_mint(input_mint, output_mint)) {
+                                return Ok(());
+                            }
+                            if !has_bought && !entry_token_programs_supported(&pool_info, &full_token_balances, &[input_mint, output_mint]) {
+                                return Ok(());
                             }
 
-                            let entry_slippage = pool_info.user_bot_data.bot_setting.entry_slippage;
-                            let exit_slippage = pool_info.user_bot_data.bot_setting.exit_slippage;
+                            let entry_slippage = pool_info
+                                .user_bot_data
+                                .bot_setting
+                                .entry_slippage_for(&pool_info.user_bot_data.pool_id);
+                            let exit_slippage = pool_info
+                                .user_bot_data
+                                .bot_setting
+                                .exit_slippage_for(&pool_info.user_bot_data.pool_id);
                             let buy_usd1_amount =
                                 pool_info.user_bot_data.bot_setting.buy_usd1_amount.clone();
 
                             let amount_in = if !has_bought {
                                 (buy_usd1_amount * 10_f64.powf(6.0)) as u64
                             } else {
-                                let token_balance = match RPC_CLIENT
-                                    .get_token_account_balance_with_commitment(
-                                        &arranged.user_base_token,
-                                        CommitmentConfig::processed(),
-                                    )
-                                    .await
-                                {
-                                    Ok(response) => response.value.amount,
-                                    Err(_) => {
-                                        return Ok(());
-                                    }
-                                };
-
-                                let token_amount = match token_balance.parse::<u64>() {
-                                    Ok(amount) => amount,
-                                    Err(_) => {
-                                        return Ok(());
-                                    }
+                                let Some(token_amount) = sellable_tokens(&arranged.user_base_token).await? else {
+                                    return Ok(());
                                 };
 
-                                token_amount as u64
+                                // Only sell this position, even if another one shares the mint/ATA
+                                pool_info.sell_amount(token_amount)
                             };
+                            // The curve's reserves, not the vault balances, set the price the swap fills at
+                            let event_data = metadata
+                                .transaction_metadata
+                                .meta
+                                .inner_instructions
+                                .iter()
+                                .flatten()
+                                .flat_map(|inner| inner.instructions.iter())
+                                .map(|inner| inner.instruction.data.as_slice());
+                            let Some(curve) = launchpad_curve_reserves(event_data, &pool_address) else {
+                                println!("No trade event for launchpad pool {}, skipping", pool_id);
+                                return Ok(());
+                            };
+                            if !has_bought && !entry_price_impact_acceptable(&pool_info, amount_in, curve.base, curve.quote) {
+                                return Ok(());
+                            }
 
-                            let minimum_amount_out = 0;
+                            let minimum_amount_out = if has_bought {
+                                launchpad_min_out(amount_in, curve, false, exit_slippage)
+                            } else {
+                                launchpad_min_out(amount_in, curve, true, entry_slippage)
+                            };
                             let share_fee_rate = 0;
 
                             let mut instructions = vec![];
 
-                            if !has_bought {
+                            if has_bought {
                                 let sell_ix = arranged.get_sell_ix(SellExactIn {
                                     amount_in: amount_in,
                                     minimum_amount_out: minimum_amount_out,
@@ -2654,15 +3174,12 @@ impl RaydiumLaunchpadProcess {
                                 instructions.push(buy_ix);
                             }
 
-                            {
-                                let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.swap_buy_ixs = instructions.clone();
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), None),
+                            );
                         }
                     }
                 } else {
@@ -2676,6 +3193,7 @@ impl RaydiumLaunchpadProcess {
             }
         };
 
+        record_confirmed_fill(pool_id, user_id, &metadata.transaction_metadata).await;
         Ok(())
     }
 }
@@ -2689,51 +3207,47 @@ impl Processor for PumpSwapProcess {
         (metadata, instruction, _nested_instructions, _instructions): Self::InputType,
         _metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
+        // Routed swaps are left to the processors of the programs they route through
+        let static_account_keys = metadata.transaction_metadata.message.static_account_keys();
+        let writable_account_keys = &metadata.transaction_metadata.meta.loaded_addresses.writable;
+        let readonly_account_keys = &metadata.transaction_metadata.meta.loaded_addresses.readonly;
+        let routed = static_account_keys
+            .iter()
+            .chain(writable_account_keys)
+            .chain(readonly_account_keys)
+            .any(|key| {
+                *key == RAY_V4_PROGRAM_ID
+                    || *key == Pubkey::from_str_const("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4")
+                    || *key == Pubkey::from_str_const("6m2CDdhRgxpH4WjvdzxAYbGxwdGUz5MziiL5jek2kBma")
+            });
+        if routed {
+            return Ok(());
+        }
+
         let user_list = raydium_amm_monitor::statics::USER_LIST.read().await;
-        let user_list_clone = user_list.clone();
+        // Only users whose pool this instruction swaps get a task
+        let users: Vec<_> = users_for_instruction(&user_list, &instruction.accounts)
+            .into_iter()
+            .cloned()
+            .collect();
         drop(user_list); // Release the read lock immediately
 
-        // Process all users concurrently without blocking
-        for user_bot_data in user_list_clone.iter() {
-            // Check if this user's pool_id matches the current transaction
-            let pool_address = match user_bot_data.pool_id.parse::<Pubkey>() {
-                Ok(pubkey) => pubkey,
-                Err(_) => continue, // Skip invalid pool addresses
-            };
-
-            // Check if the pool is involved in this transaction
-            let static_account_keys = metadata.transaction_metadata.message.static_account_keys();
-            let writable_account_keys =
-                &metadata.transaction_metadata.meta.loaded_addresses.writable;
-            let readonly_account_keys =
-                &metadata.transaction_metadata.meta.loaded_addresses.readonly;
-
-            let mut account_keys: Vec<Pubkey> = vec![];
-            account_keys.extend(static_account_keys);
-            account_keys.extend(writable_account_keys);
-            account_keys.extend(readonly_account_keys);
-
-            if !account_keys.contains(&pool_address)
-                || account_keys.contains(&RAY_V4_PROGRAM_ID)
-                || account_keys.contains(&Pubkey::from_str_const(
-                    "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4",
-                ))
-                || account_keys.contains(&Pubkey::from_str_const(
-                    "6m2CDdhRgxpH4WjvdzxAYbGxwdGUz5MziiL5jek2kBma",
-                ))
-            {
-                continue; // Skip users whose pool is not involved in this transaction
-            }
-
+        // Process the matching users concurrently without blocking, batched
+        // into at most MAX_TASKS_PER_TRANSACTION tasks
+        for batch in task_batches(users, *MAX_TASKS_PER_TRANSACTION) {
             let metadata_clone: carbon_core::instruction::InstructionMetadata = metadata.clone();
             let instruction_clone = instruction.clone();
-            let user_bot_data_clone = user_bot_data.clone();
 
             // Spawn each task without waiting for completion
             tokio::spawn(async move {
-                let _ =
-                    Self::process_user_data(metadata_clone, instruction_clone, user_bot_data_clone)
-                        .await;
+                for user_bot_data in batch {
+                    let user_id = user_bot_data.user_id.clone();
+                    let pool_id = user_bot_data.pool_id.clone();
+                    let result =
+                        Self::process_user_data(metadata_clone.clone(), instruction_clone.clone(), user_bot_data)
+                            .await;
+                    report_processor_result(&user_id, &pool_id, result);
+                }
             });
         }
 
@@ -2759,75 +3273,17 @@ impl PumpSwapProcess {
         let pool_id = &user_bot_data.pool_id;
         let user_id = &user_bot_data.user_id.to_string();
 
-        let initial_pool_info = raydium_amm_monitor::backend::services::bot_service::RealPoolInfo {
-            user_bot_data: user_bot_data.clone(),
-            pool_price: 0.0,
-            latest_pool_price: 0.0,
-            swap_buy_ixs: vec![],
-            is_bought: false,
-            bought_price: None,
-            bought_at: None,
-            initial_wsol_balance: Some(0.0),
-            signature: None,
-            start_time: Some(std::time::Instant::now()),
-            last_profit_sol: None,
-            last_input_lamports_delta: None,
-            last_output_lamports_delta: None,
-            last_roi_pct: None,
-            last_duration: None,
-            fee: 0.01,
-        };
-
-        let mut pool_info = initial_pool_info.clone();
-
-        // Check if pool exists and get existing pool info
-        let pool_exists = {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            real_pool_info.contains_key(pool_id)
-        };
-
-        if !pool_exists {
-            println!("Initial pool info inserted");
-            let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-            real_pool_info.insert(pool_id.to_string(), vec![initial_pool_info.clone()]);
-            drop(real_pool_info);
-        } else {
-            // Check if user already exists in this pool
-            let user_exists = {
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    pool_infos
-                        .iter()
-                        .any(|info| info.user_bot_data.user_id.to_string() == user_id.clone())
-                } else {
-                    false
-                }
-            };
-
-            if !user_exists {
-                println!("Adding user to existing pool");
-                let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                if let Some(pool_infos) = real_pool_info.get_mut(pool_id) {
-                    pool_infos.push(initial_pool_info.clone());
-                }
-                drop(real_pool_info);
-            } else {
-                println!("User already exists in pool, getting existing info");
-                let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                if let Some(pool_infos) = real_pool_info.get(pool_id) {
-                    for info in pool_infos {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            println!("pool_id: {}", info.user_bot_data.bot_setting.pool_address);
-                            pool_info = info.clone();
-                            break;
-                        }
-                    }
-                }
-                drop(real_pool_info);
-            }
-        }
+        let initial_pool_info =
+            raydium_amm_monitor::backend::services::bot_service::RealPoolInfo::new(
+                user_bot_data.clone(),
+            );
 
-        println!("real_pool_info dropped");
+        // The user's position, added on the first transaction seen for the pool
+        let pool_info = get_or_insert_position(
+            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+            pool_id,
+            initial_pool_info,
+        );
 
         let instruction_clone: DecodedInstruction<PumpSwapInstruction> = instruction.clone();
         println!("signature: {:?}", metadata.transaction_metadata.signature);
@@ -2838,6 +3294,9 @@ impl PumpSwapProcess {
                     if arranged.pool.to_string() != pool_id.to_string() {
                         return Ok(());
                     }
+                    if !first_pool_leg(&metadata.transaction_metadata.signature, pool_id, user_id) {
+                        return Ok(());
+                    }
                     println!("pumpswap arranged Buy");
                     let post_token_balance = metadata
                         .transaction_metadata
@@ -2857,12 +3316,13 @@ impl PumpSwapProcess {
                         .collect();
 
                     let (base_raw_info, quote_raw_info, pre_base_raw_info, pre_quote_raw_info) =
-                        get_coin_pc_mint(
-                            post_token_balance.as_ref().unwrap_or(&vec![]),
-                            pre_token_balance.as_ref().unwrap_or(&vec![]),
+                        pumpswap_reserves(
+                            &metadata.transaction_metadata,
+                            &arranged.pool,
+                            &arranged.base_mint,
+                            &arranged.quote_mint,
                             arranged.pool_base_token_account,
                             arranged.pool_quote_token_account,
-                            arranged.pool,
                             &account_keys,
                         );
 
@@ -2963,160 +3423,148 @@ impl PumpSwapProcess {
                         };
 
                         if input_mint == WSOL {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == output_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &output_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
+                            };
 
                             // Calculate pool price
-                            let pool_price_sol = if post_output_reserve_val > 0.0 {
-                                (post_input_reserve_val / 10f64.powf(9 as f64))
-                                    / (post_output_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if output reserve is zero
-                            };
+                            let pool_price_sol = reserve_price(&input_reserve, 9, &output_reserve, mint_decimal);
 
                             println!("pool_price_sol pump buy 1: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(PUMPSWAP_PROGRAM_ID, pool_price_sol, post_input_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
                         } else {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == input_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
-
-                            let pool_price_sol = if post_input_reserve_val > 0.0 {
-                                (post_output_reserve_val / 10f64.powf(9 as f64))
-                                    / (post_input_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if input reserve is zero
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &input_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
                             };
 
+                            let pool_price_sol = reserve_price(&output_reserve, 9, &input_reserve, mint_decimal);
+
                             println!("pool_price_sol pump buy 2: {:?}", pool_price_sol);
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(PUMPSWAP_PROGRAM_ID, pool_price_sol, post_output_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
+                        }
+
+                        // Observer bots only track the price, no swap instructions are built
+                        if pool_info.user_bot_data.bot_setting.observer {
+                            return Ok(());
                         }
 
-                        arranged.user = pool_info
-                            .user_bot_data
-                            .public_key
-                            .parse::<Pubkey>()
-                            .unwrap();
+                        let Ok(owner) = pool_info.user_bot_data.public_key.parse::<Pubkey>() else {
+                            println!("Invalid public key for user {}, skipping", user_id);
+                            return Ok(());
+                        };
+                        arranged.user = owner;
 
                         arranged.user_base_token_account = get_associated_token_address(
-                            &pool_info
-                                .user_bot_data
-                                .public_key
-                                .parse::<Pubkey>()
-                                .unwrap(),
+                            &owner,
                             &arranged.base_mint,
                         );
                         arranged.user_quote_token_account = get_associated_token_address(
-                            &pool_info
-                                .user_bot_data
-                                .public_key
-                                .parse::<Pubkey>()
-                                .unwrap(),
+                            &owner,
                             &arranged.quote_mint,
                         );
 
                         let mut has_bought = false;
-                        {
-                            let real_pool_info =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                            let pool_info = real_pool_info.get(pool_id).unwrap();
-                            for info in pool_info {
-                                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                    has_bought = info.is_bought;
-                                }
-                            }
+                        let mut dca_level = None;
+                        read_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            pool_id,
+                            user_id,
+                            |info| {
+                                // A pending DCA level buys more while the position is held
+                                has_bought = !info.buying();
+                                dca_level = info.pending_dca_level;
+                            },
+                        );
+
+                        if !has_bought && !entry_cooldown_elapsed(&pool_info) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_mint_allowed(&pool_info, &traded_mint(arranged.base_mint, arranged.quote_mint)) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_token_programs_supported(&pool_info, &full_token_balances, &[arranged.base_mint, arranged.quote_mint]) {
+                            return Ok(());
                         }
 
-                        let entry_slippage = pool_info.user_bot_data.bot_setting.entry_slippage;
-                        let exit_slippage = pool_info.user_bot_data.bot_setting.exit_slippage;
+                        let entry_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .entry_slippage_for(&pool_info.user_bot_data.pool_id);
+                        let exit_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .exit_slippage_for(&pool_info.user_bot_data.pool_id);
                         let buy_sol_amount =
-                            pool_info.user_bot_data.bot_setting.buy_sol_amount.clone();
+                            pool_info.user_bot_data.bot_setting.dca_buy_sol_amount(dca_level);
 
                         let amount_in = if !has_bought {
                             (buy_sol_amount * 10_f64.powf(9.0)) as u64
                         } else {
-                            let token_balance = match RPC_CLIENT
-                                .get_token_account_balance_with_commitment(
-                                    &arranged.user_base_token_account,
-                                    CommitmentConfig::processed(),
-                                )
-                                .await
-                            {
-                                Ok(response) => response.value.amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
-                            };
-
-                            let token_amount = match token_balance.parse::<u64>() {
-                                Ok(amount) => amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
+                            let Some(token_amount) = sellable_tokens(&arranged.user_base_token_account).await? else {
+                                return Ok(());
                             };
 
-                            token_amount
+                            // Only sell this position, even if another one shares the mint/ATA
+                            pool_info.sell_amount(token_amount)
                         };
 
-                        let pool_quote_token_reserves = match RPC_CLIENT
-                            .get_token_account_balance_with_commitment(
-                                &arranged.pool_quote_token_account,
-                                CommitmentConfig::processed(),
-                            )
-                            .await
-                        {
-                            Ok(response) => response.value.amount,
-                            Err(_) => {
+                        let Some(reserves) = pool_reserves(
+                            &arranged.pool,
+                            &arranged.pool_base_token_account,
+                            &arranged.pool_quote_token_account,
+                        )
+                        .await
+                        else {
+                            return Ok(());
+                        };
+                        let pool_quote_token_reserves = reserves.quote;
+                        let pool_base_token_reserves = reserves.base;
+                        if !has_bought {
+                            // Entries are sized in SOL, a pool without a WSOL side can't be checked or bought
+                            let Some((sol_reserve, token_reserve)) =
+                                reserves.sol_and_token(&arranged.base_mint, &arranged.quote_mint)
+                            else {
+                                println!("Pool {} has no WSOL side, skipping the buy", pool_id);
+                                return Ok(());
+                            };
+                            if !entry_liquidity_sufficient(&pool_info, sol_reserve as f64) {
                                 return Ok(());
                             }
-                        };
-
-                        let pool_base_token_reserves = match RPC_CLIENT
-                            .get_token_account_balance_with_commitment(
-                                &arranged.pool_base_token_account,
-                                CommitmentConfig::processed(),
-                            )
-                            .await
-                        {
-                            Ok(response) => response.value.amount,
-                            Err(_) => {
+                            if !entry_price_impact_acceptable(&pool_info, amount_in, token_reserve, sol_reserve) {
                                 return Ok(());
                             }
-                        };
+                        }
 
                         if !has_bought {
                             if arranged.quote_mint == WSOL && arranged.base_mint != WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
+                                    pool_quote_token_reserves,
+                                    pool_base_token_reserves,
                                     true,
                                 );
 
@@ -3160,22 +3608,17 @@ impl PumpSwapProcess {
 
                                 instructions.push(buy_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             } else if arranged.quote_mint != WSOL && arranged.base_mint == WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
+                                    pool_base_token_reserves,
+                                    pool_quote_token_reserves,
                                     true,
                                 );
                                 println!("required_token_amount: {:?}", required_token_amount);
@@ -3221,24 +3664,19 @@ impl PumpSwapProcess {
 
                                 instructions.push(buy_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             }
                         } else {
                             if arranged.quote_mint == WSOL && arranged.base_mint != WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
+                                    pool_base_token_reserves,
+                                    pool_quote_token_reserves,
                                     true,
                                 );
 
@@ -3274,22 +3712,17 @@ impl PumpSwapProcess {
                                 let close_wsol_ix = arranged.get_close_wsol();
                                 instructions.push(close_wsol_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             } else if arranged.quote_mint != WSOL && arranged.base_mint == WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
+                                    pool_quote_token_reserves,
+                                    pool_base_token_reserves,
                                     true,
                                 );
 
@@ -3325,17 +3758,12 @@ impl PumpSwapProcess {
                                 let close_wsol_ix = arranged.get_close_wsol();
                                 instructions.push(close_wsol_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             }
                         }
                     } else {
@@ -3349,6 +3777,9 @@ impl PumpSwapProcess {
                     if arranged.pool.to_string() != pool_id.to_string() {
                         return Ok(());
                     }
+                    if !first_pool_leg(&metadata.transaction_metadata.signature, pool_id, user_id) {
+                        return Ok(());
+                    }
                     println!("pumpswap arranged Sell");
                     let post_token_balance = metadata
                         .transaction_metadata
@@ -3368,12 +3799,13 @@ impl PumpSwapProcess {
                         .collect();
 
                     let (base_raw_info, quote_raw_info, pre_base_raw_info, pre_quote_raw_info) =
-                        get_coin_pc_mint(
-                            post_token_balance.as_ref().unwrap_or(&vec![]),
-                            pre_token_balance.as_ref().unwrap_or(&vec![]),
+                        pumpswap_reserves(
+                            &metadata.transaction_metadata,
+                            &arranged.pool,
+                            &arranged.base_mint,
+                            &arranged.quote_mint,
                             arranged.pool_base_token_account,
                             arranged.pool_quote_token_account,
-                            arranged.pool,
                             &account_keys,
                         );
 
@@ -3477,23 +3909,21 @@ impl PumpSwapProcess {
                         let output_change = post_output_reserve_val - pre_output_reserve_val;
 
                         if input_mint == WSOL {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == output_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &output_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
+                            };
 
                             let base_mint_amount = input_change as f64 / 10f64.powf(9 as f64);
                             let sell_amount =
                                 output_change as f64 / 10f64.powf(mint_decimal as f64);
 
-                            let pool_price_sol = if post_output_reserve_val > 0.0 {
-                                (post_input_reserve_val / 10f64.powf(9 as f64))
-                                    / (post_output_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if output reserve is zero
-                            };
+                            let pool_price_sol = reserve_price(&input_reserve, 9, &output_reserve, mint_decimal);
 
                             println!("pool_price_sol pump sell 2: {:?}", pool_price_sol);
 
@@ -3502,34 +3932,28 @@ impl PumpSwapProcess {
                                 base_mint_amount, sell_amount
                             );
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(PUMPSWAP_PROGRAM_ID, pool_price_sol, post_input_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
                         } else {
-                            mint_decimal = full_token_balances
-                                .iter()
-                                .flat_map(|balances| balances.iter())
-                                .find(|balance| balance.mint == input_mint.to_string())
-                                .and_then(|balance| Some(balance.ui_token_amount.decimals))
-                                .unwrap_or(6);
+                            mint_decimal = match token_decimals(
+                                full_token_balances.iter().flat_map(|balances| balances.iter()),
+                                &input_mint,
+                            )
+                            .await
+                            {
+                                Some(decimals) => decimals,
+                                None => return Ok(()),
+                            };
 
                             let base_mint_amount =
                                 input_change as f64 / 10f64.powf(mint_decimal as f64);
                             let sell_amount = output_change as f64 / 10f64.powf(9 as f64);
 
-                            let pool_price_sol = if post_input_reserve_val > 0.0 {
-                                (post_output_reserve_val / 10f64.powf(9 as f64))
-                                    / (post_input_reserve_val / 10f64.powf(mint_decimal as f64))
-                            } else {
-                                0.0 // Default to 0 if input reserve is zero
-                            };
+                            let pool_price_sol = reserve_price(&output_reserve, 9, &input_reserve, mint_decimal);
 
                             println!("pool_price_sol pump sell 1: {:?}", pool_price_sol);
 
@@ -3538,116 +3962,112 @@ impl PumpSwapProcess {
                                 base_mint_amount, sell_amount
                             );
 
-                            {
-                                let mut real_pool_info =
-                                    raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                for info in pool_info {
-                                    if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                        info.latest_pool_price = pool_price_sol;
-                                    }
-                                }
-                            }
+                            update_position(
+                                &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                pool_id,
+                                user_id,
+                                |info| info.record_swap(PUMPSWAP_PROGRAM_ID, pool_price_sol, post_output_reserve_val, slot_time(&metadata.transaction_metadata)),
+                            );
                         }
 
-                        arranged.user = pool_info
-                            .user_bot_data
-                            .public_key
-                            .parse::<Pubkey>()
-                            .unwrap();
+                        // Observer bots only track the price, no swap instructions are built
+                        if pool_info.user_bot_data.bot_setting.observer {
+                            return Ok(());
+                        }
+
+                        let Ok(owner) = pool_info.user_bot_data.public_key.parse::<Pubkey>() else {
+                            println!("Invalid public key for user {}, skipping", user_id);
+                            return Ok(());
+                        };
+                        arranged.user = owner;
 
                         arranged.user_base_token_account = get_associated_token_address(
-                            &pool_info
-                                .user_bot_data
-                                .public_key
-                                .parse::<Pubkey>()
-                                .unwrap(),
+                            &owner,
                             &arranged.base_mint,
                         );
                         arranged.user_quote_token_account = get_associated_token_address(
-                            &pool_info
-                                .user_bot_data
-                                .public_key
-                                .parse::<Pubkey>()
-                                .unwrap(),
+                            &owner,
                             &arranged.quote_mint,
                         );
 
                         let mut has_bought = false;
-                        {
-                            let real_pool_info =
-                                raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                            let pool_info = real_pool_info.get(pool_id).unwrap();
-                            for info in pool_info {
-                                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                    has_bought = info.is_bought;
-                                }
-                            }
+                        let mut dca_level = None;
+                        read_position(
+                            &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                            pool_id,
+                            user_id,
+                            |info| {
+                                // A pending DCA level buys more while the position is held
+                                has_bought = !info.buying();
+                                dca_level = info.pending_dca_level;
+                            },
+                        );
+
+                        if !has_bought && !entry_cooldown_elapsed(&pool_info) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_mint_allowed(&pool_info, &traded_mint(arranged.base_mint, arranged.quote_mint)) {
+                            return Ok(());
+                        }
+                        if !has_bought && !entry_token_programs_supported(&pool_info, &full_token_balances, &[arranged.base_mint, arranged.quote_mint]) {
+                            return Ok(());
                         }
 
-                        let entry_slippage = pool_info.user_bot_data.bot_setting.entry_slippage;
-                        let exit_slippage = pool_info.user_bot_data.bot_setting.exit_slippage;
+                        let entry_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .entry_slippage_for(&pool_info.user_bot_data.pool_id);
+                        let exit_slippage = pool_info
+                            .user_bot_data
+                            .bot_setting
+                            .exit_slippage_for(&pool_info.user_bot_data.pool_id);
                         let buy_sol_amount =
-                            pool_info.user_bot_data.bot_setting.buy_sol_amount.clone();
+                            pool_info.user_bot_data.bot_setting.dca_buy_sol_amount(dca_level);
 
                         let amount_in = if !has_bought {
                             (buy_sol_amount * 10_f64.powf(9.0)) as u64
                         } else {
-                            let token_balance = match RPC_CLIENT
-                                .get_token_account_balance_with_commitment(
-                                    &arranged.user_base_token_account,
-                                    CommitmentConfig::processed(),
-                                )
-                                .await
-                            {
-                                Ok(response) => response.value.amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
-                            };
-
-                            let token_amount = match token_balance.parse::<u64>() {
-                                Ok(amount) => amount,
-                                Err(_) => {
-                                    return Ok(());
-                                }
+                            let Some(token_amount) = sellable_tokens(&arranged.user_base_token_account).await? else {
+                                return Ok(());
                             };
 
-                            token_amount
+                            // Only sell this position, even if another one shares the mint/ATA
+                            pool_info.sell_amount(token_amount)
                         };
 
-                        let pool_quote_token_reserves = match RPC_CLIENT
-                            .get_token_account_balance_with_commitment(
-                                &arranged.pool_quote_token_account,
-                                CommitmentConfig::processed(),
-                            )
-                            .await
-                        {
-                            Ok(response) => response.value.amount,
-                            Err(_) => {
+                        let Some(reserves) = pool_reserves(
+                            &arranged.pool,
+                            &arranged.pool_base_token_account,
+                            &arranged.pool_quote_token_account,
+                        )
+                        .await
+                        else {
+                            return Ok(());
+                        };
+                        let pool_quote_token_reserves = reserves.quote;
+                        let pool_base_token_reserves = reserves.base;
+                        if !has_bought {
+                            // Entries are sized in SOL, a pool without a WSOL side can't be checked or bought
+                            let Some((sol_reserve, token_reserve)) =
+                                reserves.sol_and_token(&arranged.base_mint, &arranged.quote_mint)
+                            else {
+                                println!("Pool {} has no WSOL side, skipping the buy", pool_id);
+                                return Ok(());
+                            };
+                            if !entry_liquidity_sufficient(&pool_info, sol_reserve as f64) {
                                 return Ok(());
                             }
-                        };
-
-                        let pool_base_token_reserves = match RPC_CLIENT
-                            .get_token_account_balance_with_commitment(
-                                &arranged.pool_base_token_account,
-                                CommitmentConfig::processed(),
-                            )
-                            .await
-                        {
-                            Ok(response) => response.value.amount,
-                            Err(_) => {
+                            if !entry_price_impact_acceptable(&pool_info, amount_in, token_reserve, sol_reserve) {
                                 return Ok(());
                             }
-                        };
+                        }
 
                         if !has_bought {
                             if arranged.quote_mint == WSOL && arranged.base_mint != WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
+                                    pool_quote_token_reserves,
+                                    pool_base_token_reserves,
                                     true,
                                 );
 
@@ -3691,22 +4111,17 @@ impl PumpSwapProcess {
 
                                 instructions.push(buy_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             } else if arranged.quote_mint != WSOL && arranged.base_mint == WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
+                                    pool_base_token_reserves,
+                                    pool_quote_token_reserves,
                                     true,
                                 );
 
@@ -3751,24 +4166,19 @@ impl PumpSwapProcess {
 
                                 instructions.push(buy_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             }
                         } else {
                             if arranged.quote_mint == WSOL && arranged.base_mint != WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
+                                    pool_base_token_reserves,
+                                    pool_quote_token_reserves,
                                     true,
                                 );
 
@@ -3804,22 +4214,17 @@ impl PumpSwapProcess {
                                 let close_wsol_ix = arranged.get_close_wsol();
                                 instructions.push(close_wsol_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             } else if arranged.quote_mint != WSOL && arranged.base_mint == WSOL {
                                 let required_token_amount = sol_token_quote(
                                     amount_in,
-                                    pool_quote_token_reserves.parse::<u64>().unwrap(),
-                                    pool_base_token_reserves.parse::<u64>().unwrap(),
+                                    pool_quote_token_reserves,
+                                    pool_base_token_reserves,
                                     true,
                                 );
 
@@ -3855,17 +4260,12 @@ impl PumpSwapProcess {
                                 let close_wsol_ix = arranged.get_close_wsol();
                                 instructions.push(close_wsol_ix);
 
-                                {
-                                    let mut real_pool_info =
-                                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                                    for info in pool_info {
-                                        if info.user_bot_data.user_id.to_string() == user_id.clone()
-                                        {
-                                            info.swap_buy_ixs = instructions.clone();
-                                        }
-                                    }
-                                }
+                                update_position(
+                                    &raydium_amm_monitor::statics::REAL_POOL_INFO,
+                                    pool_id,
+                                    user_id,
+                                    |info| info.set_swap_ixs(instructions.clone(), SwapAction::from_held(has_bought), dca_level),
+                                );
                             }
                         }
                     }
@@ -3879,179 +4279,31 @@ impl PumpSwapProcess {
             }
         };
 
-        let mut sent_signature = None;
-        {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            let pool_info = real_pool_info.get(pool_id).unwrap();
-            for info in pool_info {
-                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                    sent_signature = info.signature.clone();
-                }
-            }
-        }
-        let metadata_signature = metadata.transaction_metadata.signature.to_string();
-        let metadata_fee: u64 = metadata.transaction_metadata.meta.fee;
-        let mut public_key = None;
-        {
-            let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-            let pool_info = real_pool_info.get(pool_id).unwrap();
-            for info in pool_info {
-                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                    public_key = info.user_bot_data.public_key.parse::<Pubkey>().ok();
-                }
-            }
-        }
-        let wsol_ata = get_associated_token_address(&public_key.unwrap(), &WSOL);
-        let Some(idx) = account_keys.iter().position(|key| key == &wsol_ata) else {
-            return Ok(());
-        };
-
-        if let Some(sig) = sent_signature {
-            if sig == metadata_signature {
-                let mut has_bought = false;
-                {
-                    let mut real_pool_info =
-                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                    for info in pool_info {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            has_bought = info.is_bought;
-                        }
-                    }
-                }
-                println!("Transaction signature confirmed: {}", sig);
-                println!("IS_BOUGHT STATE: {}", has_bought);
-                println!("Transaction fee: {}", metadata_fee);
-                {
-                    let mut real_pool_info =
-                        raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                    let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                    for info in pool_info {
-                        if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                            info.fee += metadata_fee as f64;
-                        }
-                    }
-                }
-                // println!("metadata: {:#?}", metadata);
-                // Compute SOL deltas using signed math and convert lamports -> SOL
-                let pre_lamports = metadata
-                    .transaction_metadata
-                    .meta
-                    .pre_balances
-                    .get(idx)
-                    .copied()
-                    .unwrap_or(0) as i128;
-                let post_lamports = metadata
-                    .transaction_metadata
-                    .meta
-                    .post_balances
-                    .get(idx)
-                    .copied()
-                    .unwrap_or(0) as i128;
-
-                // let input_lamports_delta: i128 = 0; // lamports spent (buy)
-                // let output_lamports_delta: i128 = 0; // lamports received (sell)
-
-                if has_bought {
-                    // Just bought: SOL decreased
-                    let input_lamports_delta = pre_lamports - post_lamports;
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_input_lamports_delta = Some(input_lamports_delta);
-                            }
-                        }
-                    }
-                    let input_sol = input_lamports_delta as f64 / 1_000_000_000.0;
-                    println!("Input SOL: {}", input_sol);
-                } else {
-                    // Just sold: SOL increased
-                    let output_lamports_delta = post_lamports - pre_lamports;
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_output_lamports_delta = Some(output_lamports_delta);
-                            }
-                        }
-                    }
-                    let output_sol = output_lamports_delta as f64 / 1_000_000_000.0;
-                    println!("Output SOL: {}", output_sol);
-                    let mut last_input_lamports_delta = None;
-                    {
-                        let real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-                        let pool_info = real_pool_info.get(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                last_input_lamports_delta = info.last_input_lamports_delta;
-                            }
-                        }
-                    }
-                    let profit_sol =
-                        (output_lamports_delta - last_input_lamports_delta.unwrap_or(0)) as f64
-                            / 1_000_000_000.0;
-                    println!("Profit: {}", profit_sol);
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_profit_sol = Some(profit_sol);
-                            }
-                        }
-                    }
-                    let input_sol = last_input_lamports_delta.unwrap_or(0) as f64 / 1_000_000_000.0;
-                    let roi = if input_sol > 0.0 {
-                        (profit_sol / input_sol) * 100.0
-                    } else {
-                        0.0
-                    };
-                    println!("ROI: {}", roi);
-                    {
-                        let mut real_pool_info =
-                            raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-                        let pool_info = real_pool_info.get_mut(pool_id).unwrap();
-                        for info in pool_info {
-                            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                                info.last_roi_pct = Some(roi);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        record_confirmed_fill(pool_id, user_id, &metadata.transaction_metadata).await;
         Ok(())
     }
 }
 
-/// Clean up bot state after sell
-async fn set_bot_after_sell(
-    pool_info: &raydium_amm_monitor::backend::services::bot_service::RealPoolInfo,
-) {
-    println!(
-        "🧹 Setting bot state after sell for user: {}",
-        pool_info.user_bot_data.user_id
-    );
-    
-    let pool_id = pool_info.user_bot_data.pool_id.clone();
-    let user_id = pool_info.user_bot_data.user_id.clone();
-
-    {
-        let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-        let pool_info = real_pool_info.get_mut(&pool_id).unwrap();
-        for info in pool_info {
-            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                info.is_bought = false;
-            }
-        }
-        drop(real_pool_info);
+/// Save how long the position was held since `start_time`
+fn save_position_duration(pool_id: &str, user_id: &str) {
+    let start_time: Option<std::time::Instant> =
+        read_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+            info.start_time
+        })
+        .flatten();
+    if let Some(start_time) = start_time {
+        let end_time = std::time::Instant::now();
+        println!("End time: {:?}", end_time);
+        let duration = end_time.duration_since(start_time);
+        println!("Time taken: {:?}", duration);
+
+        // Save duration to static variable
+        update_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+            info.last_duration = Some(duration);
+            println!("✅ Saved duration to REAL_POOL_INFO: {:?}", duration);
+        });
+    } else {
+        println!("No start time found");
     }
 }
 
@@ -4064,105 +4316,140 @@ async fn cleanup_bot_after_stop(
         pool_info.user_bot_data.user_id
     );
 
-    let pool_id = pool_info.user_bot_data.pool_id.clone();
-    let user_id = pool_info.user_bot_data.user_id.clone();
-
-    let mut start_time: Option<std::time::Instant> = None;
-    {
-        let real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.read().await;
-        let pool_info = real_pool_info.get(&pool_id).unwrap();
-        for info in pool_info {
-            if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                start_time = info.start_time;
-            }
-        }
-    }
-    if let Some(start_time) = start_time {
-        let end_time = std::time::Instant::now();
-        println!("End time: {:?}", end_time);
-        let duration = end_time.duration_since(start_time);
-        println!("Time taken: {:?}", duration);
-
-        // Save duration to static variable
-        {
-            let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-            let pool_info = real_pool_info.get_mut(&pool_id).unwrap();
-            for info in pool_info {
-                if info.user_bot_data.user_id.to_string() == user_id.clone() {
-                    info.last_duration = Some(duration);
-                    println!("✅ Saved duration to REAL_POOL_INFO: {:?}", duration);
-                }
-            }
-            drop(real_pool_info);
-        }
-    } else {
-        println!("No start time found");
+    // Trade metrics are saved per round trip as each closing sell is recorded
+    if pool_info.user_bot_data.bot_setting.observer {
+        println!("👀 OBSERVER: skipping trade metrics, no trade was made");
     }
 
-    // Save metrics to MongoDB (best-effort)
-    let profit_sol = pool_info.last_profit_sol.unwrap_or(0.0);
-    let total_fees = pool_info.fee;
-    let roi_pct = pool_info.last_roi_pct.unwrap_or(0.0);
-    let duration_ms = pool_info
-        .last_duration
-        .as_ref()
-        .map(|d| d.as_millis() as i64)
-        .unwrap_or(0);
+    // Off USER_LIST and out of REAL_POOL_INFO, serialized with start_bot and stop_bot
+    raydium_amm_monitor::backend::services::bot_service::BotService::release(&pool_info.user_bot_data.user_id).await;
+    position_changed(&pool_info.user_bot_data.pool_id, &pool_info.user_bot_data.user_id);
 
     println!(
-        "📊 Trade metrics - Profit: {:.4} SOL, ROI: {:.2}%, Duration: {}ms",
-        profit_sol, roi_pct, duration_ms
+        "✅ Bot state cleanup completed for user: {}",
+        pool_info.user_bot_data.user_id
     );
+}
 
-    let _ = save_trade_metrics(
-        pool_info.user_bot_data.user_id.to_string(),
-        profit_sol,
-        total_fees,
-        roi_pct,
-        duration_ms,
-    )
-    .await;
-
-    // Remove from USER_LIST
-    {
-        let mut user_list = raydium_amm_monitor::statics::USER_LIST.write().await;
-        let initial_count = user_list.len();
-        user_list.retain(|user_bot_data| user_bot_data.user_id != pool_info.user_bot_data.user_id);
-        let final_count = user_list.len();
-        if initial_count != final_count {
-            println!(
-                "🧹 Removed {} entries from USER_LIST",
-                initial_count - final_count
-            );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raydium_amm_monitor::backend::{models::bot::BotSettings, services::bot_service::UserBotData};
+    use solana_sdk::{
+        hash::Hash,
+        instruction::AccountMeta,
+        message::{Message, VersionedMessage},
+        signature::Signature,
+    };
+    use solana_transaction_status_client_types::{TransactionStatusMeta, TransactionTokenBalance};
+
+    fn vault_balance(index: u8, owner: &Pubkey, mint: &Pubkey, amount: u64, decimals: u8) -> TransactionTokenBalance {
+        TransactionTokenBalance {
+            account_index: index,
+            mint: mint.to_string(),
+            ui_token_amount: serde_json::from_value(serde_json::json!({
+                "uiAmount": null,
+                "decimals": decimals,
+                "amount": amount.to_string(),
+                "uiAmountString": "",
+            }))
+            .unwrap(),
+            owner: owner.to_string(),
+            program_id: spl_token::ID.to_string(),
         }
     }
 
-    // Remove from REAL_POOL_INFO
-    {
-        let mut real_pool_info = raydium_amm_monitor::statics::REAL_POOL_INFO.write().await;
-        let initial_pools = real_pool_info.len();
-        real_pool_info.retain(|_pool_id, pool_infos| {
-            let initial_users = pool_infos.len();
-            pool_infos.retain(|current_pool_info| {
-                current_pool_info.user_bot_data.user_id != pool_info.user_bot_data.user_id
-            });
-            let final_users = pool_infos.len();
-            if initial_users != final_users {
-                println!(
-                    "🧹 Removed {} entries from pool",
-                    initial_users - final_users
-                );
-            }
-            !pool_infos.is_empty() // Keep the pool entry only if it has remaining users
-        });
-        let final_pools = real_pool_info.len();
-        if initial_pools != final_pools {
-            println!("🧹 Removed {} empty pools", initial_pools - final_pools);
+    #[tokio::test]
+    async fn a_transaction_swapping_the_pool_twice_is_priced_once() {
+        // The pool's status would otherwise be read over RPC
+        std::env::set_var("POOL_STATUS_CHECK", "false");
+
+        let keys: Vec<Pubkey> = (0..17).map(|_| Pubkey::new_unique()).collect();
+        // Accounts in SwapBaseIn order: the pool at 1, its authority at 2 and vaults at 4 and 5
+        let (pool, authority) = (keys[1], keys[2]);
+        let mint = Pubkey::new_unique();
+        let account_metas = |source: Pubkey, destination: Pubkey| {
+            let mut accounts: Vec<AccountMeta> = keys.iter().map(|key| AccountMeta::new(*key, false)).collect();
+            accounts[14].pubkey = source;
+            accounts[15].pubkey = destination;
+            accounts
+        };
+        let (user_wsol, user_token) = (keys[14], keys[15]);
+        // A buy and a sell on the pool, each the processors' own decoded instruction
+        let legs = [
+            account_metas(user_wsol, user_token),
+            account_metas(user_token, user_wsol),
+        ];
+
+        let transaction = |signature: Signature, token_program: Pubkey| {
+            let mut token_vault = vault_balance(5, &authority, &mint, 5_000_000_000_000, 6);
+            token_vault.program_id = token_program.to_string();
+            Arc::new(carbon_core::transaction::TransactionMetadata {
+                slot: 1,
+                signature,
+                fee_payer: keys[16],
+                meta: TransactionStatusMeta {
+                    post_token_balances: Some(vec![vault_balance(4, &authority, &WSOL, 80_000_000_000, 9), token_vault]),
+                    ..Default::default()
+                },
+                message: VersionedMessage::Legacy(Message {
+                    account_keys: keys.clone(),
+                    recent_blockhash: Hash::default(),
+                    ..Default::default()
+                }),
+                block_time: None,
+                block_hash: None,
+            })
+        };
+
+        let mut bot_setting = BotSettings::new("user".to_string(), "bot".to_string(), pool.to_string());
+        bot_setting.observer = true;
+        let user_bot_data = UserBotData {
+            pool_id: pool.to_string(),
+            user_id: "user".to_string(),
+            private_key: String::new(),
+            public_key: keys[16].to_string(),
+            bot_setting,
+        };
+        let process = |transaction_metadata: Arc<carbon_core::transaction::TransactionMetadata>, accounts: Vec<AccountMeta>| {
+            RaydiumV4Process::process_user_data(
+                carbon_core::instruction::InstructionMetadata {
+                    transaction_metadata,
+                    stack_height: 1,
+                    index: 0,
+                    absolute_path: vec![],
+                },
+                DecodedInstruction {
+                    program_id: RAY_V4_PROGRAM_ID,
+                    data: RaydiumAmmV4Instruction::SwapBaseIn(SwapBaseIn { amount_in: 1_000_000_000, minimum_amount_out: 0 }),
+                    accounts,
+                },
+                user_bot_data.clone(),
+            )
+        };
+        let swaps_recorded = || {
+            read_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, &pool.to_string(), "user", |info| {
+                info.quote_reserve_history.len()
+            })
+        };
+
+        let sandwich = transaction(Signature::new_unique(), spl_token::ID);
+        for accounts in legs.clone() {
+            process(sandwich.clone(), accounts).await.unwrap();
         }
-    }
+        assert_eq!(swaps_recorded(), Some(1));
+        let price = read_position(&raydium_amm_monitor::statics::REAL_POOL_INFO, &pool.to_string(), "user", |info| {
+            info.latest_pool_price
+        });
+        assert_eq!(price, Some(80.0 / 5_000_000.0));
 
-    println!(
-        "✅ Bot state cleanup completed for user: {}",
-        pool_info.user_bot_data.user_id
-    );
+        // The pool's next transaction is priced again
+        process(transaction(Signature::new_unique(), spl_token::ID), legs[0].clone()).await.unwrap();
+        assert_eq!(swaps_recorded(), Some(2));
+
+        // A mint the builders can't trade still has its price recorded
+        let token_2022 = raydium_amm_monitor::utils::token_program::TOKEN_2022_PROGRAM_ID;
+        process(transaction(Signature::new_unique(), token_2022), legs[0].clone()).await.unwrap();
+        assert_eq!(swaps_recorded(), Some(3));
+    }
 }
@@ -2,7 +2,9 @@ pub mod nozomi;
 pub mod utils;
 pub mod zero_slot;
 pub mod jito;
+pub mod submission;
 pub use nozomi::*;
 pub use utils::*;
 pub use zero_slot::*;
 pub use jito::*;
+pub use submission::*;
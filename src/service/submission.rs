@@ -0,0 +1,507 @@
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde_json::json;
+use solana_sdk::{hash::Hash, instruction::Instruction, signature::Keypair, signer::Signer};
+
+use crate::backend::{
+    models::bot::BotSettings,
+    services::bot_service::{RealPoolInfo, SwapAction, UserBotData},
+};
+use crate::config::submission_service;
+use crate::service::{Jito, Nozomi, Tips, ZeroSlot};
+use crate::utils::{
+    alt::pool_table,
+    blockhash::{latest_blockhash, WSOL},
+    build_and_sign::build_and_sign,
+    fee::{encoded_tx_fee_sol, fee_within_cap},
+    positions::{update_position, PoolInfoMap},
+    token_balance::{fetch_held_tokens, HeldTokens},
+    wsol::close_wsol_ix,
+};
+
+/// A relay swaps are submitted through. The Jito, Nozomi and ZERO_SLOT
+/// clients implement it, tests submit through a recording double instead.
+#[async_trait]
+pub trait SubmissionService: Send + Sync {
+    /// The swap behind the compute budget, followed by the relay's tip
+    /// transfer. Fails when `tip_addr_idx` is out of the relay's range.
+    fn add_tip_ix(&self, tips: Tips) -> anyhow::Result<Vec<Instruction>>;
+
+    async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<serde_json::Value>;
+
+    /// Tip account index used when the bot doesn't set `tip_addr_idx`
+    fn default_tip_addr_idx(&self) -> u8;
+}
+
+#[async_trait]
+impl SubmissionService for Jito {
+    fn add_tip_ix(&self, tips: Tips) -> anyhow::Result<Vec<Instruction>> {
+        Jito::add_tip_ix(self, tips)
+    }
+
+    async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<serde_json::Value> {
+        Jito::send_transaction(self, encoded_tx).await
+    }
+
+    fn default_tip_addr_idx(&self) -> u8 {
+        4
+    }
+}
+
+#[async_trait]
+impl SubmissionService for Nozomi {
+    fn add_tip_ix(&self, tips: Tips) -> anyhow::Result<Vec<Instruction>> {
+        Nozomi::add_tip_ix(self, tips)
+    }
+
+    async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<serde_json::Value> {
+        Nozomi::send_transaction(self, encoded_tx).await
+    }
+
+    fn default_tip_addr_idx(&self) -> u8 {
+        1
+    }
+}
+
+#[async_trait]
+impl SubmissionService for ZeroSlot {
+    fn add_tip_ix(&self, tips: Tips) -> anyhow::Result<Vec<Instruction>> {
+        ZeroSlot::add_tip_ix(self, tips)
+    }
+
+    async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<serde_json::Value> {
+        ZeroSlot::send_transaction(self, encoded_tx).await
+    }
+
+    fn default_tip_addr_idx(&self) -> u8 {
+        1
+    }
+}
+
+/// Headroom `auto_cu` leaves on top of what the simulation consumed
+pub const AUTO_CU_MARGIN: f64 = 1.2;
+
+/// Most compute units a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// Compute unit limit for a swap that consumed `units_consumed` in simulation
+pub fn padded_cu_limit(units_consumed: u64) -> u64 {
+    ((units_consumed as f64 * AUTO_CU_MARGIN).ceil() as u64).min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// The bot's settings with the `auto_cu` limit in place of `cu`, unchanged
+/// when it is off or the simulation reported nothing consumed
+pub fn with_simulated_cu(settings: &BotSettings, units_consumed: Option<u64>) -> Option<BotSettings> {
+    let units_consumed = units_consumed.filter(|units| *units > 0 && settings.auto_cu)?;
+    Some(BotSettings { cu: padded_cu_limit(units_consumed), ..settings.clone() })
+}
+
+/// Adds the bot's compute budget and `client`'s tip to the swap and signs it,
+/// against the pool's lookup table once it has one, base64 encoded the way
+/// the relays take it
+pub fn tip_and_sign(
+    client: &dyn SubmissionService,
+    settings: &BotSettings,
+    keypair: Keypair,
+    swap_ixs: Vec<Instruction>,
+    recent_blockhash: Hash,
+) -> anyhow::Result<String> {
+    tip_all_and_sign(&[client], settings, keypair, swap_ixs, recent_blockhash)
+}
+
+/// `tip_and_sign` with a tip for every relay in `clients`, each paid
+/// `third_party_fee`. Signed once, so every relay is sent the same
+/// transaction and only one of them can land it.
+pub fn tip_all_and_sign(
+    clients: &[&dyn SubmissionService],
+    settings: &BotSettings,
+    keypair: Keypair,
+    swap_ixs: Vec<Instruction>,
+    recent_blockhash: Hash,
+) -> anyhow::Result<String> {
+    let (first, others) = clients.split_first().ok_or_else(|| anyhow::anyhow!("No relay to tip"))?;
+    let payer = keypair.pubkey();
+    let tips = |client: &dyn SubmissionService, cu, priority_fee_micro_lamport, pure_ix| Tips {
+        cu,
+        priority_fee_micro_lamport,
+        payer,
+        pure_ix,
+        tip_addr_idx: settings.tip_account_index(client.default_tip_addr_idx()),
+        tip_sol_amount: settings.third_party_fee,
+    };
+    let mut ixs = first.add_tip_ix(tips(
+        *first,
+        Some(settings.cu),
+        Some(settings.priority_fee_micro_lamport),
+        swap_ixs,
+    ))?;
+    // The other relays only add their tip transfer
+    for client in others {
+        ixs.extend(client.add_tip_ix(tips(*client, None, None, Vec::new()))?);
+    }
+    let lookup_table = pool_table(&settings.pool_address);
+    Ok(build_and_sign(ixs, recent_blockhash, None, payer, keypair, lookup_table.as_ref()))
+}
+
+/// The refusal of a buy whose signed transaction, paying `tip_sol` in
+/// tips, is projected to cost more than the bot's `max_tx_fee_sol`. Exits
+/// are always let out.
+pub fn buy_fee_refusal(pool_info: &RealPoolInfo, encoded_tx: &str, tip_sol: f64) -> Option<serde_json::Value> {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    if pool_info.pending_action != SwapAction::Buy {
+        return None;
+    }
+    let projected_fee = encoded_tx_fee_sol(encoded_tx, tip_sol)?;
+    if fee_within_cap(projected_fee, settings.max_tx_fee_sol) {
+        return None;
+    }
+    tracing::warn!(
+        "💸 Skipping buy for user {}: projected fee {:.6} SOL exceeds max_tx_fee_sol {:.6}",
+        pool_info.user_bot_data.user_id, projected_fee, settings.max_tx_fee_sol
+    );
+    Some(json!({ "result": "error", "message": "Projected fee exceeds max_tx_fee_sol" }))
+}
+
+/// Writes the signature a relay returned in `data` onto the user's position
+/// in `positions`. Returns the signature and whether this was a buy.
+pub fn record_accepted(positions: &PoolInfoMap, pool_info: &RealPoolInfo, data: &serde_json::Value) -> (String, bool) {
+    let signature = data["result"].as_str().unwrap_or_default().to_string();
+    let has_bought = update_position(
+        positions,
+        &pool_info.user_bot_data.pool_id,
+        &pool_info.user_bot_data.user_id,
+        |info| info.record_submission(&signature, pool_info.pending_action),
+    )
+    .unwrap_or(false);
+    (signature, has_bought)
+}
+
+/// A swap a relay accepted
+#[derive(Debug)]
+pub struct Accepted {
+    /// The relay's response
+    pub response: serde_json::Value,
+    pub signature: String,
+    pub has_bought: bool,
+    /// Relays that failed to take the transaction while another accepted it
+    pub relay_errors: Vec<String>,
+}
+
+/// Tips, signs and sends the simulated swap through `client`, with `auto_cu`
+/// limited to the `units_consumed` in simulation, and records the signature
+/// it accepts as the position's in `positions`. Refusals and relay errors
+/// come back as the error response.
+pub async fn submit_through(
+    positions: &PoolInfoMap,
+    pool_info: &RealPoolInfo,
+    client: &dyn SubmissionService,
+    keypair: Keypair,
+    swap_ixs: Vec<Instruction>,
+    units_consumed: Option<u64>,
+    recent_blockhash: Hash,
+) -> Result<Accepted, serde_json::Value> {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    let sized = with_simulated_cu(settings, units_consumed);
+    let encoded_tx = tip_and_sign(client, sized.as_ref().unwrap_or(settings), keypair, swap_ixs, recent_blockhash)
+        .map_err(|err| json!({ "result": "error", "message": err.to_string() }))?;
+    if let Some(refusal) = buy_fee_refusal(pool_info, &encoded_tx, settings.third_party_fee) {
+        return Err(refusal);
+    }
+
+    let response = client
+        .send_transaction(&encoded_tx)
+        .await
+        .map_err(|err| json!({ "result": "error", "message": err.to_string() }))?;
+    let (signature, has_bought) = record_accepted(positions, pool_info, &response);
+    Ok(Accepted { response, signature, has_bought, relay_errors: Vec::new() })
+}
+
+/// `submit_through` every named relay in `relays` at once. The swap is
+/// signed once with each relay's tip, so they all send the same
+/// transaction: the signature is recorded when the first relay accepts it,
+/// and the others can't land a second fill.
+pub async fn submit_to_all(
+    positions: &PoolInfoMap,
+    pool_info: &RealPoolInfo,
+    relays: &[(&str, &dyn SubmissionService)],
+    keypair: Keypair,
+    swap_ixs: Vec<Instruction>,
+    units_consumed: Option<u64>,
+    recent_blockhash: Hash,
+) -> Result<Accepted, serde_json::Value> {
+    let settings = &pool_info.user_bot_data.bot_setting;
+    let sized = with_simulated_cu(settings, units_consumed);
+    let clients: Vec<&dyn SubmissionService> = relays.iter().map(|(_, client)| *client).collect();
+    let encoded_tx = tip_all_and_sign(&clients, sized.as_ref().unwrap_or(settings), keypair, swap_ixs, recent_blockhash)
+        .map_err(|err| json!({ "result": "error", "message": err.to_string() }))?;
+    if let Some(refusal) = buy_fee_refusal(pool_info, &encoded_tx, settings.third_party_fee * relays.len() as f64) {
+        return Err(refusal);
+    }
+
+    let encoded_tx = encoded_tx.as_str();
+    let mut sends: FuturesUnordered<_> = relays
+        .iter()
+        .map(|(relay, client)| async move { (*relay, client.send_transaction(encoded_tx).await) })
+        .collect();
+
+    let mut accepted = None;
+    let mut relay_errors = Vec::new();
+    while let Some((relay, result)) = sends.next().await {
+        match result {
+            Ok(response) if response["result"].as_str().is_some_and(|sig| !sig.is_empty()) => {
+                if accepted.is_none() {
+                    println!("🏁 {} accepted {} first", relay, response["result"]);
+                    let (signature, has_bought) = record_accepted(positions, pool_info, &response);
+                    accepted = Some((response, signature, has_bought));
+                }
+            }
+            Ok(response) => relay_errors.push(format!("{}: {}", relay, response)),
+            Err(err) => relay_errors.push(format!("{}: {}", relay, err)),
+        }
+    }
+
+    match accepted {
+        Some((response, signature, has_bought)) => Ok(Accepted { response, signature, has_bought, relay_errors }),
+        None => Err(json!({ "result": "error", "message": relay_errors.join("; ") })),
+    }
+}
+
+/// Closes the WSOL account `KEEP_WSOL_OPEN` kept open for a stopped bot,
+/// unwrapping what it holds, through `client`
+pub async fn close_kept_wsol_through(
+    client: &dyn SubmissionService,
+    bot: &UserBotData,
+    recent_blockhash: Hash,
+) -> anyhow::Result<serde_json::Value> {
+    let keypair = Keypair::from_base58_string(&bot.private_key);
+    let close_ix = close_wsol_ix(&keypair.pubkey());
+    let encoded_tx = tip_and_sign(client, &bot.bot_setting, keypair, vec![close_ix], recent_blockhash)?;
+    client.send_transaction(&encoded_tx).await
+}
+
+/// `close_kept_wsol_through` the bot's confirmation service, skipped when
+/// the bot never opened a WSOL account
+pub async fn close_kept_wsol(bot: &UserBotData) -> anyhow::Result<()> {
+    let owner = Keypair::from_base58_string(&bot.private_key).pubkey();
+    let wsol_ata = spl_associated_token_account::get_associated_token_address(&owner, &WSOL);
+    if fetch_held_tokens(&wsol_ata).await.map_err(anyhow::Error::msg)? == HeldTokens::NoAccount {
+        return Ok(());
+    }
+    let settings = &bot.bot_setting;
+    let client = submission_service(&settings.confirm_service, &settings.zslot_region)
+        .ok_or_else(|| anyhow::anyhow!("{} client not initialized", settings.confirm_service))?;
+    let response = close_kept_wsol_through(client, bot, latest_blockhash().0).await?;
+    println!("🧺 Closed the kept WSOL account of user {}: {}", bot.user_id, response);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::JITO_TIP;
+    use crate::utils::positions::position;
+    use solana_sdk::{
+        compute_budget::{self, ComputeBudgetInstruction},
+        pubkey::Pubkey,
+        system_instruction,
+        transaction::VersionedTransaction,
+    };
+    use std::sync::Mutex;
+
+    /// Tips like Jito and records what it is sent instead of sending it
+    #[derive(Default)]
+    struct RecordingSubmission {
+        sent: Mutex<Vec<VersionedTransaction>>,
+    }
+
+    #[async_trait]
+    impl SubmissionService for RecordingSubmission {
+        fn add_tip_ix(&self, tips: Tips) -> anyhow::Result<Vec<Instruction>> {
+            let mut ixs = tips.with_compute_budget();
+            let tip_account = tips.recipient(&JITO_TIP)?;
+            let lamports = (tips.tip_sol_amount * 1_000_000_000.0) as u64;
+            ixs.push(system_instruction::transfer(&tips.payer, &tip_account, lamports));
+            Ok(ixs)
+        }
+
+        async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<serde_json::Value> {
+            let transaction: VersionedTransaction = bincode::deserialize(&base64::decode(encoded_tx)?)?;
+            let signature = transaction.signatures[0].to_string();
+            self.sent.lock().unwrap().push(transaction);
+            Ok(json!({ "jsonrpc": "2.0", "id": 1, "result": signature }))
+        }
+
+        fn default_tip_addr_idx(&self) -> u8 {
+            4
+        }
+    }
+
+    fn entry_on(keypair: &Keypair, bot_setting: BotSettings) -> (PoolInfoMap, RealPoolInfo) {
+        let mut info = RealPoolInfo::new(UserBotData {
+            pool_id: "pool".to_string(),
+            user_id: "user".to_string(),
+            private_key: String::new(),
+            public_key: keypair.pubkey().to_string(),
+            bot_setting,
+        });
+        info.pending_action = SwapAction::Buy;
+        (PoolInfoMap::from_iter([("pool".to_string(), vec![info.clone()])]), info)
+    }
+
+    #[tokio::test]
+    async fn submitted_swap_carries_the_tip_and_its_signature_is_stored() {
+        let keypair = Keypair::new();
+        let mut bot_setting = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        bot_setting.third_party_fee = 0.001;
+        bot_setting.auto_cu = true;
+        let (pools, pool_info) = entry_on(&keypair, bot_setting);
+
+        let relay = RecordingSubmission::default();
+        let swap = system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1);
+        let accepted = submit_through(
+            &pools,
+            &pool_info,
+            &relay,
+            keypair.insecure_clone(),
+            vec![swap],
+            Some(85_000),
+            Hash::new_unique(),
+        )
+        .await
+        .unwrap();
+
+        let sent = relay.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        let message = &sent[0].message;
+        let keys = message.static_account_keys();
+        assert_eq!(keys[0], keypair.pubkey());
+        // Jito's default tip account gets third_party_fee
+        let tip = message.instructions().last().unwrap();
+        assert_eq!(keys[tip.program_id_index as usize], solana_sdk::system_program::id());
+        assert_eq!(keys[tip.accounts[1] as usize], Pubkey::from_str_const(JITO_TIP[4]));
+        assert_eq!(tip.data[4..12], 1_000_000u64.to_le_bytes());
+        // auto_cu signed it limited to the simulated units
+        let limit = ComputeBudgetInstruction::set_compute_unit_limit(102_000).data;
+        assert!(message.instructions().iter().any(|ix| {
+            keys[ix.program_id_index as usize] == compute_budget::id() && ix.data == limit
+        }));
+
+        // The entry is now held under the relay's signature
+        assert!(accepted.has_bought);
+        assert_eq!(accepted.signature, sent[0].signatures[0].to_string());
+        let info = position(&pools, "pool", "user").unwrap();
+        assert_eq!(info.signature.as_deref(), Some(accepted.signature.as_str()));
+        assert!(info.is_bought);
+    }
+
+    #[tokio::test]
+    async fn every_relay_is_sent_the_same_transaction() {
+        let keypair = Keypair::new();
+        let mut bot_setting = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        bot_setting.third_party_fee = 0.001;
+        let (pools, pool_info) = entry_on(&keypair, bot_setting);
+
+        let (jito, nozomi) = (RecordingSubmission::default(), RecordingSubmission::default());
+        let swap = system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1);
+        let accepted = submit_to_all(
+            &pools,
+            &pool_info,
+            &[("JITO", &jito), ("NOZOMI", &nozomi)],
+            keypair,
+            vec![swap],
+            None,
+            Hash::new_unique(),
+        )
+        .await
+        .unwrap();
+
+        // One signed transaction carrying both tips, so only one fill can land
+        let (jito_sent, nozomi_sent) = (jito.sent.lock().unwrap(), nozomi.sent.lock().unwrap());
+        assert_eq!(jito_sent.len(), 1);
+        assert_eq!(jito_sent[0], nozomi_sent[0]);
+        let tips = jito_sent[0].message.instructions().iter().filter(|ix| ix.data.get(4..12) == Some(&1_000_000u64.to_le_bytes()[..]));
+        assert_eq!(tips.count(), 2);
+
+        assert!(accepted.has_bought);
+        assert!(accepted.relay_errors.is_empty());
+        assert_eq!(accepted.signature, jito_sent[0].signatures[0].to_string());
+        let info = position(&pools, "pool", "user").unwrap();
+        assert_eq!(info.signature.as_deref(), Some(accepted.signature.as_str()));
+    }
+
+    #[tokio::test]
+    async fn a_buy_over_the_fee_cap_is_not_sent() {
+        let keypair = Keypair::new();
+        let mut bot_setting = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        bot_setting.third_party_fee = 0.001;
+        bot_setting.max_tx_fee_sol = 0.0005;
+        let (pools, mut pool_info) = entry_on(&keypair, bot_setting);
+        let swap = system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1);
+
+        let relay = RecordingSubmission::default();
+        let refusal = submit_through(&pools, &pool_info, &relay, keypair.insecure_clone(), vec![swap.clone()], None, Hash::new_unique())
+            .await
+            .unwrap_err();
+        assert_eq!(refusal["message"], "Projected fee exceeds max_tx_fee_sol");
+        assert!(relay.sent.lock().unwrap().is_empty());
+        assert!(!position(&pools, "pool", "user").unwrap().is_bought);
+
+        // An exit is let out whatever it costs
+        pool_info.pending_action = SwapAction::Sell;
+        submit_through(&pools, &pool_info, &relay, keypair, vec![swap], None, Hash::new_unique()).await.unwrap();
+        assert_eq!(relay.sent.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn auto_cu_resubmits_with_the_padded_simulated_limit() {
+        let keypair = Keypair::new();
+        let mut settings = BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string());
+        let swap = system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1);
+        assert!(with_simulated_cu(&settings, Some(85_000)).is_none());
+
+        settings.auto_cu = true;
+        assert!(with_simulated_cu(&settings, Some(0)).is_none());
+        assert!(with_simulated_cu(&settings, None).is_none());
+        let rebuilt = with_simulated_cu(&settings, Some(85_000)).unwrap();
+        assert_eq!(rebuilt.cu, 102_000);
+        assert_eq!(padded_cu_limit(1_300_000), MAX_COMPUTE_UNIT_LIMIT);
+
+        let relay = RecordingSubmission::default();
+        let encoded_tx = tip_and_sign(&relay, &rebuilt, keypair, vec![swap], Hash::new_unique()).unwrap();
+        let transaction: VersionedTransaction = bincode::deserialize(&base64::decode(encoded_tx).unwrap()).unwrap();
+        let keys = transaction.message.static_account_keys();
+        let budget: Vec<_> = transaction
+            .message
+            .instructions()
+            .iter()
+            .filter(|ix| keys[ix.program_id_index as usize] == compute_budget::id())
+            .map(|ix| ix.data.clone())
+            .collect();
+        assert!(budget.contains(&ComputeBudgetInstruction::set_compute_unit_limit(102_000).data));
+    }
+
+    #[tokio::test]
+    async fn stopping_closes_the_kept_wsol_account() {
+        let keypair = Keypair::new();
+        let bot = UserBotData {
+            pool_id: "pool".to_string(),
+            user_id: "user".to_string(),
+            private_key: keypair.to_base58_string(),
+            public_key: keypair.pubkey().to_string(),
+            bot_setting: BotSettings::new("user".to_string(), "bot".to_string(), "pool".to_string()),
+        };
+
+        let relay = RecordingSubmission::default();
+        close_kept_wsol_through(&relay, &bot, Hash::new_unique()).await.unwrap();
+
+        let sent = relay.sent.lock().unwrap();
+        let keys = sent[0].message.static_account_keys();
+        let wsol_ata = spl_associated_token_account::get_associated_token_address(&keypair.pubkey(), &WSOL);
+        let close = close_wsol_ix(&keypair.pubkey());
+        assert!(sent[0].message.instructions().iter().any(|ix| {
+            keys[ix.program_id_index as usize] == spl_token::ID
+                && ix.data == close.data
+                && keys[ix.accounts[0] as usize] == wsol_ata
+        }));
+    }
+}
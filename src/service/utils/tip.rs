@@ -1,4 +1,19 @@
-use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use anyhow::anyhow;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, pubkey::Pubkey,
+};
+
+use crate::service::{JITO_TIP, NOZOMI_TIP, ZSLOT_TIP};
+
+/// Tip accounts of a `confirm_service`, `tip_addr_idx` picks one by index
+pub fn tip_accounts(service: &str) -> Option<&'static [&'static str]> {
+    match service {
+        "JITO" => Some(&JITO_TIP),
+        "NOZOMI" => Some(&NOZOMI_TIP),
+        "ZERO_SLOT" => Some(&ZSLOT_TIP),
+        _ => None,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Tips {
@@ -9,3 +24,102 @@ pub struct Tips {
     pub payer: Pubkey,
     pub pure_ix: Vec<Instruction>,
 }
+
+impl Tips {
+    /// The tip account `tip_addr_idx` picks out of a relay's `accounts`
+    pub fn recipient(&self, accounts: &[&str]) -> anyhow::Result<Pubkey> {
+        let account = accounts.get(self.tip_addr_idx as usize).ok_or_else(|| {
+            anyhow!(
+                "tip_addr_idx {} is out of range, the relay has tip accounts 0..={}",
+                self.tip_addr_idx,
+                accounts.len().saturating_sub(1)
+            )
+        })?;
+        Ok(account.parse()?)
+    }
+
+    /// The swap instructions behind the bot's compute unit limit and price.
+    /// Unset or zero values are left out so the runtime defaults apply, a
+    /// zero limit would fail every transaction.
+    pub fn with_compute_budget(&self) -> Vec<Instruction> {
+        let mut ixs: Vec<Instruction> = Vec::new();
+
+        if let Some(cu) = self.cu.filter(|cu| *cu > 0) {
+            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(
+                cu.min(u32::MAX as u64) as u32,
+            ));
+        }
+
+        if let Some(price) = self.priority_fee_micro_lamport.filter(|price| *price > 0) {
+            ixs.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+
+        ixs.extend(self.pure_ix.iter().cloned());
+        ixs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::compute_budget;
+
+    fn tips(cu: Option<u64>, priority_fee_micro_lamport: Option<u64>) -> Tips {
+        let payer = Pubkey::new_unique();
+        Tips {
+            tip_sol_amount: 0.001,
+            tip_addr_idx: 0,
+            cu,
+            priority_fee_micro_lamport,
+            payer,
+            pure_ix: vec![solana_sdk::system_instruction::transfer(&payer, &Pubkey::new_unique(), 1)],
+        }
+    }
+
+    #[test]
+    fn compute_budget_leads_the_swap_instructions() {
+        let ixs = tips(Some(150_000), Some(25_000)).with_compute_budget();
+
+        assert_eq!(ixs.len(), 3);
+        assert!(ixs[..2].iter().all(|ix| ix.program_id == compute_budget::id()));
+        // SetComputeUnitLimit (2) and SetComputeUnitPrice (3), little-endian values
+        let mut limit = vec![2];
+        limit.extend_from_slice(&150_000u32.to_le_bytes());
+        let mut price = vec![3];
+        price.extend_from_slice(&25_000u64.to_le_bytes());
+        assert_eq!(ixs[0].data, limit);
+        assert_eq!(ixs[1].data, price);
+        assert_eq!(ixs[2].program_id, solana_sdk::system_program::id());
+
+        // Unconfigured values keep the runtime defaults
+        let ixs = tips(Some(0), None).with_compute_budget();
+        assert_eq!(ixs.len(), 1);
+        assert_eq!(ixs[0].program_id, solana_sdk::system_program::id());
+    }
+
+    #[test]
+    fn tip_account_index_out_of_range_is_an_error() {
+        let mut tips = tips(None, None);
+        for service in ["JITO", "NOZOMI", "ZERO_SLOT"] {
+            let accounts = tip_accounts(service).unwrap();
+            tips.tip_addr_idx = accounts.len() as u8 - 1;
+            assert_eq!(tips.recipient(accounts).unwrap().to_string(), accounts[accounts.len() - 1]);
+
+            tips.tip_addr_idx = accounts.len() as u8;
+            let err = tips.recipient(accounts).unwrap_err().to_string();
+            assert!(err.contains(&format!("0..={}", accounts.len() - 1)), "{}", err);
+        }
+        assert_eq!(tip_accounts("UNKNOWN"), None);
+
+        // Jito has the fewest accounts, an index Nozomi takes is out of its range
+        let jito = crate::service::Jito {
+            client: reqwest::Client::new(),
+            endpoint: crate::service::JITO_REGIONS[0].clone(),
+            auth_key: None,
+        };
+        tips.tip_addr_idx = 4;
+        assert_eq!(jito.add_tip_ix(tips.clone()).unwrap().len(), 2);
+        tips.tip_addr_idx = 12;
+        assert!(jito.add_tip_ix(tips).is_err());
+    }
+}
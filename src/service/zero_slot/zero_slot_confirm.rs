@@ -1,7 +1,7 @@
 use reqwest::Client;
 use serde_json::json;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, instruction::Instruction,
+    instruction::Instruction,
     native_token::sol_to_lamports, pubkey::Pubkey, system_instruction,
 };
 use std::time::{Duration, Instant};
@@ -47,6 +47,18 @@ impl ZeroSlot {
         }
     }
 
+    /// Client for a known endpoint without the startup ping
+    pub fn with_endpoint(endpoint: ZSlotEndpoint, auth_key: String) -> Self {
+        Self {
+            client: Client::builder()
+                .tcp_keepalive(Duration::from_secs(HEALTH_CHECK_SEC))
+                .build()
+                .expect("Failed to build HTTP client"),
+            endpoint,
+            auth_key,
+        }
+    }
+
     pub async fn new_auto(auth_key: String) -> Self {
         let regions: Vec<(String, String)> = ZSLOT_REGIONS
             .iter()
@@ -122,24 +134,12 @@ impl ZeroSlot {
         // });
     }
 
-    pub fn add_tip_ix(&self, tip_config: Tips) -> Vec<Instruction> {
-        let mut ixs: Vec<Instruction> = Vec::new();
-
-        if let Some(cu) = tip_config.cu {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cu as u32));
-        };
-
-        if let Some(priority_fee_micro_lamport) = tip_config.priority_fee_micro_lamport {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
-                priority_fee_micro_lamport,
-            ));
-        };
-
-        ixs.extend(tip_config.pure_ix.clone());
+    pub fn add_tip_ix(&self, tip_config: Tips) -> anyhow::Result<Vec<Instruction>> {
+        let mut ixs: Vec<Instruction> = tip_config.with_compute_budget();
 
         let relayer_fee = tip_config.tip_sol_amount.max(ZSLOT_MIN_TIP); // use `.max()` for clarity
 
-        let recipient = Pubkey::from_str_const(ZSLOT_TIP[tip_config.tip_addr_idx as usize]);
+        let recipient = tip_config.recipient(&ZSLOT_TIP)?;
         let transfer_ix = system_instruction::transfer(
             &tip_config.payer,
             &recipient,
@@ -147,7 +147,7 @@ impl ZeroSlot {
         );
         ixs.push(transfer_ix);
 
-        ixs
+        Ok(ixs)
     }
 
     pub async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<serde_json::Value> {
@@ -9,6 +9,20 @@ pub enum ZSlotRegionsType {
     Tokyo,
 }
 
+impl ZSlotRegionsType {
+    /// Region named in bot settings
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Frankfurt" => Some(Self::Frankfurt),
+            "NewYork" => Some(Self::NewYork),
+            "AMS" => Some(Self::AMS),
+            "LA" => Some(Self::LA),
+            "Tokyo" => Some(Self::Tokyo),
+            _ => None,
+        }
+    }
+}
+
 macro_rules! zslot_endpoint {
     ($region:ident, $name:expr, $submit:expr, $ping:expr) => {
         ZSlotEndpoint {
@@ -1,13 +1,35 @@
 use std::sync::Arc;
-use std::collections::HashMap;
 use once_cell::sync::Lazy;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, OnceCell, RwLock};
 
 pub static USER_LIST: Lazy<Arc<RwLock<Vec<crate::backend::services::bot_service::UserBotData>>>> =
     Lazy::new(|| Arc::new(RwLock::new(vec![])));
 
-#[allow(dead_code)]
-pub static REAL_POOL_INFO: Lazy<Arc<RwLock<HashMap<String, Vec<crate::backend::services::bot_service::RealPoolInfo>>>>> =
-    Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+/// Open positions per pool, see `utils::positions` for the usual lookups
+pub static REAL_POOL_INFO: Lazy<crate::utils::positions::PoolInfoMap> = Lazy::new(Default::default);
 
-    
\ No newline at end of file
+/// Feeds every price a processor records to the monitor task, set once the
+/// monitor starts consuming
+pub static PRICE_UPDATES: OnceCell<mpsc::UnboundedSender<crate::backend::services::bot_service::PriceUpdate>> =
+    OnceCell::const_new();
+
+/// Feeds the `(pool_id, user_id)` of every position state transition to the
+/// persistence task, set once it starts consuming
+pub static POSITION_CHANGES: OnceCell<mpsc::UnboundedSender<(String, String)>> = OnceCell::const_new();
+
+/// The `trading` database the processors write fills and metrics to,
+/// connected on first use
+pub static TRADING_DB: OnceCell<mongodb::Database> = OnceCell::const_new();
+
+/// Serializes `start_bot` and `stop_bot` for the same user
+pub static BOT_LIFECYCLE_LOCKS: Lazy<crate::utils::user_locks::UserLocks> = Lazy::new(Default::default);
+
+/// Decimals per mint, see `utils::token_decimals`
+pub static MINT_DECIMALS: Lazy<dashmap::DashMap<solana_sdk::pubkey::Pubkey, u8>> = Lazy::new(Default::default);
+
+/// Address lookup table per pool, see `utils::alt`
+pub static POOL_ALTS: Lazy<dashmap::DashMap<String, crate::utils::alt::PoolAlt>> = Lazy::new(Default::default);
+
+/// Fees paid per user this run, see `BotService::record_fee_spend`
+pub static FEE_BUDGETS: Lazy<dashmap::DashMap<String, crate::backend::models::bot::FeeBudget>> =
+    Lazy::new(Default::default);
@@ -0,0 +1,337 @@
+use carbon_pump_swap_decoder::PROGRAM_ID as PUMPSWAP_PROGRAM_ID;
+use carbon_raydium_amm_v4_decoder::PROGRAM_ID as RAY_V4_PROGRAM_ID;
+use carbon_raydium_cpmm_decoder::PROGRAM_ID as CPMM_PROGRAM_ID;
+use carbon_raydium_launchpad_decoder::PROGRAM_ID as LAUNCHPAD_PROGRAM_ID;
+use mongodb::Database;
+use solana_address_lookup_table_interface::{
+    instruction::{close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table},
+    state::{estimate_last_valid_slot, AddressLookupTable, LOOKUP_TABLE_META_SIZE},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    message::{AddressLookupTableAccount, Message},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    transaction::Transaction,
+};
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use crate::backend::{db::pool_table_repository::PoolTableRepository, models::pool_table::StoredPoolTable};
+use crate::config::{POOL_ALT_PAYER, RPC_CLIENT};
+use crate::statics::POOL_ALTS;
+use crate::utils::fee::LAMPORTS_PER_SIGNATURE;
+
+/// Most addresses the table is created with, so create and extend fit one
+/// transaction
+const MAX_TABLE_ADDRESSES: usize = 24;
+
+/// A table only resolves addresses from the slot after it was extended
+const ACTIVATION_DELAY: Duration = Duration::from_millis(800);
+
+/// Wait before retrying a failed table creation, doubling per failure
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3_600);
+
+/// How often tables of pools no bot trades anymore are looked for
+pub const POOL_TABLE_SWEEP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Where a pool's lookup table stands in `POOL_ALTS`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolAlt {
+    /// Being loaded or created, swaps are signed without it meanwhile
+    Pending { failures: u32 },
+    Ready(AddressLookupTableAccount),
+    /// `failures` attempts in a row failed, the next waits until `retry_at`
+    Failed { failures: u32, retry_at: Instant },
+}
+
+/// How long to wait after the `failures`th failed attempt in a row
+pub fn retry_delay(failures: u32) -> Duration {
+    FIRST_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Rent a table holding `addresses` addresses is charged
+pub fn table_rent_lamports(addresses: usize) -> u64 {
+    Rent::default().minimum_balance(LOOKUP_TABLE_META_SIZE + addresses * 32)
+}
+
+/// Indices of the swap's accounts that belong to the user rather than the
+/// pool, `None` for swaps the processors don't build
+fn user_account_indices(ix: &Instruction) -> Option<Vec<usize>> {
+    if ix.program_id == PUMPSWAP_PROGRAM_ID {
+        // User, their base and quote token accounts and volume accumulator
+        Some(vec![1, 5, 6, 20])
+    } else if ix.program_id == CPMM_PROGRAM_ID {
+        Some(vec![0, 4, 5])
+    } else if ix.program_id == LAUNCHPAD_PROGRAM_ID {
+        Some(vec![0, 5, 6])
+    } else if ix.program_id == RAY_V4_PROGRAM_ID {
+        // Source, destination and owner close the account list
+        let len = ix.accounts.len();
+        Some((len.saturating_sub(3)..len).collect())
+    } else {
+        None
+    }
+}
+
+/// Accounts of the transaction's swap every user shares: the pool, its
+/// vaults, configs and programs. Empty when there's no swap to take them from.
+pub fn pool_table_addresses(ixs: &[Instruction]) -> Vec<Pubkey> {
+    let Some((swap, user_indices)) = ixs.iter().find_map(|ix| Some((ix, user_account_indices(ix)?))) else {
+        return Vec::new();
+    };
+    let mut addresses: Vec<Pubkey> = Vec::new();
+    for (index, meta) in swap.accounts.iter().enumerate() {
+        if meta.is_signer || user_indices.contains(&index) || addresses.contains(&meta.pubkey) {
+            continue;
+        }
+        addresses.push(meta.pubkey);
+    }
+    addresses.truncate(MAX_TABLE_ADDRESSES);
+    addresses
+}
+
+/// The pool's lookup table once it can be used
+pub fn pool_table(pool_id: &str) -> Option<AddressLookupTableAccount> {
+    match POOL_ALTS.get(pool_id)?.value() {
+        PoolAlt::Ready(table) => Some(table.clone()),
+        PoolAlt::Pending { .. } | PoolAlt::Failed { .. } => None,
+    }
+}
+
+/// Loads the pool's stored lookup table the first time it is swapped, or
+/// creates one paid for and owned by `POOL_ALT_PAYER` and stores it. Swaps go
+/// without it until it is ready. A failed attempt is retried on a later swap,
+/// after `retry_delay`.
+pub fn ensure_pool_table(pool_id: &str, ixs: &[Instruction], database: Database) {
+    let Some(payer) = POOL_ALT_PAYER.as_ref() else {
+        return;
+    };
+    let addresses = pool_table_addresses(ixs);
+    if addresses.is_empty() {
+        return;
+    }
+    let failures = match POOL_ALTS.get(pool_id).map(|alt| alt.value().clone()) {
+        None => 0,
+        Some(PoolAlt::Failed { failures, retry_at }) if Instant::now() >= retry_at => failures,
+        Some(_) => return,
+    };
+    POOL_ALTS.insert(pool_id.to_string(), PoolAlt::Pending { failures });
+
+    let pool_id = pool_id.to_string();
+    tokio::spawn(async move {
+        let tables = PoolTableRepository::new(database);
+        match load_or_create_pool_table(&tables, &pool_id, payer, addresses).await {
+            Ok(table) => {
+                println!("🗂️ Lookup table {} ready for pool {}", table.key, pool_id);
+                POOL_ALTS.insert(pool_id, PoolAlt::Ready(table));
+            }
+            Err(e) => {
+                let failures = failures + 1;
+                let delay = retry_delay(failures);
+                println!(
+                    "Failed to set up a lookup table for pool {}, retrying in {:?}: {}",
+                    pool_id, delay, e
+                );
+                POOL_ALTS.insert(pool_id, PoolAlt::Failed { failures, retry_at: Instant::now() + delay });
+            }
+        }
+    });
+}
+
+/// The pool's stored table while it is still on chain, a new one otherwise
+async fn load_or_create_pool_table(
+    tables: &PoolTableRepository,
+    pool_id: &str,
+    payer: &Keypair,
+    addresses: Vec<Pubkey>,
+) -> anyhow::Result<AddressLookupTableAccount> {
+    if let Some(stored) = tables.find_active(pool_id).await? {
+        let key: Pubkey = stored.table.parse()?;
+        match read_table(&key).await {
+            Ok(table) => return Ok(table),
+            Err(e) => {
+                println!("Stored lookup table {} of pool {} is gone, creating another: {}", key, pool_id, e);
+                tables.delete(&stored.table).await?;
+            }
+        }
+    }
+
+    let table = create_pool_table(payer, addresses).await?;
+    tables.insert(&StoredPoolTable::new(pool_id.to_string(), table.key.to_string())).await?;
+    Ok(table)
+}
+
+async fn read_table(key: &Pubkey) -> anyhow::Result<AddressLookupTableAccount> {
+    let account = RPC_CLIENT.get_account(key).await?;
+    let state = AddressLookupTable::deserialize(&account.data)?;
+    Ok(AddressLookupTableAccount { key: *key, addresses: state.addresses.to_vec() })
+}
+
+/// Creates and extends a lookup table with `addresses` in one transaction,
+/// then reads it back once it is active. Refused up front when the payer
+/// can't cover the table's rent and the fee.
+async fn create_pool_table(payer: &Keypair, addresses: Vec<Pubkey>) -> anyhow::Result<AddressLookupTableAccount> {
+    let owner = payer.pubkey();
+    let needed = table_rent_lamports(addresses.len()) + LAMPORTS_PER_SIGNATURE;
+    let balance = RPC_CLIENT.get_balance(&owner).await?;
+    if balance < needed {
+        anyhow::bail!("payer {} has {} lamports, the table needs {}", owner, balance, needed);
+    }
+
+    let recent_slot = RPC_CLIENT.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+    let (create_ix, table) = create_lookup_table(owner, owner, recent_slot);
+    let extend_ix = extend_lookup_table(table, owner, Some(owner), addresses);
+    send_signed(payer, &[create_ix, extend_ix]).await?;
+
+    tokio::time::sleep(ACTIVATION_DELAY).await;
+    read_table(&table).await
+}
+
+async fn send_signed(payer: &Keypair, ixs: &[Instruction]) -> anyhow::Result<()> {
+    let recent_blockhash = RPC_CLIENT.get_latest_blockhash().await?;
+    let message = Message::new(ixs, Some(&payer.pubkey()));
+    let transaction = Transaction::new(&[payer], message, recent_blockhash);
+    RPC_CLIENT.send_and_confirm_transaction(&transaction).await?;
+    Ok(())
+}
+
+/// What to do with a stored table on a sweep
+#[derive(Debug, PartialEq)]
+pub enum TableRetirement {
+    Keep,
+    Deactivate,
+    Close,
+}
+
+/// Tables of pools no bot trades anymore are deactivated, and closed once
+/// their deactivation slot is no longer recent
+pub fn table_retirement(stored: &StoredPoolTable, pool_in_use: bool, current_slot: u64) -> TableRetirement {
+    match stored.deactivated_slot {
+        None if pool_in_use => TableRetirement::Keep,
+        None => TableRetirement::Deactivate,
+        Some(slot) if current_slot > estimate_last_valid_slot(slot) => TableRetirement::Close,
+        Some(_) => TableRetirement::Keep,
+    }
+}
+
+/// Deactivates the tables of pools not in `pools_in_use` and closes those
+/// done deactivating, returning their rent to `POOL_ALT_PAYER`
+pub async fn retire_unused_tables(database: Database, pools_in_use: &HashSet<String>) -> anyhow::Result<()> {
+    let Some(payer) = POOL_ALT_PAYER.as_ref() else {
+        return Ok(());
+    };
+    let tables = PoolTableRepository::new(database);
+    let current_slot = RPC_CLIENT.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+
+    for stored in tables.find_all().await? {
+        let key: Pubkey = stored.table.parse()?;
+        match table_retirement(&stored, pools_in_use.contains(&stored.pool_id), current_slot) {
+            TableRetirement::Keep => {}
+            TableRetirement::Deactivate => {
+                POOL_ALTS.remove(&stored.pool_id);
+                send_signed(payer, &[deactivate_lookup_table(key, payer.pubkey())]).await?;
+                tables.set_deactivated(&stored.table, current_slot).await?;
+                println!("🗂️ Deactivated lookup table {} of pool {}", key, stored.pool_id);
+            }
+            TableRetirement::Close => {
+                send_signed(payer, &[close_lookup_table(key, payer.pubkey(), payer.pubkey())]).await?;
+                tables.delete(&stored.table).await?;
+                println!("🗂️ Closed lookup table {} of pool {}", key, stored.pool_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::build_and_sign::build_and_sign;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::AccountMeta,
+        message::VersionedMessage,
+        system_instruction,
+        transaction::VersionedTransaction,
+    };
+
+    /// A Raydium v4 swap: 15 pool accounts, then the user's source,
+    /// destination and signing owner
+    fn amm_v4_swap(owner: &Pubkey) -> Instruction {
+        let mut accounts: Vec<_> = (0..15).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+        accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+        accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+        accounts.push(AccountMeta::new_readonly(*owner, true));
+        Instruction { program_id: RAY_V4_PROGRAM_ID, accounts, data: vec![9; 17] }
+    }
+
+    fn decode(encoded_tx: &str) -> VersionedTransaction {
+        bincode::deserialize(&base64::decode(encoded_tx).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn failed_creations_back_off_and_unused_tables_retire() {
+        assert_eq!(retry_delay(1), FIRST_RETRY_DELAY);
+        assert_eq!(retry_delay(3), FIRST_RETRY_DELAY * 4);
+        assert_eq!(retry_delay(30), MAX_RETRY_DELAY);
+
+        // Rent grows with the addresses the table holds
+        assert!(table_rent_lamports(24) > table_rent_lamports(0));
+
+        let mut stored = StoredPoolTable::new("pool".to_string(), Pubkey::new_unique().to_string());
+        assert_eq!(table_retirement(&stored, true, 1_000), TableRetirement::Keep);
+        assert_eq!(table_retirement(&stored, false, 1_000), TableRetirement::Deactivate);
+
+        stored.deactivated_slot = Some(1_000);
+        assert_eq!(table_retirement(&stored, false, 1_100), TableRetirement::Keep);
+        let closable = estimate_last_valid_slot(1_000) + 1;
+        assert_eq!(table_retirement(&stored, false, closable), TableRetirement::Close);
+        // A pool traded again gets a new table, the deactivated one still closes
+        assert_eq!(table_retirement(&stored, true, closable), TableRetirement::Close);
+    }
+
+    #[test]
+    fn v0_message_resolves_the_pool_accounts_from_the_table() {
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let swap = amm_v4_swap(&payer);
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+        let ixs = vec![transfer, swap.clone()];
+
+        let addresses = pool_table_addresses(&ixs);
+        let pool_accounts: Vec<_> = swap.accounts[..15].iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(addresses, pool_accounts);
+
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses };
+        let encoded_tx = build_and_sign(ixs.clone(), Hash::new_unique(), None, payer, keypair.insecure_clone(), Some(&table));
+        let VersionedMessage::V0(message) = decode(&encoded_tx).message else {
+            panic!("expected a v0 message");
+        };
+
+        assert_eq!(message.address_table_lookups.len(), 1);
+        let lookup = &message.address_table_lookups[0];
+        assert_eq!(lookup.account_key, table.key);
+        let resolved: Vec<_> = lookup
+            .writable_indexes
+            .iter()
+            .chain(&lookup.readonly_indexes)
+            .map(|index| table.addresses[*index as usize])
+            .collect();
+        assert_eq!(resolved.len(), pool_accounts.len());
+        assert!(pool_accounts.iter().all(|account| resolved.contains(account)));
+        assert!(pool_accounts.iter().all(|account| !message.account_keys.contains(account)));
+
+        // Without a table the swap goes out as a legacy message
+        let encoded_tx = build_and_sign(ixs, Hash::new_unique(), None, payer, keypair, None);
+        assert!(matches!(decode(&encoded_tx).message, VersionedMessage::Legacy(_)));
+    }
+}
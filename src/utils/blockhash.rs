@@ -1,32 +1,48 @@
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash, pubkey};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio::time::{Duration, sleep};
 
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 
-static GLOBAL_CURRENT_HASH: Lazy<Mutex<Hash>> = Lazy::new(|| Mutex::new(Hash::default()));
+/// Latest blockhash and its last valid block height, per RPC source
+static SOURCE_HASHES: Lazy<Mutex<HashMap<usize, (Hash, u64)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
-fn set_slot(value: Hash) {
-    let mut slot = GLOBAL_CURRENT_HASH.lock().unwrap();
-    *slot = value;
+fn set_slot(source: usize, value: Hash, last_valid_block_height: u64) {
+    let mut hashes = SOURCE_HASHES.lock().unwrap_or_else(|e| e.into_inner());
+    hashes.insert(source, (value, last_valid_block_height));
+}
+
+/// The blockhash valid the longest among the sources with its last valid
+/// block height, a lagging RPC's older one loses to it
+pub fn freshest_blockhash<'a>(samples: impl IntoIterator<Item = &'a (Hash, u64)>) -> Option<(Hash, u64)> {
+    samples
+        .into_iter()
+        .max_by_key(|(_, last_valid_block_height)| *last_valid_block_height)
+        .copied()
+}
+
+/// Blockhash to sign with and the last block height a transaction signed
+/// with it can land at, zero before any source has answered
+pub fn latest_blockhash() -> (Hash, u64) {
+    let hashes = SOURCE_HASHES.lock().unwrap_or_else(|e| e.into_inner());
+    freshest_blockhash(hashes.values()).unwrap_or_default()
 }
 
 pub fn get_slot() -> Hash {
-    let slot = GLOBAL_CURRENT_HASH.lock().unwrap();
-    *slot
+    latest_blockhash().0
 }
 
-/// Continuously retries fetching the latest blockhash until successful.
-pub async fn recent_blockhash_handler(rpc_client: Arc<RpcClient>) {
+/// Continuously retries fetching the latest blockhash from `source` until successful.
+pub async fn recent_blockhash_handler(source: usize, rpc_client: Arc<RpcClient>) {
     loop {
         match rpc_client
             .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
             .await
         {
-            Ok((latest_blockhash, _)) => {
-                set_slot(latest_blockhash);
+            Ok((latest_blockhash, last_valid_block_height)) => {
+                set_slot(source, latest_blockhash, last_valid_block_height);
                 break; // Exit the loop once we get a valid blockhash
             }
             Err(_) => {
@@ -42,4 +58,24 @@ pub const WSOL: solana_program::pubkey::Pubkey =
     pubkey!("So11111111111111111111111111111111111111112");
 
 pub const USD1: solana_program::pubkey::Pubkey =
-    pubkey!("USD1ttGY1N17NEEHLmELoaybftRBUSErhqYiQzvEmuB");
\ No newline at end of file
+    pubkey!("USD1ttGY1N17NEEHLmELoaybftRBUSErhqYiQzvEmuB");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_uses_the_fresher_source() {
+        let lagging = (Hash::new_unique(), 1_000);
+        let fresh = (Hash::new_unique(), 1_004);
+
+        assert_eq!(freshest_blockhash([&lagging, &fresh]), Some(fresh));
+        assert_eq!(freshest_blockhash([&fresh, &lagging]), Some(fresh));
+        assert_eq!(freshest_blockhash([]), None);
+
+        set_slot(0, lagging.0, lagging.1);
+        set_slot(1, fresh.0, fresh.1);
+        assert_eq!(get_slot(), fresh.0);
+        assert_eq!(latest_blockhash(), fresh);
+    }
+}
@@ -2,26 +2,34 @@ use solana_sdk::{
     pubkey::Pubkey,
     hash::Hash,
     instruction::Instruction,
-    message::{VersionedMessage, v0::Message},
+    message::{v0, AddressLookupTableAccount, Message, VersionedMessage},
     transaction::VersionedTransaction,
     signer::keypair::Keypair,
 };   
 
+/// Signs `ixs` as a v0 message resolving accounts from `lookup_table`, or a
+/// legacy one while there's no table for the pool
 pub fn build_and_sign(
     mut ixs: Vec<Instruction>,
     recent_blockhash: Hash,
     nonce_ix: Option<Instruction>,
     pubkey: Pubkey,
     keypair: Keypair,
+    lookup_table: Option<&AddressLookupTableAccount>,
 ) -> String {
     // If there's a nonce instruction, insert it at the start of the instruction list
     if let Some(nonce_instruction) = nonce_ix {
         ixs.insert(0, nonce_instruction);
     }
 
-    let message = Message::try_compile(&pubkey, &ixs, &[], recent_blockhash)
-        .expect("Failed to compile message");
-    let versioned_message = VersionedMessage::V0(message);
+    let versioned_message = match lookup_table {
+        Some(table) => {
+            let message = v0::Message::try_compile(&pubkey, &ixs, std::slice::from_ref(table), recent_blockhash)
+                .expect("Failed to compile message");
+            VersionedMessage::V0(message)
+        }
+        None => VersionedMessage::Legacy(Message::new_with_blockhash(&ixs, Some(&pubkey), &recent_blockhash)),
+    };
     let txn = VersionedTransaction::try_new(versioned_message, &[&keypair])
         .expect("Failed to create transaction");
 
@@ -0,0 +1,196 @@
+use anyhow::{anyhow, Result};
+use carbon_core::{transaction::TransactionMetadata, transformers::transaction_metadata_from_original_meta};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status_client_types::{TransactionStatus, UiTransactionEncoding};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::RPC_CLIENT;
+
+const FIRST_POLL_DELAY: Duration = Duration::from_millis(400);
+const MAX_POLL_DELAY: Duration = Duration::from_secs(4);
+/// How long past the timeout polling waits for the blockhash to expire
+const EXPIRY_GRACE: Duration = Duration::from_secs(120);
+
+/// Where a submitted transaction ended up
+#[derive(Debug, Clone, PartialEq)]
+pub enum Confirmation {
+    Confirmed(Signature),
+    Failed(Signature, String),
+    /// Its blockhash expired before it landed
+    Expired,
+    /// Polling gave up while it could still land
+    TimedOut,
+}
+
+impl Confirmation {
+    /// Whether the submission can no longer land
+    pub fn never_lands(&self) -> bool {
+        matches!(self, Confirmation::Failed(..) | Confirmation::Expired)
+    }
+
+    /// Why a submission that can no longer land didn't
+    pub fn failure_reason(&self) -> Option<String> {
+        match self {
+            Confirmation::Failed(_, err) => Some(err.clone()),
+            Confirmation::Expired => Some("dropped, its blockhash expired before it landed".to_string()),
+            Confirmation::Confirmed(_) | Confirmation::TimedOut => None,
+        }
+    }
+}
+
+/// Delay before poll `attempt`, doubling from `FIRST_POLL_DELAY` up to `MAX_POLL_DELAY`
+pub fn poll_delay(attempt: u32) -> Duration {
+    FIRST_POLL_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_POLL_DELAY)
+}
+
+/// The outcome a signature status settles, `None` while it is only processed
+pub fn settled(signature: Signature, status: &TransactionStatus) -> Option<Confirmation> {
+    if let Some(err) = &status.err {
+        return Some(Confirmation::Failed(signature, err.to_string()));
+    }
+    status
+        .satisfies_commitment(CommitmentConfig::confirmed())
+        .then_some(Confirmation::Confirmed(signature))
+}
+
+/// Polls the status of a submitted `signature` until it is confirmed,
+/// finalized or fails. Past `timeout` it keeps polling until
+/// `last_valid_block_height` is passed with nothing landed, and only gives
+/// up while the submission could still land once that takes longer than
+/// `EXPIRY_GRACE` or the height is unknown.
+pub async fn confirm_signature(
+    signature: &Signature,
+    last_valid_block_height: u64,
+    timeout: Duration,
+) -> Confirmation {
+    let deadline = Instant::now() + timeout;
+    let mut attempt = 0;
+    loop {
+        let delay = poll_delay(attempt);
+        let expiry_known = last_valid_block_height > 0;
+        let give_up_at = if expiry_known { deadline + EXPIRY_GRACE } else { deadline };
+        if Instant::now() + delay > give_up_at {
+            return Confirmation::TimedOut;
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+
+        // Read before the status, so a swap landing in its last valid block
+        // still shows up in it
+        let block_height = if expiry_known && Instant::now() >= deadline {
+            RPC_CLIENT.get_block_height().await.ok()
+        } else {
+            None
+        };
+        let status = match RPC_CLIENT.get_signature_statuses(&[*signature]).await {
+            Ok(response) => response.value.into_iter().next().flatten(),
+            Err(e) => {
+                println!("Failed to poll signature status: {}", e);
+                continue;
+            }
+        };
+        if let Some(outcome) = status.as_ref().and_then(|status| settled(*signature, status)) {
+            return outcome;
+        }
+        if status.is_none() && block_height.is_some_and(|height| height > last_valid_block_height) {
+            return Confirmation::Expired;
+        }
+    }
+}
+
+/// Fetches a confirmed transaction in the shape the gRPC stream delivers, so
+/// its fill can be recorded the same way
+pub async fn fetch_transaction_metadata(signature: &Signature) -> Result<TransactionMetadata> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let fetched = RPC_CLIENT.get_transaction_with_config(signature, config).await?;
+    let transaction = fetched
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| anyhow!("Failed to decode transaction {}", signature))?;
+    let meta = fetched
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("Transaction {} has no status meta", signature))?;
+    let fee_payer = *transaction
+        .message
+        .static_account_keys()
+        .first()
+        .ok_or_else(|| anyhow!("Transaction {} has no accounts", signature))?;
+
+    Ok(TransactionMetadata {
+        slot: fetched.slot,
+        signature: *signature,
+        fee_payer,
+        meta: transaction_metadata_from_original_meta(meta)?,
+        message: transaction.message,
+        block_time: fetched.block_time,
+        block_hash: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status_client_types::TransactionConfirmationStatus;
+
+    fn status(
+        confirmation_status: TransactionConfirmationStatus,
+        err: Option<TransactionError>,
+    ) -> TransactionStatus {
+        TransactionStatus {
+            slot: 1,
+            confirmations: Some(0),
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(confirmation_status),
+        }
+    }
+
+    #[test]
+    fn only_confirmed_or_failed_statuses_settle() {
+        let signature = Signature::new_unique();
+
+        let processed = status(TransactionConfirmationStatus::Processed, None);
+        assert_eq!(settled(signature, &processed), None);
+
+        let confirmed = status(TransactionConfirmationStatus::Confirmed, None);
+        assert_eq!(settled(signature, &confirmed), Some(Confirmation::Confirmed(signature)));
+        let finalized = status(TransactionConfirmationStatus::Finalized, None);
+        assert_eq!(settled(signature, &finalized), Some(Confirmation::Confirmed(signature)));
+
+        let failed = status(
+            TransactionConfirmationStatus::Processed,
+            Some(TransactionError::InsufficientFundsForFee),
+        );
+        assert!(matches!(settled(signature, &failed), Some(Confirmation::Failed(..))));
+
+        assert!(Confirmation::Failed(signature, String::new()).never_lands());
+        assert!(Confirmation::Expired.never_lands());
+        assert!(!Confirmation::TimedOut.never_lands());
+
+        assert_eq!(poll_delay(0), FIRST_POLL_DELAY);
+        assert_eq!(poll_delay(1), FIRST_POLL_DELAY * 2);
+        assert_eq!(poll_delay(30), MAX_POLL_DELAY);
+    }
+
+    #[test]
+    fn failed_and_dropped_submissions_have_a_failure_reason() {
+        let failed = Confirmation::Failed(Signature::new_unique(), "slippage".to_string());
+        assert_eq!(failed.failure_reason().as_deref(), Some("slippage"));
+        assert!(Confirmation::Expired.failure_reason().is_some());
+
+        // Still able to land, or landed
+        assert_eq!(Confirmation::TimedOut.failure_reason(), None);
+        assert_eq!(Confirmation::Confirmed(Signature::new_unique()).failure_reason(), None);
+    }
+}
@@ -0,0 +1,608 @@
+use futures::future::join_all;
+use reqwest::Client;
+use solana_client::{
+    rpc_config::RpcSimulateTransactionConfig, rpc_response::RpcSimulateTransactionResult,
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, message::Message, pubkey::Pubkey,
+    system_instruction, transaction::{Transaction, VersionedTransaction},
+};
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use carbon_pump_swap_decoder::PROGRAM_ID as PUMPSWAP_PROGRAM_ID;
+use carbon_raydium_amm_v4_decoder::PROGRAM_ID as RAY_V4_PROGRAM_ID;
+use carbon_raydium_cpmm_decoder::PROGRAM_ID as CPMM_PROGRAM_ID;
+use carbon_raydium_launchpad_decoder::PROGRAM_ID as LAUNCHPAD_PROGRAM_ID;
+
+use crate::{
+    backend::models::bot::{BotSettings, DryRunCheck, DryRunReport},
+    config::{LIVE_TRADING_ENABLED, RPC_CLIENT},
+    service::Tips,
+    utils::{
+        blockhash::{USD1, WSOL},
+        fee::{break_even_price, fee_within_cap, projected_tx_fee_sol, swap_fee_rate},
+        pool_reserves::{pool_vaults, token_account_amount, PoolVaults},
+        pool_status::swap_enabled_from_account,
+        rent::required_buy_lamports,
+        self_test::{probe_http, relay_submit_url, rpc_error_detail, ServiceCheck, ServiceState},
+    },
+};
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+const USD1_DECIMALS: i32 = 6;
+
+fn dex_name(owner: &Pubkey) -> Option<&'static str> {
+    if *owner == RAY_V4_PROGRAM_ID {
+        Some("Raydium AMM v4")
+    } else if *owner == CPMM_PROGRAM_ID {
+        Some("Raydium CPMM")
+    } else if *owner == LAUNCHPAD_PROGRAM_ID {
+        Some("Raydium Launchpad")
+    } else if *owner == PUMPSWAP_PROGRAM_ID {
+        Some("PumpSwap")
+    } else {
+        None
+    }
+}
+
+/// The pool exists, belongs to a supported DEX, is quoted in SOL or USD1 and
+/// accepts swaps
+pub fn pool_check(account: Option<&Account>, now_unix: u64) -> (DryRunCheck, Option<PoolVaults>) {
+    const CHECK: &str = "pool";
+
+    let Some(account) = account else {
+        return (DryRunCheck::fail(CHECK, "Pool account not found"), None);
+    };
+    let Some(dex) = dex_name(&account.owner) else {
+        return (
+            DryRunCheck::fail(
+                CHECK,
+                format!(
+                    "Pool is owned by {}, not Raydium AMM v4, CPMM, Launchpad or PumpSwap",
+                    account.owner
+                ),
+            ),
+            None,
+        );
+    };
+    let Some(vaults) = pool_vaults(&account.owner, &account.data) else {
+        return (DryRunCheck::fail(CHECK, format!("Could not decode the {} pool state", dex)), None);
+    };
+    if ![WSOL, USD1].contains(&vaults.quote_mint) && ![WSOL, USD1].contains(&vaults.base_mint) {
+        return (
+            DryRunCheck::fail(CHECK, format!("{} pool isn't paired with SOL or USD1", dex)),
+            Some(vaults),
+        );
+    }
+    if swap_enabled_from_account(&account.owner, &account.data, now_unix) == Some(false) {
+        return (
+            DryRunCheck::fail(CHECK, format!("{} pool isn't accepting swaps", dex)),
+            Some(vaults),
+        );
+    }
+
+    (DryRunCheck::pass(CHECK, format!("{} pool accepting swaps", dex)), Some(vaults))
+}
+
+/// The wallet covers the buy, rent for the token accounts it lacks and both
+/// legs' fees; on USD1 pools the buy itself comes from its USD1 balance
+pub fn wallet_check(
+    balance_lamports: u64,
+    required_lamports: u64,
+    usd1: Option<(u64, u64)>,
+) -> DryRunCheck {
+    const CHECK: &str = "wallet";
+
+    if balance_lamports < required_lamports {
+        return DryRunCheck::fail(
+            CHECK,
+            format!(
+                "Wallet has {:.9} SOL but needs {:.9} SOL for the buy, token account rent, fees and tips",
+                balance_lamports as f64 / LAMPORTS_PER_SOL,
+                required_lamports as f64 / LAMPORTS_PER_SOL
+            ),
+        );
+    }
+    if let Some((held, needed)) = usd1.filter(|(held, needed)| held < needed) {
+        let scale = 10f64.powi(USD1_DECIMALS);
+        return DryRunCheck::fail(
+            CHECK,
+            format!(
+                "Wallet has {:.2} USD1 but the buy needs {:.2} USD1",
+                held as f64 / scale,
+                needed as f64 / scale
+            ),
+        );
+    }
+
+    DryRunCheck::pass(
+        CHECK,
+        format!("Wallet has {:.9} SOL", balance_lamports as f64 / LAMPORTS_PER_SOL),
+    )
+}
+
+/// The settings can trade at all and a take-profit exit clears the fees,
+/// the pool `program`'s fees and exit slippage of the round trip
+pub fn settings_check(settings: &BotSettings, quote_mint: Option<&Pubkey>, program: Option<&Pubkey>) -> DryRunCheck {
+    const CHECK: &str = "settings";
+
+    let mut problems = Vec::new();
+    let buy_amount = match quote_mint {
+        Some(mint) if *mint == USD1 => settings.buy_usd1_amount,
+        _ => settings.buy_sol_amount,
+    };
+    if buy_amount <= 0.0 {
+        problems.push("buy amount is zero".to_string());
+    }
+
+    let leg_fee_sol = projected_tx_fee_sol(
+        settings.cu,
+        settings.priority_fee_micro_lamport,
+        settings.third_party_fee,
+    );
+    if !fee_within_cap(leg_fee_sol, settings.max_tx_fee_sol) {
+        problems.push(format!(
+            "projected fee {:.6} SOL exceeds max_tx_fee_sol {:.6}, every swap would be skipped",
+            leg_fee_sol, settings.max_tx_fee_sol
+        ));
+    }
+
+    // Percent gain a sell has to fill at to net back what the buy spent
+    let exit_slippage = settings.exit_slippage_for(&settings.pool_address);
+    let lowest_take_profit = settings
+        .regime_exits
+        .iter()
+        .flat_map(|exits| [exits.trending.take_profit, exits.ranging.take_profit])
+        .fold(settings.take_profit, f64::min);
+    // Fees are paid in SOL, so on USD1 pools only the pool fees and slippage are counted
+    let round_trip_fee_sol = if quote_mint == Some(&USD1) { 0.0 } else { 2.0 * leg_fee_sol };
+    match break_even_price(1.0, buy_amount, round_trip_fee_sol, swap_fee_rate(program), exit_slippage) {
+        Some(break_even) => {
+            let break_even_pct = (break_even - 1.0) * 100.0;
+            if lowest_take_profit <= break_even_pct {
+                problems.push(format!(
+                    "take_profit {:.2}% doesn't clear the {:.2}% break-even after fees and {:.2}% exit slippage",
+                    lowest_take_profit, break_even_pct, exit_slippage
+                ));
+            }
+        }
+        None => problems.push(format!(
+            "exit slippage {:.2}% leaves no price that breaks even",
+            exit_slippage
+        )),
+    }
+
+    if problems.is_empty() {
+        DryRunCheck::pass(CHECK, "Settings are coherent and a take-profit exit is net profitable")
+    } else {
+        DryRunCheck::fail(CHECK, problems.join("; "))
+    }
+}
+
+/// Every relay the bot submits through is initialized and answered its probe
+pub fn submission_check(missing: &[String], probes: &[ServiceCheck]) -> DryRunCheck {
+    const CHECK: &str = "submission";
+
+    let mut problems: Vec<String> = missing
+        .iter()
+        .map(|service| format!("{} isn't configured", service))
+        .collect();
+    problems.extend(probes.iter().filter(|probe| probe.state != ServiceState::Ok).map(|probe| {
+        format!(
+            "{} is {:?}{}",
+            probe.service,
+            probe.state,
+            probe.detail.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default()
+        )
+    }));
+
+    if problems.is_empty() {
+        let reached: Vec<&str> = probes.iter().map(|probe| probe.service.as_str()).collect();
+        DryRunCheck::pass(CHECK, format!("Reached {}", reached.join(", ")))
+    } else {
+        DryRunCheck::fail(CHECK, problems.join("; "))
+    }
+}
+
+/// The simulated buy setup ran without error
+pub fn simulation_check(outcome: Result<RpcSimulateTransactionResult, String>) -> DryRunCheck {
+    const CHECK: &str = "simulation";
+
+    match outcome {
+        Err(e) => DryRunCheck::fail(CHECK, format!("Simulation request failed: {}", e)),
+        Ok(result) => match result.err {
+            Some(err) => {
+                let last_log = result
+                    .logs
+                    .as_ref()
+                    .and_then(|logs| logs.last())
+                    .map(|log| format!(": {}", log))
+                    .unwrap_or_default();
+                DryRunCheck::fail(CHECK, format!("Simulation failed with {:?}{}", err, last_log))
+            }
+            None => DryRunCheck::pass(
+                CHECK,
+                format!("Simulation used {} compute units", result.units_consumed.unwrap_or(0)),
+            ),
+        },
+    }
+}
+
+pub fn live_trading_check(settings: &BotSettings, live_trading_enabled: bool) -> DryRunCheck {
+    const CHECK: &str = "live_trading";
+
+    if settings.submits_live(live_trading_enabled) {
+        DryRunCheck::pass(CHECK, "Bot will submit live trades")
+    } else if !live_trading_enabled {
+        DryRunCheck::fail(CHECK, "LIVE_TRADING_ENABLED is off, the bot would paper trade")
+    } else if settings.observer {
+        DryRunCheck::fail(CHECK, "Bot is an observer and never submits")
+    } else {
+        DryRunCheck::fail(CHECK, "live_trading_acknowledged isn't set, the bot would paper trade")
+    }
+}
+
+async fn probe_relays(settings: &BotSettings) -> DryRunCheck {
+    let services = if settings.multi_relay.is_empty() {
+        vec![settings.confirm_service.clone()]
+    } else {
+        settings.multi_relay.clone()
+    };
+
+    let mut missing = Vec::new();
+    let mut urls = Vec::new();
+    for service in &services {
+        match relay_submit_url(service, &settings.zslot_region) {
+            Some(relay) => urls.push(relay),
+            None => missing.push(service.clone()),
+        }
+    }
+
+    let client = Client::new();
+    let probes = join_all(urls.iter().map(|(service, url)| probe_http(&client, service, url))).await;
+    submission_check(&missing, &probes)
+}
+
+/// Runs the buy's account setup and SOL wrap under the bot's compute budget,
+/// unsigned. The swap instruction itself is only built from a live swap on
+/// the pool, so it isn't part of the simulation.
+async fn simulate_buy_setup(
+    settings: &BotSettings,
+    wallet: &Pubkey,
+    atas: &[(Pubkey, Pubkey, Pubkey)],
+    wrap_lamports: u64,
+) -> DryRunCheck {
+    let mut pure_ix: Vec<_> = atas
+        .iter()
+        .map(|(_, mint, program)| create_associated_token_account_idempotent(wallet, wallet, mint, program))
+        .collect();
+    if let Some((wsol_ata, _, _)) = atas.iter().find(|(_, mint, _)| *mint == WSOL) {
+        pure_ix.push(system_instruction::transfer(wallet, wsol_ata, wrap_lamports));
+        match spl_token::instruction::sync_native(&spl_token::ID, wsol_ata) {
+            Ok(ix) => pure_ix.push(ix),
+            Err(e) => return simulation_check(Err(e.to_string())),
+        }
+    }
+    let ixs = Tips {
+        tip_sol_amount: settings.third_party_fee,
+        tip_addr_idx: 0,
+        cu: Some(settings.cu),
+        priority_fee_micro_lamport: Some(settings.priority_fee_micro_lamport),
+        payer: *wallet,
+        pure_ix,
+    }
+    .with_compute_budget();
+
+    let transaction = VersionedTransaction::from(Transaction::new_unsigned(Message::new(&ixs, Some(wallet))));
+    let outcome = RPC_CLIENT
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::processed()),
+                encoding: Some(UiTransactionEncoding::Base64),
+                ..Default::default()
+            },
+        )
+        .await
+        .map(|response| response.value)
+        .map_err(|e| rpc_error_detail(&e));
+    simulation_check(outcome)
+}
+
+async fn fetch_accounts(keys: &[Pubkey]) -> Result<Vec<Option<Account>>, String> {
+    RPC_CLIENT
+        .get_multiple_accounts(keys)
+        .await
+        .map_err(|e| format!("RPC request failed: {}", rpc_error_detail(&e)))
+}
+
+fn skipped(check: &str, reason: &str) -> DryRunCheck {
+    DryRunCheck::fail(check, format!("Skipped, {}", reason))
+}
+
+/// Pool, wallet and simulation checks, with the pool's SOL or USD1 mint and
+/// its program once they are known
+async fn chain_checks(settings: &BotSettings, wallet: &Pubkey) -> (Vec<DryRunCheck>, Option<(Pubkey, Pubkey)>) {
+    // Checks after the one that failed can't run without its result
+    let failed = |mut checks: Vec<DryRunCheck>| {
+        let reason = format!("the {} check failed", checks.last().map(|c| c.check.as_str()).unwrap_or_default());
+        checks.extend(
+            ["pool", "wallet", "simulation"]
+                .into_iter()
+                .filter(|name| !checks.iter().any(|c| c.check == *name))
+                .map(|name| skipped(name, &reason))
+                .collect::<Vec<_>>(),
+        );
+        (checks, None)
+    };
+
+    let Ok(pool) = settings.pool_address.parse::<Pubkey>() else {
+        return failed(vec![DryRunCheck::fail("pool", "Pool address is invalid")]);
+    };
+    let accounts = match fetch_accounts(&[pool, *wallet]).await {
+        Ok(accounts) => accounts,
+        Err(e) => return failed(vec![DryRunCheck::fail("pool", e)]),
+    };
+    let now_unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (pool_check, vaults) = pool_check(accounts[0].as_ref(), now_unix);
+    let Some(vaults) = vaults.filter(|_| pool_check.passed) else {
+        return failed(vec![pool_check]);
+    };
+    let balance_lamports = accounts[1].as_ref().map(|account| account.lamports).unwrap_or(0);
+    let program = accounts[0].as_ref().map(|account| account.owner).unwrap_or_default();
+
+    // Token accounts the buy needs, under each mint's own token program
+    let mints = [vaults.base_mint, vaults.quote_mint];
+    let mint_accounts = match fetch_accounts(&mints).await {
+        Ok(accounts) => accounts,
+        Err(e) => return failed(vec![pool_check, DryRunCheck::fail("wallet", e)]),
+    };
+    let atas: Vec<(Pubkey, Pubkey, Pubkey)> = mints
+        .into_iter()
+        .zip(&mint_accounts)
+        .map(|(mint, account)| {
+            let program = account.as_ref().map(|a| a.owner).unwrap_or(spl_token::ID);
+            (get_associated_token_address_with_program_id(wallet, &mint, &program), mint, program)
+        })
+        .collect();
+    let ata_keys: Vec<Pubkey> = atas.iter().map(|(ata, _, _)| *ata).collect();
+    let ata_accounts = match fetch_accounts(&ata_keys).await {
+        Ok(accounts) => accounts,
+        Err(e) => return failed(vec![pool_check, DryRunCheck::fail("wallet", e)]),
+    };
+    let missing_atas = ata_accounts.iter().filter(|account| account.is_none()).count();
+
+    let quote_mint = if [WSOL, USD1].contains(&vaults.quote_mint) {
+        vaults.quote_mint
+    } else {
+        vaults.base_mint
+    };
+    let leg_fee_sol = projected_tx_fee_sol(
+        settings.cu,
+        settings.priority_fee_micro_lamport,
+        settings.third_party_fee,
+    );
+    let (wrap_lamports, usd1) = if quote_mint == USD1 {
+        let held = atas
+            .iter()
+            .zip(&ata_accounts)
+            .find(|((_, mint, _), _)| *mint == USD1)
+            .and_then(|(_, account)| account.as_ref())
+            .and_then(|account| token_account_amount(&account.data))
+            .unwrap_or(0);
+        let needed = (settings.buy_usd1_amount * 10f64.powi(USD1_DECIMALS)) as u64;
+        (0, Some((held, needed)))
+    } else {
+        ((settings.buy_sol_amount * LAMPORTS_PER_SOL) as u64, None)
+    };
+    let required = required_buy_lamports(wrap_lamports, missing_atas, 2.0 * leg_fee_sol);
+
+    let checks = vec![
+        pool_check,
+        wallet_check(balance_lamports, required, usd1),
+        simulate_buy_setup(settings, wallet, &atas, wrap_lamports).await,
+    ];
+    (checks, Some((quote_mint, program)))
+}
+
+/// Checks `settings` end to end for `wallet` without trading: the pool, the
+/// wallet's funds, the settings, the relays and a simulated buy setup
+pub async fn dry_run(settings: &BotSettings, wallet: &Pubkey) -> DryRunReport {
+    let ((mut checks, pool), submission) =
+        tokio::join!(chain_checks(settings, wallet), probe_relays(settings));
+    let (quote_mint, program) = pool.map_or((None, None), |(mint, program)| (Some(mint), Some(program)));
+    checks.push(settings_check(settings, quote_mint.as_ref(), program.as_ref()));
+    checks.push(submission);
+    checks.push(live_trading_check(settings, *LIVE_TRADING_ENABLED));
+
+    DryRunReport {
+        pool_id: settings.pool_address.clone(),
+        ready: checks.iter().all(|check| check.passed),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::models::bot::{ExitParams, RegimeExits};
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+    use std::time::Instant;
+
+    // AmmInfo: status leads, pc_vault_mint follows the u64 header, fees,
+    // state data and the three pubkeys before it
+    const AMM_V4_PC_MINT_OFFSET: usize = 432;
+    const AMM_V4_STATUS_SWAP_ONLY: u64 = 6;
+
+    fn amm_v4_pool(status: u64, pc_mint: Pubkey) -> Account {
+        let mut data = vec![0u8; 752];
+        data[..8].copy_from_slice(&status.to_le_bytes());
+        data[AMM_V4_PC_MINT_OFFSET..AMM_V4_PC_MINT_OFFSET + 32].copy_from_slice(pc_mint.as_ref());
+        Account { lamports: 1, data, owner: RAY_V4_PROGRAM_ID, executable: false, rent_epoch: 0 }
+    }
+
+    fn profitable_settings() -> BotSettings {
+        let mut settings = BotSettings::new("user".to_string(), "bot".to_string(), Pubkey::new_unique().to_string());
+        settings.buy_sol_amount = 1.0;
+        settings.exit_slippage = 1.0;
+        settings.take_profit = 5.0;
+        settings
+    }
+
+    fn simulation(err: Option<TransactionError>, logs: Vec<&str>) -> RpcSimulateTransactionResult {
+        RpcSimulateTransactionResult {
+            err,
+            logs: Some(logs.into_iter().map(String::from).collect()),
+            accounts: None,
+            units_consumed: Some(12_000),
+            return_data: None,
+            inner_instructions: None,
+            replacement_blockhash: None,
+        }
+    }
+
+    #[test]
+    fn pool_check_fails_missing_foreign_unpaired_and_disabled_pools() {
+        let (check, _) = pool_check(None, 0);
+        assert!(!check.passed);
+        assert_eq!(check.detail, "Pool account not found");
+
+        let mut foreign = amm_v4_pool(AMM_V4_STATUS_SWAP_ONLY, WSOL);
+        foreign.owner = Pubkey::new_unique();
+        let (check, _) = pool_check(Some(&foreign), 0);
+        assert!(!check.passed);
+        assert!(check.detail.contains("not Raydium AMM v4"));
+
+        let mut truncated = amm_v4_pool(AMM_V4_STATUS_SWAP_ONLY, WSOL);
+        truncated.data.truncate(16);
+        let (check, _) = pool_check(Some(&truncated), 0);
+        assert_eq!(check.detail, "Could not decode the Raydium AMM v4 pool state");
+
+        let (check, _) = pool_check(Some(&amm_v4_pool(AMM_V4_STATUS_SWAP_ONLY, Pubkey::new_unique())), 0);
+        assert_eq!(check.detail, "Raydium AMM v4 pool isn't paired with SOL or USD1");
+
+        let (check, _) = pool_check(Some(&amm_v4_pool(2, WSOL)), 0);
+        assert_eq!(check.detail, "Raydium AMM v4 pool isn't accepting swaps");
+
+        let (check, vaults) = pool_check(Some(&amm_v4_pool(AMM_V4_STATUS_SWAP_ONLY, WSOL)), 0);
+        assert!(check.passed, "{}", check.detail);
+        assert_eq!(vaults.unwrap().quote_mint, WSOL);
+    }
+
+    #[test]
+    fn wallet_check_fails_short_sol_or_usd1() {
+        assert!(!wallet_check(999, 1_000, None).passed);
+        assert!(wallet_check(1_000, 1_000, None).passed);
+
+        let short_usd1 = wallet_check(1_000, 1_000, Some((4_000_000, 5_000_000)));
+        assert!(!short_usd1.passed);
+        assert_eq!(short_usd1.detail, "Wallet has 4.00 USD1 but the buy needs 5.00 USD1");
+    }
+
+    #[test]
+    fn settings_check_fails_unprofitable_or_fee_capped_settings() {
+        assert!(settings_check(&profitable_settings(), Some(&WSOL), None).passed);
+
+        // 1% exit slippage and 0.3% pool fee each way already cost more than 1%
+        let mut thin = profitable_settings();
+        thin.take_profit = 1.0;
+        let check = settings_check(&thin, Some(&WSOL), None);
+        assert!(!check.passed);
+        assert!(check.detail.contains("doesn't clear"), "{}", check.detail);
+
+        // A regime's lower take-profit has to clear break-even too
+        let mut regimes = profitable_settings();
+        regimes.regime_exits = Some(RegimeExits {
+            trending: ExitParams { take_profit: 10.0, stop_loss: 5.0 },
+            ranging: ExitParams { take_profit: 0.5, stop_loss: 5.0 },
+            window_secs: 60,
+            trend_threshold: 0.5,
+        });
+        assert!(!settings_check(&regimes, Some(&WSOL), None).passed);
+
+        let mut capped = profitable_settings();
+        capped.max_tx_fee_sol = 0.00001;
+        let check = settings_check(&capped, Some(&WSOL), None);
+        assert!(check.detail.contains("exceeds max_tx_fee_sol"), "{}", check.detail);
+
+        // Full exit slippage, the default, never breaks even
+        let mut no_floor = profitable_settings();
+        no_floor.exit_slippage = 100.0;
+        assert!(!settings_check(&no_floor, Some(&WSOL), None).passed);
+
+        let mut no_usd1 = profitable_settings();
+        no_usd1.buy_usd1_amount = 0.0;
+        let check = settings_check(&no_usd1, Some(&USD1), None);
+        assert!(check.detail.contains("buy amount is zero"), "{}", check.detail);
+    }
+
+    #[test]
+    fn submission_check_fails_unconfigured_or_refused_relays() {
+        let ok = ServiceCheck { service: "Jito".to_string(), state: ServiceState::Ok, latency_ms: 3, detail: None };
+        assert!(submission_check(&[], std::slice::from_ref(&ok)).passed);
+
+        let check = submission_check(&["NOZOMI".to_string()], std::slice::from_ref(&ok));
+        assert_eq!(check.detail, "NOZOMI isn't configured");
+
+        let refused = crate::utils::self_test::check(
+            "ZSlot",
+            Instant::now(),
+            ServiceState::Unauthorized,
+            Some("HTTP 401 Unauthorized".to_string()),
+        );
+        let check = submission_check(&[], &[ok, refused]);
+        assert!(!check.passed);
+        assert_eq!(check.detail, "ZSlot is Unauthorized (HTTP 401 Unauthorized)");
+    }
+
+    #[tokio::test]
+    async fn unreachable_relay_is_reported_without_its_key() {
+        let down = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            format!("http://{}/?api-key=relay-secret", listener.local_addr().unwrap())
+        };
+        let probe = probe_http(&reqwest::Client::new(), "Nozomi", &down).await;
+        let check = submission_check(&[], &[probe]);
+        assert!(!check.passed);
+        assert!(check.detail.starts_with("Nozomi is Unreachable ("));
+        assert!(!check.detail.contains("relay-secret"), "{}", check.detail);
+    }
+
+    #[test]
+    fn simulation_check_fails_errors_and_unreachable_rpc() {
+        assert!(simulation_check(Ok(simulation(None, vec![]))).passed);
+
+        let failed = simulation_check(Ok(simulation(
+            Some(TransactionError::InstructionError(2, InstructionError::Custom(1))),
+            vec!["Program log: Error: insufficient lamports"],
+        )));
+        assert!(!failed.passed);
+        assert!(failed.detail.ends_with("insufficient lamports"), "{}", failed.detail);
+
+        assert!(!simulation_check(Err("connection refused".to_string())).passed);
+    }
+
+    #[test]
+    fn live_trading_check_fails_paper_trading_bots() {
+        let mut settings = profitable_settings();
+        assert!(!live_trading_check(&settings, false).passed);
+        assert!(!live_trading_check(&settings, true).passed);
+
+        settings.live_trading_acknowledged = true;
+        assert!(live_trading_check(&settings, true).passed);
+
+        settings.observer = true;
+        assert!(!live_trading_check(&settings, true).passed);
+    }
+}
@@ -0,0 +1,296 @@
+use solana_sdk::{compute_budget, pubkey::Pubkey, transaction::VersionedTransaction};
+
+use carbon_pump_swap_decoder::PROGRAM_ID as PUMPSWAP_PROGRAM_ID;
+use carbon_raydium_amm_v4_decoder::PROGRAM_ID as RAY_V4_PROGRAM_ID;
+use carbon_raydium_cpmm_decoder::PROGRAM_ID as CPMM_PROGRAM_ID;
+use carbon_raydium_launchpad_decoder::PROGRAM_ID as LAUNCHPAD_PROGRAM_ID;
+
+use crate::{
+    backend::models::trade::FeeBreakdown,
+    utils::{
+        fill_event::decode_fill_event,
+        rent::TOKEN_ACCOUNT_RENT_LAMPORTS,
+        utils::{FEE_RATE, TRADE_FEE_RATE},
+    },
+};
+
+/// Base fee charged per transaction signature
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// Converts a lamport amount to SOL for reporting
+pub fn lamports_to_sol(lamports: i64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL
+}
+
+/// Pool fee assumed on each swap while the pool's program isn't known
+pub const SWAP_FEE_RATE: f64 = 0.003;
+
+/// Pool fee `program` charges on each swap. CPMM pools are priced at the
+/// default 0.25% fee tier and Launchpad at the rate its swap quote takes;
+/// unknown programs get `SWAP_FEE_RATE`.
+pub fn swap_fee_rate(program: Option<&Pubkey>) -> f64 {
+    match program {
+        Some(program) if *program == RAY_V4_PROGRAM_ID || *program == CPMM_PROGRAM_ID => 0.0025,
+        Some(program) if *program == LAUNCHPAD_PROGRAM_ID => (TRADE_FEE_RATE + FEE_RATE) as f64 / 1_000_000.0,
+        // 0.20% to liquidity, 0.05% protocol and 0.05% coin creator fee
+        Some(program) if *program == PUMPSWAP_PROGRAM_ID => 0.003,
+        _ => SWAP_FEE_RATE,
+    }
+}
+
+/// Total fee a swap submission is projected to cost in SOL: the signature
+/// fee, the compute budget priced at `priority_fee_micro_lamport` and the
+/// relay tip.
+pub fn projected_tx_fee_sol(cu: u64, priority_fee_micro_lamport: u64, tip_sol: f64) -> f64 {
+    let priority_lamports = (cu as u128 * priority_fee_micro_lamport as u128).div_ceil(1_000_000);
+    (LAMPORTS_PER_SIGNATURE as f64 + priority_lamports as f64) / LAMPORTS_PER_SOL + tip_sol
+}
+
+/// Units an instruction gets when the transaction sets no compute unit limit
+const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u64 = 200_000;
+
+/// Most compute units a transaction may use
+const MAX_TRANSACTION_COMPUTE_UNITS: u64 = 1_400_000;
+
+/// SetComputeUnitLimit and SetComputeUnitPrice instruction tags
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// `projected_tx_fee_sol` of a signed, base64 `encoded_tx`, priced from the
+/// compute unit limit and price its own compute budget instructions set.
+/// Without a limit each of its other instructions gets the runtime default.
+pub fn encoded_tx_fee_sol(encoded_tx: &str, tip_sol: f64) -> Option<f64> {
+    let transaction: VersionedTransaction = bincode::deserialize(&base64::decode(encoded_tx).ok()?).ok()?;
+    let keys = transaction.message.static_account_keys();
+    let mut limit = None;
+    let mut price = 0;
+    let mut other_instructions = 0;
+    for ix in transaction.message.instructions() {
+        if keys.get(ix.program_id_index as usize) != Some(&compute_budget::id()) {
+            other_instructions += 1;
+            continue;
+        }
+        match ix.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_LIMIT, value)) => {
+                limit = Some(u32::from_le_bytes(value.try_into().ok()?) as u64);
+            }
+            Some((&SET_COMPUTE_UNIT_PRICE, value)) => price = u64::from_le_bytes(value.try_into().ok()?),
+            _ => {}
+        }
+    }
+    let limit = limit
+        .unwrap_or(other_instructions * DEFAULT_INSTRUCTION_COMPUTE_UNITS)
+        .min(MAX_TRANSACTION_COMPUTE_UNITS);
+    let signature_fees = (transaction.signatures.len() as u64).saturating_sub(1) * LAMPORTS_PER_SIGNATURE;
+    Some(projected_tx_fee_sol(limit, price, tip_sol) + signature_fees as f64 / LAMPORTS_PER_SOL)
+}
+
+/// What a buy and its sell cost on top of the pool fees: both transactions'
+/// fees and tips, and rent for the token account the buy creates
+pub fn round_trip_cost_sol(cu: u64, priority_fee_micro_lamport: u64, tip_sol: f64) -> f64 {
+    2.0 * projected_tx_fee_sol(cu, priority_fee_micro_lamport, tip_sol)
+        + TOKEN_ACCOUNT_RENT_LAMPORTS as f64 / LAMPORTS_PER_SOL
+}
+
+/// Smallest buy whose round-trip cost is at most `max_cost_pct` of it
+pub fn min_viable_buy_sol(round_trip_cost_sol: f64, max_cost_pct: f64) -> f64 {
+    round_trip_cost_sol * 100.0 / max_cost_pct
+}
+
+/// Whether `projected_sol` is allowed under `max_tx_fee_sol` (zero disables the cap)
+pub fn fee_within_cap(projected_sol: f64, max_tx_fee_sol: f64) -> bool {
+    max_tx_fee_sol <= 0.0 || projected_sol <= max_tx_fee_sol
+}
+
+/// Pool price at which selling the position nets exactly the SOL spent on
+/// it: the buy amount plus `round_trip_fee_sol` (both transactions' fees and
+/// tips), after the pool fee on both swaps and a sell filling at the worst
+/// price `exit_slippage_pct` allows. `None` when no price can break even.
+pub fn break_even_price(
+    bought_price: f64,
+    buy_sol: f64,
+    round_trip_fee_sol: f64,
+    swap_fee_rate: f64,
+    exit_slippage_pct: f64,
+) -> Option<f64> {
+    let retained = (1.0 - swap_fee_rate).powi(2) * (1.0 - exit_slippage_pct / 100.0);
+    if bought_price <= 0.0 || buy_sol <= 0.0 || retained <= 0.0 {
+        return None;
+    }
+    Some(bought_price * (buy_sol + round_trip_fee_sol) / (buy_sol * retained))
+}
+
+/// Pool fees `user` paid according to one PumpSwap buy or sell event CPI
+pub fn pool_fees_from_event(data: &[u8], user: &Pubkey) -> Option<FeeBreakdown> {
+    let fill = decode_fill_event(data)?;
+    (fill.user == *user).then(|| FeeBreakdown {
+        lp_fee_lamports: fill.lp_fee as i64,
+        protocol_fee_lamports: fill.protocol_fee as i64,
+        coin_creator_fee_lamports: fill.coin_creator_fee as i64,
+        ..Default::default()
+    })
+}
+
+/// Fees of one confirmed fill: pool fees from the fill events among the
+/// transaction's inner instruction data, plus its network fee and tip
+pub fn fill_fee_breakdown<'a>(
+    inner_instruction_data: impl IntoIterator<Item = &'a [u8]>,
+    user: &Pubkey,
+    network_fee_lamports: u64,
+    tip_lamports: u64,
+) -> FeeBreakdown {
+    let mut fees = FeeBreakdown {
+        network_fee_lamports: network_fee_lamports as i64,
+        tip_lamports: tip_lamports as i64,
+        ..Default::default()
+    };
+    for data in inner_instruction_data {
+        if let Some(pool_fees) = pool_fees_from_event(data, user) {
+            fees.add(&pool_fees);
+        }
+    }
+    fees
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap_trips_when_fee_and_tip_exceed_it() {
+        // 200k CU at 1 lamport/CU + 0.001 SOL tip
+        let projected = projected_tx_fee_sol(200_000, 1_000_000, 0.001);
+        assert!((projected - 0.001205).abs() < 1e-12);
+
+        assert!(fee_within_cap(projected, 0.0));
+        assert!(fee_within_cap(projected, 0.002));
+        assert!(!fee_within_cap(projected, 0.001));
+    }
+
+    #[test]
+    fn signed_fee_is_priced_from_the_transaction_compute_budget() {
+        use solana_sdk::{
+            compute_budget::ComputeBudgetInstruction, hash::Hash, message::Message, signature::Keypair,
+            signer::Signer, system_instruction, transaction::Transaction,
+        };
+
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let encode = |ixs: &[solana_sdk::instruction::Instruction]| {
+            let message = Message::new(ixs, Some(&payer.pubkey()));
+            let transaction = VersionedTransaction::from(Transaction::new(&[&payer], message, Hash::default()));
+            base64::encode(bincode::serialize(&transaction).unwrap())
+        };
+
+        // 300k CU at 2 lamports/CU + 0.001 SOL tip, not the settings it was built from
+        let budgeted = encode(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+            ComputeBudgetInstruction::set_compute_unit_price(2_000_000),
+            transfer.clone(),
+        ]);
+        let fee = encoded_tx_fee_sol(&budgeted, 0.001).unwrap();
+        assert!((fee - 0.001605).abs() < 1e-12);
+
+        // No limit: the runtime's 200k per instruction, priced at zero
+        let fee = encoded_tx_fee_sol(&encode(&[transfer.clone(), transfer]), 0.0).unwrap();
+        assert!((fee - 0.000005).abs() < 1e-12);
+
+        assert_eq!(encoded_tx_fee_sol("not a transaction", 0.0), None);
+    }
+
+    #[test]
+    fn break_even_covers_fees_pool_fee_and_slippage() {
+        // No costs at all: break-even is the entry price
+        assert_eq!(break_even_price(2.0, 1.0, 0.0, 0.0, 0.0), Some(2.0));
+
+        // 1 SOL in, 0.01 SOL of fees and tips over both legs, 0.3% pool fee
+        // per swap, 1% exit slippage: 1.01 / (0.997² × 0.99) ≈ 1.02635
+        let be = break_even_price(1.0, 1.0, 0.01, SWAP_FEE_RATE, 1.0).unwrap();
+        assert!((be - 1.026_350_888).abs() < 1e-8);
+
+        // Each program's own pool fee, the 0.3% default when it isn't known
+        assert_eq!(swap_fee_rate(Some(&RAY_V4_PROGRAM_ID)), 0.0025);
+        assert_eq!(swap_fee_rate(Some(&LAUNCHPAD_PROGRAM_ID)), 0.0125);
+        assert_eq!(swap_fee_rate(None), SWAP_FEE_RATE);
+        let v4 = break_even_price(1.0, 1.0, 0.01, swap_fee_rate(Some(&RAY_V4_PROGRAM_ID)), 1.0).unwrap();
+        let launchpad = break_even_price(1.0, 1.0, 0.01, swap_fee_rate(Some(&LAUNCHPAD_PROGRAM_ID)), 1.0).unwrap();
+        assert!(v4 < be && be < launchpad);
+
+        // Fees weigh more on a smaller position, as does a wider slippage bound
+        let small = break_even_price(1.0, 0.1, 0.01, SWAP_FEE_RATE, 1.0).unwrap();
+        let loose = break_even_price(1.0, 1.0, 0.01, SWAP_FEE_RATE, 5.0).unwrap();
+        assert!(small > be && loose > be);
+
+        assert_eq!(break_even_price(1.0, 1.0, 0.01, SWAP_FEE_RATE, 100.0), None);
+    }
+
+    const BUY_EVENT_DISCRIMINATOR: [u8; 16] = [
+        0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d, 0x67, 0xf4, 0x52, 0x1f, 0x2c, 0xf5, 0x77, 0x77,
+    ];
+
+    /// Borsh bytes of a buy event; `current_layout` appends the volume
+    /// tracking fields `BuyEventTemp` expects
+    fn buy_event(user: &Pubkey, lp_fee: u64, protocol_fee: u64, creator_fee: u64, current_layout: bool) -> Vec<u8> {
+        let mut data = BUY_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_700_000_000_i64.to_le_bytes());
+        // base_amount_out .. quote_amount_in
+        for value in [1_000_u64, 0, 0, 0, 0, 0, 100_000_000] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [20, lp_fee, 5, protocol_fee, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // pool, user, token accounts, fee recipients, coin creator
+        for key in [Pubkey::new_unique(), *user, Pubkey::new_unique(), Pubkey::new_unique()] {
+            data.extend_from_slice(key.as_ref());
+        }
+        for _ in 0..3 {
+            data.extend_from_slice(Pubkey::new_unique().as_ref());
+        }
+        data.extend_from_slice(&5_u64.to_le_bytes());
+        data.extend_from_slice(&creator_fee.to_le_bytes());
+        if current_layout {
+            data.push(1);
+            for value in [0_u64, 0, 0] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            data.extend_from_slice(&0_i64.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn fill_events_break_down_pool_fees() {
+        let user = Pubkey::new_unique();
+        let event = buy_event(&user, 200_000, 50_000, 50_000, true);
+        assert_eq!(
+            pool_fees_from_event(&event, &user),
+            Some(FeeBreakdown {
+                lp_fee_lamports: 200_000,
+                protocol_fee_lamports: 50_000,
+                coin_creator_fee_lamports: 50_000,
+                ..Default::default()
+            })
+        );
+
+        // Older buys without the volume fields still decode
+        let legacy = buy_event(&user, 300_000, 0, 0, false);
+        assert_eq!(pool_fees_from_event(&legacy, &user).unwrap().lp_fee_lamports, 300_000);
+
+        // Someone else's fill in the same transaction is not ours
+        assert_eq!(pool_fees_from_event(&event, &Pubkey::new_unique()), None);
+
+        let fees = fill_fee_breakdown(
+            [event.as_slice(), &[1, 2, 3], legacy.as_slice()],
+            &user,
+            15_000,
+            1_000_000,
+        );
+        assert_eq!(fees.lp_fee_lamports, 500_000);
+        assert_eq!(fees.protocol_fee_lamports, 50_000);
+        assert_eq!(fees.network_fee_lamports, 15_000);
+        assert_eq!(fees.tip_lamports, 1_000_000);
+    }
+}
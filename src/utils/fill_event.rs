@@ -0,0 +1,282 @@
+use carbon_core::deserialize::CarbonDeserialize;
+use carbon_pump_swap_decoder::instructions::{buy_event::BuyEvent, sell_event::SellEvent};
+use carbon_raydium_launchpad_decoder::instructions::trade_event::TradeEvent;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::instructions::BuyEventTemp;
+use crate::utils::pool_reserves::PoolReserves;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// One PumpSwap buy or sell as its event CPI reports it, in raw units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PumpSwapFill {
+    pub user: Pubkey,
+    pub is_buy: bool,
+    /// Base tokens out of a buy or into a sell
+    pub base_amount: u64,
+    /// Quote the user paid for a buy or got from a sell, fees included
+    pub quote_amount: u64,
+    pub user_quote_token_account: Pubkey,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub coin_creator_fee: u64,
+    pub pool: Pubkey,
+    /// The pool's base and quote reserves the swap was priced against
+    pub pool_reserves_before: PoolReserves,
+    /// The same reserves once the swap and its LP fee have settled
+    pub pool_reserves_after: PoolReserves,
+}
+
+impl PumpSwapFill {
+    /// SOL per whole token the fill executed at. `quote_is_sol` is false for
+    /// pools that pair WSOL as the base, where the token is the quote side.
+    pub fn price_sol(&self, token_decimals: u8, quote_is_sol: bool) -> Option<f64> {
+        let (sol, tokens) = if quote_is_sol {
+            (self.quote_amount, self.base_amount)
+        } else {
+            (self.base_amount, self.quote_amount)
+        };
+        (tokens > 0).then(|| {
+            (sol as f64 / LAMPORTS_PER_SOL) / (tokens as f64 / 10f64.powi(token_decimals as i32))
+        })
+    }
+}
+
+/// Decodes a PumpSwap buy or sell event CPI. `BuyEventTemp` is the current
+/// buy layout; the decoder's shorter `BuyEvent` still covers transactions
+/// from before the volume fields.
+pub fn decode_fill_event(data: &[u8]) -> Option<PumpSwapFill> {
+    if let Some(event) = BuyEventTemp::deserialize(data) {
+        let before = PoolReserves { base: event.pool_base_token_reserves, quote: event.pool_quote_token_reserves };
+        return Some(PumpSwapFill {
+            user: event.user,
+            is_buy: true,
+            base_amount: event.base_amount_out,
+            quote_amount: event.user_quote_amount_in,
+            user_quote_token_account: event.user_quote_token_account,
+            lp_fee: event.lp_fee,
+            protocol_fee: event.protocol_fee,
+            coin_creator_fee: event.coin_creator_fee,
+            pool: event.pool,
+            pool_reserves_before: before,
+            // The LP fee stays in the pool, the protocol and creator fees don't
+            pool_reserves_after: PoolReserves {
+                base: before.base.saturating_sub(event.base_amount_out),
+                quote: before.quote.saturating_add(event.quote_amount_in_with_lp_fee),
+            },
+        });
+    }
+    if let Some(event) = BuyEvent::deserialize(data) {
+        let before = PoolReserves { base: event.pool_base_token_reserves, quote: event.pool_quote_token_reserves };
+        return Some(PumpSwapFill {
+            user: event.user,
+            is_buy: true,
+            base_amount: event.base_amount_out,
+            quote_amount: event.user_quote_amount_in,
+            user_quote_token_account: event.user_quote_token_account,
+            lp_fee: event.lp_fee,
+            protocol_fee: event.protocol_fee,
+            coin_creator_fee: event.coin_creator_fee,
+            pool: event.pool,
+            pool_reserves_before: before,
+            // The LP fee stays in the pool, the protocol and creator fees don't
+            pool_reserves_after: PoolReserves {
+                base: before.base.saturating_sub(event.base_amount_out),
+                quote: before.quote.saturating_add(event.quote_amount_in_with_lp_fee),
+            },
+        });
+    }
+    let event = SellEvent::deserialize(data)?;
+    let before = PoolReserves { base: event.pool_base_token_reserves, quote: event.pool_quote_token_reserves };
+    Some(PumpSwapFill {
+        user: event.user,
+        is_buy: false,
+        base_amount: event.base_amount_in,
+        quote_amount: event.user_quote_amount_out,
+        user_quote_token_account: event.user_quote_token_account,
+        lp_fee: event.lp_fee,
+        protocol_fee: event.protocol_fee,
+        coin_creator_fee: event.coin_creator_fee,
+        pool: event.pool,
+        pool_reserves_before: before,
+        pool_reserves_after: PoolReserves {
+            base: before.base.saturating_add(event.base_amount_in),
+            quote: before.quote.saturating_sub(event.quote_amount_out_without_lp_fee),
+        },
+    })
+}
+
+/// The `user`'s fill among a transaction's inner instruction data
+pub fn user_fill<'a>(
+    inner_instruction_data: impl IntoIterator<Item = &'a [u8]>,
+    user: &Pubkey,
+) -> Option<PumpSwapFill> {
+    inner_instruction_data
+        .into_iter()
+        .filter_map(decode_fill_event)
+        .find(|fill| fill.user == *user)
+}
+
+/// `pool`'s reserves before the transaction's first swap on it and after its
+/// last, as its event CPIs report them
+pub fn event_pool_reserves<'a>(
+    inner_instruction_data: impl IntoIterator<Item = &'a [u8]>,
+    pool: &Pubkey,
+) -> Option<(PoolReserves, PoolReserves)> {
+    inner_instruction_data
+        .into_iter()
+        .filter_map(decode_fill_event)
+        .filter(|fill| fill.pool == *pool)
+        .fold(None, |reserves, fill| match reserves {
+            None => Some((fill.pool_reserves_before, fill.pool_reserves_after)),
+            Some((before, _)) => Some((before, fill.pool_reserves_after)),
+        })
+}
+
+/// `pool`'s Launchpad curve reserves after the transaction's last trade on
+/// it: the virtual reserves moved by what the trades have taken out of base
+/// and put into quote
+pub fn launchpad_curve_reserves<'a>(
+    inner_instruction_data: impl IntoIterator<Item = &'a [u8]>,
+    pool: &Pubkey,
+) -> Option<PoolReserves> {
+    inner_instruction_data
+        .into_iter()
+        .filter_map(TradeEvent::deserialize)
+        .filter(|event| event.pool_state == *pool)
+        .last()
+        .map(|event| PoolReserves {
+            base: event.virtual_base.saturating_sub(event.real_base_after),
+            quote: event.virtual_quote.saturating_add(event.real_quote_after),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::token_decimals::reserve_price;
+
+    const SELL_EVENT_DISCRIMINATOR: [u8; 16] = [
+        0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d, 0x3e, 0x2f, 0x37, 0x0a, 0xa5, 0x03, 0xdc, 0x2a,
+    ];
+
+    /// Borsh bytes of a sell event CPI as the program emits it: 2,000 tokens
+    /// of a 6-decimal mint sold into a 40 SOL / 1M token pool
+    fn sell_event_log(user: &Pubkey, user_quote_token_account: &Pubkey) -> Vec<u8> {
+        sell_event_log_on(&Pubkey::new_unique(), user, user_quote_token_account)
+    }
+
+    fn sell_event_log_on(pool: &Pubkey, user: &Pubkey, user_quote_token_account: &Pubkey) -> Vec<u8> {
+        let mut data = SELL_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_750_000_000_i64.to_le_bytes());
+        // base_amount_in, min_quote_amount_out, user and pool reserves
+        for value in [2_000_000_000_u64, 70_000_000, 2_000_000_000, 0, 1_000_000_000_000, 40_000_000_000] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // quote_amount_out, lp fee bps, lp fee, protocol fee bps, protocol fee,
+        // quote_amount_out_without_lp_fee, user_quote_amount_out
+        for value in [79_840_319_u64, 20, 159_681, 5, 39_921, 79_680_638, 79_600_718] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let base_account = Pubkey::new_unique();
+        for key in [pool, user, &base_account, user_quote_token_account] {
+            data.extend_from_slice(key.as_ref());
+        }
+        // Fee recipient, its token account and the coin creator
+        for _ in 0..3 {
+            data.extend_from_slice(Pubkey::new_unique().as_ref());
+        }
+        data.extend_from_slice(&5_u64.to_le_bytes());
+        data.extend_from_slice(&39_920_u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn sell_event_gives_the_executed_price() {
+        let user = Pubkey::new_unique();
+        let quote_account = Pubkey::new_unique();
+        let log = sell_event_log(&user, &quote_account);
+
+        let fill = decode_fill_event(&log).unwrap();
+        assert!(!fill.is_buy);
+        assert_eq!(fill.user, user);
+        assert_eq!(fill.user_quote_token_account, quote_account);
+        assert_eq!((fill.base_amount, fill.quote_amount), (2_000_000_000, 79_600_718));
+        assert_eq!((fill.lp_fee, fill.protocol_fee, fill.coin_creator_fee), (159_681, 39_921, 39_920));
+
+        // 0.0796 SOL for 2,000 tokens, below the 0.00004 reserve ratio once
+        // price impact and fees are paid
+        let price = fill.price_sol(6, true).unwrap();
+        assert!((price - 0.000_039_800_359).abs() < 1e-15);
+        assert!(price < 40.0 / 1_000_000.0);
+
+        let inverted = PumpSwapFill { base_amount: 79_600_718, quote_amount: 2_000_000_000, ..fill };
+        assert_eq!(inverted.price_sol(6, false), Some(price));
+
+        let other = sell_event_log(&Pubkey::new_unique(), &quote_account);
+        assert_eq!(user_fill([other.as_slice(), &[1, 2, 3], log.as_slice()], &user), Some(fill));
+        assert_eq!(user_fill([other.as_slice()], &user), None);
+    }
+
+    #[test]
+    fn event_reserves_price_the_pool_after_the_swap() {
+        let pool = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let log = sell_event_log_on(&pool, &user, &Pubkey::new_unique());
+
+        let fill = decode_fill_event(&log).unwrap();
+        assert_eq!(fill.pool, pool);
+        assert_eq!(fill.pool_reserves_before, PoolReserves { base: 1_000_000_000_000, quote: 40_000_000_000 });
+        // 2,000 tokens in, the quote out less the LP fee that stays behind
+        assert_eq!(fill.pool_reserves_after, PoolReserves { base: 1_002_000_000_000, quote: 39_920_319_362 });
+
+        let (before, after) = event_pool_reserves([log.as_slice()], &pool).unwrap();
+        assert_eq!((before, after), (fill.pool_reserves_before, fill.pool_reserves_after));
+        let price = reserve_price(&after.quote.to_string(), 9, &after.base.to_string(), 6);
+        assert!((price - 39.920_319_362 / 1_002_000.0).abs() < 1e-12);
+        assert!(price < reserve_price(&before.quote.to_string(), 9, &before.base.to_string(), 6));
+
+        // Several swaps on the pool span the first one's before to the last one's after
+        let second = sell_event_log_on(&pool, &Pubkey::new_unique(), &Pubkey::new_unique());
+        let other_pool = sell_event_log(&user, &Pubkey::new_unique());
+        let (first_before, last_after) =
+            event_pool_reserves([log.as_slice(), other_pool.as_slice(), second.as_slice()], &pool).unwrap();
+        assert_eq!(first_before, fill.pool_reserves_before);
+        assert_eq!(last_after, decode_fill_event(&second).unwrap().pool_reserves_after);
+        assert_eq!(event_pool_reserves([other_pool.as_slice()], &pool), None);
+    }
+
+    #[test]
+    fn launchpad_reserves_follow_the_last_trade_on_the_pool() {
+        let trade_event = |pool: &Pubkey, real_base_after: u64, real_quote_after: u64| {
+            let mut data = vec![0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d, 0xbd, 0xdb, 0x7f, 0xd3, 0x4e, 0xe6, 0x61, 0xee];
+            data.extend_from_slice(pool.as_ref());
+            // total_base_sell, virtual base and quote, real base and quote before
+            for value in [793_100_000_000_000_u64, 1_073_025_605_596_382, 30_000_852_951, 0, 0] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            // amount in and out and the three fees
+            for value in [real_base_after, real_quote_after, 0, 0, 0, 0, 0] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            // Trade direction and pool status
+            data.extend_from_slice(&[0, 0]);
+            data
+        };
+        let pool = Pubkey::new_unique();
+        let first = trade_event(&pool, 100_000_000_000_000, 3_000_000_000);
+        let last = trade_event(&pool, 555_337_575_467_276, 32_182_704_639);
+        let other_pool = trade_event(&Pubkey::new_unique(), 0, 0);
+
+        let reserves = launchpad_curve_reserves([first.as_slice(), last.as_slice(), other_pool.as_slice()], &pool);
+        assert_eq!(
+            reserves,
+            Some(PoolReserves {
+                base: 1_073_025_605_596_382 - 555_337_575_467_276,
+                quote: 30_000_852_951 + 32_182_704_639,
+            })
+        );
+        assert_eq!(launchpad_curve_reserves([other_pool.as_slice()], &pool), None);
+    }
+}
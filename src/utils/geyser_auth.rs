@@ -0,0 +1,207 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use tokio::time::sleep;
+use yellowstone_grpc_client::{
+    ClientTlsConfig, GeyserGrpcBuilderError, GeyserGrpcClient, GeyserGrpcClientError,
+};
+use yellowstone_grpc_proto::tonic::{Code, Status};
+
+use crate::utils::self_test::{check, record_check, ServiceState};
+
+const GEYSER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait before the next attempt grows with each failed one
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeyserConnectError {
+    /// The endpoint refused `X_TOKEN`, retrying with it won't help
+    Unauthorized(String),
+    /// The endpoint couldn't be reached or failed for another reason
+    Unavailable(String),
+}
+
+impl fmt::Display for GeyserConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeyserConnectError::Unauthorized(detail) => {
+                write!(f, "Geyser rejected X_TOKEN: {}", detail)
+            }
+            GeyserConnectError::Unavailable(detail) => write!(f, "Geyser unavailable: {}", detail),
+        }
+    }
+}
+
+impl std::error::Error for GeyserConnectError {}
+
+/// Unauthenticated and PermissionDenied mean a missing, expired or wrong
+/// token; tonic maps plain HTTP 401/403 from gateways onto the same codes
+pub fn classify_status(status: &Status) -> GeyserConnectError {
+    match status.code() {
+        Code::Unauthenticated | Code::PermissionDenied => {
+            GeyserConnectError::Unauthorized(status.message().to_string())
+        }
+        _ => GeyserConnectError::Unavailable(status.to_string()),
+    }
+}
+
+fn from_builder_error(err: GeyserGrpcBuilderError) -> GeyserConnectError {
+    match err {
+        // A token that isn't valid header text can never be accepted
+        GeyserGrpcBuilderError::MetadataValueError(err) => {
+            GeyserConnectError::Unauthorized(format!("invalid X_TOKEN: {}", err))
+        }
+        GeyserGrpcBuilderError::TonicError(err) => GeyserConnectError::Unavailable(err.to_string()),
+    }
+}
+
+/// Connects with the token and makes one authenticated call. The pipeline's
+/// datasource only logs a refused subscription, so this is where a bad token
+/// is told apart from a transient disconnect.
+pub async fn check_geyser(url: &str, x_token: Option<String>) -> Result<(), GeyserConnectError> {
+    let mut builder = GeyserGrpcClient::build_from_shared(url.to_string())
+        .and_then(|builder| builder.x_token(x_token))
+        .map_err(from_builder_error)?
+        .connect_timeout(GEYSER_TIMEOUT)
+        .timeout(GEYSER_TIMEOUT);
+    if url.starts_with("https") {
+        builder = builder
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .map_err(from_builder_error)?;
+    }
+
+    let mut client = builder.connect().await.map_err(from_builder_error)?;
+    match client.get_version().await {
+        Ok(_) => Ok(()),
+        Err(GeyserGrpcClientError::TonicStatus(status)) => Err(classify_status(&status)),
+        Err(err) => Err(GeyserConnectError::Unavailable(err.to_string())),
+    }
+}
+
+/// Retries an unreachable endpoint up to `attempts` times but gives up on
+/// the first refused token, recording the outcome for the health endpoint
+pub async fn wait_for_geyser(
+    url: &str,
+    x_token: Option<String>,
+    attempts: u32,
+) -> Result<(), GeyserConnectError> {
+    let mut attempt = 1;
+    loop {
+        let started = Instant::now();
+        let err = match check_geyser(url, x_token.clone()).await {
+            Ok(()) => {
+                record_check(check("Geyser", started, ServiceState::Ok, None));
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+
+        match &err {
+            GeyserConnectError::Unauthorized(_) => {
+                record_check(check("Geyser", started, ServiceState::Unauthorized, Some(err.to_string())));
+                return Err(err);
+            }
+            GeyserConnectError::Unavailable(_) if attempt >= attempts => {
+                record_check(check("Geyser", started, ServiceState::Unreachable, Some(err.to_string())));
+                return Err(err);
+            }
+            GeyserConnectError::Unavailable(_) => {
+                tracing::warn!(
+                    "⚠️ Geyser connection attempt {}/{} failed: {}",
+                    attempt,
+                    attempts,
+                    err
+                );
+                sleep(RETRY_BASE_DELAY * attempt).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::self_test::latest_self_test;
+    use hyper::{
+        header::{HeaderMap, HeaderValue},
+        service::{make_service_fn, service_fn},
+        Body, Response, Server,
+    };
+    use std::convert::Infallible;
+
+    /// gRPC server answering every call with `code`, the way a Geyser
+    /// endpoint answers a subscription with a bad token
+    fn mock_geyser(code: Code) -> String {
+        let make_service = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |_request| async move {
+                let (mut sender, body) = Body::channel();
+                tokio::spawn(async move {
+                    if code == Code::Ok {
+                        // GetVersionResponse { version: "1" }
+                        let _ = sender
+                            .send_data(vec![0, 0, 0, 0, 3, 0x0a, 1, b'1'].into())
+                            .await;
+                    }
+                    let mut trailers = HeaderMap::new();
+                    trailers.insert("grpc-status", HeaderValue::from(code as i32));
+                    trailers.insert("grpc-message", HeaderValue::from_static("invalid x-token"));
+                    let _ = sender.send_trailers(trailers).await;
+                });
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header("content-type", "application/grpc")
+                        .body(body)
+                        .unwrap(),
+                )
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into())
+            .http2_only(true)
+            .serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn refused_token_is_fatal_and_unreachable_endpoint_is_not() {
+        let refused = mock_geyser(Code::Unauthenticated);
+        let err = wait_for_geyser(&refused, Some("expired".to_string()), 3)
+            .await
+            .unwrap_err();
+        assert_eq!(err, GeyserConnectError::Unauthorized("invalid x-token".to_string()));
+        let recorded = latest_self_test()
+            .into_iter()
+            .find(|check| check.service == "Geyser")
+            .unwrap();
+        assert_eq!(recorded.state, ServiceState::Unauthorized);
+
+        let forbidden = mock_geyser(Code::PermissionDenied);
+        assert!(matches!(
+            check_geyser(&forbidden, None).await,
+            Err(GeyserConnectError::Unauthorized(_))
+        ));
+
+        let up = mock_geyser(Code::Ok);
+        assert_eq!(check_geyser(&up, Some("valid".to_string())).await, Ok(()));
+
+        let down = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        assert!(matches!(
+            check_geyser(&down, None).await,
+            Err(GeyserConnectError::Unavailable(_))
+        ));
+
+        // Header text can't carry a newline, so the token can never work
+        assert!(matches!(
+            check_geyser(&up, Some("bad\ntoken".to_string())).await,
+            Err(GeyserConnectError::Unauthorized(_))
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+use carbon_pump_swap_decoder::PROGRAM_ID as PUMPSWAP_PROGRAM_ID;
+use carbon_raydium_amm_v4_decoder::PROGRAM_ID as RAY_V4_PROGRAM_ID;
+use carbon_raydium_cpmm_decoder::PROGRAM_ID as CPMM_PROGRAM_ID;
+use carbon_raydium_launchpad_decoder::PROGRAM_ID as LAUNCHPAD_PROGRAM_ID;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+use crate::config::RPC_CLIENT;
+
+/// Lighthouse, whose assertions fail the transaction when an account doesn't
+/// hold what they state
+pub const LIGHTHOUSE_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("L2TExMFKdjpN9kozasaurPirfHy9P8sbXoAN1qA3S95");
+
+const ASSERT_TOKEN_ACCOUNT: u8 = 8;
+const LOG_LEVEL_SILENT: u8 = 0;
+const TOKEN_ACCOUNT_AMOUNT: u8 = 2;
+const GREATER_THAN_OR_EQUAL: u8 = 4;
+
+const PUMPSWAP_BUY: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+const PUMPSWAP_SELL: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+const LAUNCHPAD_BUY_EXACT_IN: [u8; 8] = [250, 234, 13, 123, 213, 156, 19, 236];
+const LAUNCHPAD_SELL_EXACT_IN: [u8; 8] = [149, 39, 222, 155, 211, 124, 152, 26];
+const CPMM_SWAP_BASE_INPUT: [u8; 8] = [146, 190, 90, 218, 196, 30, 51, 222];
+const RAY_V4_SWAP_BASE_IN: u8 = 9;
+const RAY_V4_SWAP_BASE_OUT: u8 = 11;
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+/// Token account a swap instruction pays out to and the least it must
+/// receive, for the swaps the processors build
+pub fn swap_min_output(ix: &Instruction) -> Option<(Pubkey, u64)> {
+    let account = |index: usize| ix.accounts.get(index).map(|meta| meta.pubkey);
+    let data = ix.data.as_slice();
+
+    if ix.program_id == PUMPSWAP_PROGRAM_ID {
+        // Buys name the exact base amount out, sells a minimum quote amount
+        match data.get(..8)? {
+            d if d == PUMPSWAP_BUY => Some((account(5)?, u64_at(data, 8)?)),
+            d if d == PUMPSWAP_SELL => Some((account(6)?, u64_at(data, 16)?)),
+            _ => None,
+        }
+    } else if ix.program_id == LAUNCHPAD_PROGRAM_ID {
+        match data.get(..8)? {
+            d if d == LAUNCHPAD_BUY_EXACT_IN => Some((account(5)?, u64_at(data, 16)?)),
+            d if d == LAUNCHPAD_SELL_EXACT_IN => Some((account(6)?, u64_at(data, 16)?)),
+            _ => None,
+        }
+    } else if ix.program_id == CPMM_PROGRAM_ID {
+        (data.get(..8)? == CPMM_SWAP_BASE_INPUT).then_some((account(5)?, u64_at(data, 16)?))
+    } else if ix.program_id == RAY_V4_PROGRAM_ID {
+        // The destination is second to last with or without target orders
+        let destination = account(ix.accounts.len().checked_sub(2)?)?;
+        match *data.first()? {
+            RAY_V4_SWAP_BASE_IN | RAY_V4_SWAP_BASE_OUT => Some((destination, u64_at(data, 9)?)),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Where a swap instruction keeps its slippage limit: the byte offset and
+/// whether it caps the input rather than flooring the output
+fn slippage_limit(ix: &Instruction) -> Option<(usize, bool)> {
+    let data = ix.data.as_slice();
+    if ix.program_id == PUMPSWAP_PROGRAM_ID {
+        match data.get(..8)? {
+            d if d == PUMPSWAP_BUY => Some((16, true)),
+            d if d == PUMPSWAP_SELL => Some((16, false)),
+            _ => None,
+        }
+    } else if ix.program_id == LAUNCHPAD_PROGRAM_ID {
+        let d = data.get(..8)?;
+        (d == LAUNCHPAD_BUY_EXACT_IN || d == LAUNCHPAD_SELL_EXACT_IN).then_some((16, false))
+    } else if ix.program_id == CPMM_PROGRAM_ID {
+        (data.get(..8)? == CPMM_SWAP_BASE_INPUT).then_some((16, false))
+    } else if ix.program_id == RAY_V4_PROGRAM_ID {
+        match *data.first()? {
+            RAY_V4_SWAP_BASE_IN => Some((9, false)),
+            RAY_V4_SWAP_BASE_OUT => Some((1, true)),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Raises the swap's minimum output to `min_out`, and a min-output
+/// assertion's floor with it. `None` when there's no swap that floors its
+/// output, or its minimum is already at least `min_out`.
+pub fn raise_min_output(ixs: &[Instruction], min_out: u64) -> Option<Vec<Instruction>> {
+    let (index, offset) = ixs.iter().enumerate().find_map(|(index, ix)| match slippage_limit(ix) {
+        Some((offset, false)) => Some((index, offset)),
+        _ => None,
+    })?;
+    let current = u64_at(&ixs[index].data, offset)?;
+    if current >= min_out {
+        return None;
+    }
+
+    let mut ixs = ixs.to_vec();
+    ixs[index].data[offset..offset + 8].copy_from_slice(&min_out.to_le_bytes());
+    if let Some(assertion) = ixs.iter_mut().find(|ix| ix.program_id == LIGHTHOUSE_PROGRAM_ID) {
+        let floor = u64_at(&assertion.data, 3)?.saturating_add(min_out - current);
+        assertion.data[3..11].copy_from_slice(&floor.to_le_bytes());
+    }
+    Some(ixs)
+}
+
+/// Rewrites the swap's slippage limit from `from_pct` to `to_pct` of
+/// slippage, moving a min-output assertion's floor along with it. `None`
+/// when there's no swap the processors build or the limit can't widen.
+pub fn widen_slippage(ixs: &[Instruction], from_pct: f64, to_pct: f64) -> Option<Vec<Instruction>> {
+    let (index, (offset, caps_input)) = ixs
+        .iter()
+        .enumerate()
+        .find_map(|(index, ix)| slippage_limit(ix).map(|limit| (index, limit)))?;
+    let factor = if caps_input {
+        (1.0 + to_pct / 100.0) / (1.0 + from_pct / 100.0)
+    } else if from_pct < 100.0 {
+        (1.0 - to_pct / 100.0).max(0.0) / (1.0 - from_pct / 100.0)
+    } else {
+        return None;
+    };
+
+    let mut ixs = ixs.to_vec();
+    let limit = u64_at(&ixs[index].data, offset)?;
+    let widened = (limit as f64 * factor).round() as u64;
+    if widened == limit {
+        return None;
+    }
+    ixs[index].data[offset..offset + 8].copy_from_slice(&widened.to_le_bytes());
+
+    if !caps_input {
+        let lowered = limit - widened.min(limit);
+        if let Some(assertion) = ixs.iter_mut().find(|ix| ix.program_id == LIGHTHOUSE_PROGRAM_ID) {
+            let floor = u64_at(&assertion.data, 3)?.saturating_sub(lowered);
+            assertion.data[3..11].copy_from_slice(&floor.to_le_bytes());
+        }
+    }
+    Some(ixs)
+}
+
+/// Fails the transaction unless `account` holds at least `floor` tokens
+pub fn assert_min_balance_ix(account: Pubkey, floor: u64) -> Instruction {
+    let mut data = vec![ASSERT_TOKEN_ACCOUNT, LOG_LEVEL_SILENT, TOKEN_ACCOUNT_AMOUNT];
+    data.extend_from_slice(&floor.to_le_bytes());
+    data.push(GREATER_THAN_OR_EQUAL);
+
+    Instruction {
+        program_id: LIGHTHOUSE_PROGRAM_ID,
+        accounts: vec![AccountMeta::new_readonly(account, false)],
+        data,
+    }
+}
+
+/// Follows the swap with an assertion that its output account grew by at
+/// least the swap's minimum, so a fill below it fails even if the AMM's own
+/// check were bypassed. `pre_balance` is what the account holds beforehand.
+pub fn insert_min_output_assertion(mut ixs: Vec<Instruction>, pre_balance: u64) -> Vec<Instruction> {
+    let Some((index, (account, min_out))) = ixs
+        .iter()
+        .enumerate()
+        .find_map(|(index, ix)| swap_min_output(ix).map(|output| (index, output)))
+    else {
+        return ixs;
+    };
+    ixs.insert(index + 1, assert_min_balance_ix(account, pre_balance.saturating_add(min_out)));
+    ixs
+}
+
+/// Adds the min-output assertion, reading the output account's balance
+/// first. An account the transaction creates starts empty; when the balance
+/// can't be read the swap goes without the assertion, its own min-out still
+/// applies.
+pub async fn with_min_output_assertion(ixs: Vec<Instruction>) -> Vec<Instruction> {
+    let Some((account, _)) = ixs.iter().find_map(swap_min_output) else {
+        return ixs;
+    };
+
+    let pre_balance = match RPC_CLIENT.get_multiple_accounts(&[account]).await {
+        // Token and Token-2022 accounts both keep the amount at bytes 64..72
+        Ok(accounts) => match accounts.into_iter().next().flatten() {
+            Some(existing) => match u64_at(&existing.data, 64) {
+                Some(amount) => amount,
+                None => return ixs,
+            },
+            None => 0,
+        },
+        Err(e) => {
+            println!("Failed to read {} for the min-output assertion: {}", account, e);
+            return ixs;
+        }
+    };
+
+    insert_min_output_assertion(ixs, pre_balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What Lighthouse checks for the assertion, given the balance after the swap
+    fn assertion_passes(ix: &Instruction, post_balance: u64) -> bool {
+        assert_eq!(ix.program_id, LIGHTHOUSE_PROGRAM_ID);
+        assert_eq!(ix.data[..3], [ASSERT_TOKEN_ACCOUNT, LOG_LEVEL_SILENT, TOKEN_ACCOUNT_AMOUNT]);
+        assert_eq!(ix.data[11], GREATER_THAN_OR_EQUAL);
+        post_balance >= u64_at(&ix.data, 3).unwrap()
+    }
+
+    fn pumpswap_sell(min_quote_out: u64) -> Instruction {
+        let mut data = PUMPSWAP_SELL.to_vec();
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&min_quote_out.to_le_bytes());
+        let accounts = (0..21).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+        Instruction { program_id: PUMPSWAP_PROGRAM_ID, accounts, data }
+    }
+
+    #[test]
+    fn assertion_fails_a_fill_below_the_floor() {
+        let sell = pumpswap_sell(500);
+        let quote_account = sell.accounts[6].pubkey;
+        assert_eq!(swap_min_output(&sell), Some((quote_account, 500)));
+
+        let close = Instruction::new_with_bytes(spl_token::ID, &[9], vec![]);
+        let ixs = insert_min_output_assertion(vec![sell.clone(), close.clone()], 200);
+
+        // Right after the swap, ahead of anything closing the output account
+        assert_eq!(ixs.len(), 3);
+        assert_eq!((&ixs[0], &ixs[2]), (&sell, &close));
+        assert_eq!(ixs[1].accounts, vec![AccountMeta::new_readonly(quote_account, false)]);
+        assert!(assertion_passes(&ixs[1], 700));
+        assert!(assertion_passes(&ixs[1], 900));
+        assert!(!assertion_passes(&ixs[1], 699));
+    }
+
+    #[test]
+    fn raydium_swaps_pay_out_to_the_second_to_last_account() {
+        let mut data = vec![RAY_V4_SWAP_BASE_IN];
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&42u64.to_le_bytes());
+        let accounts: Vec<_> = (0..17).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+        let destination = accounts[15].pubkey;
+        let swap = Instruction { program_id: RAY_V4_PROGRAM_ID, accounts, data };
+
+        assert_eq!(swap_min_output(&swap), Some((destination, 42)));
+
+        let other = Instruction::new_with_bytes(Pubkey::new_unique(), &[9], vec![]);
+        assert_eq!(swap_min_output(&other), None);
+        assert_eq!(insert_min_output_assertion(vec![other.clone()], 0), vec![other]);
+    }
+
+    #[test]
+    fn widening_slippage_lowers_the_floor_and_its_assertion() {
+        // 1,000 out at 5% slippage gave a floor of 950
+        let ixs = insert_min_output_assertion(vec![pumpswap_sell(950)], 200);
+        let widened = widen_slippage(&ixs, 5.0, 10.0).unwrap();
+        assert_eq!(swap_min_output(&widened[0]).unwrap().1, 900);
+        assert!(assertion_passes(&widened[1], 1_100));
+        assert!(!assertion_passes(&widened[1], 1_099));
+
+        // PumpSwap buys cap the quote paid instead
+        let mut buy = pumpswap_sell(1_050);
+        buy.data[..8].copy_from_slice(&PUMPSWAP_BUY);
+        let widened = widen_slippage(&[buy], 5.0, 15.0).unwrap();
+        assert_eq!(u64_at(&widened[0].data, 16), Some(1_150));
+
+        let other = Instruction::new_with_bytes(Pubkey::new_unique(), &[9], vec![]);
+        assert_eq!(widen_slippage(&[other], 5.0, 10.0), None);
+        assert_eq!(widen_slippage(&[pumpswap_sell(0)], 100.0, 110.0), None);
+    }
+
+    #[test]
+    fn raising_the_min_output_raises_its_assertion() {
+        let ixs = insert_min_output_assertion(vec![pumpswap_sell(900)], 200);
+        let raised = raise_min_output(&ixs, 1_000).unwrap();
+        assert_eq!(swap_min_output(&raised[0]).unwrap().1, 1_000);
+        assert!(assertion_passes(&raised[1], 1_200));
+        assert!(!assertion_passes(&raised[1], 1_199));
+
+        // Never lowers it
+        assert_eq!(raise_min_output(&ixs, 800), None);
+
+        // PumpSwap buys name an exact output, there's no floor to raise
+        let mut buy = pumpswap_sell(900);
+        buy.data[..8].copy_from_slice(&PUMPSWAP_BUY);
+        assert_eq!(raise_min_output(&[buy], 1_000), None);
+    }
+}
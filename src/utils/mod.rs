@@ -4,3 +4,28 @@ pub mod swap_quote;
 pub mod parse;
 pub mod utils;
 pub mod build_and_sign;
+pub mod pool_status;
+pub mod price_history;
+pub mod token_program;
+pub mod processor_errors;
+pub mod fee;
+pub mod rent;
+pub mod tx_failures;
+pub mod self_test;
+pub mod positions;
+pub mod geyser_auth;
+pub mod pool_reserves;
+pub mod stuck;
+pub mod dry_run;
+pub mod wsol;
+pub mod min_output;
+pub mod confirm;
+pub mod token_balance;
+pub mod fill_event;
+pub mod swap_legs;
+pub mod tx_users;
+pub mod user_locks;
+pub mod token_decimals;
+pub mod price;
+pub mod simulation_failure;
+pub mod alt;
@@ -1,4 +1,4 @@
-use solana_sdk::{bs58, pubkey::Pubkey};
+use solana_sdk::{bs58, instruction::CompiledInstruction, pubkey::Pubkey};
 use solana_transaction_status_client_types::TransactionTokenBalance;
 use yellowstone_grpc_proto::prelude::{Message, TransactionStatusMeta};
 
@@ -41,6 +41,11 @@ pub fn get_pre_post_sol_balance(
     (pre_amount.clone(), post_amount.clone())
 }
 
+/// Post and pre `(amount, mint)` of a pool's two vaults: base, quote, pre
+/// base, pre quote
+pub type VaultBalances =
+    (Option<(String, String)>, Option<(String, String)>, Option<(String, String)>, Option<(String, String)>);
+
 pub fn get_coin_pc_mint(
     post_token_balance: &Vec<TransactionTokenBalance>,
     pre_token_balance: &Vec<TransactionTokenBalance>,
@@ -48,7 +53,7 @@ pub fn get_coin_pc_mint(
     quote_vault: Pubkey,
     pool_auth: Pubkey,
     account_keys: &[Pubkey],
-) -> (Option<(String, String)>, Option<(String, String)>, Option<(String, String)>, Option<(String, String)>) {
+) -> VaultBalances {
     let base_info = post_token_balance
         .iter()
         .find(|tb| {
@@ -83,3 +88,193 @@ pub fn get_coin_pc_mint(
 
     (base_info, quote_info, pre_base_info, pre_quote_info)
 }
+
+/// Net change of `owner`'s token balance in a transaction, ignoring
+/// `skip_mint` (WSOL). Returns the traded mint and its raw amount delta.
+pub fn owner_token_delta(
+    pre_token_balance: &[TransactionTokenBalance],
+    post_token_balance: &[TransactionTokenBalance],
+    owner: &str,
+    skip_mint: &str,
+) -> Option<(String, i128)> {
+    let mut deltas: Vec<(String, i128)> = Vec::new();
+    let balances = pre_token_balance
+        .iter()
+        .map(|tb| (tb, -1))
+        .chain(post_token_balance.iter().map(|tb| (tb, 1)));
+
+    for (tb, sign) in balances {
+        if tb.owner != owner || tb.mint == skip_mint {
+            continue;
+        }
+        let amount = tb.ui_token_amount.amount.parse::<i128>().unwrap_or(0) * sign;
+        match deltas.iter_mut().find(|(mint, _)| *mint == tb.mint) {
+            Some((_, delta)) => *delta += amount,
+            None => deltas.push((tb.mint.clone(), amount)),
+        }
+    }
+
+    deltas.into_iter().find(|(_, delta)| *delta != 0)
+}
+
+/// SPL Token `Transfer` and `TransferChecked` instruction tags
+const TOKEN_TRANSFER: u8 = 3;
+const TOKEN_TRANSFER_CHECKED: u8 = 12;
+
+/// Net amount a transaction's token transfers, top-level and inner, moved
+/// into `account`, negative when more left it. For the user's quote account
+/// this is what the swap spent or returned in the quote token: wrapping SOL
+/// into the account and closing it aren't transfers, so whether the WSOL
+/// account is kept open or closed doesn't change it.
+pub fn token_account_flow<'a>(
+    instructions: impl IntoIterator<Item = &'a CompiledInstruction>,
+    account_keys: &[Pubkey],
+    account: &Pubkey,
+) -> i128 {
+    let key = |index: Option<&u8>| index.and_then(|index| account_keys.get(*index as usize));
+    let mut flow = 0;
+    for ix in instructions {
+        let program = key(Some(&ix.program_id_index));
+        if program != Some(&spl_token::ID) && program != Some(&crate::utils::token_program::TOKEN_2022_PROGRAM_ID) {
+            continue;
+        }
+        let (source, destination) = match ix.data.first() {
+            Some(&TOKEN_TRANSFER) => (key(ix.accounts.first()), key(ix.accounts.get(1))),
+            Some(&TOKEN_TRANSFER_CHECKED) => (key(ix.accounts.first()), key(ix.accounts.get(2))),
+            _ => continue,
+        };
+        let Some(amount) = ix.data.get(1..9).and_then(|amount| amount.try_into().ok()).map(u64::from_le_bytes) else {
+            continue;
+        };
+        if destination == Some(account) {
+            flow += amount as i128;
+        }
+        if source == Some(account) {
+            flow -= amount as i128;
+        }
+    }
+    flow
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::models::trade::FundRoute;
+
+    const OWNER: &str = "owner";
+    const MINT: &str = "mint";
+    const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+    fn balance(owner: &str, mint: &str, amount: u64) -> TransactionTokenBalance {
+        TransactionTokenBalance {
+            account_index: 0,
+            mint: mint.to_string(),
+            ui_token_amount: serde_json::from_value(serde_json::json!({
+                "uiAmount": null,
+                "decimals": 6,
+                "amount": amount.to_string(),
+                "uiAmountString": "",
+            }))
+            .unwrap(),
+            owner: owner.to_string(),
+            program_id: String::new(),
+        }
+    }
+
+    #[test]
+    fn buy_and_sell_reconstruct_the_route() {
+        // Buy: 0.5 WSOL out of the user's ATA, 1_000_000 tokens in
+        let buy_pre = vec![balance(OWNER, WSOL_MINT, 500_000_000), balance("pool", MINT, 9_000_000)];
+        let buy_post = vec![
+            balance(OWNER, WSOL_MINT, 0),
+            balance(OWNER, MINT, 1_000_000),
+            balance("pool", MINT, 8_000_000),
+        ];
+        let (mint, received) = owner_token_delta(&buy_pre, &buy_post, OWNER, WSOL_MINT).unwrap();
+        assert_eq!((mint.as_str(), received), (MINT, 1_000_000));
+
+        // Sell: all tokens out, 0.55 WSOL back
+        let sell_pre = vec![balance(OWNER, WSOL_MINT, 0), balance(OWNER, MINT, 1_000_000)];
+        let sell_post = vec![balance(OWNER, WSOL_MINT, 550_000_000), balance(OWNER, MINT, 0)];
+        let (_, sold) = owner_token_delta(&sell_pre, &sell_post, OWNER, WSOL_MINT).unwrap();
+        assert_eq!(sold, -1_000_000);
+
+        let mut route = FundRoute::default();
+        route.record_buy(mint, 500_000_000, received);
+        route.record_sell(-sold, 550_000_000);
+        assert_eq!(
+            route,
+            FundRoute {
+                mint: MINT.to_string(),
+                sol_in_lamports: 500_000_000,
+                tokens_received: 1_000_000,
+                tokens_sold: 1_000_000,
+                sol_out_lamports: 550_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn pool_reserves_come_from_the_vaults_the_authority_owns() {
+        let (trader_in, trader_out, vault_in, vault_out, authority) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let account_keys = [trader_in, trader_out, vault_in, vault_out];
+        let at = |index: u8, owner: &Pubkey, mint: &str, amount: u64| TransactionTokenBalance {
+            account_index: index,
+            ..balance(&owner.to_string(), mint, amount)
+        };
+        let post = vec![
+            at(0, &Pubkey::default(), WSOL_MINT, 0),
+            at(1, &Pubkey::default(), MINT, 1_000),
+            at(2, &authority, WSOL_MINT, 80_000_000_000),
+            at(3, &authority, MINT, 5_000_000),
+        ];
+
+        let (coin, pc, _, _) =
+            get_coin_pc_mint(&post, &vec![], vault_in, vault_out, authority, &account_keys);
+        assert_eq!(coin, Some(("80000000000".to_string(), WSOL_MINT.to_string())));
+        assert_eq!(pc, Some(("5000000".to_string(), MINT.to_string())));
+
+        // The trader's own accounts hold no pool reserves
+        let (coin, pc, _, _) =
+            get_coin_pc_mint(&post, &vec![], trader_in, trader_out, authority, &account_keys);
+        assert_eq!((coin, pc), (None, None));
+    }
+
+    #[test]
+    fn quote_flow_counts_only_token_transfers_of_the_account() {
+        let (user_quote, vault, fee_recipient, owner) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let account_keys = [spl_token::ID, user_quote, vault, fee_recipient, owner, Pubkey::default()];
+        let transfer = |tag: u8, accounts: Vec<u8>, amount: u64| {
+            let mut data = vec![tag];
+            data.extend_from_slice(&amount.to_le_bytes());
+            if tag == TOKEN_TRANSFER_CHECKED {
+                data.push(9);
+            }
+            CompiledInstruction::new_from_raw_parts(0, data, accounts)
+        };
+
+        // Buy: 1 WSOL to the vault and a 0.01 fee out of the user's account
+        let buy = [
+            transfer(TOKEN_TRANSFER, vec![1, 2, 4], 1_000_000_000),
+            transfer(TOKEN_TRANSFER_CHECKED, vec![1, 5, 3, 4], 10_000_000),
+        ];
+        assert_eq!(token_account_flow(&buy, &account_keys, &user_quote), -1_010_000_000);
+
+        // Sell: 1.2 WSOL back from the vault. Closing the account (tag 9) and
+        // the system program's wrapping transfer don't count
+        let sell = [
+            transfer(TOKEN_TRANSFER_CHECKED, vec![2, 5, 1, 4], 1_200_000_000),
+            CompiledInstruction::new_from_raw_parts(0, vec![9], vec![1, 4, 4]),
+            CompiledInstruction::new_from_raw_parts(5, vec![2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], vec![4, 1]),
+        ];
+        assert_eq!(token_account_flow(&sell, &account_keys, &user_quote), 1_200_000_000);
+        assert_eq!(token_account_flow(&sell, &account_keys, &fee_recipient), 0);
+    }
+}
@@ -0,0 +1,389 @@
+use carbon_core::deserialize::CarbonDeserialize;
+use carbon_pump_swap_decoder::{accounts::pool::Pool as PumpSwapPool, PROGRAM_ID as PUMPSWAP_PROGRAM_ID};
+use carbon_raydium_amm_v4_decoder::{accounts::amm_info::AmmInfo, PROGRAM_ID as RAY_V4_PROGRAM_ID};
+use carbon_raydium_cpmm_decoder::{
+    accounts::pool_state::PoolState as CpmmPoolState, PROGRAM_ID as CPMM_PROGRAM_ID,
+};
+use carbon_raydium_launchpad_decoder::{
+    accounts::pool_state::PoolState as LaunchpadPoolState, PROGRAM_ID as LAUNCHPAD_PROGRAM_ID,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::config::{POOL_ACCOUNT_RESERVES_ENABLED, RPC_CLIENT};
+use crate::utils::blockhash::WSOL;
+
+// SPL token accounts (and Token-2022's base layout) keep the amount after the
+// mint and owner pubkeys
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// Tradable reserves of a pool. Base and quote follow each program's own
+/// naming: coin/pc for AMM v4 and token0/token1 for CPMM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolReserves {
+    pub base: u64,
+    pub quote: u64,
+}
+
+impl PoolReserves {
+    /// Quote per base, in raw units
+    pub fn price(&self) -> Option<f64> {
+        (self.base > 0).then(|| self.quote as f64 / self.base as f64)
+    }
+
+    /// The WSOL reserve and the token's, by which side holds WSOL. `None` for
+    /// pools with no WSOL side, such as those quoted in USD1.
+    pub fn sol_and_token(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Option<(u64, u64)> {
+        if *quote_mint == WSOL {
+            Some((self.quote, self.base))
+        } else if *base_mint == WSOL {
+            Some((self.base, self.quote))
+        } else {
+            None
+        }
+    }
+}
+
+/// Mints and vaults a pool's reserves are held in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolVaults {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+}
+
+/// Reads the mints and vaults out of a raw pool state account, in the same
+/// base/quote order as `PoolReserves`
+pub fn pool_vaults(owner: &Pubkey, data: &[u8]) -> Option<PoolVaults> {
+    if *owner == RAY_V4_PROGRAM_ID {
+        let amm_info = AmmInfo::deserialize(data)?;
+        return Some(PoolVaults {
+            base_mint: amm_info.coin_mint,
+            quote_mint: amm_info.pc_mint,
+            base_vault: amm_info.token_coin,
+            quote_vault: amm_info.token_pc,
+        });
+    }
+
+    if *owner == CPMM_PROGRAM_ID {
+        let pool_state = CpmmPoolState::deserialize(data)?;
+        return Some(PoolVaults {
+            base_mint: pool_state.token0_mint,
+            quote_mint: pool_state.token1_mint,
+            base_vault: pool_state.token0_vault,
+            quote_vault: pool_state.token1_vault,
+        });
+    }
+
+    if *owner == LAUNCHPAD_PROGRAM_ID {
+        let pool_state = LaunchpadPoolState::deserialize(data)?;
+        return Some(PoolVaults {
+            base_mint: pool_state.base_mint,
+            quote_mint: pool_state.quote_mint,
+            base_vault: pool_state.base_vault,
+            quote_vault: pool_state.quote_vault,
+        });
+    }
+
+    if *owner == PUMPSWAP_PROGRAM_ID {
+        let pool = PumpSwapPool::deserialize(data)?;
+        return Some(PoolVaults {
+            base_mint: pool.base_mint,
+            quote_mint: pool.quote_mint,
+            base_vault: pool.pool_base_token_account,
+            quote_vault: pool.pool_quote_token_account,
+        });
+    }
+
+    None
+}
+
+/// Reads the amount out of a raw token account
+pub fn token_account_amount(data: &[u8]) -> Option<u64> {
+    let bytes = data.get(TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Derives the reserves the pool's program prices swaps against from its
+/// decoded state account and vault balances. Vault balances still hold fees
+/// that aren't tradable, so those are taken out; a Launchpad curve carries its
+/// reserves in the state account and needs no vaults.
+///
+/// Returns `None` when the owner is not a supported AMM program or an account
+/// can't be decoded.
+pub fn reserves_from_accounts(
+    owner: &Pubkey,
+    pool_data: &[u8],
+    base_vault_data: Option<&[u8]>,
+    quote_vault_data: Option<&[u8]>,
+) -> Option<PoolReserves> {
+    if *owner == LAUNCHPAD_PROGRAM_ID {
+        let pool_state = LaunchpadPoolState::deserialize(pool_data)?;
+        return Some(PoolReserves {
+            base: pool_state.virtual_base.checked_sub(pool_state.real_base)?,
+            quote: pool_state.virtual_quote.checked_add(pool_state.real_quote)?,
+        });
+    }
+
+    let base_vault = token_account_amount(base_vault_data?)?;
+    let quote_vault = token_account_amount(quote_vault_data?)?;
+
+    if *owner == RAY_V4_PROGRAM_ID {
+        let amm_info = AmmInfo::deserialize(pool_data)?;
+        return Some(PoolReserves {
+            base: base_vault.saturating_sub(amm_info.out_put.need_take_pnl_coin),
+            quote: quote_vault.saturating_sub(amm_info.out_put.need_take_pnl_pc),
+        });
+    }
+
+    if *owner == CPMM_PROGRAM_ID {
+        let pool_state = CpmmPoolState::deserialize(pool_data)?;
+        return Some(PoolReserves {
+            base: base_vault
+                .saturating_sub(pool_state.protocol_fees_token0)
+                .saturating_sub(pool_state.fund_fees_token0),
+            quote: quote_vault
+                .saturating_sub(pool_state.protocol_fees_token1)
+                .saturating_sub(pool_state.fund_fees_token1),
+        });
+    }
+
+    if *owner == PUMPSWAP_PROGRAM_ID {
+        PumpSwapPool::deserialize(pool_data)?;
+        return Some(PoolReserves {
+            base: base_vault,
+            quote: quote_vault,
+        });
+    }
+
+    None
+}
+
+/// Fetches the pool state and both vaults in a single request and derives
+/// the reserves from them, see `reserves_from_accounts`
+pub async fn fetch_pool_reserves(pool: &Pubkey, base_vault: &Pubkey, quote_vault: &Pubkey) -> Option<PoolReserves> {
+    let accounts = match RPC_CLIENT
+        .get_multiple_accounts_with_commitment(&[*pool, *base_vault, *quote_vault], CommitmentConfig::processed())
+        .await
+    {
+        Ok(response) => response.value,
+        Err(e) => {
+            println!("Failed to fetch pool accounts for {}: {}", pool, e);
+            return None;
+        }
+    };
+
+    let pool_account = accounts.first()?.as_ref()?;
+    let data = |index: usize| {
+        accounts
+            .get(index)
+            .and_then(|account| account.as_ref())
+            .map(|account| account.data.as_slice())
+    };
+
+    reserves_from_accounts(&pool_account.owner, &pool_account.data, data(1), data(2))
+}
+
+/// Reserves of a pool known only by its address: the state account names the
+/// vaults, which are then fetched together
+pub async fn fetch_reserves_by_pool(pool: &Pubkey) -> Option<(PoolVaults, PoolReserves)> {
+    let pool_account = match RPC_CLIENT
+        .get_account_with_commitment(pool, CommitmentConfig::processed())
+        .await
+    {
+        Ok(response) => response.value?,
+        Err(e) => {
+            println!("Failed to fetch pool state for {}: {}", pool, e);
+            return None;
+        }
+    };
+    let vaults = pool_vaults(&pool_account.owner, &pool_account.data)?;
+
+    let accounts = RPC_CLIENT
+        .get_multiple_accounts_with_commitment(&[vaults.base_vault, vaults.quote_vault], CommitmentConfig::processed())
+        .await
+        .ok()?
+        .value;
+    let data = |index: usize| {
+        accounts
+            .get(index)
+            .and_then(|account| account.as_ref())
+            .map(|account| account.data.as_slice())
+    };
+
+    let reserves = reserves_from_accounts(&pool_account.owner, &pool_account.data, data(0), data(1))?;
+    Some((vaults, reserves))
+}
+
+/// Balances of a pool's two vaults as `(base_amount, quote_amount)`, read in
+/// a single `getMultipleAccounts` request
+pub async fn fetch_vault_amounts(rpc: &RpcClient, base_vault: &Pubkey, quote_vault: &Pubkey) -> Option<(u64, u64)> {
+    let accounts = match rpc
+        .get_multiple_accounts_with_commitment(&[*base_vault, *quote_vault], CommitmentConfig::processed())
+        .await
+    {
+        Ok(response) => response.value,
+        Err(e) => {
+            println!("Failed to fetch vaults {} and {}: {}", base_vault, quote_vault, e);
+            return None;
+        }
+    };
+    let amount = |index: usize| token_account_amount(&accounts.get(index)?.as_ref()?.data);
+
+    Some((amount(0)?, amount(1)?))
+}
+
+/// Reserves for quote math. Decodes the pool accounts when
+/// `POOL_ACCOUNT_RESERVES` is on, falling back to the raw vault balances when
+/// it's off or the accounts can't be decoded.
+pub async fn pool_reserves(pool: &Pubkey, base_vault: &Pubkey, quote_vault: &Pubkey) -> Option<PoolReserves> {
+    if *POOL_ACCOUNT_RESERVES_ENABLED {
+        if let Some(reserves) = fetch_pool_reserves(pool, base_vault, quote_vault).await {
+            return Some(reserves);
+        }
+    }
+
+    let (base, quote) = fetch_vault_amounts(&RPC_CLIENT, base_vault, quote_vault).await?;
+    Some(PoolReserves { base, quote })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+
+    const POOL_STATE_DISCRIMINATOR: [u8; 8] = [0xf7, 0xed, 0xe3, 0xf5, 0xd7, 0xc3, 0xde, 0x46];
+    const PUMPSWAP_POOL_DISCRIMINATOR: [u8; 8] = [0xf1, 0x9a, 0x6d, 0x04, 0x11, 0xb1, 0x6d, 0xbc];
+
+    fn token_account(amount: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 165];
+        data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    fn put_u64(data: &mut [u8], offset: usize, value: u64) {
+        data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn amm_v4_reserves_exclude_pnl_owed() {
+        let mut pool = vec![0u8; 1024];
+        // 16 u64 header fields + 8 u64 fees, then need_take_pnl_coin/pc
+        put_u64(&mut pool, 192, 1_000);
+        put_u64(&mut pool, 200, 50);
+
+        let reserves = reserves_from_accounts(
+            &RAY_V4_PROGRAM_ID,
+            &pool,
+            Some(&token_account(101_000)),
+            Some(&token_account(20_050)),
+        );
+        assert_eq!(reserves, Some(PoolReserves { base: 100_000, quote: 20_000 }));
+        assert_eq!(reserves.unwrap().price(), Some(0.2));
+    }
+
+    #[test]
+    fn cpmm_reserves_exclude_protocol_and_fund_fees() {
+        let mut pool = vec![0u8; 1024];
+        pool[..8].copy_from_slice(&POOL_STATE_DISCRIMINATOR);
+        // discriminator + 10 pubkeys + 5 u8 + lp_supply, then the fee counters
+        let fees = 8 + 32 * 10 + 5 + 8;
+        put_u64(&mut pool, fees, 10);
+        put_u64(&mut pool, fees + 8, 20);
+        put_u64(&mut pool, fees + 16, 30);
+        put_u64(&mut pool, fees + 24, 40);
+
+        let reserves = reserves_from_accounts(
+            &CPMM_PROGRAM_ID,
+            &pool,
+            Some(&token_account(1_040)),
+            Some(&token_account(5_060)),
+        );
+        assert_eq!(reserves, Some(PoolReserves { base: 1_000, quote: 5_000 }));
+    }
+
+    #[test]
+    fn launchpad_reserves_come_from_the_curve() {
+        let mut pool = vec![0u8; 1024];
+        pool[..8].copy_from_slice(&POOL_STATE_DISCRIMINATOR);
+        // discriminator + epoch + 5 u8 + supply + total_base_sell
+        let curve = 8 + 8 + 5 + 8 + 8;
+        put_u64(&mut pool, curve, 1_073_000_000);
+        put_u64(&mut pool, curve + 8, 30_000);
+        put_u64(&mut pool, curve + 16, 73_000_000);
+        put_u64(&mut pool, curve + 24, 2_000);
+
+        let reserves = reserves_from_accounts(&LAUNCHPAD_PROGRAM_ID, &pool, None, None);
+        assert_eq!(reserves, Some(PoolReserves { base: 1_000_000_000, quote: 32_000 }));
+    }
+
+    #[test]
+    fn pumpswap_reserves_are_the_vault_balances() {
+        let mut pool = vec![0u8; 512];
+        pool[..8].copy_from_slice(&PUMPSWAP_POOL_DISCRIMINATOR);
+
+        let reserves = reserves_from_accounts(
+            &PUMPSWAP_PROGRAM_ID,
+            &pool,
+            Some(&token_account(7)),
+            Some(&token_account(9)),
+        );
+        assert_eq!(reserves, Some(PoolReserves { base: 7, quote: 9 }));
+
+        // A missing vault or a foreign owner can't be priced from accounts
+        assert_eq!(reserves_from_accounts(&PUMPSWAP_PROGRAM_ID, &pool, None, Some(&token_account(9))), None);
+        assert_eq!(
+            reserves_from_accounts(&Pubkey::new_unique(), &pool, Some(&token_account(7)), Some(&token_account(9))),
+            None
+        );
+    }
+
+    /// A `getMultipleAccounts` result holding the given token accounts, in
+    /// the base64 encoding the client asks for
+    fn multiple_accounts_response(accounts: &[Option<Vec<u8>>]) -> serde_json::Value {
+        let value: Vec<_> = accounts
+            .iter()
+            .map(|data| {
+                data.as_ref().map(|data| {
+                    json!({
+                        "lamports": 2_039_280,
+                        "data": [base64::encode(data), "base64"],
+                        "owner": spl_token::ID.to_string(),
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": data.len(),
+                    })
+                })
+            })
+            .collect();
+        json!({ "context": { "slot": 1 }, "value": value })
+    }
+
+    #[tokio::test]
+    async fn both_vaults_are_read_in_one_request() {
+        let (base_vault, quote_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mocks = [(
+            RpcRequest::GetMultipleAccounts,
+            multiple_accounts_response(&[Some(token_account(1_000_000)), Some(token_account(40_000))]),
+        )];
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks.into_iter().collect());
+        assert_eq!(fetch_vault_amounts(&rpc, &base_vault, &quote_vault).await, Some((1_000_000, 40_000)));
+
+        // A vault that doesn't exist leaves nothing to price against
+        let mocks = [(RpcRequest::GetMultipleAccounts, multiple_accounts_response(&[Some(token_account(7)), None]))];
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks.into_iter().collect());
+        assert_eq!(fetch_vault_amounts(&rpc, &base_vault, &quote_vault).await, None);
+    }
+
+    #[test]
+    fn sol_side_is_found_by_mint() {
+        let reserves = PoolReserves { base: 1_000_000, quote: 40 };
+        let token = Pubkey::new_unique();
+        assert_eq!(reserves.sol_and_token(&token, &WSOL), Some((40, 1_000_000)));
+        assert_eq!(reserves.sol_and_token(&WSOL, &token), Some((1_000_000, 40)));
+        // A USD1 pool's quote side isn't SOL
+        assert_eq!(reserves.sol_and_token(&token, &crate::utils::blockhash::USD1), None);
+    }
+}
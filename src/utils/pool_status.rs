@@ -0,0 +1,151 @@
+use carbon_core::deserialize::CarbonDeserialize;
+use carbon_pump_swap_decoder::PROGRAM_ID as PUMPSWAP_PROGRAM_ID;
+use carbon_raydium_amm_v4_decoder::{accounts::amm_info::AmmInfo, PROGRAM_ID as RAY_V4_PROGRAM_ID};
+use carbon_raydium_cpmm_decoder::{
+    accounts::pool_state::PoolState as CpmmPoolState, PROGRAM_ID as CPMM_PROGRAM_ID,
+};
+use carbon_raydium_launchpad_decoder::{
+    accounts::pool_state::PoolState as LaunchpadPoolState, PROGRAM_ID as LAUNCHPAD_PROGRAM_ID,
+};
+use once_cell::sync::Lazy;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::RwLock;
+
+use crate::config::{POOL_STATUS_CACHE_TTL, POOL_STATUS_CHECK_ENABLED, RPC_CLIENT};
+
+// Raydium AMM v4 `AmmStatus` values that still accept swaps
+const AMM_V4_STATUS_INITIALIZED: u64 = 1;
+const AMM_V4_STATUS_SWAP_ONLY: u64 = 6;
+const AMM_V4_STATUS_WAITING_TRADE: u64 = 7;
+
+// Raydium CPMM keeps a bitmask where bit 2 disables swaps
+const CPMM_STATUS_SWAP_DISABLED_BIT: u8 = 1 << 2;
+
+// Raydium Launchpad pools only trade on the curve while funding
+const LAUNCHPAD_STATUS_FUND: u8 = 0;
+
+static POOL_STATUS_CACHE: Lazy<RwLock<HashMap<Pubkey, (bool, Instant)>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Reads the swap status flag out of a raw pool state account.
+///
+/// Returns `None` when the owner is not a supported AMM program or the data
+/// can't be decoded, so callers can fall back to their default behaviour.
+pub fn swap_enabled_from_account(owner: &Pubkey, data: &[u8], now_unix: u64) -> Option<bool> {
+    if *owner == RAY_V4_PROGRAM_ID {
+        let amm_info = AmmInfo::deserialize(data)?;
+        return Some(match amm_info.status {
+            AMM_V4_STATUS_INITIALIZED | AMM_V4_STATUS_SWAP_ONLY => true,
+            AMM_V4_STATUS_WAITING_TRADE => now_unix >= amm_info.out_put.pool_open_time,
+            _ => false,
+        });
+    }
+
+    if *owner == CPMM_PROGRAM_ID {
+        let pool_state = CpmmPoolState::deserialize(data)?;
+        return Some(
+            pool_state.status & CPMM_STATUS_SWAP_DISABLED_BIT == 0
+                && now_unix >= pool_state.open_time,
+        );
+    }
+
+    if *owner == LAUNCHPAD_PROGRAM_ID {
+        let pool_state = LaunchpadPoolState::deserialize(data)?;
+        return Some(pool_state.status == LAUNCHPAD_STATUS_FUND);
+    }
+
+    if *owner == PUMPSWAP_PROGRAM_ID {
+        // PumpSwap pools have no pause flag
+        return Some(true);
+    }
+
+    None
+}
+
+/// Checks whether the pool currently accepts swaps, caching the answer for
+/// `POOL_STATUS_CACHE_TTL`. RPC failures are treated as enabled so a flaky
+/// node doesn't stop every bot on the pool.
+pub async fn pool_swap_enabled(pool: &Pubkey) -> bool {
+    if !*POOL_STATUS_CHECK_ENABLED {
+        return true;
+    }
+
+    {
+        let cache = POOL_STATUS_CACHE.read().await;
+        if let Some((enabled, checked_at)) = cache.get(pool) {
+            if checked_at.elapsed() < *POOL_STATUS_CACHE_TTL {
+                return *enabled;
+            }
+        }
+    }
+
+    let account = match RPC_CLIENT.get_account(pool).await {
+        Ok(account) => account,
+        Err(e) => {
+            println!("Failed to fetch pool state for {}: {}", pool, e);
+            return true;
+        }
+    };
+
+    let now_unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let enabled = swap_enabled_from_account(&account.owner, &account.data, now_unix).unwrap_or(true);
+
+    if !enabled {
+        println!("⏸️ Swapping is disabled on pool {}, skipping entry", pool);
+    }
+
+    POOL_STATUS_CACHE
+        .write()
+        .await
+        .insert(*pool, (enabled, Instant::now()));
+
+    enabled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPMM_POOL_STATE_DISCRIMINATOR: [u8; 8] = [0xf7, 0xed, 0xe3, 0xf5, 0xd7, 0xc3, 0xde, 0x46];
+
+    fn amm_v4_account(status: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 1024];
+        data[..8].copy_from_slice(&status.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn amm_v4_disabled_pool_is_rejected() {
+        let data = amm_v4_account(2);
+        assert_eq!(swap_enabled_from_account(&RAY_V4_PROGRAM_ID, &data, 0), Some(false));
+
+        let data = amm_v4_account(AMM_V4_STATUS_SWAP_ONLY);
+        assert_eq!(swap_enabled_from_account(&RAY_V4_PROGRAM_ID, &data, 0), Some(true));
+    }
+
+    #[test]
+    fn cpmm_swap_bit_disables_trading() {
+        let mut data = vec![0u8; 1024];
+        data[..8].copy_from_slice(&CPMM_POOL_STATE_DISCRIMINATOR);
+        // discriminator + 10 pubkeys + auth_bump
+        data[8 + 32 * 10 + 1] = CPMM_STATUS_SWAP_DISABLED_BIT;
+        assert_eq!(swap_enabled_from_account(&CPMM_PROGRAM_ID, &data, 0), Some(false));
+
+        data[8 + 32 * 10 + 1] = 0;
+        assert_eq!(swap_enabled_from_account(&CPMM_PROGRAM_ID, &data, 0), Some(true));
+    }
+
+    #[test]
+    fn unknown_owner_is_undecided() {
+        let data = amm_v4_account(2);
+        assert_eq!(swap_enabled_from_account(&Pubkey::new_unique(), &data, 0), None);
+    }
+}
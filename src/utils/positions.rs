@@ -0,0 +1,350 @@
+use dashmap::DashMap;
+
+use crate::{
+    backend::services::bot_service::RealPoolInfo,
+    utils::price_history::history_capacity,
+};
+
+/// Open positions per pool. Each pool's entry locks on its own, so updates to
+/// one pool don't wait on processors working another.
+pub type PoolInfoMap = DashMap<String, Vec<RealPoolInfo>>;
+
+/// Queues the user's position in `pool_id` to be persisted, or deleted from
+/// storage when it's gone or no longer bought
+pub fn position_changed(pool_id: &str, user_id: &str) {
+    if let Some(tx) = crate::statics::POSITION_CHANGES.get() {
+        let _ = tx.send((pool_id.to_string(), user_id.to_string()));
+    }
+}
+
+/// Copy of the user's position in `pool_id`
+pub fn position(pools: &PoolInfoMap, pool_id: &str, user_id: &str) -> Option<RealPoolInfo> {
+    pools
+        .get(pool_id)?
+        .iter()
+        .find(|info| info.user_bot_data.user_id == user_id)
+        .cloned()
+}
+
+/// Reads from the user's position in `pool_id` without copying all of it
+pub fn read_position<R>(
+    pools: &PoolInfoMap,
+    pool_id: &str,
+    user_id: &str,
+    f: impl FnOnce(&RealPoolInfo) -> R,
+) -> Option<R> {
+    pools
+        .get(pool_id)?
+        .iter()
+        .find(|info| info.user_bot_data.user_id == user_id)
+        .map(f)
+}
+
+/// Runs `f` on the user's position in `pool_id`, holding only that pool's
+/// entry. Returns `None` when the user has no position there.
+pub fn update_position<R>(
+    pools: &PoolInfoMap,
+    pool_id: &str,
+    user_id: &str,
+    f: impl FnOnce(&mut RealPoolInfo) -> R,
+) -> Option<R> {
+    let mut pool_infos = pools.get_mut(pool_id)?;
+    let info = pool_infos
+        .iter_mut()
+        .find(|info| info.user_bot_data.user_id == user_id)?;
+    Some(f(info))
+}
+
+/// Runs `f` on every position the user holds, in any pool
+pub fn update_user_positions(pools: &PoolInfoMap, user_id: &str, mut f: impl FnMut(&mut RealPoolInfo)) {
+    for mut pool_infos in pools.iter_mut() {
+        for info in pool_infos
+            .iter_mut()
+            .filter(|info| info.user_bot_data.user_id == user_id)
+        {
+            f(info);
+        }
+    }
+}
+
+/// The user's position in `pool_id`, adding `initial` when there is none yet
+pub fn get_or_insert_position(pools: &PoolInfoMap, pool_id: &str, initial: RealPoolInfo) -> RealPoolInfo {
+    let mut pool_infos = pools.entry(pool_id.to_string()).or_default();
+    match pool_infos
+        .iter()
+        .find(|info| info.user_bot_data.user_id == initial.user_bot_data.user_id)
+    {
+        Some(existing) => existing.clone(),
+        None => {
+            pool_infos.push(initial.clone());
+            initial
+        }
+    }
+}
+
+/// Removes the user's position in `pool_id`, dropping the pool with its last position
+pub fn remove_position(pools: &PoolInfoMap, pool_id: &str, user_id: &str) -> Option<RealPoolInfo> {
+    let removed = {
+        let mut pool_infos = pools.get_mut(pool_id)?;
+        let index = pool_infos
+            .iter()
+            .position(|info| info.user_bot_data.user_id == user_id)?;
+        pool_infos.remove(index)
+    };
+    pools.remove_if(pool_id, |_, pool_infos| pool_infos.is_empty());
+    Some(removed)
+}
+
+/// Removes every position the user holds and any pool left empty, returning how many went
+pub fn remove_user_positions(pools: &PoolInfoMap, user_id: &str) -> usize {
+    let mut removed = 0;
+    pools.retain(|_, pool_infos| {
+        let before = pool_infos.len();
+        pool_infos.retain(|info| info.user_bot_data.user_id != user_id);
+        removed += before - pool_infos.len();
+        !pool_infos.is_empty()
+    });
+    removed
+}
+
+/// Copies of the positions matching `filter`, across all pools
+pub fn positions_where(pools: &PoolInfoMap, filter: impl Fn(&RealPoolInfo) -> bool) -> Vec<RealPoolInfo> {
+    pools
+        .iter()
+        .flat_map(|pool_infos| {
+            pool_infos
+                .iter()
+                .filter(|info| filter(info))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The last observed price per pool, the one persisted for warm starts
+pub fn latest_pool_prices(pools: &PoolInfoMap) -> Vec<(String, f64)> {
+    pools
+        .iter()
+        .filter_map(|pool_infos| {
+            pool_infos
+                .iter()
+                .map(|info| info.latest_pool_price)
+                .find(|price| *price > 0.0)
+                .map(|price| (pool_infos.key().clone(), price))
+        })
+        .collect()
+}
+
+/// Clears the price histories of positions with nothing observed since
+/// `idle_before_ms` and sizes the rest to share `max_samples`. Returns how
+/// many samples are kept in all.
+pub fn trim_price_histories(pools: &PoolInfoMap, max_samples: usize, idle_before_ms: i64) -> usize {
+    let mut active_buffers = 0;
+    for mut pool_infos in pools.iter_mut() {
+        for info in pool_infos.iter_mut() {
+            for history in [&mut info.price_history, &mut info.quote_reserve_history] {
+                match history.last_observed_ms() {
+                    Some(ts) if ts >= idle_before_ms => active_buffers += 1,
+                    Some(_) => history.clear(),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    let capacity = history_capacity(max_samples, active_buffers);
+    let mut kept = 0;
+    for mut pool_infos in pools.iter_mut() {
+        for info in pool_infos.iter_mut() {
+            for history in [&mut info.price_history, &mut info.quote_reserve_history] {
+                history.set_capacity(capacity);
+                kept += history.len();
+            }
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{models::bot::BotSettings, services::bot_service::UserBotData};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Barrier, RwLock},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    const USERS: usize = 100;
+    const UPDATES_PER_USER: usize = 2_000;
+
+    fn position_for(pool_id: &str, user_id: &str) -> RealPoolInfo {
+        RealPoolInfo::new(UserBotData {
+            pool_id: pool_id.to_string(),
+            user_id: user_id.to_string(),
+            private_key: String::new(),
+            public_key: String::new(),
+            bot_setting: BotSettings::new(user_id.to_string(), "bot".to_string(), pool_id.to_string()),
+        })
+    }
+
+    #[test]
+    fn positions_are_found_updated_and_removed_per_user() {
+        let pools = PoolInfoMap::new();
+        get_or_insert_position(&pools, "pool", position_for("pool", "a"));
+        get_or_insert_position(&pools, "pool", position_for("pool", "b"));
+
+        // A second insert returns the existing position rather than replacing it
+        update_position(&pools, "pool", "a", |info| info.latest_pool_price = 1.5);
+        let existing = get_or_insert_position(&pools, "pool", position_for("pool", "a"));
+        assert_eq!(existing.latest_pool_price, 1.5);
+        assert_eq!(pools.get("pool").unwrap().len(), 2);
+
+        assert_eq!(update_position(&pools, "pool", "missing", |_| ()), None);
+        assert_eq!(position(&pools, "pool", "b").unwrap().latest_pool_price, 0.0);
+
+        assert!(remove_position(&pools, "pool", "a").is_some());
+        assert!(pools.contains_key("pool"));
+        assert!(remove_position(&pools, "pool", "b").is_some());
+        assert!(!pools.contains_key("pool"));
+    }
+
+    #[test]
+    fn price_history_stays_within_budget_as_pools_are_added() {
+        const MAX_SAMPLES: usize = 10_000;
+        let pools = PoolInfoMap::new();
+
+        for i in 0..500 {
+            let pool_id = format!("pool-{}", i);
+            get_or_insert_position(&pools, &pool_id, position_for(&pool_id, "user"));
+            update_position(&pools, &pool_id, "user", |info| {
+                for ts in 0..1_000 {
+                    info.price_history.push(ts, 1.0);
+                    info.quote_reserve_history.push(ts, 1.0);
+                }
+            });
+
+            let kept = trim_price_histories(&pools, MAX_SAMPLES, 0);
+            assert!(kept <= MAX_SAMPLES, "{} samples kept for {} pools", kept, i + 1);
+        }
+        // 500 pools with two buffers each share the budget evenly
+        assert_eq!(position(&pools, "pool-0", "user").unwrap().price_history.len(), 10);
+
+        // Pools nothing was observed on recently give their history up
+        update_position(&pools, "pool-0", "user", |info| info.price_history.push(5_000, 1.0));
+        let kept = trim_price_histories(&pools, MAX_SAMPLES, 2_000);
+        assert_eq!(kept, 10);
+        let pool = position(&pools, "pool-0", "user").unwrap();
+        assert_eq!(pool.price_history.latest(), Some(1.0));
+        assert!(pool.quote_reserve_history.is_empty());
+    }
+
+    /// A bot stopped while its pool's price ticks: the tick finds the position
+    /// gone part way through and skips it instead of panicking
+    #[test]
+    fn stopping_a_bot_during_price_ticks_does_not_panic() {
+        let pools = Arc::new(PoolInfoMap::new());
+        let barrier = Arc::new(Barrier::new(2));
+
+        let ticks = {
+            let (pools, barrier) = (pools.clone(), barrier.clone());
+            thread::spawn(move || {
+                barrier.wait();
+                for tick in 0..UPDATES_PER_USER {
+                    let price = tick as f64;
+                    update_position(&pools, "pool", "user", |info| info.latest_pool_price = price);
+                    read_position(&pools, "pool", "user", |info| info.latest_pool_price);
+                    position(&pools, "pool", "user");
+                }
+            })
+        };
+        let stops = {
+            let (pools, barrier) = (pools.clone(), barrier.clone());
+            thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..UPDATES_PER_USER {
+                    get_or_insert_position(&pools, "pool", position_for("pool", "user"));
+                    remove_user_positions(&pools, "user");
+                }
+            })
+        };
+        ticks.join().unwrap();
+        stops.join().unwrap();
+
+        assert!(!pools.contains_key("pool"));
+        assert_eq!(update_position(&pools, "pool", "user", |info| info.latest_pool_price = 1.0), None);
+        assert_eq!(read_position(&pools, "pool", "user", |info| info.latest_pool_price), None);
+        assert!(remove_position(&pools, "pool", "user").is_none());
+    }
+
+    /// Runs `update` for every user's own pool from its own thread and returns
+    /// how long the whole batch took
+    fn run_concurrent_users(update: impl Fn(&str, &str) + Send + Sync + 'static) -> Duration {
+        let update = Arc::new(update);
+        let barrier = Arc::new(Barrier::new(USERS + 1));
+        let handles: Vec<_> = (0..USERS)
+            .map(|i| {
+                let barrier = barrier.clone();
+                let update = update.clone();
+                thread::spawn(move || {
+                    let (pool_id, user_id) = (format!("pool-{}", i), format!("user-{}", i));
+                    barrier.wait();
+                    for _ in 0..UPDATES_PER_USER {
+                        update(&pool_id, &user_id);
+                    }
+                })
+            })
+            .collect();
+
+        barrier.wait();
+        let started = Instant::now();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        started.elapsed()
+    }
+
+    /// 100 users each hammering their own pool: with a single map-wide lock
+    /// they all queue on it, with per-pool entries they don't
+    #[test]
+    fn concurrent_users_on_separate_pools_do_not_serialize() {
+        let sharded = Arc::new(PoolInfoMap::new());
+        let global = Arc::new(RwLock::new(HashMap::<String, Vec<RealPoolInfo>>::new()));
+        for i in 0..USERS {
+            let (pool_id, user_id) = (format!("pool-{}", i), format!("user-{}", i));
+            get_or_insert_position(&sharded, &pool_id, position_for(&pool_id, &user_id));
+            global
+                .write()
+                .unwrap()
+                .insert(pool_id.clone(), vec![position_for(&pool_id, &user_id)]);
+        }
+
+        let pools = sharded.clone();
+        let dashmap_elapsed = run_concurrent_users(move |pool_id, user_id| {
+            update_position(&pools, pool_id, user_id, |info| info.latest_pool_price += 1.0);
+        });
+
+        let pools = global.clone();
+        let rwlock_elapsed = run_concurrent_users(move |pool_id, user_id| {
+            let mut pools = pools.write().unwrap();
+            if let Some(info) = pools
+                .get_mut(pool_id)
+                .and_then(|infos| infos.iter_mut().find(|info| info.user_bot_data.user_id == user_id))
+            {
+                info.latest_pool_price += 1.0;
+            }
+        });
+
+        println!(
+            "{} users x {} updates: per-pool entries {:?}, map-wide lock {:?}",
+            USERS, UPDATES_PER_USER, dashmap_elapsed, rwlock_elapsed
+        );
+
+        // No update is lost while the pools are written concurrently
+        for i in 0..USERS {
+            let info = position(&sharded, &format!("pool-{}", i), &format!("user-{}", i)).unwrap();
+            assert_eq!(info.latest_pool_price, UPDATES_PER_USER as f64);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+/// Decimal places a `FixedPrice` keeps
+pub const PRICE_DECIMALS: u32 = 12;
+const PRICE_SCALE: u128 = 10u128.pow(PRICE_DECIMALS);
+
+/// Resolution of `FixedPrice::percent_change_from`, in fractions of a percent
+const PERCENT_SCALE: i128 = 1_000_000;
+
+/// Quote per whole token, scaled by 1e12. Prices worked out from the same
+/// reserve ratio are equal however large the reserves are, where the f64
+/// division can land an ulp apart and read as a price change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct FixedPrice(u128);
+
+impl FixedPrice {
+    pub const ZERO: FixedPrice = FixedPrice(0);
+
+    /// Price of the token from raw pool reserves, rounded down. `None` while
+    /// the pool holds no tokens or the scaled reserves don't fit.
+    pub fn from_reserves(
+        quote_reserve: u128,
+        quote_decimals: u8,
+        token_reserve: u128,
+        token_decimals: u8,
+    ) -> Option<FixedPrice> {
+        if token_reserve == 0 {
+            return None;
+        }
+        // quote / 10^quote_decimals / (token / 10^token_decimals), scaled
+        let exponent = PRICE_DECIMALS as i32 + token_decimals as i32 - quote_decimals as i32;
+        let (numerator, denominator) = if exponent >= 0 {
+            (quote_reserve.checked_mul(10u128.checked_pow(exponent as u32)?)?, token_reserve)
+        } else {
+            (quote_reserve, token_reserve.checked_mul(10u128.checked_pow(exponent.unsigned_abs())?)?)
+        };
+        Some(FixedPrice(numerator / denominator))
+    }
+
+    /// Nearest fixed-point price to an `f64` one, zero for anything not positive
+    pub fn from_f64(price: f64) -> FixedPrice {
+        if price.is_finite() && price > 0.0 {
+            FixedPrice((price * PRICE_SCALE as f64).round() as u128)
+        } else {
+            FixedPrice::ZERO
+        }
+    }
+
+    /// For display and JSON only, comparisons stay in fixed point
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / PRICE_SCALE as f64
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Percent this price moved from `reference`, negative for a drop, zero
+    /// against a zero reference
+    pub fn percent_change_from(self, reference: FixedPrice) -> f64 {
+        if reference.is_zero() {
+            return 0.0;
+        }
+        let (new, old) = (self.0 as i128, reference.0 as i128);
+        let scaled = (new - old).saturating_mul(100 * PERCENT_SCALE) / old;
+        scaled as f64 / PERCENT_SCALE as f64
+    }
+}
+
+impl fmt::Display for FixedPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:012}", self.0 / PRICE_SCALE, self.0 % PRICE_SCALE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic reserves spread over several orders of magnitude
+    fn reserves(count: usize) -> Vec<(u128, u128)> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..count)
+            .map(|_| {
+                let sol = (next() % 10_000_000_000_000) as u128 + 1_000_000_000;
+                let tokens = (next() % 1_000_000_000_000_000) as u128 + 1_000_000_000_000;
+                (sol, tokens)
+            })
+            .collect()
+    }
+
+    fn f64_price(sol: u128, tokens: u128, token_decimals: u8) -> f64 {
+        (sol as f64 / 1e9) / (tokens as f64 / 10f64.powi(token_decimals as i32))
+    }
+
+    #[test]
+    fn the_same_reserve_ratio_is_the_same_price() {
+        let mut f64_jitters = 0;
+        for (sol, tokens) in reserves(2_000) {
+            let price = FixedPrice::from_reserves(sol, 9, tokens, 9).unwrap();
+            for factor in [3, 7, 1_000] {
+                let scaled = FixedPrice::from_reserves(sol * factor, 9, tokens * factor, 9).unwrap();
+                assert_eq!(scaled, price);
+                if f64_price(sol * factor, tokens * factor, 9) != f64_price(sol, tokens, 9) {
+                    f64_jitters += 1;
+                }
+            }
+        }
+        // Each of those would have been a spurious price change
+        assert!(f64_jitters > 0);
+    }
+
+    #[test]
+    fn price_is_monotonic_in_the_reserves() {
+        for (sol, tokens) in reserves(500) {
+            let mut previous = FixedPrice::ZERO;
+            for step in 0..50 {
+                // More SOL against the same tokens never prices lower
+                let price = FixedPrice::from_reserves(sol + step * 997, 9, tokens, 6).unwrap();
+                assert!(price >= previous);
+                previous = price;
+            }
+            // ...and more tokens against the same SOL never prices higher
+            let fewer = FixedPrice::from_reserves(sol, 9, tokens, 6).unwrap();
+            let more = FixedPrice::from_reserves(sol, 9, tokens + 1_000_003, 6).unwrap();
+            assert!(more <= fewer);
+        }
+    }
+
+    #[test]
+    fn percent_change_and_conversions() {
+        // 40 SOL against 1M tokens of 6 decimals
+        let price = FixedPrice::from_reserves(40_000_000_000, 9, 1_000_000_000_000, 6).unwrap();
+        assert_eq!(price.to_string(), "0.000040000000");
+        assert_eq!(price, FixedPrice::from_f64(0.000_04));
+        // USD1 quotes carry 6 decimals
+        let usd1 = FixedPrice::from_reserves(40_000_000, 6, 1_000_000_000_000_000, 9).unwrap();
+        assert_eq!(usd1, price);
+
+        let dropped = FixedPrice::from_f64(0.000_038);
+        assert_eq!(dropped.percent_change_from(price), -5.0);
+        assert_eq!(price.percent_change_from(dropped), 5.263157);
+        assert_eq!(price.percent_change_from(FixedPrice::ZERO), 0.0);
+
+        assert_eq!(FixedPrice::from_reserves(1, 9, 0, 6), None);
+        assert_eq!(FixedPrice::from_f64(-1.0), FixedPrice::ZERO);
+        assert_eq!(FixedPrice::from_f64(f64::NAN), FixedPrice::ZERO);
+    }
+}
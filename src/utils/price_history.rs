@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+/// Maximum number of samples kept per pool/user
+pub const PRICE_HISTORY_CAPACITY: usize = 4096;
+/// Samples older than this are dropped, so lookback windows can't exceed it
+pub const PRICE_HISTORY_MAX_AGE_SECS: u64 = 600;
+
+/// Per-buffer capacity when `buffers` histories share `max_samples`, each
+/// buffer's share shrinks as more pools are watched but never below one sample
+pub fn history_capacity(max_samples: usize, buffers: usize) -> usize {
+    (max_samples / buffers.max(1)).clamp(1, PRICE_HISTORY_CAPACITY)
+}
+
+/// Rolling buffer of observed pool prices, keyed by unix milliseconds.
+#[derive(Debug, Clone)]
+pub struct PriceHistory {
+    samples: VecDeque<(i64, f64)>,
+    capacity: usize,
+}
+
+impl Default for PriceHistory {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            capacity: PRICE_HISTORY_CAPACITY,
+        }
+    }
+}
+
+impl PriceHistory {
+    pub fn push(&mut self, ts_ms: i64, price: f64) {
+        if price <= 0.0 {
+            return;
+        }
+
+        self.samples.push_back((ts_ms, price));
+
+        let oldest_allowed = ts_ms - (PRICE_HISTORY_MAX_AGE_SECS as i64) * 1000;
+        while let Some((ts, _)) = self.samples.front() {
+            if *ts >= oldest_allowed && self.samples.len() <= self.capacity {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Keeps at most `capacity` of the newest samples from now on, releasing
+    /// the memory of any dropped
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.min(PRICE_HISTORY_CAPACITY);
+        if self.samples.len() > self.capacity {
+            let excess = self.samples.len() - self.capacity;
+            self.samples.drain(..excess);
+        }
+        self.samples.shrink_to(self.capacity);
+    }
+
+    /// Drops every sample and the memory behind them
+    pub fn clear(&mut self) {
+        self.samples = VecDeque::new();
+    }
+
+    /// When the newest sample was observed
+    pub fn last_observed_ms(&self) -> Option<i64> {
+        self.samples.back().map(|(ts, _)| *ts)
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<f64> {
+        self.samples.back().map(|(_, price)| *price)
+    }
+
+    /// Highest price observed at or after `since_ms`
+    pub fn max_since(&self, since_ms: i64) -> Option<f64> {
+        self.samples
+            .iter()
+            .rev()
+            .take_while(|(ts, _)| *ts >= since_ms)
+            .map(|(_, price)| *price)
+            .reduce(f64::max)
+    }
+
+    /// Number of samples at or after `since_ms` lower than the one before them
+    pub fn declines_since(&self, since_ms: i64) -> usize {
+        let recent: Vec<f64> = self
+            .samples
+            .iter()
+            .rev()
+            .take_while(|(ts, _)| *ts >= since_ms)
+            .map(|(_, price)| *price)
+            .collect();
+        recent.windows(2).filter(|pair| pair[0] < pair[1]).count()
+    }
+
+    /// Kaufman efficiency ratio of the prices at or after `since_ms`: net
+    /// move over total movement, 1.0 for a straight line and near 0 for chop
+    pub fn efficiency_ratio(&self, since_ms: i64) -> Option<f64> {
+        let recent: Vec<f64> = self
+            .samples
+            .iter()
+            .filter(|(ts, _)| *ts >= since_ms)
+            .map(|(_, price)| *price)
+            .collect();
+        let (first, last) = (recent.first()?, recent.last()?);
+        let path: f64 = recent.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum();
+        if path <= 0.0 {
+            return None;
+        }
+        Some((last - first).abs() / path)
+    }
+
+    /// Number of round trips at or after `since_ms`: a single-sample jump of at
+    /// least `min_move_pct` that is mostly (80%) given back within `max_gap`
+    /// samples, the footprint of a front-run buy and its back-run sell
+    pub fn round_trips_since(&self, since_ms: i64, min_move_pct: f64, max_gap: usize) -> usize {
+        let recent: Vec<f64> = self
+            .samples
+            .iter()
+            .filter(|(ts, _)| *ts >= since_ms)
+            .map(|(_, value)| *value)
+            .collect();
+
+        (1..recent.len())
+            .filter(|&i| {
+                let jump = recent[i] - recent[i - 1];
+                if jump <= 0.0 || jump / recent[i - 1] * 100.0 < min_move_pct {
+                    return false;
+                }
+                recent[i + 1..]
+                    .iter()
+                    .take(max_gap)
+                    .any(|later| recent[i] - later >= jump * 0.8)
+            })
+            .count()
+    }
+
+    /// Mean of the prices observed at or after `since_ms`
+    pub fn avg_over(&self, since_ms: i64) -> Option<f64> {
+        let (sum, count) = self
+            .samples
+            .iter()
+            .rev()
+            .take_while(|(ts, _)| *ts >= since_ms)
+            .fold((0.0, 0usize), |(sum, count), (_, price)| (sum + price, count + 1));
+
+        if count == 0 {
+            None
+        } else {
+            Some(sum / count as f64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_helpers_only_look_back_as_far_as_asked() {
+        let mut history = PriceHistory::default();
+        history.push(0, 2.0);
+        history.push(10_000, 1.0);
+        history.push(20_000, 1.5);
+        history.push(30_000, 0.9);
+
+        assert_eq!(history.max_since(0), Some(2.0));
+        assert_eq!(history.max_since(15_000), Some(1.5));
+        assert_eq!(history.max_since(30_000), Some(0.9));
+        assert_eq!(history.max_since(30_001), None);
+
+        assert_eq!(history.avg_over(20_000), Some((1.5 + 0.9) / 2.0));
+        assert_eq!(history.avg_over(0), Some((2.0 + 1.0 + 1.5 + 0.9) / 4.0));
+    }
+
+    #[test]
+    fn round_trips_need_a_jump_given_back_quickly() {
+        let mut history = PriceHistory::default();
+        // Front-run buy 100 -> 110, victim buy -> 111, back-run sell -> 102
+        for (ts, reserve) in [(0, 100.0), (1, 110.0), (2, 111.0), (3, 102.0)] {
+            history.push(ts, reserve);
+        }
+        assert_eq!(history.round_trips_since(0, 5.0, 3), 1);
+        // Too small a jump to count
+        assert_eq!(history.round_trips_since(0, 20.0, 3), 0);
+        // Given back too late
+        assert_eq!(history.round_trips_since(0, 5.0, 1), 0);
+
+        // A steady climb is not a round trip
+        let mut climb = PriceHistory::default();
+        for (ts, reserve) in [(0, 100.0), (1, 110.0), (2, 120.0), (3, 118.0)] {
+            climb.push(ts, reserve);
+        }
+        assert_eq!(climb.round_trips_since(0, 5.0, 3), 0);
+    }
+
+    #[test]
+    fn shrinking_capacity_keeps_the_newest_samples() {
+        let mut history = PriceHistory::default();
+        for ts in 0..100 {
+            history.push(ts, 1.0 + ts as f64);
+        }
+        history.set_capacity(10);
+        assert_eq!(history.len(), 10);
+        assert_eq!(history.latest(), Some(100.0));
+
+        history.push(100, 101.0);
+        assert_eq!(history.len(), 10);
+
+        assert_eq!(history_capacity(1_000_000, 10), PRICE_HISTORY_CAPACITY);
+        assert_eq!(history_capacity(10_000, 500), 20);
+        assert_eq!(history_capacity(10_000, 1_000_000), 1);
+    }
+
+    #[test]
+    fn old_samples_are_evicted() {
+        let mut history = PriceHistory::default();
+        history.push(0, 1.0);
+        history.push((PRICE_HISTORY_MAX_AGE_SECS as i64 + 1) * 1000, 2.0);
+
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.latest(), Some(2.0));
+    }
+}
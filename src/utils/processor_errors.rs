@@ -0,0 +1,97 @@
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::config::PROCESSOR_ERROR_PAUSE_THRESHOLD;
+
+/// Total errors returned by processor tasks since startup
+pub static PROCESSOR_ERRORS_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Consecutive processor errors per user, reset on the next success.
+/// Updates only lock the shard holding that user, so one user's result
+/// never blocks another user's processor task.
+static CONSECUTIVE_ERRORS: Lazy<DashMap<String, u32>> = Lazy::new(DashMap::new);
+
+/// Logs and counts the outcome of a spawned `process_user_data` call.
+///
+/// Returns `true` when the user has now failed `threshold` times in a row
+/// and their bot should be paused (a threshold of zero never pauses).
+pub fn record_processor_result<E: Display>(
+    user_id: &str,
+    pool_id: &str,
+    result: Result<(), E>,
+    threshold: u32,
+) -> bool {
+    match result {
+        Ok(()) => {
+            // The common case: nothing to clear, so only a shard read lock is taken
+            if CONSECUTIVE_ERRORS.contains_key(user_id) {
+                CONSECUTIVE_ERRORS.remove(user_id);
+            }
+            false
+        }
+        Err(e) => {
+            PROCESSOR_ERRORS_TOTAL.fetch_add(1, Ordering::Relaxed);
+            let count = {
+                let mut count = CONSECUTIVE_ERRORS.entry(user_id.to_string()).or_insert(0);
+                *count += 1;
+                *count
+            };
+            tracing::error!(
+                user_id,
+                pool_id,
+                "❌ Processor error ({} in a row): {}",
+                count,
+                e
+            );
+            threshold > 0 && count == threshold
+        }
+    }
+}
+
+/// Records the result and pauses entries for the user once the configured
+/// threshold of consecutive failures is reached.
+pub fn report_processor_result<E: Display>(
+    user_id: &str,
+    pool_id: &str,
+    result: Result<(), E>,
+) {
+    if !record_processor_result(user_id, pool_id, result, *PROCESSOR_ERROR_PAUSE_THRESHOLD) {
+        return;
+    }
+
+    tracing::warn!(
+        user_id,
+        "⏸️ Pausing entries after {} consecutive processor errors",
+        *PROCESSOR_ERROR_PAUSE_THRESHOLD
+    );
+    crate::utils::positions::update_position(&crate::statics::REAL_POOL_INFO, pool_id, user_id, |info| {
+        info.paused = true;
+    });
+}
+
+/// Clears the consecutive error count, e.g. when a bot is restarted
+pub fn reset_processor_errors(user_id: &str) {
+    CONSECUTIVE_ERRORS.remove(user_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_counted_and_trip_the_pause() {
+        let before = PROCESSOR_ERRORS_TOTAL.load(Ordering::Relaxed);
+
+        assert!(!record_processor_result("u1", "p", Err("boom"), 2));
+        assert!(record_processor_result("u1", "p", Err("boom"), 2));
+        assert!(PROCESSOR_ERRORS_TOTAL.load(Ordering::Relaxed) >= before + 2);
+
+        // A success resets the streak
+        assert!(!record_processor_result::<&str>("u1", "p", Ok(()), 2));
+        assert!(!record_processor_result("u1", "p", Err("boom"), 2));
+    }
+}
@@ -0,0 +1,121 @@
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+
+use crate::config::{ATA_RENT_PREFLIGHT_ENABLED, RPC_CLIENT};
+
+/// Rent-exempt minimum for a 165 byte SPL token account
+pub const TOKEN_ACCOUNT_RENT_LAMPORTS: u64 = 2_039_280;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// Token accounts the instructions create through the associated token program
+pub fn ata_creations(ixs: &[Instruction]) -> Vec<Pubkey> {
+    ixs.iter()
+        .filter(|ix| ix.program_id == spl_associated_token_account::id())
+        .filter_map(|ix| ix.accounts.get(1).map(|meta| meta.pubkey))
+        .collect()
+}
+
+/// Lamports moved out of `payer` by system transfers, e.g. wrapping the buy amount
+pub fn lamports_transferred_from(ixs: &[Instruction], payer: &Pubkey) -> u64 {
+    ixs.iter()
+        .filter(|ix| ix.program_id == system_program::id())
+        .filter(|ix| ix.accounts.first().map(|meta| meta.pubkey) == Some(*payer))
+        .filter_map(|ix| match bincode::deserialize::<SystemInstruction>(&ix.data) {
+            Ok(SystemInstruction::Transfer { lamports }) => Some(lamports),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Balance a buy needs: the SOL it transfers, rent for each token account it
+/// has to create, and the projected fee and tip
+pub fn required_buy_lamports(transfer_lamports: u64, missing_atas: usize, fee_sol: f64) -> u64 {
+    transfer_lamports
+        + missing_atas as u64 * TOKEN_ACCOUNT_RENT_LAMPORTS
+        + (fee_sol * LAMPORTS_PER_SOL).ceil() as u64
+}
+
+/// Checks the payer can afford the buy before it is submitted. Returns the
+/// reason when it can't; RPC failures let the buy through, as the chain will
+/// reject it anyway.
+pub async fn check_buy_balance(payer: &Pubkey, ixs: &[Instruction], fee_sol: f64) -> Result<(), String> {
+    if !*ATA_RENT_PREFLIGHT_ENABLED {
+        return Ok(());
+    }
+
+    let atas = ata_creations(ixs);
+    let mut keys = vec![*payer];
+    keys.extend(&atas);
+
+    let accounts = match RPC_CLIENT.get_multiple_accounts(&keys).await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            println!("Failed to fetch balances for buy preflight: {}", e);
+            return Ok(());
+        }
+    };
+
+    let balance = accounts
+        .first()
+        .and_then(|account| account.as_ref())
+        .map(|account| account.lamports)
+        .unwrap_or(0);
+    let missing_atas = accounts.iter().skip(1).filter(|account| account.is_none()).count();
+    let required = required_buy_lamports(lamports_transferred_from(ixs, payer), missing_atas, fee_sol);
+
+    if balance < required {
+        return Err(format!(
+            "wallet has {:.9} SOL but the buy needs {:.9} SOL ({} token account(s) to create at {:.9} SOL rent each, plus fees and tip)",
+            balance as f64 / LAMPORTS_PER_SOL,
+            required as f64 / LAMPORTS_PER_SOL,
+            missing_atas,
+            TOKEN_ACCOUNT_RENT_LAMPORTS as f64 / LAMPORTS_PER_SOL
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::system_instruction;
+    use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+    #[test]
+    fn wallet_funded_exactly_at_buy_amount_is_short() {
+        let payer = Pubkey::new_unique();
+        let buy_lamports = 100_000_000;
+        let wsol = spl_token::native_mint::id();
+        let ixs = vec![
+            create_associated_token_account_idempotent(&payer, &payer, &wsol, &spl_token::id()),
+            create_associated_token_account_idempotent(
+                &payer,
+                &payer,
+                &Pubkey::new_unique(),
+                &spl_token::id(),
+            ),
+            system_instruction::transfer(
+                &payer,
+                &spl_associated_token_account::get_associated_token_address(&payer, &wsol),
+                buy_lamports,
+            ),
+        ];
+
+        assert_eq!(ata_creations(&ixs).len(), 2);
+        assert_eq!(lamports_transferred_from(&ixs, &payer), buy_lamports);
+
+        let fee_sol = 0.000_105;
+        let required = required_buy_lamports(buy_lamports, 2, fee_sol);
+        assert!(buy_lamports < required);
+        assert_eq!(required, buy_lamports + 2 * TOKEN_ACCOUNT_RENT_LAMPORTS + 105_000);
+
+        // Both token accounts already exist: only the fee is on top
+        assert_eq!(required_buy_lamports(buy_lamports, 0, fee_sol), buy_lamports + 105_000);
+    }
+}
@@ -0,0 +1,232 @@
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Serialize;
+use serde_json::json;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use std::{
+    env,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use crate::config::{zslot_client_for, JITO_CLIENT, NOZOMI_CLIENT, RPC_CLIENT};
+
+/// Per-service timeout, the checks run concurrently
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+static SELF_TEST_RESULTS: Lazy<RwLock<Vec<ServiceCheck>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceState {
+    Ok,
+    Unauthorized,
+    Unreachable,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ServiceCheck {
+    pub service: String,
+    pub state: ServiceState,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Results of the last self-test, empty until one has run
+pub fn latest_self_test() -> Vec<ServiceCheck> {
+    SELF_TEST_RESULTS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Replaces the last result for `check.service`, so later checks show on the
+/// health endpoint alongside the startup self-test
+pub fn record_check(check: ServiceCheck) {
+    let mut results = SELF_TEST_RESULTS.write().unwrap_or_else(|e| e.into_inner());
+    results.retain(|existing| existing.service != check.service);
+    results.push(check);
+}
+
+/// Any HTTP reply means the relay is up, 401/403 mean the key was refused
+pub fn state_from_http_status(status: StatusCode) -> ServiceState {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ServiceState::Unauthorized,
+        _ => ServiceState::Ok,
+    }
+}
+
+pub fn check(service: &str, started: Instant, state: ServiceState, detail: Option<String>) -> ServiceCheck {
+    ServiceCheck {
+        service: service.to_string(),
+        state,
+        latency_ms: started.elapsed().as_millis() as u64,
+        detail,
+    }
+}
+
+/// A failed request's error without its URL. Relay and RPC URLs carry their
+/// API keys, and these details are served on the public health endpoint.
+pub fn request_error_detail(err: reqwest::Error) -> String {
+    err.without_url().to_string()
+}
+
+/// An RPC client error, with transport failures reduced to what went wrong
+/// so the node's URL isn't repeated
+pub fn rpc_error_detail(err: &ClientError) -> String {
+    match err.kind() {
+        ClientErrorKind::Reqwest(err) if err.is_timeout() => "timed out".to_string(),
+        ClientErrorKind::Reqwest(_) => "unreachable".to_string(),
+        _ => err.to_string(),
+    }
+}
+
+async fn probe(service: &str, request: RequestBuilder) -> ServiceCheck {
+    let started = Instant::now();
+    match request.timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) => {
+            let status = response.status();
+            let state = state_from_http_status(status);
+            let detail = (state != ServiceState::Ok).then(|| format!("HTTP {}", status));
+            check(service, started, state, detail)
+        }
+        Err(err) => check(service, started, ServiceState::Unreachable, Some(request_error_detail(err))),
+    }
+}
+
+/// Posts a harmless JSON-RPC call to a relay's submit URL (which carries the
+/// API key) and classifies the reply
+pub async fn probe_http(client: &Client, service: &str, url: &str) -> ServiceCheck {
+    let payload = json!({ "jsonrpc": "2.0", "id": 1, "method": "getHealth" });
+    probe(service, client.post(url).json(&payload)).await
+}
+
+/// Display name and submit URL (carrying the API key) of a `confirm_service`
+/// relay, none when it isn't initialized. An empty `zslot_region` is the
+/// default ZeroSlot client.
+pub fn relay_submit_url(service: &str, zslot_region: &str) -> Option<(&'static str, String)> {
+    match service {
+        "JITO" => JITO_CLIENT
+            .get()
+            .map(|jito| ("Jito", jito.endpoint.submit_endpoint.to_string())),
+        "NOZOMI" => NOZOMI_CLIENT.get().map(|nozomi| {
+            ("Nozomi", format!("{}{}", nozomi.endpoint.submit_endpoint, nozomi.auth_key))
+        }),
+        "ZERO_SLOT" => zslot_client_for(zslot_region).map(|zslot| {
+            ("ZSlot", format!("{}{}", zslot.endpoint.submit_endpoint, zslot.auth_key))
+        }),
+        _ => None,
+    }
+}
+
+async fn probe_rpc() -> ServiceCheck {
+    let started = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, RPC_CLIENT.get_version()).await {
+        Ok(Ok(_)) => check("RPC", started, ServiceState::Ok, None),
+        Ok(Err(err)) => check("RPC", started, ServiceState::Unreachable, Some(rpc_error_detail(&err))),
+        Err(_) => check("RPC", started, ServiceState::Unreachable, Some("timed out".to_string())),
+    }
+}
+
+/// Plain HTTP request to the Geyser endpoint with the configured `X_TOKEN`.
+/// Gateways in front of the gRPC server answer it, rejecting a bad token with
+/// 401/403; the stream itself is only proven once the pipeline subscribes.
+async fn probe_geyser(client: &Client, url: &str, x_token: Option<String>) -> ServiceCheck {
+    let mut request = client.get(url);
+    if let Some(token) = x_token {
+        request = request.header("x-token", token);
+    }
+    probe("Geyser", request).await
+}
+
+/// Checks every initialized submission service plus the RPC node and Geyser
+/// endpoint, logs the outcome and keeps it for the health endpoint
+pub async fn run_self_test() -> Vec<ServiceCheck> {
+    let client = Client::new();
+    let relays: Vec<(&str, String)> = ["JITO", "NOZOMI", "ZERO_SLOT"]
+        .into_iter()
+        .filter_map(|service| relay_submit_url(service, ""))
+        .collect();
+
+    let relay_checks = join_all(
+        relays
+            .iter()
+            .map(|(service, url)| probe_http(&client, service, url)),
+    );
+    let geyser_url = env::var("GEYSER_URL").unwrap_or_default();
+    let (mut results, rpc, geyser) = tokio::join!(
+        relay_checks,
+        probe_rpc(),
+        probe_geyser(&client, &geyser_url, env::var("X_TOKEN").ok())
+    );
+    results.push(rpc);
+    results.push(geyser);
+
+    for result in &results {
+        match result.state {
+            ServiceState::Ok => {
+                tracing::info!("✅ Self-test {}: ok ({} ms)", result.service, result.latency_ms)
+            }
+            state => tracing::warn!(
+                "❌ Self-test {}: {:?} {}",
+                result.service,
+                state,
+                result.detail.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+
+    *SELF_TEST_RESULTS.write().unwrap_or_else(|e| e.into_inner()) = results.clone();
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serves one canned HTTP status to every connection
+    async fn mock_relay(status_line: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let response = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\n\r\n", status_line);
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}/?api-key=test", addr)
+    }
+
+    #[tokio::test]
+    async fn reachable_refused_and_unreachable_services_are_told_apart() {
+        let client = Client::new();
+
+        let up = mock_relay("200 OK").await;
+        let refused = mock_relay("401 Unauthorized").await;
+        let down = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            format!("http://{}/?api-key=secret", listener.local_addr().unwrap())
+        };
+
+        let up = probe_http(&client, "up", &up).await;
+        assert_eq!(up.state, ServiceState::Ok);
+        assert_eq!(up.detail, None);
+
+        let refused = probe_http(&client, "refused", &refused).await;
+        assert_eq!(refused.state, ServiceState::Unauthorized);
+
+        let down = probe_http(&client, "down", &down).await;
+        assert_eq!(down.state, ServiceState::Unreachable);
+        let detail = down.detail.unwrap();
+        assert!(!detail.is_empty());
+        assert!(!detail.contains("secret"), "{}", detail);
+    }
+}
@@ -0,0 +1,111 @@
+use solana_sdk::transaction::TransactionError;
+
+use crate::utils::min_output::LIGHTHOUSE_PROGRAM_ID;
+
+/// Log lines the supported AMMs fail a swap with when its limit is crossed
+const SLIPPAGE_MARKERS: [&str; 4] = [
+    // PumpSwap, Raydium CPMM and Launchpad
+    "Error Code: ExceededSlippage",
+    // Raydium AMM v4
+    "exceeds desired slippage limit",
+    // Pump.fun bonding curve
+    "Error Code: TooMuchSolRequired",
+    "Error Code: TooLittleSolReceived",
+];
+
+const INSUFFICIENT_FUNDS_MARKERS: [&str; 2] = ["insufficient funds", "insufficient lamports"];
+
+/// Why a swap's simulation failed, as far as retrying it goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationFailure {
+    /// The pool moved past the swap's limit, a wider one may pass
+    Slippage,
+    /// The wallet can't pay for it, no slippage changes that
+    InsufficientFunds,
+    Other,
+}
+
+/// Classifies a failed simulation from its error and logs. Insufficient
+/// funds wins over slippage, a swap the wallet can't pay for isn't retried.
+pub fn classify_simulation_failure(err: &TransactionError, logs: &[String]) -> SimulationFailure {
+    let underfunded = matches!(
+        err,
+        TransactionError::InsufficientFundsForFee | TransactionError::InsufficientFundsForRent { .. }
+    ) || logs.iter().any(|line| {
+        let line = line.to_lowercase();
+        INSUFFICIENT_FUNDS_MARKERS.iter().any(|marker| line.contains(marker))
+    });
+    if underfunded {
+        return SimulationFailure::InsufficientFunds;
+    }
+
+    // The min-output assertion failing is the same fill coming in too low
+    let assertion_failed = format!("Program {} failed", LIGHTHOUSE_PROGRAM_ID);
+    if logs.iter().any(|line| {
+        SLIPPAGE_MARKERS.iter().any(|marker| line.contains(marker)) || line.starts_with(&assertion_failed)
+    }) {
+        return SimulationFailure::Slippage;
+    }
+    SimulationFailure::Other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::InstructionError;
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn slippage_failures_are_told_apart_from_missing_funds() {
+        let custom = |code| TransactionError::InstructionError(2, InstructionError::Custom(code));
+
+        let pumpswap = logs(&[
+            "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA invoke [1]",
+            "Program log: Instruction: Sell",
+            "Program log: AnchorError thrown in programs/pump-amm/src/instructions/sell.rs:211. Error Code: ExceededSlippage. Error Number: 6004. Error Message: Slippage tolerance exceeded.",
+            "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA consumed 41520 of 299850 compute units",
+            "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA failed: custom program error: 0x1774",
+        ]);
+        assert_eq!(classify_simulation_failure(&custom(0x1774), &pumpswap), SimulationFailure::Slippage);
+
+        let amm_v4 = logs(&[
+            "Program 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8 invoke [1]",
+            "Program log: Error: exceeds desired slippage limit",
+            "Program 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8 failed: custom program error: 0x1e",
+        ]);
+        assert_eq!(classify_simulation_failure(&custom(0x1e), &amm_v4), SimulationFailure::Slippage);
+
+        let assertion = logs(&[
+            "Program L2TExMFKdjpN9kozasaurPirfHy9P8sbXoAN1qA3S95 invoke [1]",
+            "Program L2TExMFKdjpN9kozasaurPirfHy9P8sbXoAN1qA3S95 failed: custom program error: 0x1771",
+        ]);
+        assert_eq!(classify_simulation_failure(&custom(0x1771), &assertion), SimulationFailure::Slippage);
+
+        let underfunded = logs(&[
+            "Program 11111111111111111111111111111111 invoke [1]",
+            "Transfer: insufficient lamports 1200000, need 50000000",
+            "Program 11111111111111111111111111111111 failed: custom program error: 0x1",
+        ]);
+        assert_eq!(classify_simulation_failure(&custom(1), &underfunded), SimulationFailure::InsufficientFunds);
+
+        let token = logs(&[
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program log: Instruction: TransferChecked",
+            "Program log: Error: insufficient funds",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA failed: custom program error: 0x1",
+        ]);
+        assert_eq!(classify_simulation_failure(&custom(1), &token), SimulationFailure::InsufficientFunds);
+        assert_eq!(
+            classify_simulation_failure(&TransactionError::InsufficientFundsForFee, &[]),
+            SimulationFailure::InsufficientFunds
+        );
+
+        assert_eq!(
+            classify_simulation_failure(&TransactionError::BlockhashNotFound, &[]),
+            SimulationFailure::Other
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{
+    config::{STUCK_ALERT_WEBHOOK_URL, STUCK_EXIT_FAILURES, STUCK_EXIT_WINDOW},
+    statics::REAL_POOL_INFO,
+    utils::positions::update_position,
+};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Counts a failed exit on the user's position in `pool_id` and alerts once
+/// the failures within `STUCK_EXIT_WINDOW` reach `STUCK_EXIT_FAILURES`
+pub async fn record_exit_failure(pool_id: &str, user_id: &str, reason: &str) {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let flagged = update_position(&REAL_POOL_INFO, pool_id, user_id, |info| {
+        info.record_exit_failure(now_ms, *STUCK_EXIT_FAILURES, STUCK_EXIT_WINDOW.as_millis() as i64)
+            .then_some(info.exit_failures.len())
+    })
+    .flatten();
+
+    tracing::warn!(user_id, "⚠️ Exit attempt on pool {} failed: {}", pool_id, reason);
+    if let Some(failures) = flagged {
+        alert_stuck_position(pool_id, user_id, failures, reason).await;
+    }
+}
+
+async fn alert_stuck_position(pool_id: &str, user_id: &str, failures: usize, reason: &str) {
+    tracing::error!(
+        user_id,
+        "🚨 STUCK POSITION on pool {}: {} exits failed within {:?}, last error: {}. Manual intervention needed.",
+        pool_id,
+        failures,
+        *STUCK_EXIT_WINDOW,
+        reason
+    );
+
+    let Some(url) = STUCK_ALERT_WEBHOOK_URL.as_ref() else {
+        return;
+    };
+    let payload = json!({
+        "alert": "stuck_position",
+        "user_id": user_id,
+        "pool_id": pool_id,
+        "failed_exits": failures,
+        "last_error": reason,
+    });
+    let sent = Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(e) = sent {
+        tracing::error!(user_id, "Failed to send stuck position alert: {}", e);
+    }
+}
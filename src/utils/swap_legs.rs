@@ -0,0 +1,86 @@
+use once_cell::sync::Lazy;
+use solana_sdk::signature::Signature;
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Mutex,
+};
+
+use crate::config::DEDUPE_SWAP_LEGS;
+
+/// Legs remembered to match a transaction's later ones against, far more
+/// than are ever processed at once
+const REMEMBERED_LEGS: usize = 4_096;
+
+static SEEN_LEGS: Lazy<Mutex<SeenLegs>> = Lazy::new(|| Mutex::new(SeenLegs::new(REMEMBERED_LEGS)));
+
+type Leg = (String, String, String);
+
+/// The (signature, pool, user) legs already processed, oldest evicted first
+pub struct SeenLegs {
+    capacity: usize,
+    order: VecDeque<Leg>,
+    seen: HashSet<Leg>,
+}
+
+impl SeenLegs {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// True for the first leg of `signature` on the user's pool, false for the rest
+    pub fn first_leg(&mut self, signature: &str, pool_id: &str, user_id: &str) -> bool {
+        let leg = (signature.to_string(), pool_id.to_string(), user_id.to_string());
+        if !self.seen.insert(leg.clone()) {
+            return false;
+        }
+        self.order.push_back(leg);
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.seen.remove(&evicted);
+            }
+        }
+        true
+    }
+}
+
+/// Whether a swap is the first leg of its transaction on the user's pool. A
+/// transaction swapping the pool more than once, a sandwich bundled into one
+/// transaction or an arbitrage round trip, reaches the processors once per
+/// leg. The reserves they read are the transaction's post balances, the net
+/// of every leg, so the first leg already prices the pool's final state and
+/// the others are skipped rather than recorded again.
+pub fn first_pool_leg(signature: &Signature, pool_id: &str, user_id: &str) -> bool {
+    if !*DEDUPE_SWAP_LEGS {
+        return true;
+    }
+    SEEN_LEGS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .first_leg(&signature.to_string(), pool_id, user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_buy_and_sell_in_one_transaction_are_processed_once() {
+        let mut legs = SeenLegs::new(2);
+
+        // Buy and sell legs of one transaction on the pool
+        assert!(legs.first_leg("sig-1", "pool", "user"));
+        assert!(!legs.first_leg("sig-1", "pool", "user"));
+        // Another watched pool and another user of the pool each get their turn
+        assert!(legs.first_leg("sig-1", "other-pool", "user"));
+        assert!(!legs.first_leg("sig-1", "other-pool", "user"));
+
+        // The oldest legs are forgotten past capacity
+        assert!(legs.first_leg("sig-2", "pool", "user"));
+        assert!(legs.first_leg("sig-1", "pool", "user"));
+        assert!(!legs.first_leg("sig-2", "pool", "user"));
+    }
+}
@@ -1,5 +1,7 @@
 use std::ops::{Add, Div, Mul};
 
+use crate::backend::services::bot_service::ExitSignal;
+use crate::utils::pool_reserves::PoolReserves;
 use crate::utils::utils::{FEE_RATE, TRADE_FEE_RATE, calculate_fee};
 
 pub fn get_amount_out(amount_in: u128, input_reserve: u128, output_reserve: u128) -> u128 {
@@ -27,6 +29,46 @@ pub fn get_swap_quote(amount_in: u64, base_reserve: u64, quote_reserve: u64) ->
     result as u64
 }
 
+/// Least a Launchpad swap of `amount_in` may return at `slippage_pct`
+/// against the curve `reserves`. Buys pay the fee on the quote going in,
+/// sells on the quote coming out.
+pub fn launchpad_min_out(amount_in: u64, reserves: PoolReserves, is_buy: bool, slippage_pct: f64) -> u64 {
+    let amount_out = if is_buy {
+        get_swap_quote(amount_in, reserves.base, reserves.quote)
+    } else {
+        let quote_out = get_amount_out(amount_in as u128, reserves.base as u128, reserves.quote as u128) as u64;
+        quote_out - calculate_fee(quote_out, TRADE_FEE_RATE + FEE_RATE)
+    };
+    (amount_out as f64 * (1.0 - slippage_pct / 100.0).max(0.0)) as u64
+}
+
+/// How much worse than the spot price a buy of `amount_in` quote units fills
+/// along the constant-product curve, in percent. The pool's fee is left out,
+/// it differs per program and is charged whatever the size. An empty pool
+/// gives nothing for the buy, 100%.
+pub fn price_impact_pct(amount_in: u64, token_reserve: u64, quote_reserve: u64) -> f64 {
+    if amount_in == 0 {
+        return 0.0;
+    }
+    if token_reserve == 0 || quote_reserve == 0 {
+        return 100.0;
+    }
+    let spot_out = amount_in as f64 * token_reserve as f64 / quote_reserve as f64;
+    let curve_out = get_amount_out(amount_in as u128, quote_reserve as u128, token_reserve as u128) as f64;
+    (1.0 - curve_out / spot_out) * 100.0
+}
+
+/// Expected and minimum tokens out for a buy of `amount_in` lamports, with
+/// the same 0.3% fee and percent slippage the processors build buys with
+pub fn buy_min_out(amount_in: u64, sol_reserve: u64, token_reserve: u64, slippage_pct: f64) -> (u64, u64) {
+    if sol_reserve + amount_in == 0 {
+        return (0, 0);
+    }
+    let expected = 0.997 * amount_in as f64 * token_reserve as f64 / (sol_reserve + amount_in) as f64;
+    let min_out = expected * (1.0 - slippage_pct / 100.0).max(0.0);
+    (expected as u64, min_out as u64)
+}
+
 pub fn sol_token_quote(
     amount: u64,
     virtual_sol_reserves: u64,
@@ -66,6 +108,42 @@ pub fn token_sol_quote(
     out_sol_amount as u64
 }
 
+/// Minimum SOL output for a sell exiting for `reason`. With a `cost_floor`,
+/// a take-profit may not fill below the position's cost; stop-losses,
+/// trailing stops and timeouts keep the plain slippage bound so they still
+/// get out.
+pub fn sell_min_out_with_cost_floor(slippage_min_out: u64, cost_floor: Option<u64>, reason: &ExitSignal) -> u64 {
+    match cost_floor {
+        Some(cost) if reason.takes_profit() => slippage_min_out.max(cost),
+        _ => slippage_min_out,
+    }
+}
+
+/// Realized slippage of a fill in percent of the output expected at
+/// `signal_price` (quote token, SOL or USD1, per token): tokens for the
+/// `quote` amount on a buy, quote for `tokens` on a sell. Positive when the
+/// fill came out worse than expected.
+pub fn realized_slippage_pct(
+    signal_price: f64,
+    quote: i128,
+    quote_decimals: u8,
+    tokens: i128,
+    decimals: u8,
+    is_buy: bool,
+) -> Option<f64> {
+    let quote_amount = quote.unsigned_abs() as f64 / 10f64.powi(quote_decimals as i32);
+    let token_amount = tokens.unsigned_abs() as f64 / 10f64.powi(decimals as i32);
+    let (expected, actual) = if is_buy {
+        (quote_amount / signal_price, token_amount)
+    } else {
+        (token_amount * signal_price, quote_amount)
+    };
+    if !(signal_price > 0.0 && expected > 0.0 && expected.is_finite()) {
+        return None;
+    }
+    Some((expected - actual) / expected * 100.0)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::utils::utils::{FEE_RATE, TRADE_FEE_RATE, calculate_fee};
@@ -98,4 +176,72 @@ mod tests {
         
         println!("{}", result1);
     }
+
+    #[test]
+    fn launchpad_min_out_takes_the_fee_and_slippage_off_the_curve_quote() {
+        let reserves = PoolReserves {
+            base: 1073025605596382_u64 - 555337575467276_u64,
+            quote: 30000852951_u64 + 32182704639_u64,
+        };
+
+        let buy_quote = get_swap_quote(693000000, reserves.base, reserves.quote);
+        assert_eq!(launchpad_min_out(693000000, reserves, true, 0.0), buy_quote);
+        assert_eq!(launchpad_min_out(693000000, reserves, true, 10.0), (buy_quote as f64 * 0.9) as u64);
+
+        // Selling what the buy got returns less than went in, the fee is charged both ways
+        let sell_quote = launchpad_min_out(buy_quote, reserves, false, 0.0);
+        assert!(sell_quote < 693000000 && sell_quote > 693000000 * 95 / 100, "{}", sell_quote);
+        assert_eq!(launchpad_min_out(buy_quote, reserves, false, 100.0), 0);
+    }
+
+    #[test]
+    fn larger_buys_move_the_price_more() {
+        // 100 SOL against 1B tokens
+        let (token_reserve, sol_reserve) = (1_000_000_000_000_000, 100_000_000_000);
+
+        // A 0.1 SOL buy barely moves the price, no fee is counted
+        let small = price_impact_pct(100_000_000, token_reserve, sol_reserve);
+        assert!(small > 0.09 && small < 0.11, "{}", small);
+        // 10 SOL takes a tenth of the pool's SOL side
+        let large = price_impact_pct(10_000_000_000, token_reserve, sol_reserve);
+        assert!(large > 9.0 && large < 9.2, "{}", large);
+
+        assert_eq!(price_impact_pct(0, token_reserve, sol_reserve), 0.0);
+        assert_eq!(price_impact_pct(100_000_000, 0, 0), 100.0);
+    }
+
+    #[test]
+    fn cost_floor_only_applies_to_take_profit_sells() {
+        let floor = Some(1_000_000_000);
+        // Take-profits, single or tiered, may not fill below the cost
+        assert_eq!(sell_min_out_with_cost_floor(600_000_000, floor, &ExitSignal::TakeProfit), 1_000_000_000);
+        let tiers = ExitSignal::TakeProfitTiers { tiers: vec![0], fraction: 0.5, closes: false };
+        assert_eq!(sell_min_out_with_cost_floor(600_000_000, floor, &tiers), 1_000_000_000);
+        // Stops and timeouts get out at the slippage bound, whatever the quote
+        assert_eq!(sell_min_out_with_cost_floor(400_000_000, floor, &ExitSignal::StopLoss), 400_000_000);
+        assert_eq!(sell_min_out_with_cost_floor(400_000_000, floor, &ExitSignal::TrailingStop(5.0)), 400_000_000);
+        assert_eq!(sell_min_out_with_cost_floor(400_000_000, floor, &ExitSignal::AutoExit), 400_000_000);
+        assert_eq!(sell_min_out_with_cost_floor(400_000_000, floor, &ExitSignal::ForceExit), 400_000_000);
+        // Floor disabled
+        assert_eq!(sell_min_out_with_cost_floor(600_000_000, None, &ExitSignal::TakeProfit), 600_000_000);
+    }
+
+    #[test]
+    fn realized_slippage_compares_the_fill_with_the_signal_price() {
+        // Buy 1 SOL at 0.001 SOL/token expecting 1000 tokens, 980 arrive
+        let buy = realized_slippage_pct(0.001, -1_000_000_000, 9, 980_000_000, 6, true).unwrap();
+        assert!((buy - 2.0).abs() < 1e-9);
+
+        // Sell 1000 tokens expecting 1 SOL, 1.01 SOL arrives
+        let sell = realized_slippage_pct(0.001, 1_010_000_000, 9, -1_000_000_000, 6, false).unwrap();
+        assert!((sell + 1.0).abs() < 1e-9);
+
+        // USD1 quotes carry 6 decimals: 5 USD1 at 0.005 USD1/token expects 1000 tokens
+        let usd1_buy = realized_slippage_pct(0.005, -5_000_000, 6, 990_000_000, 6, true).unwrap();
+        assert!((usd1_buy - 1.0).abs() < 1e-9);
+
+        // Nothing to compare against without a signal price or an amount
+        assert_eq!(realized_slippage_pct(0.0, 1_000_000_000, 9, 1, 6, true), None);
+        assert_eq!(realized_slippage_pct(0.001, 0, 9, 0, 6, true), None);
+    }
 }
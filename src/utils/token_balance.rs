@@ -0,0 +1,113 @@
+use carbon_core::error::{CarbonResult, Error};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::{future::Future, time::Duration};
+
+use crate::config::{RPC_CLIENT, TOKEN_BALANCE_RPC_ATTEMPTS};
+
+const RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// What a position's token account holds when its sell is built
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeldTokens {
+    Amount(u64),
+    /// The account was never created, so there is nothing to sell
+    NoAccount,
+}
+
+/// Reads the amount out of a fetched token account, Token and Token-2022
+/// accounts both keep it at bytes 64..72
+pub fn held_tokens(account: Option<&Account>) -> Result<HeldTokens, String> {
+    let Some(account) = account else {
+        return Ok(HeldTokens::NoAccount);
+    };
+    account
+        .data
+        .get(64..72)
+        .and_then(|amount| amount.try_into().ok())
+        .map(|amount| HeldTokens::Amount(u64::from_le_bytes(amount)))
+        .ok_or_else(|| format!("not a token account, {} bytes of data", account.data.len()))
+}
+
+/// Runs `read` up to `attempts` times, returning the first success or the last error
+pub async fn with_retries<T, E, F, Fut>(attempts: u32, delay: Duration, mut read: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 1;
+    loop {
+        match read().await {
+            Err(_) if attempt < attempts => {
+                attempt += 1;
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Fetches what `account` holds. A missing account is `NoAccount`, an RPC
+/// error is retried `TOKEN_BALANCE_RPC_ATTEMPTS` times before it is returned.
+pub async fn fetch_held_tokens(account: &Pubkey) -> Result<HeldTokens, String> {
+    let response = with_retries(*TOKEN_BALANCE_RPC_ATTEMPTS, RETRY_DELAY, || {
+        RPC_CLIENT.get_account_with_commitment(account, CommitmentConfig::processed())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    held_tokens(response.value.as_ref())
+}
+
+/// Tokens the sell can spend from `account`, `None` when the account doesn't
+/// exist. A balance that can't be read fails the processor call rather than
+/// silently skipping the sell.
+pub async fn sellable_tokens(account: &Pubkey) -> CarbonResult<Option<u64>> {
+    match fetch_held_tokens(account).await {
+        Ok(HeldTokens::Amount(amount)) => Ok(Some(amount)),
+        Ok(HeldTokens::NoAccount) => {
+            println!("No token account {} to sell from, nothing is held", account);
+            Ok(None)
+        }
+        Err(e) => Err(Error::Custom(format!("Failed to read token balance of {}: {}", account, e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn a_missing_account_holds_nothing_to_sell() {
+        assert_eq!(held_tokens(None), Ok(HeldTokens::NoAccount));
+
+        let mut data = vec![0; 165];
+        data[64..72].copy_from_slice(&1_234u64.to_le_bytes());
+        let account = Account { data, owner: spl_token::ID, ..Account::default() };
+        assert_eq!(held_tokens(Some(&account)), Ok(HeldTokens::Amount(1_234)));
+
+        let wallet = Account { lamports: 1, ..Account::default() };
+        assert!(held_tokens(Some(&wallet)).is_err());
+    }
+
+    #[tokio::test]
+    async fn rpc_errors_are_retried_then_returned() {
+        let calls = Cell::new(0);
+        let flaky = with_retries(3, Duration::ZERO, || {
+            calls.set(calls.get() + 1);
+            let call = calls.get();
+            async move { if call < 3 { Err("timeout") } else { Ok(None::<Account>) } }
+        })
+        .await;
+        assert_eq!(flaky.map(|account| held_tokens(account.as_ref())), Ok(Ok(HeldTokens::NoAccount)));
+        assert_eq!(calls.get(), 3);
+
+        calls.set(0);
+        let down: Result<(), _> = with_retries(3, Duration::ZERO, || {
+            calls.set(calls.get() + 1);
+            async { Err("connection refused") }
+        })
+        .await;
+        assert_eq!(down, Err("connection refused"));
+        assert_eq!(calls.get(), 3);
+    }
+}
@@ -0,0 +1,95 @@
+use dashmap::DashMap;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status_client_types::TransactionTokenBalance;
+
+use crate::config::RPC_CLIENT;
+use crate::statics::MINT_DECIMALS;
+use crate::utils::price::FixedPrice;
+
+/// Decimals of `mint` from `cache`, else from a token balance the transaction
+/// carries for it, which is cached from then on
+pub fn cached_decimals<'a>(
+    cache: &DashMap<Pubkey, u8>,
+    mint: &Pubkey,
+    balances: impl IntoIterator<Item = &'a TransactionTokenBalance>,
+) -> Option<u8> {
+    if let Some(decimals) = cache.get(mint) {
+        return Some(*decimals);
+    }
+    let mint_str = mint.to_string();
+    let decimals = balances.into_iter().find(|balance| balance.mint == mint_str)?.ui_token_amount.decimals;
+    cache.insert(*mint, decimals);
+    Some(decimals)
+}
+
+/// Decimals of a mint the processors price. A mint the transaction has no
+/// balance for is read once with `getTokenSupply`; `None` when that fails
+/// too, rather than pricing the token with a guess.
+pub async fn token_decimals<'a>(
+    balances: impl IntoIterator<Item = &'a TransactionTokenBalance>,
+    mint: &Pubkey,
+) -> Option<u8> {
+    if let Some(decimals) = cached_decimals(&MINT_DECIMALS, mint, balances) {
+        return Some(decimals);
+    }
+    match RPC_CLIENT.get_token_supply(mint).await {
+        Ok(supply) => {
+            MINT_DECIMALS.insert(*mint, supply.decimals);
+            Some(supply.decimals)
+        }
+        Err(e) => {
+            println!("Failed to read decimals of {}: {}", mint, e);
+            None
+        }
+    }
+}
+
+/// Quote per whole token from the raw reserve amounts a transaction reports,
+/// worked out in fixed point. Zero while the pool holds no tokens or an
+/// amount doesn't parse.
+pub fn reserve_price(quote_reserve: &str, quote_decimals: u8, token_reserve: &str, token_decimals: u8) -> f64 {
+    let (Ok(quote_reserve), Ok(token_reserve)) = (quote_reserve.parse::<u128>(), token_reserve.parse::<u128>()) else {
+        return 0.0;
+    };
+    FixedPrice::from_reserves(quote_reserve, quote_decimals, token_reserve, token_decimals)
+        .map_or(0.0, FixedPrice::to_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance(mint: &Pubkey, decimals: u8) -> TransactionTokenBalance {
+        TransactionTokenBalance {
+            account_index: 1,
+            mint: mint.to_string(),
+            ui_token_amount: serde_json::from_value(serde_json::json!({
+                "uiAmount": null,
+                "decimals": decimals,
+                "amount": "0",
+                "uiAmountString": "",
+            }))
+            .unwrap(),
+            owner: String::new(),
+            program_id: spl_token::ID.to_string(),
+        }
+    }
+
+    #[test]
+    fn an_eight_decimal_mint_is_priced_with_its_own_decimals() {
+        let cache = DashMap::new();
+        let mint = Pubkey::new_unique();
+
+        // Seen once, then known without any balance for it
+        assert_eq!(cached_decimals(&cache, &mint, &[balance(&mint, 8)]), Some(8));
+        assert_eq!(cached_decimals(&cache, &mint, &[]), Some(8));
+        assert_eq!(cached_decimals(&cache, &Pubkey::new_unique(), &[balance(&mint, 8)]), None);
+
+        // 40 SOL against 1M whole tokens
+        let price = reserve_price("40000000000", 9, "100000000000000", 8);
+        assert_eq!(price, 0.000_04);
+        let six_decimal_guess = reserve_price("40000000000", 9, "100000000000000", 6);
+        assert_eq!(six_decimal_guess, 0.000_000_4);
+        assert_eq!(reserve_price("40000000000", 9, "0", 8), 0.0);
+    }
+}
@@ -0,0 +1,91 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{ClientError, ClientResult};
+
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Checks the token program owning each traded mint, as reported by the
+/// transaction's token balances.
+///
+/// The instruction builders only produce classic SPL token accounts, so
+/// Token-2022 mints are refused unless `allow_token_2022` is set, and any
+/// other token program always is.
+#[allow(clippy::result_large_err)]
+pub fn check_token_programs<'a>(
+    balances: impl IntoIterator<Item = (&'a str, &'a str)>,
+    mints: &[Pubkey],
+    allow_token_2022: bool,
+) -> ClientResult<()> {
+    let classic = spl_token::ID.to_string();
+    let token_2022 = TOKEN_2022_PROGRAM_ID.to_string();
+
+    for (mint, program_id) in balances {
+        // Older transaction metadata doesn't record the owning program
+        if program_id.is_empty() || program_id == classic {
+            continue;
+        }
+        if !mints.iter().any(|m| m.to_string() == mint) {
+            continue;
+        }
+        if program_id == token_2022 {
+            if allow_token_2022 {
+                continue;
+            }
+            return Err(ClientError::UnsupportedTokenProgram(format!(
+                "mint {} uses Token-2022",
+                mint
+            )));
+        }
+        return Err(ClientError::UnsupportedTokenProgram(format!(
+            "mint {} is owned by {}",
+            mint, program_id
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_mints_pass_and_token_2022_follows_config() {
+        let classic_mint = Pubkey::new_unique();
+        let token_2022_mint = Pubkey::new_unique();
+        let classic_mint_str = classic_mint.to_string();
+        let token_2022_mint_str = token_2022_mint.to_string();
+        let classic_program = spl_token::ID.to_string();
+        let token_2022_program = TOKEN_2022_PROGRAM_ID.to_string();
+
+        let balances = vec![
+            (classic_mint_str.as_str(), classic_program.as_str()),
+            (token_2022_mint_str.as_str(), token_2022_program.as_str()),
+        ];
+
+        let other_mint = Pubkey::new_unique();
+        let other_mint_str = other_mint.to_string();
+        let other_program = Pubkey::new_unique().to_string();
+        let traded = [classic_mint, token_2022_mint];
+
+        // Denied
+        assert!(check_token_programs(balances.clone(), &[classic_mint], false).is_ok());
+        assert!(matches!(
+            check_token_programs(balances.clone(), &traded, false),
+            Err(ClientError::UnsupportedTokenProgram(_))
+        ));
+
+        // Allowed
+        assert!(check_token_programs(balances.clone(), &[classic_mint], true).is_ok());
+        assert!(check_token_programs(balances.clone(), &traded, true).is_ok());
+
+        // Allowing Token-2022 doesn't let other token programs through
+        let mut with_other = balances;
+        with_other.push((other_mint_str.as_str(), other_program.as_str()));
+        assert!(matches!(
+            check_token_programs(with_other, &[classic_mint, token_2022_mint, other_mint], true),
+            Err(ClientError::UnsupportedTokenProgram(_))
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    UiTransactionEncoding,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::config::{FAILED_TX_BUFFER_SIZE, FAILED_TX_DIAGNOSTICS_ENABLED, RPC_CLIENT};
+
+/// How long a submitted transaction is watched before giving up on it
+const LANDING_POLL_ATTEMPTS: u32 = 15;
+const LANDING_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static FAILED_TXS: Lazy<Mutex<HashMap<String, VecDeque<FailedTransaction>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A submitted transaction that landed on-chain with an error
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FailedTransaction {
+    pub ts: String,
+    pub signature: String,
+    pub pool_id: String,
+    pub slot: u64,
+    pub error: String,
+    pub logs: Vec<String>,
+}
+
+/// Most recent `limit` failures for the user, newest first
+pub fn recent_failures(user_id: &str, limit: usize) -> Vec<FailedTransaction> {
+    let failures = FAILED_TXS.lock().unwrap_or_else(|e| e.into_inner());
+    failures
+        .get(user_id)
+        .map(|list| list.iter().rev().take(limit).cloned().collect())
+        .unwrap_or_default()
+}
+
+fn push_failure(user_id: &str, failure: FailedTransaction, capacity: usize) {
+    let mut failures = FAILED_TXS.lock().unwrap_or_else(|e| e.into_inner());
+    let list = failures.entry(user_id.to_string()).or_default();
+    list.push_back(failure);
+    while list.len() > capacity {
+        list.pop_front();
+    }
+}
+
+/// Pulls the error and program logs out of a fetched transaction, `None`
+/// when it succeeded
+pub fn failure_from_transaction(
+    signature: &str,
+    pool_id: &str,
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Option<FailedTransaction> {
+    let meta = tx.transaction.meta.as_ref()?;
+    let err = meta.err.as_ref()?;
+    let logs = match &meta.log_messages {
+        OptionSerializer::Some(logs) => logs.clone(),
+        _ => vec![],
+    };
+
+    Some(FailedTransaction {
+        ts: chrono::Utc::now().to_rfc3339(),
+        signature: signature.to_string(),
+        pool_id: pool_id.to_string(),
+        slot: tx.slot,
+        error: err.to_string(),
+        logs,
+    })
+}
+
+/// Watches a submitted transaction until it lands and, if it failed on-chain,
+/// fetches its error and logs and keeps them for `/bots/failures`
+pub async fn diagnose_submitted_transaction(user_id: String, pool_id: String, signature: String) {
+    if !*FAILED_TX_DIAGNOSTICS_ENABLED {
+        return;
+    }
+    let Ok(sig) = Signature::from_str(&signature) else {
+        return;
+    };
+
+    let mut landed_with_error = false;
+    for _ in 0..LANDING_POLL_ATTEMPTS {
+        tokio::time::sleep(LANDING_POLL_INTERVAL).await;
+        if let Ok(response) = RPC_CLIENT.get_signature_statuses(&[sig]).await {
+            if let Some(Some(status)) = response.value.first() {
+                landed_with_error = status.err.is_some();
+                break;
+            }
+        }
+    }
+    if !landed_with_error {
+        return;
+    }
+
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let tx = match RPC_CLIENT.get_transaction_with_config(&sig, config).await {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::warn!(user_id, "Failed to fetch failed transaction {}: {}", signature, e);
+            return;
+        }
+    };
+
+    if let Some(failure) = failure_from_transaction(&signature, &pool_id, &tx) {
+        tracing::error!(
+            user_id,
+            "💥 Transaction {} failed on-chain: {}",
+            failure.signature,
+            failure.error
+        );
+        push_failure(&user_id, failure, *FAILED_TX_BUFFER_SIZE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetched(err: serde_json::Value) -> EncodedConfirmedTransactionWithStatusMeta {
+        serde_json::from_value(serde_json::json!({
+            "slot": 321,
+            "blockTime": null,
+            "transaction": ["", "base64"],
+            "meta": {
+                "err": err,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "logMessages": [
+                    "Program 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8 invoke [1]",
+                    "Program log: Error: exceeds desired slippage limit",
+                ],
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn failed_transaction_error_and_logs_are_captured() {
+        let tx = fetched(serde_json::json!({ "InstructionError": [2, { "Custom": 30 }] }));
+
+        let failure = failure_from_transaction("sig", "pool", &tx).unwrap();
+        assert_eq!(failure.slot, 321);
+        assert_eq!(failure.error, "Error processing Instruction 2: custom program error: 0x1e");
+        assert_eq!(failure.logs.len(), 2);
+
+        push_failure("failures-user", failure.clone(), 1);
+        push_failure("failures-user", FailedTransaction { signature: "newer".into(), ..failure }, 1);
+        let recent = recent_failures("failures-user", 10);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].signature, "newer");
+    }
+
+    #[test]
+    fn successful_transaction_is_not_a_failure() {
+        assert_eq!(failure_from_transaction("sig", "pool", &fetched(serde_json::Value::Null)), None);
+    }
+}
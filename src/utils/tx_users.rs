@@ -0,0 +1,86 @@
+use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+use std::collections::HashSet;
+
+use crate::backend::services::bot_service::UserBotData;
+
+/// Users whose pool is one of the instruction's accounts, the only ones a
+/// swap through it can concern
+pub fn users_for_instruction<'a>(
+    users: &'a [UserBotData],
+    instruction_accounts: &[AccountMeta],
+) -> Vec<&'a UserBotData> {
+    let accounts: HashSet<Pubkey> = instruction_accounts.iter().map(|meta| meta.pubkey).collect();
+    users
+        .iter()
+        .filter(|user| {
+            user.pool_id
+                .parse::<Pubkey>()
+                .is_ok_and(|pool| accounts.contains(&pool))
+        })
+        .collect()
+}
+
+/// Splits `users` into at most `max_tasks` batches of about the same size,
+/// one task each, or one batch per user when it's zero. Every user lands in
+/// a batch.
+pub fn task_batches<T>(users: Vec<T>, max_tasks: usize) -> Vec<Vec<T>> {
+    let batch_size = match max_tasks {
+        0 => 1,
+        max_tasks => users.len().div_ceil(max_tasks).max(1),
+    };
+    let mut batches = Vec::new();
+    let mut users = users.into_iter().peekable();
+    while users.peek().is_some() {
+        batches.push(users.by_ref().take(batch_size).collect());
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::models::bot::BotSettings;
+
+    fn user_on(pool: &Pubkey, user_id: &str) -> UserBotData {
+        UserBotData {
+            pool_id: pool.to_string(),
+            user_id: user_id.to_string(),
+            private_key: String::new(),
+            public_key: String::new(),
+            bot_setting: BotSettings::new(user_id.to_string(), "bot".to_string(), pool.to_string()),
+        }
+    }
+
+    #[test]
+    fn only_users_of_the_swapped_pool_get_a_task() {
+        let (swapped, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let users = vec![
+            user_on(&swapped, "a"),
+            user_on(&other, "b"),
+            user_on(&swapped, "c"),
+            UserBotData { pool_id: "not-a-pool".to_string(), ..user_on(&swapped, "d") },
+        ];
+        let accounts = vec![
+            AccountMeta::new(swapped, false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        ];
+
+        let matched: Vec<_> = users_for_instruction(&users, &accounts)
+            .iter()
+            .map(|user| user.user_id.as_str())
+            .collect();
+        assert_eq!(matched, ["a", "c"]);
+
+        assert!(users_for_instruction(&users, &[AccountMeta::new(Pubkey::new_unique(), false)]).is_empty());
+    }
+
+    #[test]
+    fn capping_tasks_batches_users_without_dropping_any() {
+        let users: Vec<u32> = (0..7).collect();
+        assert_eq!(task_batches(users.clone(), 0).len(), 7);
+        assert_eq!(task_batches(users.clone(), 3), vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+        assert_eq!(task_batches(users.clone(), 10).len(), 7);
+        assert_eq!(task_batches(users, 1).concat(), (0..7).collect::<Vec<_>>());
+        assert!(task_batches(Vec::<u32>::new(), 3).is_empty());
+    }
+}
@@ -0,0 +1,37 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// One async lock per user, for operations that have to run one at a time
+/// for the same user but may run concurrently across users
+#[derive(Default)]
+pub struct UserLocks {
+    locks: DashMap<String, Arc<Mutex<()>>>,
+}
+
+impl UserLocks {
+    /// Waits for the user's lock, held until the guard is dropped
+    pub async fn lock(&self, user_id: &str) -> OwnedMutexGuard<()> {
+        let lock = self.locks.entry(user_id.to_string()).or_default().clone();
+        lock.lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_held_lock_only_holds_up_its_user() {
+        let locks = Arc::new(UserLocks::default());
+        let held = locks.lock("user").await;
+
+        let other = tokio::time::timeout(std::time::Duration::from_secs(1), locks.lock("other")).await;
+        assert!(other.is_ok());
+        let same = tokio::time::timeout(std::time::Duration::from_millis(50), locks.lock("user")).await;
+        assert!(same.is_err());
+
+        drop(held);
+        assert!(tokio::time::timeout(std::time::Duration::from_secs(1), locks.lock("user")).await.is_ok());
+    }
+}
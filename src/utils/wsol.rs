@@ -0,0 +1,64 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::utils::blockhash::WSOL;
+
+/// Closes the owner's WSOL account, unwrapping what it holds back to SOL
+pub fn close_wsol_ix(owner: &Pubkey) -> Instruction {
+    let wsol_ata = get_associated_token_address(owner, &WSOL);
+    spl_token::instruction::close_account(&spl_token::ID, &wsol_ata, owner, owner, &[]).unwrap()
+}
+
+fn is_close_wsol(ix: &Instruction, owner: &Pubkey) -> bool {
+    ix.program_id == spl_token::ID
+        && ix.data.first() == Some(&9)
+        && ix.accounts.first().map(|meta| meta.pubkey)
+            == Some(get_associated_token_address(owner, &WSOL))
+}
+
+/// With `keep_open` the WSOL account survives the session's trades: closes
+/// the processors built are dropped, and a single close ends the final exit
+/// of a stopped bot. Otherwise the instructions are left as built.
+pub fn apply_wsol_close(
+    mut ixs: Vec<Instruction>,
+    owner: &Pubkey,
+    keep_open: bool,
+    final_exit: bool,
+) -> Vec<Instruction> {
+    if !keep_open {
+        return ixs;
+    }
+    ixs.retain(|ix| !is_close_wsol(ix, owner));
+    if final_exit {
+        ixs.push(close_wsol_ix(owner));
+    }
+    ixs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kept_wsol_account_closes_only_on_final_exit() {
+        let owner = Pubkey::new_unique();
+        let swap = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+        let built = vec![swap.clone(), close_wsol_ix(&owner)];
+
+        let mid_session = apply_wsol_close(built.clone(), &owner, true, false);
+        assert_eq!(mid_session, vec![swap.clone()]);
+
+        let final_exit = apply_wsol_close(built.clone(), &owner, true, true);
+        assert_eq!(final_exit, built);
+        // Added when the processor built no close, e.g. a Raydium sell
+        let final_exit = apply_wsol_close(vec![swap.clone()], &owner, true, true);
+        assert_eq!(final_exit, built);
+
+        // Someone else's close isn't ours to drop
+        let other_close = close_wsol_ix(&Pubkey::new_unique());
+        let kept = apply_wsol_close(vec![other_close.clone()], &owner, true, false);
+        assert_eq!(kept, vec![other_close]);
+
+        assert_eq!(apply_wsol_close(built.clone(), &owner, false, false), built);
+    }
+}